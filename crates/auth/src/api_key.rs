@@ -1,5 +1,9 @@
+use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use hex;
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
 
 pub struct ApiKeyService;
 
@@ -24,3 +28,124 @@ impl ApiKeyService {
     }
 }
 
+/// An entry of an API key's IP allow-list: a single address or a CIDR block
+/// such as `10.0.0.0/8`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpRange {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        // IPv4 clients may show up as IPv4-mapped IPv6 addresses
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            IpAddr::V4(_) => ip,
+        };
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                prefix_matches(u32::from(network) as u128, u32::from(ip) as u128, self.prefix_len, 32)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                prefix_matches(u128::from(network), u128::from(ip), self.prefix_len, 128)
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether every address of `other` is in this range
+    pub fn covers(&self, other: &IpRange) -> bool {
+        self.network.is_ipv4() == other.network.is_ipv4()
+            && self.prefix_len <= other.prefix_len
+            && self.contains(other.network)
+    }
+}
+
+fn prefix_matches(network: u128, ip: u128, prefix_len: u8, bits: u8) -> bool {
+    if prefix_len == 0 {
+        return true;
+    }
+    let shift = u32::from(bits - prefix_len);
+    network >> shift == ip >> shift
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bits = if self.network.is_ipv4() { 32 } else { 128 };
+        if self.prefix_len == bits {
+            write!(f, "{}", self.network)
+        } else {
+            write!(f, "{}/{}", self.network, self.prefix_len)
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (address, prefix_len) = match s.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len)),
+            None => (s, None),
+        };
+        let network: IpAddr = address.trim().parse().map_err(|_| format!("Invalid IP address: {}", s))?;
+        let bits = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            Some(prefix_len) => prefix_len
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= bits)
+                .ok_or_else(|| format!("Invalid CIDR prefix: {}", s))?,
+            None => bits,
+        };
+        Ok(Self { network, prefix_len })
+    }
+}
+
+impl TryFrom<String> for IpRange {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<IpRange> for String {
+    fn from(range: IpRange) -> Self {
+        range.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ip_ranges() {
+        let range: IpRange = "10.1.0.0/16".parse().unwrap();
+        assert!(range.contains("10.1.200.3".parse().unwrap()));
+        assert!(!range.contains("10.2.0.1".parse().unwrap()));
+        assert!(range.contains("::ffff:10.1.0.9".parse().unwrap()));
+
+        let single: IpRange = "203.0.113.7".parse().unwrap();
+        assert!(single.contains("203.0.113.7".parse().unwrap()));
+        assert!(!single.contains("203.0.113.8".parse().unwrap()));
+        assert_eq!(single.to_string(), "203.0.113.7");
+
+        let v6: IpRange = "2001:db8::/32".parse().unwrap();
+        assert!(v6.contains("2001:db8:1::1".parse().unwrap()));
+        assert!(!v6.contains("10.1.0.1".parse().unwrap()));
+
+        assert!("0.0.0.0/0".parse::<IpRange>().unwrap().contains("8.8.8.8".parse().unwrap()));
+        assert!(range.covers(&"10.1.2.0/24".parse().unwrap()));
+        assert!(range.covers(&range));
+        assert!(!range.covers(&"10.0.0.0/8".parse().unwrap()));
+        assert!(!v6.covers(&"10.1.0.1".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("example.com".parse::<IpRange>().is_err());
+    }
+}
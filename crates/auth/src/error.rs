@@ -22,6 +22,9 @@ pub enum AuthError {
     
     #[error("Database error: {0}")]
     DatabaseError(String),
+
+    #[error("Invalid configuration: {0}")]
+    Configuration(String),
 }
 
 impl From<jsonwebtoken::errors::Error> for AuthError {
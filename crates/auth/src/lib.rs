@@ -3,10 +3,20 @@ pub mod api_key;
 pub mod user;
 pub mod middleware;
 pub mod error;
+pub mod secrets;
+pub mod workspace;
+pub mod scope;
+pub mod oidc;
+pub mod webhook;
 
 pub use jwt::JwtService;
-pub use api_key::ApiKeyService;
-pub use user::User;
-pub use middleware::{auth_middleware, AuthContext, extract_user_id, AuthStateForMiddleware, ApiKeyValidator};
+pub use api_key::{ApiKeyService, IpRange};
+pub use user::{Claims, User};
+pub use middleware::{auth_middleware, optional_auth_middleware, AuthContext, AuthUser, extract_user_id, AuthStateForMiddleware, WEBSOCKET_TOKEN_PROTOCOL_PREFIX, AccessTokenValidator, ApiKeyValidator, WorkspaceResolver};
 pub use error::AuthError;
+pub use secrets::{EncryptedSecret, SecretCipher, SecretError};
+pub use oidc::{OidcClient, OidcConfig, OidcError, OidcIdentity};
+pub use scope::{ApiKeyScope, ScopeAccess, ScopeResource, ScopeSet};
+pub use webhook::{generate_webhook_secret, sign_webhook, WebhookAuth, WebhookAuthError};
+pub use workspace::{WorkspaceMembership, WorkspaceRole, WORKSPACE_HEADER};
 
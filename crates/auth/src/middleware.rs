@@ -1,27 +1,100 @@
 use axum::{
-    extract::Request,
-    http::{HeaderMap, StatusCode},
+    extract::{ConnectInfo, Request},
+    http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderMap, Method, StatusCode},
     middleware::Next,
     response::Response,
 };
 use crate::jwt::JwtService;
-use crate::api_key::ApiKeyService;
+use crate::api_key::{ApiKeyService, IpRange};
+use crate::scope::{ScopeAccess, ScopeResource, ScopeSet};
+use crate::user::Claims;
+use crate::workspace::{WorkspaceMembership, WorkspaceRole, WORKSPACE_HEADER};
+use std::net::{IpAddr, SocketAddr};
 use std::sync::Arc;
 
 #[derive(Clone)]
 pub struct AuthContext {
     pub user_id: String,
     pub email: String,
+    /// Workspace the request acts on
+    pub workspace_id: String,
+    /// Role of the user in `workspace_id`
+    pub role: WorkspaceRole,
+    /// Scopes of the API key used; `None` for unrestricted access
+    pub scopes: Option<ScopeSet>,
+    /// Identity provider groups of the user; empty without single sign-on
+    pub groups: Vec<String>,
+    /// Expiry of the API key used, if it has one
+    pub key_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// IP allow-list of the API key used, if it has one
+    pub key_allowed_ips: Option<Vec<IpRange>>,
 }
 
+impl AuthContext {
+    /// Fails with 403 Forbidden unless the caller's role includes `role`
+    pub fn require(&self, role: WorkspaceRole) -> Result<(), StatusCode> {
+        if self.role.allows(role) {
+            Ok(())
+        } else {
+            Err(StatusCode::FORBIDDEN)
+        }
+    }
+
+    /// Fails with 403 Forbidden unless the caller may run `flow_id`
+    pub fn require_trigger(&self, flow_id: &str) -> Result<(), StatusCode> {
+        match &self.scopes {
+            Some(scopes) if !scopes.may_trigger(flow_id) => Err(StatusCode::FORBIDDEN),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// An authenticated user, before a workspace is chosen
+#[derive(Clone)]
+pub struct AuthUser {
+    pub user_id: String,
+    pub email: String,
+    /// Scopes of the API key used; `None` for unrestricted access
+    pub scopes: Option<ScopeSet>,
+    /// Identity provider groups of the user
+    pub groups: Vec<String>,
+    /// Expiry of the API key used, if it has one
+    pub key_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// IP allow-list of the API key used, if it has one
+    pub key_allowed_ips: Option<Vec<IpRange>>,
+}
+
+/// Prefix of the WebSocket subprotocol that carries an access token or API
+/// key, for browser clients that cannot set the Authorization header
+pub const WEBSOCKET_TOKEN_PROTOCOL_PREFIX: &str = "flowmason.bearer.";
+
 /// Callback function type for API key validation
 /// This allows the routes module to provide repository access without circular dependencies
-pub type ApiKeyValidator = Arc<dyn Fn(String) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<AuthContext, StatusCode>> + Send>> + Send + Sync>;
+///
+/// Receives the key and the address of the client, if known, for checking
+/// the key's IP allow-list.
+pub type ApiKeyValidator = Arc<dyn Fn(String, Option<IpAddr>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<AuthUser, StatusCode>> + Send>> + Send + Sync>;
+
+/// Callback function type for accepting a verified access token
+///
+/// Receives the claims of a token with a valid signature; fails with 401
+/// Unauthorized if the token was revoked.
+pub type AccessTokenValidator = Arc<dyn Fn(Claims) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<AuthUser, StatusCode>> + Send>> + Send + Sync>;
+
+/// Callback function type for choosing the workspace of a request
+///
+/// Receives the user id and the workspace named in the `X-Workspace-Id`
+/// header, if any. Fails with 403 Forbidden if the user is not a member.
+pub type WorkspaceResolver = Arc<dyn Fn(String, Option<String>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<WorkspaceMembership, StatusCode>> + Send>> + Send + Sync>;
 
 /// AuthState that can be stored in request extensions for API key validation
 #[derive(Clone)]
 pub struct AuthStateForMiddleware {
+    /// Token keys, loaded once at startup
+    pub jwt: Arc<JwtService>,
+    pub validate_access_token: AccessTokenValidator,
     pub validate_api_key: ApiKeyValidator,
+    pub resolve_workspace: WorkspaceResolver,
 }
 
 pub async fn auth_middleware(
@@ -29,28 +102,67 @@ pub async fn auth_middleware(
     mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    // Check for Authorization header
-    let auth_header = headers.get("authorization")
-        .and_then(|h| h.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+    let auth_state = request.extensions().get::<AuthStateForMiddleware>().cloned();
+    let client_ip = client_ip(&request);
+    let user = authenticate(&headers, auth_state.as_ref(), client_ip).await?;
+    let auth_context = workspace_context(&headers, auth_state.as_ref(), user).await?;
+
+    // Scoped API keys only reach the resources they were granted
+    if let (Some(scopes), Some(resource)) = (&auth_context.scopes, request.extensions().get::<ScopeResource>()) {
+        check_scope(scopes, *resource, request.method())?;
+    }
+
+    // Store auth context in request extensions
+    request.extensions_mut().insert(auth_context);
+
+    Ok(next.run(request).await)
+}
 
-    let auth_context = if auth_header.starts_with("Bearer ") {
+/// Address of the connected client, when the server records it
+fn client_ip(request: &Request) -> Option<IpAddr> {
+    request.extensions().get::<ConnectInfo<SocketAddr>>().map(|info| info.0.ip())
+}
+
+/// Fails with 403 Forbidden unless `scopes` allow the request
+///
+/// Starting executions is also allowed for keys that may trigger single
+/// flows; the handlers check the flow with `AuthContext::require_trigger`.
+fn check_scope(scopes: &ScopeSet, resource: ScopeResource, method: &Method) -> Result<(), StatusCode> {
+    let access = ScopeAccess::for_method(method);
+    let allowed = scopes.allows(resource, access)
+        || (resource == ScopeResource::Executions && method == Method::POST && scopes.may_trigger_any());
+    if allowed {
+        Ok(())
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+/// Identifies the caller from the Authorization header
+async fn authenticate(
+    headers: &HeaderMap,
+    auth_state: Option<&AuthStateForMiddleware>,
+    client_ip: Option<IpAddr>,
+) -> Result<AuthUser, StatusCode> {
+    // Check for Authorization header; WebSocket requests from browsers carry
+    // the token in a subprotocol instead
+    let auth_header = match headers.get("authorization") {
+        Some(value) => value.to_str().map_err(|_| StatusCode::UNAUTHORIZED)?.to_string(),
+        None => websocket_token(headers)
+            .map(|token| format!("Bearer {}", token))
+            .ok_or(StatusCode::UNAUTHORIZED)?,
+    };
+
+    let auth_state = auth_state.ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let user = if auth_header.starts_with("Bearer ") {
         let token = auth_header.strip_prefix("Bearer ").unwrap();
-        let jwt_service = JwtService::from_env();
         
         // Try JWT verification first
-        match jwt_service.verify_token(token) {
-            Ok(claims) => {
-                AuthContext {
-                    user_id: claims.sub,
-                    email: claims.email,
-                }
-            }
+        match auth_state.jwt.verify_token(token) {
+            Ok(claims) => (auth_state.validate_access_token)(claims).await?,
             Err(_) => {
                 // JWT verification failed, try as API key
-                let auth_state = request.extensions()
-                    .get::<AuthStateForMiddleware>()
-                    .ok_or(StatusCode::UNAUTHORIZED)?;
                 
                 // Validate API key format
                 if !ApiKeyService::validate_format(token) {
@@ -58,32 +170,59 @@ pub async fn auth_middleware(
                 }
                 
                 // Use the validation function from auth_state
-                (auth_state.validate_api_key)(token.to_string()).await?
+                (auth_state.validate_api_key)(token.to_string(), client_ip).await?
             }
         }
     } else if auth_header.starts_with("ApiKey ") {
         // Support ApiKey prefix for backward compatibility
         let api_key = auth_header.strip_prefix("ApiKey ").unwrap();
         
-        let auth_state = request.extensions()
-            .get::<AuthStateForMiddleware>()
-            .ok_or(StatusCode::UNAUTHORIZED)?;
-        
         // Validate API key format
         if !ApiKeyService::validate_format(api_key) {
             return Err(StatusCode::UNAUTHORIZED);
         }
         
         // Use the validation function from auth_state
-        (auth_state.validate_api_key)(api_key.to_string()).await?
+        (auth_state.validate_api_key)(api_key.to_string(), client_ip).await?
     } else {
         return Err(StatusCode::UNAUTHORIZED);
     };
 
-    // Store auth context in request extensions
-    request.extensions_mut().insert(auth_context);
+    Ok(user)
+}
 
-    Ok(next.run(request).await)
+/// Token offered as a `flowmason.bearer.<token>` WebSocket subprotocol
+fn websocket_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get_all(SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .find_map(|protocol| protocol.trim().strip_prefix(WEBSOCKET_TOKEN_PROTOCOL_PREFIX))
+}
+
+/// Resolves the workspace of the request and the caller's role in it
+async fn workspace_context(
+    headers: &HeaderMap,
+    auth_state: Option<&AuthStateForMiddleware>,
+    user: AuthUser,
+) -> Result<AuthContext, StatusCode> {
+    let auth_state = auth_state.ok_or(StatusCode::UNAUTHORIZED)?;
+    let requested = match headers.get(WORKSPACE_HEADER) {
+        Some(value) => Some(value.to_str().map_err(|_| StatusCode::BAD_REQUEST)?.to_string()),
+        None => None,
+    };
+
+    let membership = (auth_state.resolve_workspace)(user.user_id.clone(), requested).await?;
+    Ok(AuthContext {
+        user_id: user.user_id,
+        email: user.email,
+        workspace_id: membership.workspace_id,
+        role: membership.role,
+        scopes: user.scopes,
+        groups: user.groups,
+        key_expires_at: user.key_expires_at,
+        key_allowed_ips: user.key_allowed_ips,
+    })
 }
 
 /// Extract user_id from request extensions
@@ -93,22 +232,20 @@ pub fn extract_user_id(request: &Request) -> Option<String> {
 }
 
 // Optional auth middleware - allows requests with or without auth
+//
+// Requests with valid credentials get an AuthContext like with
+// auth_middleware; invalid credentials are ignored.
 pub async fn optional_auth_middleware(
     headers: HeaderMap,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Response {
-    // If auth header is present, validate it
-    if let Some(auth_header) = headers.get("authorization")
-        .and_then(|h| h.to_str().ok()) {
-        
-        if auth_header.starts_with("Bearer ") {
-            let token = auth_header.strip_prefix("Bearer ").unwrap();
-            let jwt_service = JwtService::from_env();
-            let _ = jwt_service.verify_token(token);
-        } else if auth_header.starts_with("ApiKey ") {
-            let api_key = auth_header.strip_prefix("ApiKey ").unwrap();
-            let _ = crate::api_key::ApiKeyService::validate_format(api_key);
+    if headers.contains_key("authorization") {
+        let auth_state = request.extensions().get::<AuthStateForMiddleware>().cloned();
+        if let Ok(user) = authenticate(&headers, auth_state.as_ref(), client_ip(&request)).await {
+            if let Ok(auth_context) = workspace_context(&headers, auth_state.as_ref(), user).await {
+                request.extensions_mut().insert(auth_context);
+            }
         }
     }
     
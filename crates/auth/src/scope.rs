@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Part of the API an API key scope applies to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ScopeResource {
+    Flows,
+    Executions,
+    Scheduler,
+    Templates,
+    DeadLetters,
+    Secrets,
+    Connections,
+    Usage,
+    Workspaces,
+    ApiKeys,
+}
+
+impl ScopeResource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScopeResource::Flows => "flows",
+            ScopeResource::Executions => "executions",
+            ScopeResource::Scheduler => "scheduler",
+            ScopeResource::Templates => "templates",
+            ScopeResource::DeadLetters => "dead-letters",
+            ScopeResource::Secrets => "secrets",
+            ScopeResource::Connections => "connections",
+            ScopeResource::Usage => "usage",
+            ScopeResource::Workspaces => "workspaces",
+            ScopeResource::ApiKeys => "api-keys",
+        }
+    }
+
+    /// Name of the write scope; running executions reads better than writing them
+    fn write_verb(&self) -> &'static str {
+        match self {
+            ScopeResource::Executions => "run",
+            _ => "write",
+        }
+    }
+}
+
+impl FromStr for ScopeResource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "flows" => Ok(ScopeResource::Flows),
+            "executions" => Ok(ScopeResource::Executions),
+            "scheduler" => Ok(ScopeResource::Scheduler),
+            "templates" => Ok(ScopeResource::Templates),
+            "dead-letters" => Ok(ScopeResource::DeadLetters),
+            "secrets" => Ok(ScopeResource::Secrets),
+            "connections" => Ok(ScopeResource::Connections),
+            "usage" => Ok(ScopeResource::Usage),
+            "workspaces" => Ok(ScopeResource::Workspaces),
+            "api-keys" => Ok(ScopeResource::ApiKeys),
+            other => Err(format!("Unknown scope resource: {}", other)),
+        }
+    }
+}
+
+/// Kind of access a request needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeAccess {
+    Read,
+    Write,
+}
+
+impl ScopeAccess {
+    /// Safe methods only read; everything else writes
+    pub fn for_method(method: &axum::http::Method) -> Self {
+        use axum::http::Method;
+        if method == Method::GET || method == Method::HEAD || method == Method::OPTIONS {
+            ScopeAccess::Read
+        } else {
+            ScopeAccess::Write
+        }
+    }
+}
+
+/// A permission granted to an API key
+///
+/// Written as `<resource>:read`, `<resource>:write` (`executions:run` for
+/// executions) or `flow:<id>:trigger`. Write scopes include reading.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ApiKeyScope {
+    Read(ScopeResource),
+    Write(ScopeResource),
+    /// Runs one flow, without access to anything else
+    TriggerFlow(String),
+}
+
+impl fmt::Display for ApiKeyScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiKeyScope::Read(resource) => write!(f, "{}:read", resource.as_str()),
+            ApiKeyScope::Write(resource) => write!(f, "{}:{}", resource.as_str(), resource.write_verb()),
+            ApiKeyScope::TriggerFlow(flow_id) => write!(f, "flow:{}:trigger", flow_id),
+        }
+    }
+}
+
+impl FromStr for ApiKeyScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(flow_id) = s.strip_prefix("flow:").and_then(|rest| rest.strip_suffix(":trigger")) {
+            if flow_id.is_empty() {
+                return Err(format!("Invalid scope: {}", s));
+            }
+            return Ok(ApiKeyScope::TriggerFlow(flow_id.to_string()));
+        }
+
+        let (resource, verb) = s.split_once(':').ok_or_else(|| format!("Invalid scope: {}", s))?;
+        let resource: ScopeResource = resource.parse()?;
+        match verb {
+            "read" => Ok(ApiKeyScope::Read(resource)),
+            verb if verb == resource.write_verb() => Ok(ApiKeyScope::Write(resource)),
+            _ => Err(format!("Invalid scope: {}", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for ApiKeyScope {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<ApiKeyScope> for String {
+    fn from(scope: ApiKeyScope) -> Self {
+        scope.to_string()
+    }
+}
+
+/// The scopes of an API key
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ScopeSet(BTreeSet<ApiKeyScope>);
+
+impl ScopeSet {
+    pub fn new(scopes: impl IntoIterator<Item = ApiKeyScope>) -> Self {
+        Self(scopes.into_iter().collect())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ApiKeyScope> {
+        self.0.iter()
+    }
+
+    /// Whether the scopes grant `access` to `resource`
+    pub fn allows(&self, resource: ScopeResource, access: ScopeAccess) -> bool {
+        self.0.iter().any(|scope| match scope {
+            ApiKeyScope::Write(r) => *r == resource,
+            ApiKeyScope::Read(r) => *r == resource && access == ScopeAccess::Read,
+            ApiKeyScope::TriggerFlow(_) => false,
+        })
+    }
+
+    /// Whether the scopes allow running `flow_id`
+    pub fn may_trigger(&self, flow_id: &str) -> bool {
+        self.allows(ScopeResource::Executions, ScopeAccess::Write)
+            || self.0.contains(&ApiKeyScope::TriggerFlow(flow_id.to_string()))
+    }
+
+    /// Whether the scopes allow running at least one flow
+    pub fn may_trigger_any(&self) -> bool {
+        self.allows(ScopeResource::Executions, ScopeAccess::Write)
+            || self.0.iter().any(|scope| matches!(scope, ApiKeyScope::TriggerFlow(_)))
+    }
+
+    /// Whether every scope of `other` is granted by these scopes
+    pub fn covers(&self, other: &ScopeSet) -> bool {
+        other.0.iter().all(|scope| match scope {
+            ApiKeyScope::Read(resource) => self.allows(*resource, ScopeAccess::Read),
+            ApiKeyScope::Write(resource) => self.allows(*resource, ScopeAccess::Write),
+            ApiKeyScope::TriggerFlow(flow_id) => self.may_trigger(flow_id),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_parsing() {
+        assert_eq!("flows:read".parse::<ApiKeyScope>(), Ok(ApiKeyScope::Read(ScopeResource::Flows)));
+        assert_eq!("executions:run".parse::<ApiKeyScope>(), Ok(ApiKeyScope::Write(ScopeResource::Executions)));
+        assert_eq!("dead-letters:write".parse::<ApiKeyScope>(), Ok(ApiKeyScope::Write(ScopeResource::DeadLetters)));
+        assert_eq!(
+            "flow:abc-123:trigger".parse::<ApiKeyScope>(),
+            Ok(ApiKeyScope::TriggerFlow("abc-123".to_string()))
+        );
+        assert!("executions:write".parse::<ApiKeyScope>().is_err());
+        assert!("flows:delete".parse::<ApiKeyScope>().is_err());
+        assert!("flow::trigger".parse::<ApiKeyScope>().is_err());
+        assert!("admin".parse::<ApiKeyScope>().is_err());
+
+        let scopes: ScopeSet = serde_json::from_str(r#"["flows:read", "flow:f1:trigger", "flows:read"]"#).unwrap();
+        assert_eq!(serde_json::to_string(&scopes).unwrap(), r#"["flows:read","flow:f1:trigger"]"#);
+    }
+
+    #[test]
+    fn test_scope_matching() {
+        let scopes = ScopeSet::new(vec![
+            ApiKeyScope::Read(ScopeResource::Flows),
+            ApiKeyScope::Write(ScopeResource::Templates),
+            ApiKeyScope::TriggerFlow("f1".to_string()),
+        ]);
+        assert!(scopes.allows(ScopeResource::Flows, ScopeAccess::Read));
+        assert!(!scopes.allows(ScopeResource::Flows, ScopeAccess::Write));
+        assert!(scopes.allows(ScopeResource::Templates, ScopeAccess::Read));
+        assert!(!scopes.allows(ScopeResource::Executions, ScopeAccess::Read));
+        assert!(scopes.may_trigger("f1"));
+        assert!(!scopes.may_trigger("f2"));
+        assert!(scopes.may_trigger_any());
+
+        let runner = ScopeSet::new(vec![ApiKeyScope::Write(ScopeResource::Executions)]);
+        assert!(runner.may_trigger("f2"));
+        assert!(runner.covers(&ScopeSet::new(vec![ApiKeyScope::TriggerFlow("f2".to_string())])));
+        assert!(!scopes.covers(&runner));
+        assert!(scopes.covers(&ScopeSet::new(vec![ApiKeyScope::Read(ScopeResource::Templates)])));
+    }
+}
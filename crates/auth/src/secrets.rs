@@ -0,0 +1,146 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use thiserror::Error;
+
+/// Length in bytes of the AES-256 master key
+pub const MASTER_KEY_LEN: usize = 32;
+
+#[derive(Debug, Error)]
+pub enum SecretError {
+    #[error("No master key configured; set FLOWMASON_MASTER_KEY or FLOWMASON_MASTER_KEY_FILE")]
+    MissingMasterKey,
+
+    #[error("Invalid master key: {0}")]
+    InvalidMasterKey(String),
+
+    #[error("Failed to encrypt secret")]
+    Encryption,
+
+    #[error("Failed to decrypt secret (wrong master key or corrupted data)")]
+    Decryption,
+}
+
+/// A secret value encrypted with AES-256-GCM, both parts base64 encoded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedSecret {
+    pub nonce: String,
+    pub ciphertext: String,
+}
+
+/// Encrypts and decrypts secret values with the master key
+///
+/// The secret's name is bound to the ciphertext as associated data, so a
+/// value copied to another secret's row fails to decrypt.
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    pub fn new(key: &[u8]) -> Result<Self, SecretError> {
+        if key.len() != MASTER_KEY_LEN {
+            return Err(SecretError::InvalidMasterKey(format!(
+                "expected {} bytes, got {}",
+                MASTER_KEY_LEN,
+                key.len()
+            )));
+        }
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        })
+    }
+
+    /// Loads the master key from `FLOWMASON_MASTER_KEY`, or from the file named
+    /// by `FLOWMASON_MASTER_KEY_FILE`
+    ///
+    /// The key is 32 bytes encoded as base64 or hex.
+    pub fn from_env() -> Result<Self, SecretError> {
+        let encoded = match std::env::var("FLOWMASON_MASTER_KEY") {
+            Ok(key) => key,
+            Err(_) => {
+                let path = std::env::var("FLOWMASON_MASTER_KEY_FILE").map_err(|_| SecretError::MissingMasterKey)?;
+                std::fs::read_to_string(&path)
+                    .map_err(|e| SecretError::InvalidMasterKey(format!("cannot read {}: {}", path, e)))?
+            }
+        };
+        Self::new(&decode_key(encoded.trim())?)
+    }
+
+    /// Generates a random master key, base64 encoded
+    pub fn generate_key() -> String {
+        BASE64.encode(Aes256Gcm::generate_key(OsRng))
+    }
+
+    pub fn encrypt(&self, name: &str, value: &str) -> Result<EncryptedSecret, SecretError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: value.as_bytes(), aad: name.as_bytes() })
+            .map_err(|_| SecretError::Encryption)?;
+        Ok(EncryptedSecret {
+            nonce: BASE64.encode(nonce),
+            ciphertext: BASE64.encode(ciphertext),
+        })
+    }
+
+    pub fn decrypt(&self, name: &str, secret: &EncryptedSecret) -> Result<String, SecretError> {
+        let nonce = BASE64.decode(&secret.nonce).map_err(|_| SecretError::Decryption)?;
+        if nonce.len() != 12 {
+            return Err(SecretError::Decryption);
+        }
+        let ciphertext = BASE64.decode(&secret.ciphertext).map_err(|_| SecretError::Decryption)?;
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad: name.as_bytes() })
+            .map_err(|_| SecretError::Decryption)?;
+        String::from_utf8(plaintext).map_err(|_| SecretError::Decryption)
+    }
+}
+
+fn decode_key(encoded: &str) -> Result<Vec<u8>, SecretError> {
+    if encoded.len() == MASTER_KEY_LEN * 2 {
+        if let Ok(key) = hex::decode(encoded) {
+            return Ok(key);
+        }
+    }
+    BASE64
+        .decode(encoded)
+        .map_err(|_| SecretError::InvalidMasterKey("expected base64 or hex".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cipher() -> SecretCipher {
+        SecretCipher::new(&[7u8; MASTER_KEY_LEN]).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip_with_fresh_nonces() {
+        let cipher = cipher();
+        let first = cipher.encrypt("openai", "sk-123").unwrap();
+        let second = cipher.encrypt("openai", "sk-123").unwrap();
+        assert_ne!(first, second);
+        assert!(!first.ciphertext.contains("sk-123"));
+        assert_eq!(cipher.decrypt("openai", &first).unwrap(), "sk-123");
+    }
+
+    #[test]
+    fn test_decrypt_rejects_other_names_and_keys() {
+        let encrypted = cipher().encrypt("openai", "sk-123").unwrap();
+        assert!(matches!(cipher().decrypt("hubspot", &encrypted), Err(SecretError::Decryption)));
+
+        let other = SecretCipher::new(&[8u8; MASTER_KEY_LEN]).unwrap();
+        assert!(matches!(other.decrypt("openai", &encrypted), Err(SecretError::Decryption)));
+    }
+
+    #[test]
+    fn test_master_key_encodings() {
+        let key = [1u8; MASTER_KEY_LEN];
+        assert_eq!(decode_key(&hex::encode(key)).unwrap(), key);
+        assert_eq!(decode_key(&BASE64.encode(key)).unwrap(), key);
+        assert!(SecretCipher::new(&decode_key(&BASE64.encode([1u8; 16])).unwrap()).is_err());
+        assert!(decode_key("not a key!").is_err());
+    }
+}
@@ -6,6 +6,9 @@ pub struct User {
     pub id: String,
     pub email: String,
     pub password_hash: String,
+    /// Groups reported by the identity provider at the last single sign-on
+    #[serde(default)]
+    pub groups: Vec<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -17,6 +20,7 @@ impl User {
             id: Uuid::new_v4().to_string(),
             email,
             password_hash,
+            groups: Vec::new(),
             created_at: now,
             updated_at: now,
         }
@@ -29,5 +33,11 @@ pub struct Claims {
     pub email: String,
     pub exp: i64, // expiration time
     pub iat: i64, // issued at
+    /// Token id, used to revoke single tokens; empty in tokens issued before ids existed
+    #[serde(default)]
+    pub jti: String,
+    /// Identity provider groups of the user when the token was issued
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
 }
 
@@ -0,0 +1,221 @@
+use axum::http::{HeaderMap, HeaderName};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use subtle::ConstantTimeEq;
+use thiserror::Error;
+
+pub const DEFAULT_SIGNATURE_HEADER: &str = "X-Flowmason-Signature";
+pub const DEFAULT_TIMESTAMP_HEADER: &str = "X-Flowmason-Timestamp";
+pub const DEFAULT_TOKEN_HEADER: &str = "X-Flowmason-Token";
+/// How far a signed timestamp may be from the server's clock by default
+pub const DEFAULT_TIMESTAMP_TOLERANCE_SECS: u64 = 300;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum WebhookAuthError {
+    #[error("Missing {0} header")]
+    MissingHeader(String),
+
+    #[error("Malformed {0} header")]
+    MalformedHeader(String),
+
+    #[error("Timestamp outside the allowed tolerance")]
+    StaleTimestamp,
+
+    #[error("Signature or credentials do not match")]
+    Mismatch,
+}
+
+/// How callers of a flow's webhook authenticate; the secret is stored apart
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum WebhookAuth {
+    /// HMAC-SHA256 of `<timestamp>.<body>` keyed with the secret, hex encoded
+    /// and optionally prefixed with `sha256=`
+    Hmac {
+        #[serde(default = "default_signature_header")]
+        signature_header: String,
+        /// Header carrying the Unix timestamp the signature covers
+        #[serde(default = "default_timestamp_header")]
+        timestamp_header: String,
+        /// Requests signed longer ago (or further ahead) are rejected as replays
+        #[serde(default = "default_tolerance_secs")]
+        tolerance_secs: u64,
+    },
+    /// The secret sent as is; as `Bearer <secret>` in the `Authorization` header
+    Token {
+        #[serde(default = "default_token_header")]
+        header: String,
+    },
+    /// HTTP basic authentication with the secret as password
+    Basic { username: String },
+}
+
+fn default_signature_header() -> String {
+    DEFAULT_SIGNATURE_HEADER.to_string()
+}
+
+fn default_timestamp_header() -> String {
+    DEFAULT_TIMESTAMP_HEADER.to_string()
+}
+
+fn default_tolerance_secs() -> u64 {
+    DEFAULT_TIMESTAMP_TOLERANCE_SECS
+}
+
+fn default_token_header() -> String {
+    DEFAULT_TOKEN_HEADER.to_string()
+}
+
+impl WebhookAuth {
+    /// Checks header names and limits
+    pub fn validate(&self) -> Result<(), String> {
+        let headers: Vec<&str> = match self {
+            Self::Hmac { signature_header, timestamp_header, tolerance_secs } => {
+                if *tolerance_secs == 0 {
+                    return Err("tolerance_secs must be positive".to_string());
+                }
+                vec![signature_header, timestamp_header]
+            }
+            Self::Token { header } => vec![header],
+            Self::Basic { username } => {
+                if username.is_empty() || username.contains(':') {
+                    return Err("username must be non-empty and must not contain ':'".to_string());
+                }
+                Vec::new()
+            }
+        };
+        for header in headers {
+            HeaderName::from_bytes(header.as_bytes()).map_err(|_| format!("Invalid header name: {}", header))?;
+        }
+        Ok(())
+    }
+
+    /// Verifies a request against any of `secrets`, which lets a rotated
+    /// secret stay valid for a grace period
+    pub fn verify(&self, headers: &HeaderMap, body: &[u8], secrets: &[String], now: i64) -> Result<(), WebhookAuthError> {
+        match self {
+            Self::Hmac { signature_header, timestamp_header, tolerance_secs } => {
+                let timestamp = header_value(headers, timestamp_header)?;
+                let signed_at: i64 = timestamp.parse()
+                    .map_err(|_| WebhookAuthError::MalformedHeader(timestamp_header.clone()))?;
+                if now.abs_diff(signed_at) > *tolerance_secs {
+                    return Err(WebhookAuthError::StaleTimestamp);
+                }
+
+                let signature = header_value(headers, signature_header)?;
+                let signature = hex::decode(signature.strip_prefix("sha256=").unwrap_or(signature))
+                    .map_err(|_| WebhookAuthError::MalformedHeader(signature_header.clone()))?;
+                let matches = secrets.iter().any(|secret| {
+                    signature_mac(secret, timestamp, body).verify_slice(&signature).is_ok()
+                });
+                matches.then_some(()).ok_or(WebhookAuthError::Mismatch)
+            }
+            Self::Token { header } => {
+                let value = header_value(headers, header)?;
+                let token = if header.eq_ignore_ascii_case("authorization") {
+                    value.strip_prefix("Bearer ").ok_or_else(|| WebhookAuthError::MalformedHeader(header.clone()))?
+                } else {
+                    value
+                };
+                matches_any(token.as_bytes(), secrets)
+            }
+            Self::Basic { username } => {
+                let value = header_value(headers, "authorization")?;
+                let malformed = || WebhookAuthError::MalformedHeader("authorization".to_string());
+                let decoded = value.strip_prefix("Basic ")
+                    .and_then(|encoded| BASE64.decode(encoded).ok())
+                    .and_then(|decoded| String::from_utf8(decoded).ok())
+                    .ok_or_else(malformed)?;
+                let (given_user, password) = decoded.split_once(':').ok_or_else(malformed)?;
+                let user_matches: bool = given_user.as_bytes().ct_eq(username.as_bytes()).into();
+                let password_matches = matches_any(password.as_bytes(), secrets);
+                if user_matches { password_matches } else { Err(WebhookAuthError::Mismatch) }
+            }
+        }
+    }
+}
+
+/// Signature of a request body, as the `Hmac` mode expects it
+pub fn sign_webhook(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    hex::encode(signature_mac(secret, &timestamp.to_string(), body).finalize().into_bytes())
+}
+
+/// Generates a random webhook secret
+pub fn generate_webhook_secret() -> String {
+    format!("whsec_{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+fn signature_mac(secret: &str, timestamp: &str, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    mac
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, WebhookAuthError> {
+    headers.get(name)
+        .ok_or_else(|| WebhookAuthError::MissingHeader(name.to_string()))?
+        .to_str()
+        .map_err(|_| WebhookAuthError::MalformedHeader(name.to_string()))
+}
+
+fn matches_any(given: &[u8], secrets: &[String]) -> Result<(), WebhookAuthError> {
+    // Compare against every secret so timing does not reveal which one matched
+    let matched = secrets.iter().fold(false, |matched, secret| matched | bool::from(given.ct_eq(secret.as_bytes())));
+    matched.then_some(()).ok_or(WebhookAuthError::Mismatch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&str, &str)]) -> HeaderMap {
+        pairs.iter()
+            .map(|(name, value)| (HeaderName::from_bytes(name.as_bytes()).unwrap(), value.parse().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_hmac_signatures() {
+        let auth: WebhookAuth = serde_json::from_value(serde_json::json!({"mode": "hmac", "tolerance_secs": 60})).unwrap();
+        let secrets = vec!["old".to_string(), "new".to_string()];
+        let body = br#"{"deal": 1}"#;
+        let now = 1_700_000_000;
+        let signature = format!("sha256={}", sign_webhook("new", now - 30, body));
+        let request = headers(&[(DEFAULT_TIMESTAMP_HEADER, &(now - 30).to_string()), (DEFAULT_SIGNATURE_HEADER, &signature)]);
+
+        assert_eq!(auth.verify(&request, body, &secrets, now), Ok(()));
+        // Previous secrets stay valid while listed
+        assert_eq!(auth.verify(&request, body, &["new".to_string()], now), Ok(()));
+        assert_eq!(auth.verify(&request, body, &["old".to_string()], now), Err(WebhookAuthError::Mismatch));
+        assert_eq!(auth.verify(&request, b"{}", &secrets, now), Err(WebhookAuthError::Mismatch));
+        // Replays outside the tolerance are rejected even with a valid signature
+        assert_eq!(auth.verify(&request, body, &secrets, now + 60), Err(WebhookAuthError::StaleTimestamp));
+        assert_eq!(
+            auth.verify(&headers(&[(DEFAULT_SIGNATURE_HEADER, &signature)]), body, &secrets, now),
+            Err(WebhookAuthError::MissingHeader(DEFAULT_TIMESTAMP_HEADER.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_token_and_basic_modes() {
+        let secrets = vec!["s3cret".to_string()];
+        let token = WebhookAuth::Token { header: "Authorization".to_string() };
+        assert_eq!(token.verify(&headers(&[("authorization", "Bearer s3cret")]), b"", &secrets, 0), Ok(()));
+        assert_eq!(token.verify(&headers(&[("authorization", "Bearer nope")]), b"", &secrets, 0), Err(WebhookAuthError::Mismatch));
+        assert!(token.verify(&headers(&[("authorization", "s3cret")]), b"", &secrets, 0).is_err());
+
+        let basic = WebhookAuth::Basic { username: "hubspot".to_string() };
+        let credentials = format!("Basic {}", BASE64.encode("hubspot:s3cret"));
+        assert_eq!(basic.verify(&headers(&[("authorization", &credentials)]), b"", &secrets, 0), Ok(()));
+        let wrong_user = format!("Basic {}", BASE64.encode("other:s3cret"));
+        assert_eq!(basic.verify(&headers(&[("authorization", &wrong_user)]), b"", &secrets, 0), Err(WebhookAuthError::Mismatch));
+
+        assert!(WebhookAuth::Token { header: "bad header".to_string() }.validate().is_err());
+        assert!(WebhookAuth::Basic { username: "a:b".to_string() }.validate().is_err());
+        assert!(basic.validate().is_ok());
+    }
+}
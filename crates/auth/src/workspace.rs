@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Header naming the workspace a request acts on
+pub const WORKSPACE_HEADER: &str = "x-workspace-id";
+
+/// Role of a user in a workspace
+///
+/// Roles are ordered: each role can do everything the roles below it can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkspaceRole {
+    /// Reads flows, executions and usage
+    Viewer,
+    /// Also runs flows and replays executions
+    Runner,
+    /// Also creates and changes flows, templates, schedules, secrets and connections
+    Editor,
+    /// Also manages members
+    Admin,
+    /// Also grants ownership and deletes the workspace
+    Owner,
+}
+
+impl WorkspaceRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkspaceRole::Viewer => "viewer",
+            WorkspaceRole::Runner => "runner",
+            WorkspaceRole::Editor => "editor",
+            WorkspaceRole::Admin => "admin",
+            WorkspaceRole::Owner => "owner",
+        }
+    }
+
+    /// Whether the role includes the permissions of `required`
+    pub fn allows(&self, required: WorkspaceRole) -> bool {
+        *self >= required
+    }
+}
+
+impl fmt::Display for WorkspaceRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WorkspaceRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(WorkspaceRole::Viewer),
+            "runner" => Ok(WorkspaceRole::Runner),
+            "editor" => Ok(WorkspaceRole::Editor),
+            "admin" => Ok(WorkspaceRole::Admin),
+            "owner" => Ok(WorkspaceRole::Owner),
+            other => Err(format!("Unknown workspace role: {}", other)),
+        }
+    }
+}
+
+/// The workspace a request acts on and the caller's role in it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkspaceMembership {
+    pub workspace_id: String,
+    pub role: WorkspaceRole,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roles_are_ordered() {
+        assert!(WorkspaceRole::Owner.allows(WorkspaceRole::Admin));
+        assert!(WorkspaceRole::Editor.allows(WorkspaceRole::Runner));
+        assert!(WorkspaceRole::Runner.allows(WorkspaceRole::Runner));
+        assert!(!WorkspaceRole::Viewer.allows(WorkspaceRole::Runner));
+
+        assert_eq!("admin".parse::<WorkspaceRole>(), Ok(WorkspaceRole::Admin));
+        assert!("root".parse::<WorkspaceRole>().is_err());
+        assert_eq!(serde_json::to_string(&WorkspaceRole::Runner).unwrap(), "\"runner\"");
+    }
+}
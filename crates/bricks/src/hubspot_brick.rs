@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use flowmason_core::{Brick, BrickError, BrickType};
+use flowmason_core::{Brick, BrickError, BrickType, ConnectionSpec};
 use serde_json::{json, Value};
 use crate::http_client::{get_client, execute_with_default_retry};
 
@@ -53,6 +53,33 @@ impl Brick for HubSpotBrick {
             _ => Err(BrickError::ConfigError(format!("Unknown operation: {}", operation))),
         }
     }
+
+    fn connection_spec(&self) -> Option<ConnectionSpec> {
+        Some(ConnectionSpec {
+            connection_type: "hubspot",
+            base_url_field: None,
+            credential_fields: &["api_key"],
+        })
+    }
+
+    async fn test_connection(&self, config: Value) -> Result<(), BrickError> {
+        let api_key = config
+            .get("api_key")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| BrickError::ConfigError("api_key is required".to_string()))?;
+
+        let response = get_client()
+            .get("https://api.hubapi.com/crm/v3/objects/contacts")
+            .query(&[("hapikey", api_key), ("limit", "1")])
+            .send()
+            .await
+            .map_err(|e| BrickError::NetworkError(format!("Failed to connect to HubSpot API: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(BrickError::ExecutionError(format!("HubSpot API error ({})", response.status())));
+        }
+        Ok(())
+    }
 }
 
 impl HubSpotBrick {
@@ -7,6 +7,8 @@ pub mod notion_brick;
 pub mod n8n_brick;
 pub mod mapper_bricks;
 pub mod rules_brick;
+pub mod respond_brick;
+pub mod registry;
 
 pub use openai_brick::OpenAiBrick;
 pub use nvidia_brick::NvidiaBrick;
@@ -16,4 +18,5 @@ pub use notion_brick::NotionBrick;
 pub use n8n_brick::N8nBrick;
 pub use mapper_bricks::{FieldMappingBrick, CombineTextBrick, ConditionalBrick};
 pub use rules_brick::RulesEngineBrick;
-
+pub use respond_brick::RespondBrick;
+pub use registry::{default_registry, register_builtin_bricks};
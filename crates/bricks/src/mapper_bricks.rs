@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use flowmason_core::{Brick, BrickError, BrickType, Mapper, MappingRule, MappingDirection, MergeStrategy, SplitStrategy};
+use flowmason_core::{Brick, BrickError, BrickType, JsonPath, Mapper, MappingError, MappingRule, MappingDirection, MappingMode, MergeStrategy, SplitStrategy, TransformType};
 use serde_json::{json, Value};
 
 pub struct FieldMappingBrick;
@@ -25,24 +25,30 @@ impl Brick for FieldMappingBrick {
                         "properties": {
                             "source_path": {
                                 "type": "string",
+                                "minLength": 1,
                                 "description": "Single source path (legacy format, e.g., 'user.name')"
                             },
                             "target_path": {
                                 "type": "string",
+                                "minLength": 1,
                                 "description": "Single target path (legacy format, e.g., 'customer_name')"
                             },
                             "source_paths": {
                                 "type": "array",
                                 "items": {
-                                    "type": "string"
+                                    "type": "string",
+                                    "minLength": 1
                                 },
+                                "minItems": 1,
                                 "description": "Multiple source paths for multi-directional mapping"
                             },
                             "target_paths": {
                                 "type": "array",
                                 "items": {
-                                    "type": "string"
+                                    "type": "string",
+                                    "minLength": 1
                                 },
+                                "minItems": 1,
                                 "description": "Multiple target paths for multi-directional mapping"
                             },
                             "direction": {
@@ -60,9 +66,24 @@ impl Brick for FieldMappingBrick {
                                 "type": "string",
                                 "enum": ["copy", "extract", "transform_each"],
                                 "description": "Strategy for splitting one source to multiple targets"
+                            },
+                            "transform": {
+                                "type": ["array", "object", "string"],
+                                "description": "Transform, or ordered list of transforms, applied to the mapped value (e.g., [\"trim\", {\"number_multiply\": {\"operand\": 100}}])"
                             }
-                        }
+                        },
+                        "allOf": [
+                            { "anyOf": [{ "required": ["source_path"] }, { "required": ["source_paths"] }] },
+                            { "anyOf": [{ "required": ["target_path"] }, { "required": ["target_paths"] }] }
+                        ],
+                        "additionalProperties": false
                     }
+                },
+                "mode": {
+                    "type": "string",
+                    "enum": ["forward", "reverse"],
+                    "default": "forward",
+                    "description": "Run the mappings forward (source to target) or in reverse (target back to source, e.g. for write-back flows); reverse runs only backward and bidirectional mappings"
                 }
             },
             "required": ["mappings"]
@@ -75,6 +96,12 @@ impl Brick for FieldMappingBrick {
             .and_then(|v| v.as_array())
             .ok_or_else(|| BrickError::ConfigError("mappings array is required".to_string()))?;
 
+        let mode = match config.get("mode").and_then(|v| v.as_str()) {
+            None | Some("forward") => MappingMode::Forward,
+            Some("reverse") => MappingMode::Reverse,
+            Some(other) => return Err(BrickError::ConfigError(format!("Unknown mapping mode: {}", other))),
+        };
+
         let mut rules = Vec::new();
         for mapping in mappings_array {
             // Support both legacy (single path) and new (multi-path) formats
@@ -129,23 +156,56 @@ impl Brick for FieldMappingBrick {
                     _ => SplitStrategy::Copy,
                 });
 
-            rules.push(MappingRule {
+            let transform = TransformType::parse_pipeline(mapping.get("transform").cloned().unwrap_or(Value::Null))
+                .map_err(|e| BrickError::ConfigError(format!("Invalid transform: {}", e)))?;
+
+            // Compile the paths up front so syntax errors surface as config errors
+            for path in source_paths.iter().chain(target_paths.iter()) {
+                compile_path(path)?;
+            }
+
+            let rule = MappingRule {
                 source_path: source_paths.first().cloned().unwrap_or_default(),
                 target_path: target_paths.first().cloned().unwrap_or_default(),
                 source_paths,
                 target_paths,
                 direction,
-                transform: None,
+                transform,
                 merge_strategy,
                 split_strategy,
-            });
+            };
+            if mode == MappingMode::Reverse && rule.direction.applies_to(mode) {
+                Mapper::ensure_reversible(&rule).map_err(|e| BrickError::ConfigError(e.to_string()))?;
+            }
+            rules.push(rule);
         }
 
-        Mapper::apply_mappings(&input, &rules)
-            .map_err(|e| BrickError::ExecutionError(format!("Mapping error: {}", e)))
+        let result = match mode {
+            MappingMode::Forward => Mapper::apply_mappings(&input, &rules),
+            MappingMode::Reverse => Mapper::apply_mappings_reverse(&input, &rules),
+        };
+        result.map_err(|e| BrickError::ExecutionError(format!("Mapping error: {}", e)))
     }
 }
 
+/// Compiles a configured path, reporting syntax errors as config errors
+fn compile_path(path: &str) -> Result<std::sync::Arc<JsonPath>, BrickError> {
+    JsonPath::cached(path).map_err(|e| BrickError::ConfigError(e.to_string()))
+}
+
+/// Sets a brick's result at its configured output path
+fn set_output(output: &mut Value, output_field: &str, value: Value) -> Result<(), BrickError> {
+    compile_path(output_field)?;
+    Mapper::set_value_at_path(output, output_field, value).map_err(|e| match e {
+        MappingError::InvalidPath(msg) => BrickError::ConfigError(msg),
+        e => BrickError::ExecutionError(format!("Failed to set {}: {}", output_field, e)),
+    })
+}
+
+fn text_of(value: &Value) -> String {
+    value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string())
+}
+
 pub struct CombineTextBrick;
 
 #[async_trait]
@@ -167,7 +227,7 @@ impl Brick for CombineTextBrick {
                     "items": {
                         "type": "string"
                     },
-                    "description": "Array of field paths to combine"
+                    "description": "Array of field paths to combine; paths selecting several values (e.g. 'items[*].name') contribute each of them"
                 },
                 "separator": {
                     "type": "string",
@@ -176,7 +236,7 @@ impl Brick for CombineTextBrick {
                 },
                 "output_field": {
                     "type": "string",
-                    "description": "Field name for the combined result",
+                    "description": "Field path for the combined result",
                     "default": "combined_text"
                 }
             },
@@ -206,20 +266,19 @@ impl Brick for CombineTextBrick {
                 .as_str()
                 .ok_or_else(|| BrickError::ConfigError("Field path must be a string".to_string()))?;
             
-            let value = Mapper::get_value_at_path(&input, path)
-                .map_err(|e| BrickError::InvalidInput(format!("Failed to get field {}: {}", path, e)))?;
-            
-            let str_value = value.as_str()
-                .unwrap_or(&value.to_string())
-                .to_string();
-            values.push(str_value);
+            let compiled = compile_path(path)?;
+            if compiled.is_singular() {
+                let value = compiled.get(&input)
+                    .ok_or_else(|| BrickError::InvalidInput(format!("Failed to get field {}: Path not found", path)))?;
+                values.push(text_of(&value));
+            } else {
+                values.extend(compiled.query(&input).into_iter().map(text_of));
+            }
         }
 
         let combined = values.join(separator);
         let mut output = input.clone();
-        if let Some(obj) = output.as_object_mut() {
-            obj.insert(output_field.to_string(), Value::String(combined));
-        }
+        set_output(&mut output, output_field, Value::String(combined))?;
 
         Ok(output)
     }
@@ -243,7 +302,7 @@ impl Brick for ConditionalBrick {
             "properties": {
                 "condition_field": {
                     "type": "string",
-                    "description": "Field path to evaluate condition on"
+                    "description": "Field path to evaluate condition on; with a wildcard or filter the condition is evaluated for each selected value"
                 },
                 "condition": {
                     "type": "string",
@@ -257,7 +316,7 @@ impl Brick for ConditionalBrick {
                 },
                 "output_field": {
                     "type": "string",
-                    "description": "Field name for the result",
+                    "description": "Field path for the result; a wildcard path (e.g. 'items[*].status') receives per-element results",
                     "default": "status"
                 }
             },
@@ -291,17 +350,24 @@ impl Brick for ConditionalBrick {
             .and_then(|v| v.as_str())
             .unwrap_or("status");
 
-        let field_value = Mapper::get_value_at_path(&input, condition_field)
-            .map_err(|e| BrickError::InvalidInput(format!("Failed to get field {}: {}", condition_field, e)))?;
+        let compiled = compile_path(condition_field)?;
+        let select = |field_value: &Value| {
+            Mapper::evaluate_condition(field_value, condition)
+                .map(|met| if met { true_value.clone() } else { false_value.clone() })
+                .map_err(|e| BrickError::ExecutionError(format!("Condition evaluation error: {}", e)))
+        };
+
+        let result_value = if compiled.is_singular() {
+            let field_value = compiled.get(&input)
+                .ok_or_else(|| BrickError::InvalidInput(format!("Failed to get field {}: Path not found", condition_field)))?;
+            select(&field_value)?
+        } else {
+            // One result per selected value, in order
+            Value::Array(compiled.query(&input).into_iter().map(select).collect::<Result<_, _>>()?)
+        };
 
-        let condition_met = Mapper::evaluate_condition(&field_value, condition)
-            .map_err(|e| BrickError::ExecutionError(format!("Condition evaluation error: {}", e)))?;
-
-        let result_value = if condition_met { true_value } else { false_value };
         let mut output = input.clone();
-        if let Some(obj) = output.as_object_mut() {
-            obj.insert(output_field.to_string(), result_value);
-        }
+        set_output(&mut output, output_field, result_value)?;
 
         Ok(output)
     }
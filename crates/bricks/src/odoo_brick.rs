@@ -1,5 +1,5 @@
 use async_trait::async_trait;
-use flowmason_core::{Brick, BrickError, BrickType};
+use flowmason_core::{Brick, BrickError, BrickType, ConnectionSpec};
 use serde_json::{json, Value};
 use crate::http_client::{get_client, execute_with_default_retry};
 
@@ -79,6 +79,26 @@ impl Brick for OdooBrick {
             _ => Err(BrickError::ConfigError(format!("Unknown operation: {}", operation))),
         }
     }
+
+    fn connection_spec(&self) -> Option<ConnectionSpec> {
+        Some(ConnectionSpec {
+            connection_type: "odoo",
+            base_url_field: Some("url"),
+            credential_fields: &["database", "username", "password"],
+        })
+    }
+
+    async fn test_connection(&self, config: Value) -> Result<(), BrickError> {
+        let field = |name: &str| {
+            config
+                .get(name)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| BrickError::ConfigError(format!("{} is required", name)))
+        };
+        self.authenticate(field("url")?, field("database")?, field("username")?, field("password")?)
+            .await
+            .map(|_| ())
+    }
 }
 
 impl OdooBrick {
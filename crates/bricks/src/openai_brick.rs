@@ -30,15 +30,18 @@ impl Brick for OpenAiBrick {
                 },
                 "prompt_template": {
                     "type": "string",
-                    "description": "Prompt template with {{field}} placeholders"
+                    "description": "Prompt template; {{ field }} expressions are rendered from the input payload, flow variables ({{ vars.name }}) and execution metadata"
                 },
                 "temperature": {
                     "type": "number",
+                    "minimum": 0,
+                    "maximum": 2,
                     "description": "Temperature for generation",
                     "default": 0.7
                 },
                 "max_tokens": {
-                    "type": "number",
+                    "type": "integer",
+                    "minimum": 1,
                     "description": "Maximum tokens to generate",
                     "default": 1000
                 }
@@ -73,8 +76,8 @@ impl Brick for OpenAiBrick {
             .and_then(|v| v.as_u64())
             .unwrap_or(1000);
 
-        // Replace placeholders in prompt template
-        let prompt = replace_placeholders(prompt_template, &input);
+        // Placeholders are rendered by the flow runner before execution
+        let prompt = prompt_template;
 
         // Call OpenAI API using shared HTTP client with retry logic
         let client = get_client();
@@ -136,23 +139,3 @@ impl Brick for OpenAiBrick {
         }))
     }
 }
-
-fn replace_placeholders(template: &str, input: &Value) -> String {
-    let mut result = template.to_string();
-    
-    if let Some(obj) = input.as_object() {
-        for (key, value) in obj {
-            let placeholder = format!("{{{{{}}}}}", key);
-            let replacement = match value {
-                Value::String(s) => s.clone(),
-                Value::Number(n) => n.to_string(),
-                Value::Bool(b) => b.to_string(),
-                _ => value.to_string(),
-            };
-            result = result.replace(&placeholder, &replacement);
-        }
-    }
-    
-    result
-}
-
@@ -0,0 +1,44 @@
+use flowmason_core::{BrickRegistry, BrickType};
+
+use crate::{
+    CombineTextBrick, ConditionalBrick, FieldMappingBrick, HubSpotBrick, N8nBrick, NotionBrick,
+    NvidiaBrick, OdooBrick, OpenAiBrick, RespondBrick, RulesEngineBrick,
+};
+
+/// Registers all bricks shipped with FlowMason
+pub fn register_builtin_bricks(registry: &mut BrickRegistry) {
+    registry.register_type(BrickType::OpenAi, || Box::new(OpenAiBrick));
+    registry.register_type(BrickType::Nvidia, || Box::new(NvidiaBrick));
+    registry.register_type(BrickType::HubSpot, || Box::new(HubSpotBrick));
+    registry.register_type(BrickType::Notion, || Box::new(NotionBrick));
+    registry.register_type(BrickType::Odoo, || Box::new(OdooBrick));
+    registry.register_type(BrickType::N8n, || Box::new(N8nBrick));
+    registry.register_type(BrickType::FieldMapping, || Box::new(FieldMappingBrick));
+    registry.register_type(BrickType::CombineText, || Box::new(CombineTextBrick));
+    registry.register_type(BrickType::Conditional, || Box::new(ConditionalBrick));
+    registry.register_type(BrickType::RulesEngine, || Box::new(RulesEngineBrick));
+    registry.register_type(BrickType::Respond, || Box::new(RespondBrick));
+}
+
+/// Creates a registry containing the built-in bricks
+pub fn default_registry() -> BrickRegistry {
+    let mut registry = BrickRegistry::new();
+    register_builtin_bricks(&mut registry);
+    registry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_config_schemas_are_supported() {
+        let registry = default_registry();
+        for id in registry.ids() {
+            let brick = registry.create(&BrickType::from_id(id)).unwrap();
+            if let Err(e) = flowmason_core::schema::check_schema(&brick.config_schema()) {
+                panic!("{}: {}", id, e);
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use async_trait::async_trait;
+use flowmason_core::{Brick, BrickError, BrickType, FlowResponse, ResponseBody};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// Sets the HTTP response returned to the caller of a flow's endpoint
+///
+/// Object payloads pass through unchanged (others are wrapped as `value`), so
+/// bricks after it keep working on the payload once the response is sent.
+pub struct RespondBrick;
+
+#[async_trait]
+impl Brick for RespondBrick {
+    fn name(&self) -> &'static str {
+        "respond"
+    }
+
+    fn brick_type(&self) -> BrickType {
+        BrickType::Respond
+    }
+
+    fn config_schema(&self) -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "status": {
+                    "type": "integer",
+                    "minimum": 100,
+                    "maximum": 599,
+                    "description": "HTTP status code; defaults to 200, or 302 for redirects"
+                },
+                "headers": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Response headers"
+                },
+                "body_type": {
+                    "type": "string",
+                    "enum": ["json", "text", "redirect"],
+                    "default": "json"
+                },
+                "body": {
+                    "description": "Response body: any value for json (defaults to the payload), a string for text"
+                },
+                "location": {
+                    "type": "string",
+                    "description": "Target of a redirect"
+                }
+            }
+        })
+    }
+
+    async fn execute(&self, input: Value, config: Value) -> Result<Value, BrickError> {
+        let body_type = config.get("body_type").and_then(|v| v.as_str()).unwrap_or("json");
+        let body = match body_type {
+            "json" => ResponseBody::Json(config.get("body").cloned().unwrap_or_else(|| input.clone())),
+            "text" => {
+                let text = match config.get("body") {
+                    Some(Value::String(text)) => text.clone(),
+                    Some(value) => value.to_string(),
+                    None => String::new(),
+                };
+                ResponseBody::Text(text)
+            }
+            "redirect" => {
+                let location = config
+                    .get("location")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| BrickError::ConfigError("location is required for redirects".to_string()))?;
+                ResponseBody::Redirect(location.to_string())
+            }
+            other => return Err(BrickError::ConfigError(format!("Unknown body_type '{}'", other))),
+        };
+
+        let default_status = if matches!(body, ResponseBody::Redirect(_)) { 302 } else { 200 };
+        let status = match config.get("status") {
+            Some(status) => status
+                .as_u64()
+                .and_then(|s| u16::try_from(s).ok())
+                .ok_or_else(|| BrickError::ConfigError("status must be an integer".to_string()))?,
+            None => default_status,
+        };
+
+        let mut headers = BTreeMap::new();
+        if let Some(configured) = config.get("headers") {
+            let configured = configured
+                .as_object()
+                .ok_or_else(|| BrickError::ConfigError("headers must be an object".to_string()))?;
+            for (name, value) in configured {
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    value => value.to_string(),
+                };
+                headers.insert(name.clone(), value);
+            }
+        }
+
+        let response = FlowResponse { status, headers, body };
+        response.validate().map_err(BrickError::ConfigError)?;
+        let response = serde_json::to_value(&response)
+            .map_err(|e| BrickError::ExecutionError(format!("Failed to encode response: {}", e)))?;
+
+        let mut output = input;
+        match output.as_object_mut() {
+            Some(obj) => {
+                obj.insert("_respond".to_string(), response);
+            }
+            None => output = json!({ "value": output, "_respond": response }),
+        }
+        Ok(output)
+    }
+}
@@ -20,31 +20,120 @@ impl Brick for RulesEngineBrick {
             "properties": {
                 "rules": {
                     "type": "array",
-                    "items": {
-                        "type": "object",
-                        "properties": {
-                            "name": {
-                                "type": "string",
-                                "description": "Rule name"
-                            },
-                            "condition": {
-                                "description": "Rule condition"
-                            },
-                            "actions": {
-                                "type": "array",
-                                "description": "Actions to execute if condition matches"
-                            }
-                        },
-                        "required": ["name", "condition", "actions"]
-                    },
+                    "items": { "$ref": "#/$defs/rule" },
                     "description": "Array of rules to evaluate"
                 },
                 "default_actions": {
                     "type": "array",
+                    "items": { "$ref": "#/$defs/action" },
                     "description": "Actions to execute if no rules match"
                 }
             },
-            "required": ["rules"]
+            "required": ["rules"],
+            "$defs": {
+                "rule": {
+                    "type": "object",
+                    "properties": {
+                        "name": {
+                            "type": "string",
+                            "description": "Rule name"
+                        },
+                        "condition": {
+                            "$ref": "#/$defs/condition",
+                            "description": "Rule condition"
+                        },
+                        "actions": {
+                            "type": "array",
+                            "items": { "$ref": "#/$defs/action" },
+                            "description": "Actions to execute if condition matches"
+                        }
+                    },
+                    "required": ["name", "condition", "actions"]
+                },
+                "condition": {
+                    "type": "object",
+                    "properties": {
+                        "type": { "enum": ["field", "and", "or", "not"] }
+                    },
+                    "required": ["type"],
+                    "allOf": [
+                        {
+                            "if": { "properties": { "type": { "const": "field" } } },
+                            "then": {
+                                "properties": {
+                                    "path": { "type": "string", "minLength": 1 },
+                                    "operator": {
+                                        "enum": [
+                                            "equals", "not_equals", "greater_than", "less_than",
+                                            "greater_than_or_equal", "less_than_or_equal", "contains",
+                                            "starts_with", "ends_with", "regex", "in", "not_in",
+                                            "is_null", "is_not_null"
+                                        ]
+                                    },
+                                    "value": { "description": "Value the field is compared with" }
+                                },
+                                "required": ["path", "operator"]
+                            }
+                        },
+                        {
+                            "if": { "properties": { "type": { "enum": ["and", "or"] } } },
+                            "then": {
+                                "properties": {
+                                    "conditions": { "type": "array", "items": { "$ref": "#/$defs/condition" } }
+                                },
+                                "required": ["conditions"]
+                            }
+                        },
+                        {
+                            "if": { "properties": { "type": { "const": "not" } } },
+                            "then": {
+                                "properties": { "condition": { "$ref": "#/$defs/condition" } },
+                                "required": ["condition"]
+                            }
+                        }
+                    ]
+                },
+                "action": {
+                    "type": "object",
+                    "properties": {
+                        "type": { "enum": ["set_field", "transform", "branch", "skip_bricks"] }
+                    },
+                    "required": ["type"],
+                    "allOf": [
+                        {
+                            "if": { "properties": { "type": { "const": "set_field" } } },
+                            "then": {
+                                "properties": { "path": { "type": "string", "minLength": 1 } },
+                                "required": ["path"]
+                            }
+                        },
+                        {
+                            "if": { "properties": { "type": { "const": "transform" } } },
+                            "then": {
+                                "properties": { "transform": { "type": ["object", "string"] } },
+                                "required": ["transform"]
+                            }
+                        },
+                        {
+                            "if": { "properties": { "type": { "const": "branch" } } },
+                            "then": {
+                                "properties": {
+                                    "flow_id": { "type": "string", "minLength": 1 },
+                                    "mode": { "enum": ["inline", "replace"], "default": "inline" }
+                                },
+                                "required": ["flow_id"]
+                            }
+                        },
+                        {
+                            "if": { "properties": { "type": { "const": "skip_bricks" } } },
+                            "then": {
+                                "properties": { "count": { "type": "integer", "minimum": 0 } },
+                                "required": ["count"]
+                            }
+                        }
+                    ]
+                }
+            }
         })
     }
 
@@ -74,16 +163,24 @@ impl Brick for RulesEngineBrick {
         let mut output = input.clone();
 
         let mut skip_bricks_count = None;
+        let mut branch = None;
         
         if let Some(rule) = matching_rule {
             // Execute actions from matching rule
             let results = RulesEngine::execute_actions(&rule.actions, &mut output)
                 .map_err(|e| BrickError::ExecutionError(format!("Action execution error: {}", e)))?;
             
-            // Check for SkipBricks action result
+            // Check for SkipBricks and Branch action results
             for result in results {
-                if let flowmason_core::RuleActionResult::SkipBricks { count } = result {
-                    skip_bricks_count = Some(count);
+                match result {
+                    flowmason_core::RuleActionResult::SkipBricks { count } => {
+                        skip_bricks_count = Some(count);
+                    }
+                    flowmason_core::RuleActionResult::Branch { flow_id, mode } => {
+                        // First branch wins; the flow runner starts the sub-flow
+                        branch.get_or_insert(json!({ "flow_id": flow_id, "mode": mode }));
+                    }
+                    _ => {}
                 }
             }
         } else if !default_actions.is_empty() {
@@ -95,10 +192,17 @@ impl Brick for RulesEngineBrick {
             let results = RulesEngine::execute_actions(&actions, &mut output)
                 .map_err(|e| BrickError::ExecutionError(format!("Default action execution error: {}", e)))?;
             
-            // Check for SkipBricks action result
+            // Check for SkipBricks and Branch action results
             for result in results {
-                if let flowmason_core::RuleActionResult::SkipBricks { count } = result {
-                    skip_bricks_count = Some(count);
+                match result {
+                    flowmason_core::RuleActionResult::SkipBricks { count } => {
+                        skip_bricks_count = Some(count);
+                    }
+                    flowmason_core::RuleActionResult::Branch { flow_id, mode } => {
+                        // First branch wins; the flow runner starts the sub-flow
+                        branch.get_or_insert(json!({ "flow_id": flow_id, "mode": mode }));
+                    }
+                    _ => {}
                 }
             }
         }
@@ -114,20 +218,12 @@ impl Brick for RulesEngineBrick {
             if let Some(skip_count) = skip_bricks_count {
                 obj.insert("_skip_bricks".to_string(), json!(skip_count));
             }
-        }
-
-        Ok(output)
-    }
 
-    fn validate_config(&self, config: &Value) -> Result<(), BrickError> {
-        if !config.is_object() {
-            return Err(BrickError::ConfigError("Config must be an object".to_string()));
-        }
-
-        if config.get("rules").and_then(|v| v.as_array()).is_none() {
-            return Err(BrickError::ConfigError("rules array is required".to_string()));
+            if let Some(branch) = branch {
+                obj.insert("_branch".to_string(), branch);
+            }
         }
 
-        Ok(())
+        Ok(output)
     }
 }
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::brick_traits::Brick;
+use crate::flow_runner::FlowError;
+use crate::types::{BrickConfig, BrickType};
+
+/// Creates a fresh brick instance
+pub type BrickFactory = Arc<dyn Fn() -> Box<dyn Brick> + Send + Sync>;
+
+/// Maps brick ids (see `BrickType::id`) to factories
+///
+/// Executors resolve every brick of a flow through the registry, so additional
+/// bricks only need to be registered once at startup.
+#[derive(Clone, Default)]
+pub struct BrickRegistry {
+    factories: HashMap<String, BrickFactory>,
+}
+
+impl BrickRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a factory under a brick id, replacing any previous registration
+    pub fn register<F>(&mut self, id: impl Into<String>, factory: F)
+    where
+        F: Fn() -> Box<dyn Brick> + Send + Sync + 'static,
+    {
+        self.factories.insert(id.into(), Arc::new(factory));
+    }
+
+    /// Registers a factory for a brick type
+    pub fn register_type<F>(&mut self, brick_type: BrickType, factory: F)
+    where
+        F: Fn() -> Box<dyn Brick> + Send + Sync + 'static,
+    {
+        self.register(brick_type.id().to_string(), factory);
+    }
+
+    pub fn contains(&self, brick_type: &BrickType) -> bool {
+        self.factories.contains_key(brick_type.id())
+    }
+
+    /// Returns all registered brick ids, sorted
+    pub fn ids(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self.factories.keys().map(|id| id.as_str()).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Creates a brick instance for a brick type
+    pub fn create(&self, brick_type: &BrickType) -> Result<Box<dyn Brick>, FlowError> {
+        self.factories
+            .get(brick_type.id())
+            .map(|factory| factory())
+            .ok_or_else(|| FlowError::UnknownBrick(brick_type.id().to_string()))
+    }
+
+    /// Creates brick instances for a list of brick configs, in order
+    pub fn create_all(&self, bricks: &[BrickConfig]) -> Result<Vec<Box<dyn Brick>>, FlowError> {
+        bricks.iter().map(|b| self.create(&b.brick_type)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brick_traits::BrickError;
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+
+    struct EchoBrick;
+
+    #[async_trait]
+    impl Brick for EchoBrick {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        fn brick_type(&self) -> BrickType {
+            BrickType::Custom("echo".to_string())
+        }
+
+        fn config_schema(&self) -> Value {
+            json!({})
+        }
+
+        async fn execute(&self, input: Value, _config: Value) -> Result<Value, BrickError> {
+            Ok(input)
+        }
+    }
+
+    #[test]
+    fn test_custom_brick_registration() {
+        let mut registry = BrickRegistry::new();
+        registry.register("echo", || Box::new(EchoBrick));
+
+        let brick_type: BrickType = serde_json::from_value(json!("echo")).unwrap();
+        assert_eq!(brick_type, BrickType::Custom("echo".to_string()));
+        assert!(registry.contains(&brick_type));
+        assert_eq!(registry.create(&brick_type).unwrap().name(), "echo");
+    }
+
+    #[test]
+    fn test_unknown_brick_is_rejected() {
+        let registry = BrickRegistry::new();
+        let result = registry.create(&BrickType::Custom("missing".to_string()));
+        assert!(matches!(result, Err(FlowError::UnknownBrick(id)) if id == "missing"));
+    }
+
+    #[test]
+    fn test_builtin_ids_round_trip() {
+        for id in ["openai", "hubspot", "field_mapping", "rules_engine"] {
+            let brick_type = BrickType::from_id(id);
+            assert!(!brick_type.is_custom());
+            assert_eq!(serde_json::to_value(&brick_type).unwrap(), json!(id));
+        }
+        // Ids written by older releases still resolve to the built-in types
+        assert_eq!(BrickType::from_id("open_ai"), BrickType::OpenAi);
+        assert_eq!(BrickType::from_id("hub_spot"), BrickType::HubSpot);
+    }
+}
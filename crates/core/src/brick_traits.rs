@@ -2,6 +2,9 @@ use async_trait::async_trait;
 use serde_json::Value;
 use thiserror::Error;
 
+use crate::connections::{self, ConnectionSpec};
+use crate::schema::{self, SchemaError};
+use crate::templating;
 use crate::types::BrickType;
 
 #[derive(Debug, Error)]
@@ -23,6 +26,23 @@ pub enum BrickError {
     
     #[error("Unknown error: {0}")]
     Unknown(String),
+
+    #[error("Timed out after {0} ms")]
+    Timeout(u64),
+}
+
+impl BrickError {
+    /// Whether another attempt may succeed; configuration, input and quota
+    /// errors are returned without retrying
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            BrickError::ExecutionError(_)
+                | BrickError::NetworkError(_)
+                | BrickError::Unknown(_)
+                | BrickError::Timeout(_)
+        )
+    }
 }
 
 #[async_trait]
@@ -36,74 +56,33 @@ pub trait Brick: Send + Sync {
     /// Returns the JSON schema for the brick's configuration
     fn config_schema(&self) -> Value;
     
-    /// Validates the configuration against the schema
+    /// Validates the configuration against `config_schema` (JSON Schema
+    /// draft 2020-12), reporting every mismatch with its JSON pointer
     fn validate_config(&self, config: &Value) -> Result<(), BrickError> {
-        // Basic validation
         if !config.is_object() {
             return Err(BrickError::ConfigError("Config must be a JSON object".to_string()));
         }
-
-        let schema = self.config_schema();
-        
-        // Check required fields
-        if let Some(required) = schema.get("required").and_then(|v| v.as_array()) {
-            for field in required {
-                if let Some(field_name) = field.as_str() {
-                    if !config.get(field_name).is_some() {
-                        return Err(BrickError::ConfigError(
-                            format!("Required field '{}' is missing", field_name)
-                        ));
-                    }
-                }
-            }
-        }
-
-        // Validate field types if properties are defined
-        if let Some(properties) = schema.get("properties").and_then(|v| v.as_object()) {
-            for (field_name, field_schema) in properties {
-                if let Some(config_value) = config.get(field_name) {
-                    if let Some(field_type) = field_schema.get("type").and_then(|v| v.as_str()) {
-                        let type_matches = match field_type {
-                            "string" => config_value.is_string(),
-                            "number" => config_value.is_number(),
-                            "integer" => config_value.is_i64() || config_value.is_u64(),
-                            "boolean" => config_value.is_boolean(),
-                            "array" => config_value.is_array(),
-                            "object" => config_value.is_object(),
-                            _ => true, // Unknown type, skip validation
-                        };
-
-                        if !type_matches {
-                            return Err(BrickError::ConfigError(
-                                format!("Field '{}' must be of type '{}'", field_name, field_type)
-                            ));
-                        }
-                    }
-
-                    // Validate enum values if present
-                    if let Some(enum_values) = field_schema.get("enum").and_then(|v| v.as_array()) {
-                        let value_str = config_value.as_str();
-                        if let Some(val) = value_str {
-                            let is_valid = enum_values.iter().any(|ev| {
-                                ev.as_str().map(|s| s == val).unwrap_or(false)
-                            });
-                            if !is_valid {
-                                return Err(BrickError::ConfigError(
-                                    format!("Field '{}' must be one of: {:?}", field_name, 
-                                        enum_values.iter().filter_map(|v| v.as_str()).collect::<Vec<_>>())
-                                ));
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(())
+        schema::validate(&self.config_schema(), config)
+            .map_err(|errors| BrickError::ConfigError(schema::describe_errors(&errors)))
     }
     
     /// Executes the brick with the given input payload
     async fn execute(&self, input: Value, config: Value) -> Result<Value, BrickError>;
+
+    /// Describes how the brick takes its settings from a saved connection;
+    /// `None` if it does not use connections
+    fn connection_spec(&self) -> Option<ConnectionSpec> {
+        None
+    }
+
+    /// Checks that the settings of a connection work, e.g. by making a cheap
+    /// authenticated request
+    ///
+    /// `config` holds the fields supplied by the connection (see
+    /// `ResolvedConnection::test_config`).
+    async fn test_connection(&self, _config: Value) -> Result<(), BrickError> {
+        Err(BrickError::ConfigError(format!("{} does not support connection tests", self.name())))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -143,3 +122,123 @@ impl BrickExecutionResult {
     }
 }
 
+/// Checks a brick config as stored in a flow, before its templates are
+/// rendered and its connection is applied
+///
+/// Values holding a template are only known at run time and are not checked,
+/// except that their secret references must be usable (see `templating`).
+/// Bricks that use connections may leave out the fields a connection supplies
+/// when they name one (see `connections::with_connection_schema`).
+pub fn check_stored_config(brick: &dyn Brick, config: &Value) -> Result<(), Vec<SchemaError>> {
+    if !config.is_object() {
+        return Err(vec![SchemaError {
+            instance_path: String::new(),
+            schema_path: String::new(),
+            message: "Config must be a JSON object".to_string(),
+        }]);
+    }
+    let schema = match brick.connection_spec() {
+        Some(spec) => connections::with_connection_schema(brick.config_schema(), &spec, &[]),
+        None => brick.config_schema(),
+    };
+    // Defaults are filled in before bricks run, so they satisfy `required`
+    let mut config = config.clone();
+    schema::apply_defaults(&schema, &mut config);
+    let mut errors = schema::validate(&schema, &config).err().unwrap_or_default();
+    errors.retain(|e| !config.pointer(&e.instance_path).and_then(Value::as_str).is_some_and(templating::is_template));
+    check_secret_references(&config, "", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+/// Reports templates whose secret references do not compile, such as secrets
+/// piped through filters
+fn check_secret_references(value: &Value, path: &str, errors: &mut Vec<SchemaError>) {
+    match value {
+        Value::String(_) => {
+            if let Err(e) = templating::secret_references(value) {
+                errors.push(SchemaError { instance_path: path.to_string(), schema_path: String::new(), message: e.to_string() });
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                check_secret_references(item, &format!("{}/{}", path, index), errors);
+            }
+        }
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                check_secret_references(field, &format!("{}/{}", path, name.replace('~', "~0").replace('/', "~1")), errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct CrmBrick;
+
+    #[async_trait]
+    impl Brick for CrmBrick {
+        fn name(&self) -> &'static str {
+            "crm"
+        }
+
+        fn brick_type(&self) -> BrickType {
+            BrickType::HubSpot
+        }
+
+        fn config_schema(&self) -> Value {
+            json!({
+                "type": "object",
+                "properties": {
+                    "api_key": { "type": "string" },
+                    "limit": { "type": "integer", "minimum": 1, "maximum": 100 },
+                    "operation": { "enum": ["get_deals", "create_deal"], "default": "get_deals" },
+                    "fields": { "type": "array", "items": { "type": "string", "minLength": 1 } }
+                },
+                "required": ["api_key", "operation"]
+            })
+        }
+
+        async fn execute(&self, input: Value, _config: Value) -> Result<Value, BrickError> {
+            Ok(input)
+        }
+
+        fn connection_spec(&self) -> Option<ConnectionSpec> {
+            Some(ConnectionSpec { connection_type: "crm", base_url_field: None, credential_fields: &["api_key"] })
+        }
+    }
+
+    #[test]
+    fn test_validate_config_reports_every_error() {
+        let config = json!({ "api_key": "k", "operation": "get_deals", "limit": 500, "fields": ["name", ""] });
+        let error = CrmBrick.validate_config(&config).unwrap_err().to_string();
+        assert!(error.contains("/limit: "), "{}", error);
+        assert!(error.contains("/fields/1: "), "{}", error);
+    }
+
+    #[test]
+    fn test_check_stored_config() {
+        // Connections supply the api key, defaults the operation, and
+        // templates are only checked once rendered
+        let config = json!({ "connection_id": "crm-prod", "limit": "{{ vars.limit }}" });
+        assert_eq!(check_stored_config(&CrmBrick, &config), Ok(()));
+
+        let errors = check_stored_config(&CrmBrick, &json!({ "limit": 0 })).unwrap_err();
+        let paths: Vec<&str> = errors.iter().map(|e| e.instance_path.as_str()).collect();
+        assert_eq!(paths, vec!["/limit", ""]);
+
+        // Secrets can only be output as is
+        let config = json!({ "api_key": "{{ secret:crm | upper }}", "fields": ["{{ secret:crm }}"] });
+        let errors = check_stored_config(&CrmBrick, &config).unwrap_err();
+        let paths: Vec<&str> = errors.iter().map(|e| e.instance_path.as_str()).collect();
+        assert_eq!(paths, vec!["/api_key"]);
+    }
+}
@@ -0,0 +1,206 @@
+//! Saved connections to external services
+//!
+//! A brick config can name a saved connection with `connection_id` instead of
+//! repeating URLs and credentials. Just before the brick runs, the flow runner
+//! resolves the connection through a `ConnectionResolver` and fills the
+//! config from it according to the brick's `ConnectionSpec`.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+/// Config key naming the connection a brick uses
+pub const CONNECTION_ID_KEY: &str = "connection_id";
+
+/// How a brick takes its settings from a connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionSpec {
+    /// Type of the connections the brick accepts, usually the brick id
+    pub connection_type: &'static str,
+    /// Config field that receives the connection's base URL
+    pub base_url_field: Option<&'static str>,
+    /// Config fields expected in the connection's credentials
+    pub credential_fields: &'static [&'static str],
+}
+
+impl ConnectionSpec {
+    /// Config fields supplied by a connection
+    pub fn fields(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.base_url_field.into_iter().chain(self.credential_fields.iter().copied())
+    }
+}
+
+/// A connection with its decrypted credentials
+#[derive(Debug, Clone, Default)]
+pub struct ResolvedConnection {
+    pub id: String,
+    pub connection_type: String,
+    pub base_url: Option<String>,
+    pub credentials: Map<String, Value>,
+    /// Config values used when the brick config does not set them
+    pub defaults: Map<String, Value>,
+}
+
+impl ResolvedConnection {
+    /// Fills a brick config from the connection
+    ///
+    /// Defaults only fill fields the config leaves out, while the base URL and
+    /// credentials always replace the config's values so rotating them only
+    /// takes an update of the connection. `connection_id` is removed.
+    pub fn apply(&self, config: &mut Value, spec: &ConnectionSpec) {
+        if !config.is_object() {
+            *config = Value::Object(Map::new());
+        }
+        let Some(fields) = config.as_object_mut() else {
+            return;
+        };
+        fields.remove(CONNECTION_ID_KEY);
+
+        for (key, value) in &self.defaults {
+            fields.entry(key.clone()).or_insert_with(|| value.clone());
+        }
+        for (key, value) in &self.credentials {
+            fields.insert(key.clone(), value.clone());
+        }
+        if let (Some(field), Some(base_url)) = (spec.base_url_field, &self.base_url) {
+            fields.insert(field.to_string(), Value::String(base_url.clone()));
+        }
+    }
+
+    /// Builds the config used to test the connection
+    pub fn test_config(&self, spec: &ConnectionSpec) -> Value {
+        let mut config = Value::Object(Map::new());
+        self.apply(&mut config, spec);
+        config
+    }
+
+    /// String values of the credentials, for redaction
+    pub fn credential_values(&self) -> impl Iterator<Item = &str> {
+        self.credentials.values().filter_map(|value| value.as_str())
+    }
+}
+
+/// Trait for looking up saved connections (to avoid circular dependencies)
+#[async_trait]
+pub trait ConnectionResolver: Send + Sync {
+    /// Returns the connection with its credentials, or `None` if the workspace
+    /// has no connection with the id
+    async fn resolve_connection(
+        &self,
+        workspace_id: Option<&str>,
+        id: &str,
+    ) -> Result<Option<ResolvedConnection>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// A connection offered in a brick's config schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectionOption {
+    pub id: String,
+    pub name: String,
+}
+
+/// The connection id set in a brick config, if any
+pub fn connection_id(config: &Value) -> Option<&str> {
+    config.get(CONNECTION_ID_KEY).and_then(|id| id.as_str()).filter(|id| !id.is_empty())
+}
+
+/// Adds a `connection_id` picker to a brick's config schema
+///
+/// The fields a connection supplies are no longer required on their own:
+/// the schema requires either `connection_id` or all of them.
+pub fn with_connection_schema(mut schema: Value, spec: &ConnectionSpec, connections: &[ConnectionOption]) -> Value {
+    let Some(object) = schema.as_object_mut() else {
+        return schema;
+    };
+
+    let mut property = json!({
+        "type": "string",
+        "description": format!("Id of a saved {} connection", spec.connection_type),
+        "x-connection-type": spec.connection_type,
+    });
+    if !connections.is_empty() {
+        property["oneOf"] = connections
+            .iter()
+            .map(|c| json!({ "const": c.id, "title": c.name }))
+            .collect();
+    }
+    if let Some(properties) = object.entry("properties").or_insert_with(|| json!({})).as_object_mut() {
+        properties.insert(CONNECTION_ID_KEY.to_string(), property);
+    }
+
+    let supplied: Vec<&str> = spec.fields().collect();
+    if let Some(required) = object.get_mut("required").and_then(|r| r.as_array_mut()) {
+        let moved: Vec<Value> = required
+            .iter()
+            .filter(|field| field.as_str().is_some_and(|f| supplied.contains(&f)))
+            .cloned()
+            .collect();
+        if !moved.is_empty() {
+            required.retain(|field| !moved.contains(field));
+            object.insert(
+                "anyOf".to_string(),
+                json!([{ "required": [CONNECTION_ID_KEY] }, { "required": moved }]),
+            );
+        }
+    }
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ODOO: ConnectionSpec = ConnectionSpec {
+        connection_type: "odoo",
+        base_url_field: Some("url"),
+        credential_fields: &["username", "password"],
+    };
+
+    fn connection() -> ResolvedConnection {
+        ResolvedConnection {
+            id: "conn-1".to_string(),
+            connection_type: "odoo".to_string(),
+            base_url: Some("https://erp.example.com".to_string()),
+            credentials: json!({"username": "bot", "password": "hunter2"}).as_object().unwrap().clone(),
+            defaults: json!({"database": "prod", "operation": "get_invoices"}).as_object().unwrap().clone(),
+        }
+    }
+
+    #[test]
+    fn test_apply_fills_config() {
+        let mut config = json!({
+            "connection_id": "conn-1",
+            "operation": "get_products",
+            "password": "stale",
+            "url": "https://old.example.com"
+        });
+        connection().apply(&mut config, &ODOO);
+        assert_eq!(config, json!({
+            "operation": "get_products",
+            "database": "prod",
+            "username": "bot",
+            "password": "hunter2",
+            "url": "https://erp.example.com"
+        }));
+        assert_eq!(connection_id(&json!({"connection_id": "conn-1"})), Some("conn-1"));
+        assert_eq!(connection_id(&json!({"connection_id": ""})), None);
+    }
+
+    #[test]
+    fn test_schema_offers_connections() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"url": {"type": "string"}, "operation": {"type": "string"}},
+            "required": ["url", "username", "password", "operation"]
+        });
+        let options = vec![ConnectionOption { id: "conn-1".to_string(), name: "ERP".to_string() }];
+        let schema = with_connection_schema(schema, &ODOO, &options);
+
+        assert_eq!(schema["required"], json!(["operation"]));
+        assert_eq!(schema["anyOf"], json!([
+            {"required": ["connection_id"]},
+            {"required": ["url", "username", "password"]}
+        ]));
+        assert_eq!(schema["properties"]["connection_id"]["oneOf"], json!([{"const": "conn-1", "title": "ERP"}]));
+    }
+}
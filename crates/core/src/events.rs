@@ -0,0 +1,225 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+use crate::types::{BrickType, ExecutionStatus};
+
+/// Number of events buffered for slow subscribers before they start lagging
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Number of finished executions whose events are kept for late subscribers
+const MAX_FINISHED_EXECUTIONS: usize = 100;
+
+/// A progress event of an execution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionEvent {
+    pub execution_id: String,
+    /// Position of the event within its execution, starting at 0
+    pub sequence: u64,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    #[serde(flatten)]
+    pub kind: ExecutionEventKind,
+}
+
+impl ExecutionEvent {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.kind, ExecutionEventKind::ExecutionFinished { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExecutionEventKind {
+    ExecutionStarted {
+        flow_id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        parent_execution_id: Option<String>,
+    },
+    BrickStarted {
+        brick_index: usize,
+        brick_type: BrickType,
+    },
+    BrickCompleted {
+        brick_index: usize,
+        brick_type: BrickType,
+        attempts: u32,
+        duration_ms: u64,
+    },
+    BrickFailed {
+        brick_index: usize,
+        brick_type: BrickType,
+        attempts: u32,
+        duration_ms: u64,
+        error: String,
+    },
+    ExecutionFinished {
+        status: ExecutionStatus,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+impl ExecutionEventKind {
+    /// Name of the event, as used for the `type` field
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExecutionEventKind::ExecutionStarted { .. } => "execution_started",
+            ExecutionEventKind::BrickStarted { .. } => "brick_started",
+            ExecutionEventKind::BrickCompleted { .. } => "brick_completed",
+            ExecutionEventKind::BrickFailed { .. } => "brick_failed",
+            ExecutionEventKind::ExecutionFinished { .. } => "execution_finished",
+        }
+    }
+}
+
+/// Events published so far for one execution
+#[derive(Default)]
+struct TrackedExecution {
+    /// Workspace named by `track`; `None` for executions that were not tracked
+    workspace_id: Option<String>,
+    events: Vec<ExecutionEvent>,
+}
+
+/// Events published so far, per execution
+#[derive(Default)]
+struct EventHistory {
+    executions: HashMap<String, TrackedExecution>,
+    /// Finished executions, oldest first
+    finished: VecDeque<String>,
+}
+
+/// Subscription to the events of one execution
+pub struct EventSubscription {
+    /// Workspace the execution was tracked for, if it was tracked
+    pub workspace_id: Option<String>,
+    /// Events published before the subscription was made
+    pub history: Vec<ExecutionEvent>,
+    /// Events published afterwards, for all executions
+    pub receiver: broadcast::Receiver<ExecutionEvent>,
+}
+
+/// Broadcasts execution events to subscribers within the process
+///
+/// The events of running executions and of the most recently finished ones
+/// are kept, so subscribers that connect late still see the whole execution.
+pub struct ExecutionEventBus {
+    sender: broadcast::Sender<ExecutionEvent>,
+    history: Mutex<EventHistory>,
+}
+
+impl Default for ExecutionEventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExecutionEventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            sender,
+            history: Mutex::new(EventHistory::default()),
+        }
+    }
+
+    /// Starts keeping the events of an execution of `workspace_id` before
+    /// its first event
+    ///
+    /// Lets callers hand out an execution id for subscription before the
+    /// execution has started; subscribers are checked against the workspace.
+    pub fn track(&self, execution_id: &str, workspace_id: &str) {
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let execution = history.executions.entry(execution_id.to_string()).or_default();
+        execution.workspace_id = Some(workspace_id.to_string());
+    }
+
+    /// Publishes an event of an execution
+    pub fn publish(&self, execution_id: &str, kind: ExecutionEventKind) {
+        let mut history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let events = &mut history.executions.entry(execution_id.to_string()).or_default().events;
+        let event = ExecutionEvent {
+            execution_id: execution_id.to_string(),
+            sequence: events.len() as u64,
+            timestamp: chrono::Utc::now(),
+            kind,
+        };
+        events.push(event.clone());
+
+        if event.is_finished() {
+            history.finished.push_back(execution_id.to_string());
+            while history.finished.len() > MAX_FINISHED_EXECUTIONS {
+                if let Some(expired) = history.finished.pop_front() {
+                    history.executions.remove(&expired);
+                }
+            }
+        }
+
+        // Sending under the lock keeps history and live events in order;
+        // an error only means nobody is subscribed
+        let _ = self.sender.send(event);
+    }
+
+    /// Subscribes to the events of an execution
+    ///
+    /// Returns `None` for executions the bus knows nothing about, such as
+    /// executions run by another process or finished long ago.
+    pub fn subscribe(&self, execution_id: &str) -> Option<EventSubscription> {
+        let history = self.history.lock().unwrap_or_else(|e| e.into_inner());
+        let execution = history.executions.get(execution_id)?;
+        Some(EventSubscription {
+            workspace_id: execution.workspace_id.clone(),
+            history: execution.events.clone(),
+            receiver: self.sender.subscribe(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_subscriber_gets_history_then_live_events() {
+        let bus = ExecutionEventBus::new();
+        assert!(bus.subscribe("exec-1").is_none());
+
+        bus.publish("exec-1", ExecutionEventKind::ExecutionStarted {
+            flow_id: "flow-1".to_string(),
+            parent_execution_id: None,
+        });
+        let mut subscription = bus.subscribe("exec-1").unwrap();
+        assert_eq!(subscription.history.len(), 1);
+
+        bus.publish("exec-1", ExecutionEventKind::ExecutionFinished {
+            status: ExecutionStatus::Completed,
+            error: None,
+        });
+        let event = subscription.receiver.recv().await.unwrap();
+        assert_eq!(event.sequence, 1);
+        assert!(event.is_finished());
+        assert_eq!(
+            serde_json::to_value(&event.kind).unwrap(),
+            json!({"type": "execution_finished", "status": "completed"})
+        );
+    }
+
+    #[test]
+    fn test_finished_executions_expire() {
+        let bus = ExecutionEventBus::new();
+        bus.track("pending", "workspace-1");
+        for i in 0..=MAX_FINISHED_EXECUTIONS {
+            bus.publish(&format!("exec-{}", i), ExecutionEventKind::ExecutionFinished {
+                status: ExecutionStatus::Completed,
+                error: None,
+            });
+        }
+
+        assert!(bus.subscribe("exec-0").is_none());
+        assert!(bus.subscribe(&format!("exec-{}", MAX_FINISHED_EXECUTIONS)).is_some());
+        let pending = bus.subscribe("pending").unwrap();
+        assert!(pending.history.is_empty());
+        assert_eq!(pending.workspace_id.as_deref(), Some("workspace-1"));
+    }
+}
@@ -1,15 +1,28 @@
 use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 use thiserror::Error;
 use uuid::Uuid;
 use tokio::sync::Semaphore;
 use std::sync::OnceLock;
 
+use crate::brick_registry::BrickRegistry;
 use crate::brick_traits::{Brick, BrickError};
+use crate::connections::{self, ConnectionResolver};
+use crate::events::{ExecutionEventBus, ExecutionEventKind};
+use crate::graph_runner::GraphRunner;
 use crate::quota::{QuotaError, QuotaManager};
-use crate::types::{Flow, FlowExecution, ExecutionStatus, BrickType, UsageLog};
+use crate::response::{FlowResponder, FlowResponse};
+use crate::retry::retry_with_backoff_if;
+use crate::schema::SchemaError;
+use crate::secrets::{SecretRedactor, SecretResolver};
+use crate::templating::{self, TemplateContext};
+use crate::types::{Flow, FlowExecution, ExecutionStatus, BrickType, UsageLog, FlowNodeKind, BranchMode, BrickPolicy, BrickFallback, ExecutionStep, StepStatus};
 use async_trait::async_trait;
 
+/// Maximum nesting depth of sub-flows started by `Branch` actions
+pub const MAX_SUB_FLOW_DEPTH: usize = 8;
+
 /// Maximum number of concurrent background tasks for execution data storage
 const MAX_CONCURRENT_STORAGE_TASKS: usize = 100;
 
@@ -32,9 +45,30 @@ pub enum FlowError {
     
     #[error("Flow not found: {0}")]
     FlowNotFound(String),
+
+    #[error("Unknown brick type: {0}")]
+    UnknownBrick(String),
     
     #[error("Invalid flow configuration: {0}")]
     InvalidFlow(String),
+
+    #[error("Edge condition error: {0}")]
+    ConditionError(String),
+
+    #[error("Sub-flow error: {0}")]
+    SubFlowError(String),
+
+    #[error("Sub-flow depth limit of {0} exceeded (possible branch cycle)")]
+    SubFlowDepthExceeded(usize),
+
+    #[error("Invalid response: {0}")]
+    ResponseError(String),
+
+    #[error("Input does not match the flow's input schema: {}", crate::schema::describe_errors(.0))]
+    InvalidInput(Vec<SchemaError>),
+
+    #[error("Output does not match the flow's output schema: {}", crate::schema::describe_errors(.0))]
+    InvalidOutput(Vec<SchemaError>),
 }
 
 /// Context for flow execution with optional quota and usage tracking
@@ -42,8 +76,30 @@ pub struct FlowRunnerContext {
     pub quota_manager: Option<Arc<dyn QuotaManager>>,
     pub usage_logger: Option<Arc<dyn UsageLogger>>,
     pub execution_data_storage: Option<Arc<dyn ExecutionDataStorage>>,
+    pub sub_flow_executor: Option<Arc<dyn SubFlowExecutor>>,
+    /// Creates fallback bricks named in brick policies
+    pub brick_registry: Option<Arc<BrickRegistry>>,
+    /// Receives progress events of the execution and its sub-flows
+    pub event_bus: Option<Arc<ExecutionEventBus>>,
     pub flow_id: String,
     pub execution_id: String,
+    /// Workspace of the running flow; secrets and connections are looked up in it
+    pub workspace_id: Option<String>,
+    /// Execution that started this flow through a `Branch` action
+    pub parent_execution_id: Option<String>,
+    /// Number of sub-flow levels above this execution (0 for top-level runs)
+    pub depth: usize,
+    /// Variables of the running flow, available to config templates
+    pub variables: serde_json::Map<String, Value>,
+    /// Resolves `{{ secret:name }}` references in brick configs
+    pub secret_resolver: Option<Arc<dyn SecretResolver>>,
+    /// Looks up the connections named by `connection_id` in brick configs
+    pub connection_resolver: Option<Arc<dyn ConnectionResolver>>,
+    /// Secret values used by the execution, removed from everything it records
+    pub redactor: Arc<SecretRedactor>,
+    /// Receives the response of a `respond` brick for the caller of the flow's
+    /// endpoint; without one, responses are dropped
+    pub responder: Option<FlowResponder>,
 }
 
 /// Trait for usage logging (to avoid circular dependencies)
@@ -84,6 +140,70 @@ pub trait ExecutionDataStorage: Send + Sync {
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 }
 
+/// Trait for running another flow as a sub-flow (to avoid circular dependencies)
+///
+/// Implementations load the flow by id and run it with a child context derived
+/// from `parent` (see `FlowRunnerContext::child`).
+#[async_trait]
+pub trait SubFlowExecutor: Send + Sync {
+    async fn execute_sub_flow(
+        &self,
+        flow_id: &str,
+        payload: Value,
+        parent: &FlowRunnerContext,
+    ) -> Result<Value, FlowError>;
+}
+
+impl FlowRunnerContext {
+    /// Creates the context for a sub-flow started from this execution
+    pub fn child(&self, flow_id: &str) -> Result<FlowRunnerContext, FlowError> {
+        if self.depth + 1 > MAX_SUB_FLOW_DEPTH {
+            return Err(FlowError::SubFlowDepthExceeded(MAX_SUB_FLOW_DEPTH));
+        }
+
+        Ok(FlowRunnerContext {
+            quota_manager: self.quota_manager.clone(),
+            usage_logger: self.usage_logger.clone(),
+            execution_data_storage: self.execution_data_storage.clone(),
+            sub_flow_executor: self.sub_flow_executor.clone(),
+            brick_registry: self.brick_registry.clone(),
+            event_bus: self.event_bus.clone(),
+            flow_id: flow_id.to_string(),
+            execution_id: String::new(), // Set by execute_flow_with_tracking
+            workspace_id: self.workspace_id.clone(),
+            parent_execution_id: Some(self.execution_id.clone()),
+            depth: self.depth + 1,
+            variables: serde_json::Map::new(), // Set by execute_flow_with_tracking
+            secret_resolver: self.secret_resolver.clone(),
+            connection_resolver: self.connection_resolver.clone(),
+            redactor: self.redactor.clone(),
+            // Only the top-level flow answers its caller
+            responder: None,
+        })
+    }
+
+    /// Publishes a progress event of this execution, if an event bus is set
+    pub(crate) fn emit(&self, kind: ExecutionEventKind) {
+        if let Some(ref event_bus) = self.event_bus {
+            if !self.execution_id.is_empty() {
+                let kind = match kind {
+                    ExecutionEventKind::BrickFailed { brick_index, brick_type, attempts, duration_ms, error } => {
+                        ExecutionEventKind::BrickFailed {
+                            brick_index,
+                            brick_type,
+                            attempts,
+                            duration_ms,
+                            error: self.redactor.redact_str(&error),
+                        }
+                    }
+                    kind => kind,
+                };
+                event_bus.publish(&self.execution_id, kind);
+            }
+        }
+    }
+}
+
 pub struct FlowRunner;
 
 impl FlowRunner {
@@ -94,7 +214,26 @@ impl FlowRunner {
         initial_payload: Value,
         context: Option<FlowRunnerContext>,
     ) -> Result<Value, FlowError> {
-        if bricks.len() != configs.len() {
+        let policies = vec![None; configs.len()];
+        let mut steps = Vec::new();
+        Self::execute_linear(bricks, configs, &policies, initial_payload, HashMap::new(), &mut steps, context).await
+    }
+
+    /// Executes bricks in order, applying each brick's policy
+    ///
+    /// Bricks with an output in `completed` (keyed by brick index) are not run
+    /// again; execution continues after the last of them. A step record is
+    /// appended to `steps` for every brick that runs or is skipped.
+    async fn execute_linear(
+        bricks: Vec<Box<dyn Brick>>,
+        configs: Vec<Value>,
+        policies: &[Option<&BrickPolicy>],
+        initial_payload: Value,
+        mut completed: HashMap<usize, Value>,
+        steps: &mut Vec<ExecutionStep>,
+        context: Option<FlowRunnerContext>,
+    ) -> Result<Value, FlowError> {
+        if bricks.len() != configs.len() || bricks.len() != policies.len() {
             return Err(FlowError::InvalidFlow(
                 "Number of bricks must match number of configs".to_string(),
             ));
@@ -103,32 +242,40 @@ impl FlowRunner {
         let mut current_payload = initial_payload;
         let mut skip_count = 0;
 
-        for (index, brick) in bricks.iter().enumerate() {
+        // Restore the outputs of bricks completed by the execution being resumed
+        let resume_from = (0..bricks.len()).take_while(|i| completed.contains_key(i)).count();
+        for (index, brick) in bricks.iter().enumerate().take(resume_from) {
+            if let Some(output) = completed.remove(&index) {
+                Self::record_restored_output(brick.as_ref(), index, &output, context.as_ref());
+                current_payload = output;
+            }
+        }
+
+        for (index, brick) in bricks.iter().enumerate().skip(resume_from) {
             // Skip bricks if skip_count > 0
             if skip_count > 0 {
                 skip_count -= 1;
+                steps.push(ExecutionStep::skipped(index, brick.brick_type(), current_payload.clone()));
                 continue;
             }
 
             let config = &configs[index];
-            let brick_type = brick.brick_type();
-            
-            // Validate config before execution
-            brick.validate_config(config)
-                .map_err(FlowError::BrickError)?;
-
-            // Check quota before execution
-            if let Some(ref ctx) = context {
-                if let Some(ref quota_manager) = ctx.quota_manager {
-                    quota_manager.check_quota(&brick_type).await?;
-                }
-            }
 
-            // Execute brick with current payload (move ownership to avoid clone)
-            // Note: config.clone() is necessary because execute() takes ownership
-            let mut result = brick.execute(current_payload, config.clone())
-                .await
-                .map_err(FlowError::BrickError)?;
+            let (result, mut step) = Self::execute_brick(
+                brick.as_ref(),
+                index,
+                config,
+                policies[index],
+                current_payload,
+                context.as_ref(),
+            ).await;
+            let mut result = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    steps.push(step);
+                    return Err(e);
+                }
+            };
 
             // Check for branching metadata in result
             if let Some(obj) = result.as_object_mut() {
@@ -138,98 +285,92 @@ impl FlowRunner {
                         skip_count = skip_num as usize;
                     }
                 }
-                // Note: Branch action is handled at API level, not in FlowRunner
-                // as it requires access to flow repository
             }
 
-            // Extract cost and token usage from result
-            let (cost_unit, token_usage) = Self::extract_execution_metadata(&result, &brick_type);
+            if let Err(e) = Self::send_response(&mut result, context.as_ref()) {
+                step.fail(e.to_string());
+                steps.push(step);
+                return Err(e);
+            }
 
-            // Record usage after execution
-            if let Some(ref ctx) = context {
-                if let Some(ref usage_logger) = ctx.usage_logger {
-                    let _ = usage_logger.record_usage(
-                        brick.name(),
-                        &brick_type,
-                        &ctx.flow_id,
-                        &ctx.execution_id,
-                        cost_unit,
-                        token_usage,
-                        None,
-                    ).await;
-                }
-                
-                // Record usage in quota manager
-                if let Some(ref quota_manager) = ctx.quota_manager {
-                    let _ = quota_manager.record_usage(&brick_type, cost_unit, token_usage).await;
+            // Run a sub-flow requested by a Branch action
+            let branch_mode = match Self::run_branch(&mut result, context.as_ref()).await {
+                Ok(branch_mode) => branch_mode,
+                Err(e) => {
+                    step.fail(e.to_string());
+                    steps.push(step);
+                    return Err(e);
                 }
+            };
 
-                // Store execution data asynchronously (non-blocking)
-                // Clone result once for storage operations to avoid multiple clones
-                if let Some(ref data_storage) = ctx.execution_data_storage {
-                    let execution_id = ctx.execution_id.clone();
-                    let brick_type_clone = brick_type.clone();
-                    // Clone result once before spawning task
-                    let result_for_storage = result.clone();
-                    let brick_index = index;
-                    let is_api_fetch = Self::is_api_fetch_brick(&brick_type);
-                    let data_storage_clone = data_storage.clone();
-                    let semaphore = get_storage_semaphore();
-                    
-                    // Spawn background task to store data without blocking flow execution
-                    // Use semaphore to limit concurrent tasks and prevent resource exhaustion
-                    tokio::spawn(async move {
-                        // Acquire permit from semaphore (waits if limit reached)
-                        let _permit = match semaphore.acquire().await {
-                            Ok(p) => p,
-                            Err(_) => {
-                                // Semaphore closed, skip storage
-                                return;
-                            }
-                        };
-                        
-                        // Store intermediate output
-                        let _ = data_storage_clone.store_data(
-                            &execution_id,
-                            brick_index,
-                            &brick_type_clone,
-                            "intermediate",
-                            &format!("brick_{}", brick_index),
-                            result_for_storage.clone(),
-                        ).await;
-
-                        // If brick fetches data from external API, store as "fetched" with same data
-                        // Reuse the already-cloned result_for_storage instead of cloning again
-                        if is_api_fetch {
-                            let _ = data_storage_clone.store_data(
-                                &execution_id,
-                                brick_index,
-                                &brick_type_clone,
-                                "fetched",
-                                &format!("fetched_{}", brick_index),
-                                result_for_storage, // Move ownership instead of cloning again
-                            ).await;
-                        }
-                        // Permit is automatically released when dropped
-                    });
-                }
-            }
+            Self::record_brick_result(brick.as_ref(), index, &result, context.as_ref()).await;
+            step.output = Some(result.clone());
+            steps.push(step);
 
             // Update payload for next brick (move ownership)
             current_payload = result;
+
+            if branch_mode == Some(BranchMode::Replace) {
+                break;
+            }
         }
 
         Ok(current_payload)
     }
 
     /// Executes a flow with detailed execution tracking
+    ///
+    /// Fails with the flow error; use `execute_flow_recorded` to also get the
+    /// record of a failed execution.
     pub async fn execute_flow_with_tracking(
         flow: &Flow,
         bricks: Vec<Box<dyn Brick>>,
         initial_payload: Value,
         context: Option<FlowRunnerContext>,
     ) -> Result<FlowExecution, FlowError> {
-        let execution_id = Uuid::new_v4().to_string();
+        let (execution, result) = Self::run_tracked(flow, bricks, initial_payload, HashMap::new(), context).await;
+        result.map(|_| execution)
+    }
+
+    /// Executes a flow and returns its execution record, whether it completed
+    /// or failed. Failed executions carry the error message in `error`.
+    pub async fn execute_flow_recorded(
+        flow: &Flow,
+        bricks: Vec<Box<dyn Brick>>,
+        initial_payload: Value,
+        context: Option<FlowRunnerContext>,
+    ) -> FlowExecution {
+        Self::run_tracked(flow, bricks, initial_payload, HashMap::new(), context).await.0
+    }
+
+    /// Resumes a failed execution as a new execution
+    ///
+    /// `completed` holds the outputs of bricks that succeeded in the failed
+    /// execution, keyed by brick index (the node index for graph flows). Those
+    /// bricks are not run again; their outputs are stored for the new
+    /// execution so it can be resumed as well.
+    pub async fn resume_flow_recorded(
+        flow: &Flow,
+        bricks: Vec<Box<dyn Brick>>,
+        initial_payload: Value,
+        completed: HashMap<usize, Value>,
+        context: Option<FlowRunnerContext>,
+    ) -> FlowExecution {
+        Self::run_tracked(flow, bricks, initial_payload, completed, context).await.0
+    }
+
+    async fn run_tracked(
+        flow: &Flow,
+        bricks: Vec<Box<dyn Brick>>,
+        initial_payload: Value,
+        completed: HashMap<usize, Value>,
+        context: Option<FlowRunnerContext>,
+    ) -> (FlowExecution, Result<(), FlowError>) {
+        // Callers may pick the execution id up front (e.g. to link a replay)
+        let execution_id = context.as_ref()
+            .map(|c| c.execution_id.clone())
+            .filter(|id| !id.is_empty())
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
         let started_at = chrono::Utc::now();
 
         // Create context with execution tracking info
@@ -237,11 +378,33 @@ impl FlowRunner {
             quota_manager: None,
             usage_logger: None,
             execution_data_storage: None,
+            sub_flow_executor: None,
+            brick_registry: None,
+            event_bus: None,
             flow_id: flow.id.clone(),
             execution_id: execution_id.clone(),
+            workspace_id: None,
+            parent_execution_id: None,
+            depth: 0,
+            variables: Default::default(),
+            secret_resolver: None,
+            connection_resolver: None,
+            redactor: Default::default(),
+            responder: None,
         });
         exec_context.flow_id = flow.id.clone();
         exec_context.execution_id = execution_id.clone();
+        exec_context.variables = flow.variables.clone();
+        if flow.workspace_id.is_some() {
+            exec_context.workspace_id = flow.workspace_id.clone();
+        }
+        let parent_execution_id = exec_context.parent_execution_id.clone();
+        let event_bus = exec_context.event_bus.clone();
+        let redactor = exec_context.redactor.clone();
+        exec_context.emit(ExecutionEventKind::ExecutionStarted {
+            flow_id: flow.id.clone(),
+            parent_execution_id: parent_execution_id.clone(),
+        });
 
         let mut execution = FlowExecution {
             flow_id: flow.id.clone(),
@@ -252,28 +415,588 @@ impl FlowRunner {
             input_payload: initial_payload.clone(),
             output_payload: None,
             error: None,
+            parent_execution_id,
+            replay_of_execution_id: None,
+            steps: Vec::new(),
         };
 
-        // Collect configs once to avoid repeated cloning
-        let configs: Vec<Value> = flow.bricks.iter().map(|b| b.config.clone()).collect();
-        match Self::execute_flow(
-            bricks,
-            configs,
-            initial_payload,
-            Some(exec_context),
-        ).await {
+        let mut steps = Vec::new();
+        // Payloads that do not match the input schema never reach a brick
+        let result = if let Err(errors) = flow.schema.validate_input(&initial_payload) {
+            Err(FlowError::InvalidInput(errors))
+        } else {
+            match flow.graph {
+                Some(ref graph) => {
+                    // Bricks are supplied in the same order as the graph's brick nodes
+                    let brick_node_ids: Vec<String> = graph.nodes.iter()
+                        .filter(|n| matches!(n.kind, FlowNodeKind::Brick(_)))
+                        .map(|n| n.id.clone())
+                        .collect();
+                    if brick_node_ids.len() != bricks.len() {
+                        Err(FlowError::InvalidFlow(
+                            "Number of bricks must match number of brick nodes".to_string(),
+                        ))
+                    } else {
+                        let bricks_by_node: HashMap<String, Box<dyn Brick>> =
+                            brick_node_ids.into_iter().zip(bricks).collect();
+                        GraphRunner::resume_graph(graph, bricks_by_node, initial_payload, completed, &mut steps, Some(exec_context)).await
+                    }
+                }
+                None => {
+                    // Collect configs once to avoid repeated cloning
+                    let configs: Vec<Value> = flow.bricks.iter().map(|b| b.config.clone()).collect();
+                    let policies: Vec<Option<&BrickPolicy>> = flow.bricks.iter().map(|b| b.policy.as_ref()).collect();
+                    Self::execute_linear(bricks, configs, &policies, initial_payload, completed, &mut steps, Some(exec_context)).await
+                }
+            }
+        }
+        .and_then(|output| match flow.schema.validate_output(&output) {
+            Ok(()) => Ok(output),
+            Err(errors) => Err(FlowError::InvalidOutput(errors)),
+        });
+
+        execution.steps = steps;
+        execution.completed_at = Some(chrono::Utc::now());
+        let result = match result {
             Ok(output) => {
                 execution.status = ExecutionStatus::Completed;
-                execution.completed_at = Some(chrono::Utc::now());
                 execution.output_payload = Some(output);
-                Ok(execution)
+                Ok(())
             }
             Err(e) => {
+                let e = redactor.redact_error(e);
                 execution.status = ExecutionStatus::Failed;
-                execution.completed_at = Some(chrono::Utc::now());
                 execution.error = Some(e.to_string());
                 Err(e)
             }
+        };
+        redactor.redact_execution(&mut execution);
+
+        if let Some(event_bus) = event_bus {
+            event_bus.publish(&execution_id, ExecutionEventKind::ExecutionFinished {
+                status: execution.status.clone(),
+                error: execution.error.clone(),
+            });
+        }
+
+        (execution, result)
+    }
+
+    /// Executes a single brick, applying its policy
+    ///
+    /// Without a policy the brick runs once and any error fails the flow. With
+    /// a policy, retryable errors (see `BrickError::is_retryable`) are retried
+    /// with backoff and every attempt is recorded as `attempt` execution data.
+    /// Once all attempts have failed the fallback is used, and with
+    /// `continue_on_error` the input is passed through unchanged.
+    ///
+    /// A failure is stored as `error` execution data of the brick, which lets
+    /// a resumed execution find the brick to continue from.
+    ///
+    /// Also returns the step record of the brick. Its output is left for the
+    /// caller to fill in, since branching metadata is stripped afterwards.
+    pub(crate) async fn execute_brick(
+        brick: &dyn Brick,
+        brick_index: usize,
+        config: &Value,
+        policy: Option<&BrickPolicy>,
+        input: Value,
+        context: Option<&FlowRunnerContext>,
+    ) -> (Result<Value, FlowError>, ExecutionStep) {
+        let mut step = ExecutionStep {
+            brick_index,
+            brick_type: brick.brick_type(),
+            status: StepStatus::Succeeded,
+            input: input.clone(),
+            output: None,
+            error: None,
+            attempts: 1,
+            started_at: chrono::Utc::now(),
+            duration_ms: 0,
+        };
+        let started = std::time::Instant::now();
+        if let Some(ctx) = context {
+            ctx.emit(ExecutionEventKind::BrickStarted {
+                brick_index,
+                brick_type: step.brick_type.clone(),
+            });
+        }
+
+        let result = match Self::render_config(brick, config, brick_index, &input, context).await {
+            Ok(config) => match policy {
+                Some(policy) => {
+                    Self::execute_with_policy(brick, brick_index, &config, policy, input, &mut step.attempts, context).await
+                }
+                None => Self::run_brick(brick, &config, input, context).await,
+            },
+            Err(e) => Err(e),
+        };
+        step.duration_ms = started.elapsed().as_millis() as u64;
+
+        if let Some(ctx) = context {
+            ctx.emit(match result {
+                Ok(_) => ExecutionEventKind::BrickCompleted {
+                    brick_index,
+                    brick_type: step.brick_type.clone(),
+                    attempts: step.attempts,
+                    duration_ms: step.duration_ms,
+                },
+                Err(ref e) => ExecutionEventKind::BrickFailed {
+                    brick_index,
+                    brick_type: step.brick_type.clone(),
+                    attempts: step.attempts,
+                    duration_ms: step.duration_ms,
+                    error: e.to_string(),
+                },
+            });
+        }
+
+        if let Err(ref e) = result {
+            step.fail(e.to_string());
+            Self::store_execution_data(
+                brick,
+                brick_index,
+                "error",
+                format!("error_{}", brick_index),
+                serde_json::json!({ "error": e.to_string() }),
+                context,
+            );
+        }
+
+        (result, step)
+    }
+
+    async fn execute_with_policy(
+        brick: &dyn Brick,
+        brick_index: usize,
+        config: &Value,
+        policy: &BrickPolicy,
+        input: Value,
+        attempts: &mut u32,
+        context: Option<&FlowRunnerContext>,
+    ) -> Result<Value, FlowError> {
+        *attempts = 0;
+        let result = retry_with_backoff_if(
+            || {
+                *attempts += 1;
+                let attempt = *attempts;
+                let input = input.clone();
+                async move {
+                    let started = std::time::Instant::now();
+                    let result = match policy.timeout() {
+                        Some(timeout) => tokio::time::timeout(timeout, Self::run_brick(brick, config, input, context))
+                            .await
+                            .unwrap_or(Err(FlowError::BrickError(BrickError::Timeout(timeout.as_millis() as u64)))),
+                        None => Self::run_brick(brick, config, input, context).await,
+                    };
+                    Self::record_attempt(brick, brick_index, attempt, started.elapsed(), &result, context);
+                    result
+                }
+            },
+            policy.retry_config(),
+            |e| matches!(e, FlowError::BrickError(e) if e.is_retryable()),
+        ).await;
+
+        let mut error = match result {
+            Ok(output) => return Ok(output),
+            Err(e) => e,
+        };
+
+        if let Some(ref fallback) = policy.fallback {
+            match Self::run_fallback(fallback, brick_index, input.clone(), context).await {
+                Ok(output) => {
+                    tracing::warn!(brick = brick.name(), error = %error, "Brick failed, using its fallback");
+                    return Ok(output);
+                }
+                Err(e) => error = e,
+            }
+        }
+
+        if policy.continue_on_error {
+            tracing::warn!(brick = brick.name(), error = %error, "Brick failed, continuing with its input");
+            return Ok(input);
+        }
+
+        Err(error)
+    }
+
+    /// Renders the templates in a brick's config
+    ///
+    /// Templates see the brick's input as `payload`, the flow's variables as
+    /// `vars`, the execution's metadata as `execution` and the secrets they
+    /// reference as `secret:<name>`. Template errors are config errors of the
+    /// brick. A `connection_id` in the rendered config is then replaced with
+    /// the connection's settings. Resolved secrets and the credentials of the
+    /// final config are added to the execution's redactor.
+    async fn render_config(
+        brick: &dyn Brick,
+        config: &Value,
+        brick_index: usize,
+        input: &Value,
+        context: Option<&FlowRunnerContext>,
+    ) -> Result<Value, FlowError> {
+        let secrets = Self::resolve_secrets(config, context).await?;
+        let no_variables = serde_json::Map::new();
+        let mut execution = serde_json::json!({
+            "brick_index": brick_index,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        });
+        if let Some(ctx) = context {
+            execution["id"] = Value::String(ctx.execution_id.clone());
+            execution["flow_id"] = Value::String(ctx.flow_id.clone());
+            execution["parent_execution_id"] = serde_json::json!(ctx.parent_execution_id);
+            execution["depth"] = Value::from(ctx.depth);
+        }
+
+        let template_context = TemplateContext {
+            payload: input,
+            variables: context.map(|ctx| &ctx.variables).unwrap_or(&no_variables),
+            execution,
+            secrets: &secrets,
+        };
+        let mut config = templating::render_config(config, &template_context)
+            .map_err(|e| FlowError::BrickError(BrickError::ConfigError(e.to_string())))?;
+        if let Some(connection_id) = connections::connection_id(&config).map(str::to_string) {
+            Self::apply_connection(brick, &connection_id, &mut config, context).await?;
+        }
+        if let Some(ctx) = context {
+            ctx.redactor.add_credentials(&config);
+        }
+        Ok(config)
+    }
+
+    /// Fetches the values of the secrets referenced by a config
+    async fn resolve_secrets(
+        config: &Value,
+        context: Option<&FlowRunnerContext>,
+    ) -> Result<HashMap<String, String>, FlowError> {
+        let names = templating::secret_references(config)
+            .map_err(|e| FlowError::BrickError(BrickError::ConfigError(e.to_string())))?;
+        let mut secrets = HashMap::new();
+        if names.is_empty() {
+            return Ok(secrets);
+        }
+
+        let (resolver, redactor) = match context {
+            Some(FlowRunnerContext { secret_resolver: Some(resolver), redactor, .. }) => (resolver, redactor),
+            _ => {
+                return Err(FlowError::BrickError(BrickError::ConfigError(
+                    "No secrets store configured to resolve secret references".to_string(),
+                )))
+            }
+        };
+        let workspace_id = context.and_then(|c| c.workspace_id.as_deref());
+        for name in names {
+            let value = resolver.resolve_secret(workspace_id, &name).await
+                .map_err(|e| FlowError::BrickError(BrickError::ExecutionError(format!("Failed to resolve secret '{}': {}", name, e))))?
+                .ok_or_else(|| FlowError::BrickError(BrickError::ConfigError(format!("Unknown secret '{}'", name))))?;
+            redactor.add(&value);
+            secrets.insert(name, value);
+        }
+        Ok(secrets)
+    }
+
+    /// Fills a brick config from the saved connection it names
+    async fn apply_connection(
+        brick: &dyn Brick,
+        connection_id: &str,
+        config: &mut Value,
+        context: Option<&FlowRunnerContext>,
+    ) -> Result<(), FlowError> {
+        let config_error = |message: String| FlowError::BrickError(BrickError::ConfigError(message));
+        let spec = brick.connection_spec()
+            .ok_or_else(|| config_error(format!("Brick {} does not use connections", brick.name())))?;
+        let (resolver, redactor) = match context {
+            Some(FlowRunnerContext { connection_resolver: Some(resolver), redactor, .. }) => (resolver, redactor),
+            _ => return Err(config_error(format!("No connection store configured to resolve connection '{}'", connection_id))),
+        };
+
+        let workspace_id = context.and_then(|c| c.workspace_id.as_deref());
+        let connection = resolver.resolve_connection(workspace_id, connection_id).await
+            .map_err(|e| FlowError::BrickError(BrickError::ExecutionError(format!("Failed to resolve connection '{}': {}", connection_id, e))))?
+            .ok_or_else(|| config_error(format!("Unknown connection '{}'", connection_id)))?;
+        if connection.connection_type != spec.connection_type {
+            return Err(config_error(format!(
+                "Connection '{}' is a {} connection, expected {}",
+                connection_id, connection.connection_type, spec.connection_type
+            )));
+        }
+
+        connection.credential_values().for_each(|value| redactor.add(value));
+        connection.apply(config, &spec);
+        Ok(())
+    }
+
+    /// Validates config, checks quota and executes a single brick once
+    async fn run_brick(
+        brick: &dyn Brick,
+        config: &Value,
+        input: Value,
+        context: Option<&FlowRunnerContext>,
+    ) -> Result<Value, FlowError> {
+        let brick_type = brick.brick_type();
+
+        // Fill in schema defaults, then validate config before execution
+        let mut config = config.clone();
+        crate::schema::apply_defaults(&brick.config_schema(), &mut config);
+        brick.validate_config(&config)
+            .map_err(FlowError::BrickError)?;
+
+        // Check quota before execution
+        if let Some(ctx) = context {
+            if let Some(ref quota_manager) = ctx.quota_manager {
+                quota_manager.check_quota(&brick_type).await?;
+            }
+        }
+
+        brick.execute(input, config)
+            .await
+            .map_err(FlowError::BrickError)
+    }
+
+    async fn run_fallback(
+        fallback: &BrickFallback,
+        brick_index: usize,
+        input: Value,
+        context: Option<&FlowRunnerContext>,
+    ) -> Result<Value, FlowError> {
+        match fallback {
+            BrickFallback::Value(value) => Ok(value.clone()),
+            BrickFallback::Brick(brick_config) => {
+                let registry = context.and_then(|ctx| ctx.brick_registry.as_ref()).ok_or_else(|| {
+                    FlowError::InvalidFlow(format!(
+                        "No brick registry configured to create fallback brick {}",
+                        brick_config.brick_type.id()
+                    ))
+                })?;
+                let brick = registry.create(&brick_config.brick_type)?;
+                let config = Self::render_config(brick.as_ref(), &brick_config.config, brick_index, &input, context).await?;
+                Self::run_brick(brick.as_ref(), &config, input, context).await
+            }
+        }
+    }
+
+    /// Stores the outcome of one attempt of a brick with a policy
+    fn record_attempt(
+        brick: &dyn Brick,
+        brick_index: usize,
+        attempt: u32,
+        duration: std::time::Duration,
+        result: &Result<Value, FlowError>,
+        context: Option<&FlowRunnerContext>,
+    ) {
+        let record = serde_json::json!({
+            "attempt": attempt,
+            "status": if result.is_ok() { "succeeded" } else { "failed" },
+            "duration_ms": duration.as_millis() as u64,
+            "error": result.as_ref().err().map(|e| e.to_string()),
+        });
+        Self::store_execution_data(
+            brick,
+            brick_index,
+            "attempt",
+            format!("attempt_{}_{}", brick_index, attempt),
+            record,
+            context,
+        );
+    }
+
+    /// Stores the output of a brick restored from a resumed execution
+    pub(crate) fn record_restored_output(
+        brick: &dyn Brick,
+        brick_index: usize,
+        output: &Value,
+        context: Option<&FlowRunnerContext>,
+    ) {
+        Self::store_execution_data(
+            brick,
+            brick_index,
+            "intermediate",
+            format!("brick_{}", brick_index),
+            output.clone(),
+            context,
+        );
+    }
+
+    /// Stores execution data in the background (non-blocking)
+    fn store_execution_data(
+        brick: &dyn Brick,
+        brick_index: usize,
+        data_type: &'static str,
+        data_key: String,
+        data_value: Value,
+        context: Option<&FlowRunnerContext>,
+    ) {
+        let (execution_id, data_storage, data_value) = match context {
+            Some(ctx) => match ctx.execution_data_storage {
+                Some(ref data_storage) => (ctx.execution_id.clone(), data_storage.clone(), ctx.redactor.redact(&data_value)),
+                None => return,
+            },
+            None => return,
+        };
+        let brick_type = brick.brick_type();
+        let semaphore = get_storage_semaphore();
+
+        tokio::spawn(async move {
+            let _permit = match semaphore.acquire().await {
+                Ok(p) => p,
+                Err(_) => return,
+            };
+
+            let _ = data_storage.store_data(
+                &execution_id,
+                brick_index,
+                &brick_type,
+                data_type,
+                &data_key,
+                data_value,
+            ).await;
+        });
+    }
+
+    /// Hands a response requested through `_respond` metadata to the caller
+    /// of the flow's endpoint, if one is waiting
+    pub(crate) fn send_response(result: &mut Value, context: Option<&FlowRunnerContext>) -> Result<(), FlowError> {
+        let response = match result.as_object_mut().and_then(|obj| obj.remove("_respond")) {
+            Some(response) => response,
+            None => return Ok(()),
+        };
+        let mut response: FlowResponse = serde_json::from_value(response)
+            .map_err(|e| FlowError::ResponseError(e.to_string()))?;
+        response.validate().map_err(FlowError::ResponseError)?;
+
+        if let Some(ctx) = context {
+            // Callers of endpoints see no more of the secrets than readers of the execution
+            ctx.redactor.redact_response(&mut response);
+        }
+        if let Some(responder) = context.and_then(|ctx| ctx.responder.as_ref()) {
+            if !responder.respond(response) {
+                tracing::debug!("Ignoring response of a flow that has already responded");
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the sub-flow requested through `_branch` metadata and merges its
+    /// output into `result`. Returns the branch mode when a sub-flow ran.
+    pub(crate) async fn run_branch(
+        result: &mut Value,
+        context: Option<&FlowRunnerContext>,
+    ) -> Result<Option<BranchMode>, FlowError> {
+        let branch = match result.as_object_mut().and_then(|obj| obj.remove("_branch")) {
+            Some(branch) => branch,
+            None => return Ok(None),
+        };
+
+        let flow_id = branch.get("flow_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| FlowError::SubFlowError("Branch is missing flow_id".to_string()))?;
+        let mode: BranchMode = match branch.get("mode") {
+            Some(mode) => serde_json::from_value(mode.clone())
+                .map_err(|e| FlowError::SubFlowError(format!("Invalid branch mode: {}", e)))?,
+            None => BranchMode::default(),
+        };
+
+        let ctx = context.ok_or_else(|| {
+            FlowError::SubFlowError(format!("Cannot branch to flow {} without an execution context", flow_id))
+        })?;
+        let executor = ctx.sub_flow_executor.as_ref().ok_or_else(|| {
+            FlowError::SubFlowError(format!("No sub-flow executor configured to branch to flow {}", flow_id))
+        })?;
+        if ctx.depth >= MAX_SUB_FLOW_DEPTH {
+            return Err(FlowError::SubFlowDepthExceeded(MAX_SUB_FLOW_DEPTH));
+        }
+
+        let output = executor.execute_sub_flow(flow_id, result.clone(), ctx).await?;
+
+        // Merge sub-flow output back into the payload
+        match (result.as_object_mut(), output) {
+            (Some(obj), Value::Object(sub_obj)) => obj.extend(sub_obj),
+            (_, output) => *result = output,
+        }
+
+        Ok(Some(mode))
+    }
+
+    /// Records usage, quota consumption and execution data for a brick result
+    pub(crate) async fn record_brick_result(
+        brick: &dyn Brick,
+        brick_index: usize,
+        result: &Value,
+        context: Option<&FlowRunnerContext>,
+    ) {
+        let ctx = match context {
+            Some(ctx) => ctx,
+            None => return,
+        };
+        let brick_type = brick.brick_type();
+
+        // Extract cost and token usage from result
+        let (cost_unit, token_usage) = Self::extract_execution_metadata(result, &brick_type);
+
+        // Record usage after execution
+        if let Some(ref usage_logger) = ctx.usage_logger {
+            let _ = usage_logger.record_usage(
+                brick.name(),
+                &brick_type,
+                &ctx.flow_id,
+                &ctx.execution_id,
+                cost_unit,
+                token_usage,
+                None,
+            ).await;
+        }
+
+        // Record usage in quota manager
+        if let Some(ref quota_manager) = ctx.quota_manager {
+            let _ = quota_manager.record_usage(&brick_type, cost_unit, token_usage).await;
+        }
+
+        // Store execution data asynchronously (non-blocking)
+        if let Some(ref data_storage) = ctx.execution_data_storage {
+            let execution_id = ctx.execution_id.clone();
+            // Redacting copies the result once before spawning the task
+            let result_for_storage = ctx.redactor.redact(result);
+            let is_api_fetch = Self::is_api_fetch_brick(&brick_type);
+            let data_storage_clone = data_storage.clone();
+            let semaphore = get_storage_semaphore();
+
+            // Spawn background task to store data without blocking flow execution
+            // Use semaphore to limit concurrent tasks and prevent resource exhaustion
+            tokio::spawn(async move {
+                // Acquire permit from semaphore (waits if limit reached)
+                let _permit = match semaphore.acquire().await {
+                    Ok(p) => p,
+                    Err(_) => {
+                        // Semaphore closed, skip storage
+                        return;
+                    }
+                };
+
+                // Store intermediate output
+                let _ = data_storage_clone.store_data(
+                    &execution_id,
+                    brick_index,
+                    &brick_type,
+                    "intermediate",
+                    &format!("brick_{}", brick_index),
+                    result_for_storage.clone(),
+                ).await;
+
+                // If brick fetches data from external API, store as "fetched" with same data
+                // Reuse the already-cloned result_for_storage instead of cloning again
+                if is_api_fetch {
+                    let _ = data_storage_clone.store_data(
+                        &execution_id,
+                        brick_index,
+                        &brick_type,
+                        "fetched",
+                        &format!("fetched_{}", brick_index),
+                        result_for_storage, // Move ownership instead of cloning again
+                    ).await;
+                }
+                // Permit is automatically released when dropped
+            });
         }
     }
 
@@ -370,5 +1093,767 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), json!({"step": 2}));
     }
-}
 
+    struct MergeInputBrick {
+        output: Value,
+    }
+
+    #[async_trait]
+    impl Brick for MergeInputBrick {
+        fn name(&self) -> &'static str {
+            "merge_input"
+        }
+
+        fn brick_type(&self) -> BrickType {
+            BrickType::FieldMapping
+        }
+
+        fn config_schema(&self) -> Value {
+            json!({})
+        }
+
+        async fn execute(&self, input: Value, _config: Value) -> Result<Value, BrickError> {
+            let mut output = input;
+            if let (Some(obj), Some(extra)) = (output.as_object_mut(), self.output.as_object()) {
+                obj.extend(extra.clone());
+            }
+            Ok(output)
+        }
+    }
+
+    struct MockSubFlowExecutor;
+
+    #[async_trait]
+    impl SubFlowExecutor for MockSubFlowExecutor {
+        async fn execute_sub_flow(
+            &self,
+            flow_id: &str,
+            payload: Value,
+            parent: &FlowRunnerContext,
+        ) -> Result<Value, FlowError> {
+            let child = parent.child(flow_id)?;
+            if flow_id == "recursive" {
+                // Branch back into the same flow until the depth guard trips
+                let mut result = json!({ "_branch": { "flow_id": "recursive" } });
+                FlowRunner::run_branch(&mut result, Some(&child)).await?;
+                return Ok(result);
+            }
+            Ok(json!({
+                "sub_flow": flow_id,
+                "parent": child.parent_execution_id,
+                "seen_input": payload.get("start").is_some(),
+            }))
+        }
+    }
+
+    fn branch_context() -> FlowRunnerContext {
+        FlowRunnerContext {
+            quota_manager: None,
+            usage_logger: None,
+            execution_data_storage: None,
+            sub_flow_executor: Some(Arc::new(MockSubFlowExecutor)),
+            brick_registry: None,
+            event_bus: None,
+            flow_id: "parent-flow".to_string(),
+            execution_id: "parent-exec".to_string(),
+            workspace_id: None,
+            parent_execution_id: None,
+            depth: 0,
+            variables: Default::default(),
+            secret_resolver: None,
+            connection_resolver: None,
+            redactor: Default::default(),
+            responder: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_branch_inline_merges_and_continues() {
+        let bricks: Vec<Box<dyn Brick>> = vec![
+            Box::new(MergeInputBrick {
+                output: json!({ "_branch": { "flow_id": "child", "mode": "inline" } }),
+            }),
+            Box::new(MergeInputBrick { output: json!({ "after": true }) }),
+        ];
+        let configs = vec![json!({}), json!({})];
+
+        let result = FlowRunner::execute_flow(bricks, configs, json!({ "start": true }), Some(branch_context()))
+            .await
+            .unwrap();
+
+        assert_eq!(result["sub_flow"], "child");
+        assert_eq!(result["parent"], "parent-exec");
+        assert_eq!(result["seen_input"], true);
+        assert_eq!(result["after"], true);
+        assert!(result.get("_branch").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_branch_replace_skips_remaining_bricks() {
+        let bricks: Vec<Box<dyn Brick>> = vec![
+            Box::new(MergeInputBrick {
+                output: json!({ "_branch": { "flow_id": "child", "mode": "replace" } }),
+            }),
+            Box::new(MergeInputBrick { output: json!({ "after": true }) }),
+        ];
+        let configs = vec![json!({}), json!({})];
+
+        let result = FlowRunner::execute_flow(bricks, configs, json!({ "start": true }), Some(branch_context()))
+            .await
+            .unwrap();
+
+        assert_eq!(result["sub_flow"], "child");
+        assert!(result.get("after").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_branch_depth_is_limited() {
+        let bricks: Vec<Box<dyn Brick>> = vec![Box::new(MergeInputBrick {
+            output: json!({ "_branch": { "flow_id": "recursive" } }),
+        })];
+
+        let result = FlowRunner::execute_flow(bricks, vec![json!({})], json!({}), Some(branch_context())).await;
+        assert!(matches!(result, Err(FlowError::SubFlowDepthExceeded(MAX_SUB_FLOW_DEPTH))));
+    }
+
+    #[tokio::test]
+    async fn test_response_is_sent_before_remaining_bricks() {
+        let (responder, receiver) = FlowResponder::channel();
+        let mut context = branch_context();
+        context.responder = Some(responder);
+        let bricks: Vec<Box<dyn Brick>> = vec![
+            Box::new(MergeInputBrick {
+                output: json!({ "_respond": { "status": 202, "body": { "type": "text", "value": "queued" } } }),
+            }),
+            Box::new(MergeInputBrick { output: json!({ "after": true }) }),
+        ];
+        let configs = vec![json!({}), json!({})];
+
+        let result = FlowRunner::execute_flow(bricks, configs, json!({ "start": true }), Some(context))
+            .await
+            .unwrap();
+
+        let response = receiver.await.unwrap();
+        assert_eq!(response.status, 202);
+        assert_eq!(response.body, crate::response::ResponseBody::Text("queued".to_string()));
+        assert_eq!(result, json!({ "start": true, "after": true }));
+
+        // Invalid responses fail the brick that produced them
+        let bricks: Vec<Box<dyn Brick>> = vec![Box::new(MergeInputBrick {
+            output: json!({ "_respond": { "status": 200, "body": { "type": "redirect", "value": "/done" } } }),
+        })];
+        let result = FlowRunner::execute_flow(bricks, vec![json!({})], json!({}), None).await;
+        assert!(matches!(result, Err(FlowError::ResponseError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_branch_without_executor_fails() {
+        let bricks: Vec<Box<dyn Brick>> = vec![Box::new(MergeInputBrick {
+            output: json!({ "_branch": { "flow_id": "child" } }),
+        })];
+
+        let result = FlowRunner::execute_flow(bricks, vec![json!({})], json!({}), None).await;
+        assert!(matches!(result, Err(FlowError::SubFlowError(_))));
+    }
+
+    struct FailingBrick;
+
+    #[async_trait]
+    impl Brick for FailingBrick {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn brick_type(&self) -> BrickType {
+            BrickType::FieldMapping
+        }
+
+        fn config_schema(&self) -> Value {
+            json!({})
+        }
+
+        async fn execute(&self, _input: Value, _config: Value) -> Result<Value, BrickError> {
+            Err(BrickError::ExecutionError("boom".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_execution_is_recorded() {
+        let flow = Flow {
+            id: "failing-flow".to_string(),
+            name: "Failing".to_string(),
+            description: None,
+            bricks: vec![crate::types::BrickConfig {
+                brick_type: BrickType::FieldMapping,
+                config: json!({}),
+                policy: None,
+            }],
+            graph: None,
+            retry_policy: None,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: None,
+            active: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let mut context = branch_context();
+        context.execution_id = "preset-exec".to_string();
+
+        let execution = FlowRunner::execute_flow_recorded(
+            &flow,
+            vec![Box::new(FailingBrick)],
+            json!({"a": 1}),
+            Some(context),
+        )
+        .await;
+
+        assert_eq!(execution.execution_id, "preset-exec");
+        assert_eq!(execution.status, ExecutionStatus::Failed);
+        assert!(execution.error.unwrap().contains("boom"));
+        assert!(execution.completed_at.is_some());
+        assert_eq!(execution.input_payload, json!({"a": 1}));
+    }
+
+    #[tokio::test]
+    async fn test_payloads_are_validated_against_flow_schema() {
+        let mut flow = Flow {
+            id: "schema-flow".to_string(),
+            name: "Schema".to_string(),
+            description: None,
+            bricks: vec![crate::types::BrickConfig {
+                brick_type: BrickType::FieldMapping,
+                config: json!({}),
+                policy: None,
+            }],
+            graph: None,
+            retry_policy: None,
+            variables: Default::default(),
+            schema: crate::types::FlowSchema {
+                input: Some(json!({
+                    "type": "object",
+                    "required": ["email"],
+                    "properties": { "email": { "type": "string", "format": "email" } }
+                })),
+                output: Some(json!({ "type": "object", "required": ["lead_id"] })),
+            },
+            workspace_id: None,
+            active: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        let brick = || -> Vec<Box<dyn Brick>> { vec![Box::new(MergeInputBrick { output: json!({ "lead_id": 7 }) })] };
+
+        // Invalid input never reaches a brick
+        let execution = FlowRunner::execute_flow_recorded(&flow, brick(), json!({ "email": 42 }), None).await;
+        assert_eq!(execution.status, ExecutionStatus::Failed);
+        assert!(execution.steps.is_empty());
+        assert!(execution.error.unwrap().contains("/email"));
+
+        let execution = FlowRunner::execute_flow_recorded(&flow, brick(), json!({ "email": "a@example.com" }), None).await;
+        assert_eq!(execution.status, ExecutionStatus::Completed);
+
+        // Output is checked once the bricks have run
+        flow.schema.output = Some(json!({ "type": "object", "required": ["contact_id"] }));
+        let execution = FlowRunner::execute_flow_recorded(&flow, brick(), json!({ "email": "a@example.com" }), None).await;
+        assert_eq!(execution.status, ExecutionStatus::Failed);
+        assert_eq!(execution.steps.len(), 1);
+        assert!(execution.error.unwrap().starts_with("Output does not match"));
+    }
+
+    struct FlakyBrick {
+        failures: u32,
+        calls: std::sync::atomic::AtomicU32,
+    }
+
+    #[async_trait]
+    impl Brick for FlakyBrick {
+        fn name(&self) -> &'static str {
+            "flaky"
+        }
+
+        fn brick_type(&self) -> BrickType {
+            BrickType::FieldMapping
+        }
+
+        fn config_schema(&self) -> Value {
+            json!({})
+        }
+
+        async fn execute(&self, _input: Value, _config: Value) -> Result<Value, BrickError> {
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            if call <= self.failures {
+                return Err(BrickError::NetworkError(format!("call {} failed", call)));
+            }
+            Ok(json!({"calls": call}))
+        }
+    }
+
+    struct SlowBrick;
+
+    #[async_trait]
+    impl Brick for SlowBrick {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        fn brick_type(&self) -> BrickType {
+            BrickType::FieldMapping
+        }
+
+        fn config_schema(&self) -> Value {
+            json!({})
+        }
+
+        async fn execute(&self, input: Value, _config: Value) -> Result<Value, BrickError> {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            Ok(input)
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordingStorage {
+        records: std::sync::Mutex<Vec<(String, String, Value)>>,
+    }
+
+    #[async_trait]
+    impl ExecutionDataStorage for RecordingStorage {
+        async fn store_data(
+            &self,
+            _execution_id: &str,
+            _brick_index: usize,
+            _brick_type: &BrickType,
+            data_type: &str,
+            data_key: &str,
+            data_value: Value,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.records.lock().unwrap().push((data_type.to_string(), data_key.to_string(), data_value));
+            Ok(())
+        }
+    }
+
+    fn flow_with_policies(policies: Vec<Option<BrickPolicy>>, as_graph: bool) -> Flow {
+        let bricks: Vec<crate::types::BrickConfig> = policies.into_iter()
+            .map(|policy| crate::types::BrickConfig {
+                brick_type: BrickType::FieldMapping,
+                config: json!({}),
+                policy,
+            })
+            .collect();
+        Flow {
+            id: "policy-flow".to_string(),
+            name: "Policy".to_string(),
+            description: None,
+            graph: as_graph.then(|| crate::types::FlowGraph::from_linear(&bricks)),
+            bricks,
+            retry_policy: None,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: None,
+            active: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_brick_policy_retries_and_records_attempts() {
+        let storage = Arc::new(RecordingStorage::default());
+        let mut context = branch_context();
+        context.execution_data_storage = Some(storage.clone());
+        let policy = BrickPolicy { max_attempts: 3, initial_backoff_ms: 0, ..BrickPolicy::default() };
+
+        let execution = FlowRunner::execute_flow_recorded(
+            &flow_with_policies(vec![Some(policy)], false),
+            vec![Box::new(FlakyBrick { failures: 2, calls: Default::default() })],
+            json!({}),
+            Some(context),
+        )
+        .await;
+
+        assert_eq!(execution.status, ExecutionStatus::Completed);
+        assert_eq!(execution.output_payload.unwrap()["calls"], 3);
+
+        // Attempts are stored in the background
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let records = storage.records.lock().unwrap();
+        let mut attempts: Vec<&Value> = records.iter()
+            .filter(|(data_type, _, _)| data_type == "attempt")
+            .map(|(_, _, value)| value)
+            .collect();
+        attempts.sort_by_key(|a| a["attempt"].as_u64());
+        let statuses: Vec<&str> = attempts.iter().map(|a| a["status"].as_str().unwrap()).collect();
+        assert_eq!(statuses, vec!["failed", "failed", "succeeded"]);
+        assert!(attempts[0]["error"].as_str().unwrap().contains("call 1 failed"));
+    }
+
+    #[tokio::test]
+    async fn test_brick_policy_timeout_uses_fallback_value() {
+        let policy = BrickPolicy {
+            timeout_ms: Some(10),
+            fallback: Some(BrickFallback::Value(json!({"fallback": true}))),
+            ..BrickPolicy::default()
+        };
+
+        let execution = FlowRunner::execute_flow_recorded(
+            &flow_with_policies(vec![Some(policy)], true),
+            vec![Box::new(SlowBrick)],
+            json!({}),
+            None,
+        )
+        .await;
+
+        assert_eq!(execution.status, ExecutionStatus::Completed);
+        assert_eq!(execution.output_payload.unwrap(), json!({"fallback": true}));
+    }
+
+    #[tokio::test]
+    async fn test_brick_policy_fallback_brick_and_continue_on_error() {
+        let mut registry = BrickRegistry::new();
+        registry.register("fallback", || Box::new(MergeInputBrick { output: json!({"fallback": true}) }));
+        let mut context = branch_context();
+        context.brick_registry = Some(Arc::new(registry));
+
+        let fallback_policy = BrickPolicy {
+            fallback: Some(BrickFallback::Brick(Box::new(crate::types::BrickConfig {
+                brick_type: BrickType::Custom("fallback".to_string()),
+                config: json!({}),
+                policy: None,
+            }))),
+            ..BrickPolicy::default()
+        };
+        let continue_policy = BrickPolicy { continue_on_error: true, ..BrickPolicy::default() };
+
+        let execution = FlowRunner::execute_flow_recorded(
+            &flow_with_policies(vec![Some(fallback_policy), Some(continue_policy), None], true),
+            vec![
+                Box::new(FailingBrick),
+                Box::new(FailingBrick),
+                Box::new(MergeInputBrick { output: json!({"after": true}) }),
+            ],
+            json!({"start": true}),
+            Some(context),
+        )
+        .await;
+
+        assert_eq!(execution.status, ExecutionStatus::Completed);
+        assert_eq!(
+            execution.output_payload.unwrap(),
+            json!({"start": true, "fallback": true, "after": true})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_brick_without_policy_fails_flow() {
+        let execution = FlowRunner::execute_flow_recorded(
+            &flow_with_policies(vec![None], true),
+            vec![Box::new(FlakyBrick { failures: 1, calls: Default::default() })],
+            json!({}),
+            None,
+        )
+        .await;
+
+        assert_eq!(execution.status, ExecutionStatus::Failed);
+        assert!(execution.error.unwrap().contains("call 1 failed"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_skips_completed_bricks() {
+        for as_graph in [false, true] {
+            let completed = HashMap::from([
+                (0, json!({"step": 1})),
+                (1, json!({"step": 2})),
+            ]);
+
+            let execution = FlowRunner::resume_flow_recorded(
+                &flow_with_policies(vec![None, None, None], as_graph),
+                vec![
+                    Box::new(FailingBrick),
+                    Box::new(FailingBrick),
+                    Box::new(MergeInputBrick { output: json!({"resumed": true}) }),
+                ],
+                json!({"start": true}),
+                completed,
+                None,
+            )
+            .await;
+
+            assert_eq!(execution.status, ExecutionStatus::Completed, "graph: {}", as_graph);
+            assert_eq!(execution.output_payload.unwrap(), json!({"step": 2, "resumed": true}));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execution_records_steps() {
+        for as_graph in [false, true] {
+            let policy = BrickPolicy { max_attempts: 2, initial_backoff_ms: 0, ..BrickPolicy::default() };
+
+            let execution = FlowRunner::execute_flow_recorded(
+                &flow_with_policies(vec![Some(policy), None, None], as_graph),
+                vec![
+                    Box::new(FlakyBrick { failures: 1, calls: Default::default() }),
+                    Box::new(MergeInputBrick { output: json!({"merged": true}) }),
+                    Box::new(FailingBrick),
+                ],
+                json!({"start": true}),
+                None,
+            )
+            .await;
+
+            assert_eq!(execution.status, ExecutionStatus::Failed, "graph: {}", as_graph);
+            let steps = &execution.steps;
+            let statuses: Vec<StepStatus> = steps.iter().map(|s| s.status).collect();
+            assert_eq!(statuses, vec![StepStatus::Succeeded, StepStatus::Succeeded, StepStatus::Failed]);
+            let attempts: Vec<u32> = steps.iter().map(|s| s.attempts).collect();
+            assert_eq!(attempts, vec![2, 1, 1]);
+            assert_eq!(steps[0].input, json!({"start": true}));
+            assert_eq!(steps[1].input, json!({"calls": 2}));
+            assert_eq!(steps[1].output, Some(json!({"calls": 2, "merged": true})));
+            assert_eq!(steps[2].output, None);
+            assert!(steps[2].error.is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execution_publishes_events() {
+        let event_bus = Arc::new(ExecutionEventBus::new());
+        let mut context = branch_context();
+        context.event_bus = Some(event_bus.clone());
+        context.execution_id = "exec-events".to_string();
+
+        let execution = FlowRunner::execute_flow_recorded(
+            &flow_with_policies(vec![None, None], false),
+            vec![
+                Box::new(MergeInputBrick { output: json!({"merged": true}) }),
+                Box::new(FailingBrick),
+            ],
+            json!({}),
+            Some(context),
+        )
+        .await;
+        assert_eq!(execution.status, ExecutionStatus::Failed);
+
+        let events = event_bus.subscribe("exec-events").unwrap().history;
+        let names: Vec<&str> = events.iter().map(|e| e.kind.name()).collect();
+        assert_eq!(names, vec![
+            "execution_started",
+            "brick_started",
+            "brick_completed",
+            "brick_started",
+            "brick_failed",
+            "execution_finished",
+        ]);
+        assert!(matches!(
+            events[5].kind,
+            ExecutionEventKind::ExecutionFinished { status: ExecutionStatus::Failed, error: Some(_) }
+        ));
+    }
+
+    struct ConfigEchoBrick;
+
+    #[async_trait]
+    impl Brick for ConfigEchoBrick {
+        fn name(&self) -> &'static str {
+            "config_echo"
+        }
+
+        fn brick_type(&self) -> BrickType {
+            BrickType::FieldMapping
+        }
+
+        fn config_schema(&self) -> Value {
+            json!({})
+        }
+
+        async fn execute(&self, _input: Value, config: Value) -> Result<Value, BrickError> {
+            Ok(config)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_brick_configs_are_rendered_as_templates() {
+        for as_graph in [false, true] {
+            let mut flow = flow_with_policies(vec![None], false);
+            flow.variables.insert("greeting".to_string(), json!("Hello"));
+            flow.bricks[0].config = json!({
+                "prompt": "{{ vars.greeting }} {{ customer.name | upper }} ({{ execution.flow_id }} #{{ execution.brick_index }})",
+                "items": "{{ customer.items }}"
+            });
+            flow.graph = as_graph.then(|| crate::types::FlowGraph::from_linear(&flow.bricks));
+
+            let execution = FlowRunner::execute_flow_recorded(
+                &flow,
+                vec![Box::new(ConfigEchoBrick)],
+                json!({"customer": {"name": "Ada", "items": [1, 2]}}),
+                None,
+            )
+            .await;
+            assert_eq!(execution.output_payload, Some(json!({
+                "prompt": "Hello ADA (policy-flow #0)",
+                "items": [1, 2]
+            })), "graph: {}", as_graph);
+
+            flow.bricks[0].config = json!({"prompt": "{{ customer.missing }}"});
+            flow.graph = as_graph.then(|| crate::types::FlowGraph::from_linear(&flow.bricks));
+            let execution = FlowRunner::execute_flow_recorded(&flow, vec![Box::new(ConfigEchoBrick)], json!({}), None).await;
+            assert_eq!(execution.status, ExecutionStatus::Failed);
+            assert!(execution.error.unwrap().contains("Configuration error"), "graph: {}", as_graph);
+        }
+    }
+
+    struct MockSecretResolver;
+
+    #[async_trait]
+    impl SecretResolver for MockSecretResolver {
+        async fn resolve_secret(
+            &self,
+            _workspace_id: Option<&str>,
+            name: &str,
+        ) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok((name == "openai").then(|| "sk-secret-value".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_secrets_are_redacted_from_responses() {
+        let mut flow = flow_with_policies(vec![None], false);
+        flow.bricks[0].config = json!({"_respond": {
+            "status": 200,
+            "headers": {"X-Key": "{{ secret:openai }}"},
+            "body": {"type": "text", "value": "key {{ secret:openai }}"}
+        }});
+        let (responder, receiver) = FlowResponder::channel();
+        let mut context = branch_context();
+        context.secret_resolver = Some(Arc::new(MockSecretResolver));
+        context.responder = Some(responder);
+
+        let execution = FlowRunner::execute_flow_recorded(&flow, vec![Box::new(ConfigEchoBrick)], json!({}), Some(context)).await;
+        assert_eq!(execution.status, ExecutionStatus::Completed);
+        let response = receiver.await.unwrap();
+        assert_eq!(response.headers["X-Key"], "[REDACTED]");
+        assert_eq!(response.body, crate::response::ResponseBody::Text("key [REDACTED]".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_secret_references_are_resolved_and_redacted() {
+        let mut flow = flow_with_policies(vec![None], false);
+        flow.bricks[0].config = json!({"api_key": "{{ secret:openai }}", "header": "Bearer {{ secret:openai }}"});
+        let mut context = branch_context();
+        context.secret_resolver = Some(Arc::new(MockSecretResolver));
+        let redactor = context.redactor.clone();
+
+        let execution = FlowRunner::execute_flow_recorded(
+            &flow,
+            vec![Box::new(ConfigEchoBrick)],
+            json!({}),
+            Some(context),
+        )
+        .await;
+        assert_eq!(execution.status, ExecutionStatus::Completed);
+        // The brick saw the plaintext value, the record does not
+        assert_eq!(redactor.redact_str("sk-secret-value"), "[REDACTED]");
+        assert_eq!(
+            execution.output_payload,
+            Some(json!({"api_key": "[REDACTED]", "header": "Bearer [REDACTED]"}))
+        );
+        assert_eq!(execution.steps[0].output, execution.output_payload);
+
+        flow.bricks[0].config = json!({"api_key": "{{ secret:missing }}"});
+        let mut context = branch_context();
+        context.secret_resolver = Some(Arc::new(MockSecretResolver));
+        let execution = FlowRunner::execute_flow_recorded(&flow, vec![Box::new(ConfigEchoBrick)], json!({}), Some(context)).await;
+        assert_eq!(execution.status, ExecutionStatus::Failed);
+        assert!(execution.error.unwrap().contains("Unknown secret 'missing'"));
+
+        // Without a secrets store references cannot be resolved
+        let execution = FlowRunner::execute_flow_recorded(&flow, vec![Box::new(ConfigEchoBrick)], json!({}), None).await;
+        assert!(execution.error.unwrap().contains("No secrets store configured"));
+    }
+
+    /// Echoes its config; takes `token` from `crm` connections
+    struct ConnectionEchoBrick;
+
+    #[async_trait]
+    impl Brick for ConnectionEchoBrick {
+        fn name(&self) -> &'static str {
+            "connection_echo"
+        }
+
+        fn brick_type(&self) -> BrickType {
+            BrickType::FieldMapping
+        }
+
+        fn config_schema(&self) -> Value {
+            json!({})
+        }
+
+        async fn execute(&self, _input: Value, config: Value) -> Result<Value, BrickError> {
+            Ok(config)
+        }
+
+        fn connection_spec(&self) -> Option<crate::ConnectionSpec> {
+            Some(crate::ConnectionSpec {
+                connection_type: "crm",
+                base_url_field: Some("url"),
+                credential_fields: &["token"],
+            })
+        }
+    }
+
+    struct MockConnectionResolver;
+
+    #[async_trait]
+    impl ConnectionResolver for MockConnectionResolver {
+        async fn resolve_connection(
+            &self,
+            _workspace_id: Option<&str>,
+            id: &str,
+        ) -> Result<Option<crate::ResolvedConnection>, Box<dyn std::error::Error + Send + Sync>> {
+            Ok(match id {
+                "crm-prod" | "mail" => Some(crate::ResolvedConnection {
+                    id: id.to_string(),
+                    connection_type: if id == "mail" { "smtp" } else { "crm" }.to_string(),
+                    base_url: Some("https://crm.example.com".to_string()),
+                    credentials: json!({"token": "crm-token-value"}).as_object().unwrap().clone(),
+                    defaults: json!({"limit": 10}).as_object().unwrap().clone(),
+                }),
+                _ => None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_connections_fill_brick_configs() {
+        let mut flow = flow_with_policies(vec![None], false);
+        flow.variables.insert("crm".to_string(), json!("crm-prod"));
+        flow.bricks[0].config = json!({"connection_id": "{{ vars.crm }}", "limit": 5});
+        let run = |flow: Flow, context: Option<FlowRunnerContext>| async move {
+            FlowRunner::execute_flow_recorded(&flow, vec![Box::new(ConnectionEchoBrick)], json!({}), context).await
+        };
+        let context = || {
+            let mut context = branch_context();
+            context.connection_resolver = Some(Arc::new(MockConnectionResolver));
+            context
+        };
+
+        let execution = run(flow.clone(), Some(context())).await;
+        assert_eq!(execution.status, ExecutionStatus::Completed);
+        assert_eq!(execution.output_payload, Some(json!({
+            "limit": 5,
+            "token": "[REDACTED]",
+            "url": "https://crm.example.com"
+        })));
+
+        for (connection_id, error) in [
+            ("missing", "Unknown connection 'missing'"),
+            ("mail", "Connection 'mail' is a smtp connection, expected crm"),
+        ] {
+            flow.bricks[0].config = json!({"connection_id": connection_id});
+            let execution = run(flow.clone(), Some(context())).await;
+            assert!(execution.error.unwrap().contains(error));
+        }
+
+        let execution = run(flow, None).await;
+        assert!(execution.error.unwrap().contains("No connection store configured"));
+    }
+}
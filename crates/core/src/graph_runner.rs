@@ -0,0 +1,556 @@
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crate::brick_traits::Brick;
+use crate::flow_runner::{FlowError, FlowRunner, FlowRunnerContext};
+use crate::rules_engine::{RulesEngine, RulesEngineError};
+use crate::types::{BranchMode, ExecutionStep, FlowEdge, FlowGraph, FlowNodeKind, JoinStrategy};
+
+/// Executes flows described as a directed acyclic graph of nodes
+///
+/// Nodes whose inputs are all resolved run concurrently. A node runs if at
+/// least one incoming edge was taken; when no incoming edge was taken the node
+/// is skipped and its outgoing edges are not taken either.
+pub struct GraphRunner;
+
+impl GraphRunner {
+    /// Validates a graph and returns its node indices in topological order
+    pub fn validate_graph(graph: &FlowGraph) -> Result<Vec<usize>, FlowError> {
+        let mut index_of: HashMap<&str, usize> = HashMap::with_capacity(graph.nodes.len());
+        for (index, node) in graph.nodes.iter().enumerate() {
+            if node.id.is_empty() {
+                return Err(FlowError::InvalidFlow("Node id cannot be empty".to_string()));
+            }
+            if index_of.insert(node.id.as_str(), index).is_some() {
+                return Err(FlowError::InvalidFlow(format!("Duplicate node id: {}", node.id)));
+            }
+        }
+
+        let mut in_degree = vec![0usize; graph.nodes.len()];
+        let mut successors: Vec<Vec<usize>> = vec![Vec::new(); graph.nodes.len()];
+        for edge in &graph.edges {
+            let from = *index_of.get(edge.from.as_str()).ok_or_else(|| {
+                FlowError::InvalidFlow(format!("Edge references unknown node: {}", edge.from))
+            })?;
+            let to = *index_of.get(edge.to.as_str()).ok_or_else(|| {
+                FlowError::InvalidFlow(format!("Edge references unknown node: {}", edge.to))
+            })?;
+            in_degree[to] += 1;
+            successors[from].push(to);
+        }
+
+        // Kahn's algorithm; any node left over is part of a cycle
+        let mut queue: VecDeque<usize> = (0..graph.nodes.len())
+            .filter(|&i| in_degree[i] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(graph.nodes.len());
+        while let Some(index) = queue.pop_front() {
+            order.push(index);
+            for &next in &successors[index] {
+                in_degree[next] -= 1;
+                if in_degree[next] == 0 {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        if order.len() != graph.nodes.len() {
+            return Err(FlowError::InvalidFlow("Flow graph contains a cycle".to_string()));
+        }
+
+        Ok(order)
+    }
+
+    /// Executes a flow graph with bricks keyed by node id
+    pub async fn execute_graph(
+        graph: &FlowGraph,
+        bricks: HashMap<String, Box<dyn Brick>>,
+        initial_payload: Value,
+        context: Option<FlowRunnerContext>,
+    ) -> Result<Value, FlowError> {
+        let mut steps = Vec::new();
+        Self::resume_graph(graph, bricks, initial_payload, HashMap::new(), &mut steps, context).await
+    }
+
+    /// Executes a flow graph, reusing the outputs of brick nodes that completed
+    /// in an earlier execution (keyed by node index) instead of running them
+    ///
+    /// A step record is appended to `steps` for every brick node that runs or
+    /// is skipped by a `SkipBricks` action.
+    pub(crate) async fn resume_graph(
+        graph: &FlowGraph,
+        bricks: HashMap<String, Box<dyn Brick>>,
+        initial_payload: Value,
+        mut completed: HashMap<usize, Value>,
+        steps: &mut Vec<ExecutionStep>,
+        context: Option<FlowRunnerContext>,
+    ) -> Result<Value, FlowError> {
+        Self::validate_graph(graph)?;
+
+        for node in &graph.nodes {
+            if matches!(node.kind, FlowNodeKind::Brick(_)) && !bricks.contains_key(&node.id) {
+                return Err(FlowError::InvalidFlow(format!(
+                    "No brick provided for node: {}",
+                    node.id
+                )));
+            }
+        }
+
+        if graph.nodes.is_empty() {
+            return Ok(initial_payload);
+        }
+
+        let node_count = graph.nodes.len();
+        let index_of: HashMap<&str, usize> = graph.nodes.iter()
+            .enumerate()
+            .map(|(i, n)| (n.id.as_str(), i))
+            .collect();
+        let mut incoming: Vec<Vec<usize>> = vec![Vec::new(); node_count];
+        let mut outgoing: Vec<Vec<usize>> = vec![Vec::new(); node_count];
+        for (edge_index, edge) in graph.edges.iter().enumerate() {
+            outgoing[index_of[edge.from.as_str()]].push(edge_index);
+            incoming[index_of[edge.to.as_str()]].push(edge_index);
+        }
+
+        // None = unresolved, Some(true) = taken, Some(false) = not taken
+        let mut edge_taken: Vec<Option<bool>> = vec![None; graph.edges.len()];
+        let mut outputs: Vec<Option<Value>> = vec![None; node_count];
+        // Remaining `_skip_bricks` budget carried along a node's outgoing edges
+        let mut skip_budget: Vec<usize> = vec![0; node_count];
+        let mut done: HashSet<usize> = HashSet::with_capacity(node_count);
+
+        while done.len() < node_count {
+            let ready: Vec<usize> = (0..node_count)
+                .filter(|i| !done.contains(i))
+                .filter(|&i| incoming[i].iter().all(|&e| edge_taken[e].is_some()))
+                .collect();
+
+            // Validation guarantees acyclicity, so there is always a ready node
+            if ready.is_empty() {
+                return Err(FlowError::InvalidFlow("Flow graph contains a cycle".to_string()));
+            }
+
+            let mut to_run: Vec<(usize, Value)> = Vec::new();
+            for index in ready {
+                let taken: Vec<usize> = incoming[index].iter()
+                    .copied()
+                    .filter(|&e| edge_taken[e] == Some(true))
+                    .collect();
+
+                if !incoming[index].is_empty() && taken.is_empty() {
+                    // No path reached this node: skip it and its downstream edges
+                    for &e in &outgoing[index] {
+                        edge_taken[e] = Some(false);
+                    }
+                    done.insert(index);
+                    continue;
+                }
+
+                let budget = taken.iter()
+                    .map(|&e| skip_budget[index_of[graph.edges[e].from.as_str()]])
+                    .max()
+                    .unwrap_or(0);
+
+                match &graph.nodes[index].kind {
+                    FlowNodeKind::Join { strategy } => {
+                        let output = if taken.is_empty() {
+                            initial_payload.clone()
+                        } else {
+                            Self::join_inputs(graph, &taken, &outputs, &index_of, strategy)
+                        };
+                        skip_budget[index] = budget;
+                        Self::resolve_outgoing(graph, &outgoing[index], &output, &mut edge_taken)?;
+                        outputs[index] = Some(output);
+                        done.insert(index);
+                    }
+                    FlowNodeKind::Brick(_) => {
+                        let input = if taken.is_empty() {
+                            initial_payload.clone()
+                        } else {
+                            Self::join_inputs(graph, &taken, &outputs, &index_of, &JoinStrategy::Merge)
+                        };
+
+                        if budget > 0 {
+                            // Skipped by an upstream SkipBricks action: pass the payload through
+                            skip_budget[index] = budget - 1;
+                            let brick = bricks[&graph.nodes[index].id].as_ref();
+                            steps.push(ExecutionStep::skipped(index, brick.brick_type(), input.clone()));
+                            Self::resolve_outgoing(graph, &outgoing[index], &input, &mut edge_taken)?;
+                            outputs[index] = Some(input);
+                            done.insert(index);
+                        } else if let Some(output) = completed.remove(&index) {
+                            // Completed by the execution being resumed
+                            let brick = bricks[&graph.nodes[index].id].as_ref();
+                            FlowRunner::record_restored_output(brick, index, &output, context.as_ref());
+                            Self::resolve_outgoing(graph, &outgoing[index], &output, &mut edge_taken)?;
+                            outputs[index] = Some(output);
+                            done.insert(index);
+                        } else {
+                            to_run.push((index, input));
+                        }
+                    }
+                }
+            }
+
+            // Run all ready bricks of this wave concurrently
+            let runs = to_run.into_iter().map(|(index, input)| {
+                let node = &graph.nodes[index];
+                let brick = bricks[&node.id].as_ref();
+                let context = context.as_ref();
+                async move {
+                    let brick_config = match &node.kind {
+                        FlowNodeKind::Brick(brick_config) => brick_config,
+                        FlowNodeKind::Join { .. } => unreachable!("join nodes are resolved inline"),
+                    };
+                    let (result, step) = FlowRunner::execute_brick(
+                        brick,
+                        index,
+                        &brick_config.config,
+                        brick_config.policy.as_ref(),
+                        input,
+                        context,
+                    ).await;
+                    (index, result, step)
+                }
+            });
+
+            // After the first failure the rest of the wave is only recorded
+            let mut failure: Option<FlowError> = None;
+            for (index, result, mut step) in futures::future::join_all(runs).await {
+                let mut result = match result {
+                    Ok(result) if failure.is_none() => result,
+                    Ok(result) => {
+                        step.output = Some(result);
+                        steps.push(step);
+                        continue;
+                    }
+                    Err(e) => {
+                        steps.push(step);
+                        failure.get_or_insert(e);
+                        continue;
+                    }
+                };
+
+                if let Some(obj) = result.as_object_mut() {
+                    if let Some(skip_value) = obj.remove("_skip_bricks") {
+                        if let Some(skip_num) = skip_value.as_u64() {
+                            skip_budget[index] = skip_num as usize;
+                        }
+                    }
+                }
+
+                if let Err(e) = FlowRunner::send_response(&mut result, context.as_ref()) {
+                    step.fail(e.to_string());
+                    steps.push(step);
+                    failure = Some(e);
+                    continue;
+                }
+
+                // Run a sub-flow requested by a Branch action
+                let branch_mode = match FlowRunner::run_branch(&mut result, context.as_ref()).await {
+                    Ok(branch_mode) => branch_mode,
+                    Err(e) => {
+                        step.fail(e.to_string());
+                        steps.push(step);
+                        failure = Some(e);
+                        continue;
+                    }
+                };
+
+                let brick = bricks[&graph.nodes[index].id].as_ref();
+                FlowRunner::record_brick_result(brick, index, &result, context.as_ref()).await;
+                step.output = Some(result.clone());
+                steps.push(step);
+
+                if branch_mode == Some(BranchMode::Replace) {
+                    // The sub-flow replaces everything downstream of this node
+                    for &e in &outgoing[index] {
+                        edge_taken[e] = Some(false);
+                    }
+                } else if let Err(e) = Self::resolve_outgoing(graph, &outgoing[index], &result, &mut edge_taken) {
+                    failure = Some(e);
+                    continue;
+                }
+                outputs[index] = Some(result);
+                done.insert(index);
+            }
+
+            if let Some(e) = failure {
+                return Err(e);
+            }
+        }
+
+        // Terminal nodes are executed nodes that did not pass their output on
+        let mut terminal: Vec<(usize, Value)> = Vec::new();
+        for (index, output) in outputs.into_iter().enumerate() {
+            if let Some(output) = output {
+                if !outgoing[index].iter().any(|&e| edge_taken[e] == Some(true)) {
+                    terminal.push((index, output));
+                }
+            }
+        }
+
+        match terminal.len() {
+            0 => Ok(Value::Null),
+            1 => Ok(terminal.pop().map(|(_, v)| v).unwrap_or(Value::Null)),
+            _ => {
+                let mut result = Map::with_capacity(terminal.len());
+                for (index, output) in terminal {
+                    result.insert(graph.nodes[index].id.clone(), output);
+                }
+                Ok(Value::Object(result))
+            }
+        }
+    }
+
+    /// Evaluates the conditions of a node's outgoing edges against its output
+    fn resolve_outgoing(
+        graph: &FlowGraph,
+        edges: &[usize],
+        output: &Value,
+        edge_taken: &mut [Option<bool>],
+    ) -> Result<(), FlowError> {
+        for &e in edges {
+            edge_taken[e] = Some(Self::edge_matches(&graph.edges[e], output)?);
+        }
+        Ok(())
+    }
+
+    fn edge_matches(edge: &FlowEdge, output: &Value) -> Result<bool, FlowError> {
+        match edge.condition {
+            None => Ok(true),
+            Some(ref condition) => match RulesEngine::evaluate_condition(condition, output) {
+                Ok(matched) => Ok(matched),
+                // A missing field means the condition does not hold
+                Err(RulesEngineError::FieldNotFound(_)) => Ok(false),
+                Err(e) => Err(FlowError::ConditionError(format!(
+                    "{} -> {}: {}",
+                    edge.from, edge.to, e
+                ))),
+            },
+        }
+    }
+
+    /// Combines the outputs arriving on taken edges according to a join strategy
+    fn join_inputs(
+        graph: &FlowGraph,
+        taken: &[usize],
+        outputs: &[Option<Value>],
+        index_of: &HashMap<&str, usize>,
+        strategy: &JoinStrategy,
+    ) -> Value {
+        let sources: Vec<(&str, &Value)> = taken.iter()
+            .filter_map(|&e| {
+                let from = graph.edges[e].from.as_str();
+                outputs[index_of[from]].as_ref().map(|v| (from, v))
+            })
+            .collect();
+
+        if sources.len() == 1 && matches!(strategy, JoinStrategy::Merge) {
+            return sources[0].1.clone();
+        }
+
+        match strategy {
+            JoinStrategy::Merge => {
+                // Shallow merge of objects in edge order; non-object inputs replace the result
+                let mut merged = Map::new();
+                let mut last_non_object = None;
+                for (_, value) in &sources {
+                    match value {
+                        Value::Object(obj) => {
+                            for (k, v) in obj {
+                                merged.insert(k.clone(), v.clone());
+                            }
+                        }
+                        other => last_non_object = Some((*other).clone()),
+                    }
+                }
+                last_non_object.unwrap_or(Value::Object(merged))
+            }
+            JoinStrategy::Array => {
+                Value::Array(sources.iter().map(|(_, v)| (*v).clone()).collect())
+            }
+            JoinStrategy::Keyed => {
+                let mut keyed = Map::with_capacity(sources.len());
+                for (from, value) in sources {
+                    keyed.insert(from.to_string(), value.clone());
+                }
+                Value::Object(keyed)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brick_traits::BrickError;
+    use crate::types::{BrickConfig, BrickType, FlowNode, Operator, RuleCondition};
+    use async_trait::async_trait;
+    use serde_json::json;
+
+    struct SetFieldBrick;
+
+    #[async_trait]
+    impl Brick for SetFieldBrick {
+        fn name(&self) -> &'static str {
+            "set_field"
+        }
+
+        fn brick_type(&self) -> BrickType {
+            BrickType::FieldMapping
+        }
+
+        fn config_schema(&self) -> Value {
+            json!({})
+        }
+
+        async fn execute(&self, input: Value, config: Value) -> Result<Value, BrickError> {
+            let mut output = input;
+            if let (Some(obj), Some(field)) = (output.as_object_mut(), config["field"].as_str()) {
+                obj.insert(field.to_string(), config["value"].clone());
+            }
+            Ok(output)
+        }
+    }
+
+    fn brick_node(id: &str, field: &str, value: Value) -> FlowNode {
+        FlowNode {
+            id: id.to_string(),
+            kind: FlowNodeKind::Brick(BrickConfig {
+                brick_type: BrickType::FieldMapping,
+                config: json!({ "field": field, "value": value }),
+                policy: None,
+            }),
+        }
+    }
+
+    fn edge(from: &str, to: &str) -> FlowEdge {
+        FlowEdge { from: from.to_string(), to: to.to_string(), condition: None }
+    }
+
+    fn bricks_for(graph: &FlowGraph) -> HashMap<String, Box<dyn Brick>> {
+        graph.nodes.iter()
+            .filter(|n| matches!(n.kind, FlowNodeKind::Brick(_)))
+            .map(|n| (n.id.clone(), Box::new(SetFieldBrick) as Box<dyn Brick>))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_fan_out_and_join() {
+        let graph = FlowGraph {
+            nodes: vec![
+                brick_node("start", "start", json!(true)),
+                brick_node("left", "left", json!(1)),
+                brick_node("right", "right", json!(2)),
+                FlowNode {
+                    id: "join".to_string(),
+                    kind: FlowNodeKind::Join { strategy: JoinStrategy::Merge },
+                },
+            ],
+            edges: vec![
+                edge("start", "left"),
+                edge("start", "right"),
+                edge("left", "join"),
+                edge("right", "join"),
+            ],
+        };
+
+        let result = GraphRunner::execute_graph(&graph, bricks_for(&graph), json!({}), None)
+            .await
+            .unwrap();
+        assert_eq!(result, json!({ "start": true, "left": 1, "right": 2 }));
+    }
+
+    #[tokio::test]
+    async fn test_conditional_edge_skips_branch() {
+        let mut graph = FlowGraph {
+            nodes: vec![
+                brick_node("start", "kind", json!("a")),
+                brick_node("a", "handled_by", json!("a")),
+                brick_node("b", "handled_by", json!("b")),
+            ],
+            edges: vec![edge("start", "a"), edge("start", "b")],
+        };
+        graph.edges[0].condition = Some(RuleCondition::Field {
+            path: "kind".to_string(),
+            operator: Operator::Equals,
+            value: json!("a"),
+        });
+        graph.edges[1].condition = Some(RuleCondition::Field {
+            path: "kind".to_string(),
+            operator: Operator::Equals,
+            value: json!("b"),
+        });
+
+        let result = GraphRunner::execute_graph(&graph, bricks_for(&graph), json!({}), None)
+            .await
+            .unwrap();
+        assert_eq!(result, json!({ "kind": "a", "handled_by": "a" }));
+    }
+
+    #[tokio::test]
+    async fn test_linear_graph_matches_flow_runner() {
+        let bricks = vec![
+            BrickConfig { brick_type: BrickType::FieldMapping, config: json!({ "field": "a", "value": 1 }), policy: None },
+            BrickConfig { brick_type: BrickType::FieldMapping, config: json!({ "field": "b", "value": 2 }), policy: None },
+        ];
+        let graph = FlowGraph::from_linear(&bricks);
+
+        let graph_result = GraphRunner::execute_graph(&graph, bricks_for(&graph), json!({}), None)
+            .await
+            .unwrap();
+        let linear_result = FlowRunner::execute_flow(
+            vec![Box::new(SetFieldBrick), Box::new(SetFieldBrick)],
+            bricks.iter().map(|b| b.config.clone()).collect(),
+            json!({}),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(graph_result, linear_result);
+    }
+
+    #[tokio::test]
+    async fn test_graph_from_json_with_keyed_join() {
+        let graph: FlowGraph = serde_json::from_value(json!({
+            "nodes": [
+                { "id": "a", "kind": "brick", "brick_type": "field_mapping", "config": { "field": "a", "value": 1 } },
+                { "id": "b", "kind": "brick", "brick_type": "field_mapping", "config": { "field": "b", "value": 2 } },
+                { "id": "join", "kind": "join", "strategy": "keyed" }
+            ],
+            "edges": [
+                { "from": "a", "to": "join" },
+                { "from": "b", "to": "join" }
+            ]
+        }))
+        .unwrap();
+
+        let result = GraphRunner::execute_graph(&graph, bricks_for(&graph), json!({}), None)
+            .await
+            .unwrap();
+        assert_eq!(result, json!({ "a": { "a": 1 }, "b": { "b": 2 } }));
+    }
+
+    #[test]
+    fn test_cycle_is_rejected() {
+        let graph = FlowGraph {
+            nodes: vec![brick_node("a", "x", json!(1)), brick_node("b", "y", json!(2))],
+            edges: vec![edge("a", "b"), edge("b", "a")],
+        };
+        assert!(matches!(
+            GraphRunner::validate_graph(&graph),
+            Err(FlowError::InvalidFlow(_))
+        ));
+    }
+
+    #[test]
+    fn test_unknown_edge_target_is_rejected() {
+        let graph = FlowGraph {
+            nodes: vec![brick_node("a", "x", json!(1))],
+            edges: vec![edge("a", "missing")],
+        };
+        assert!(GraphRunner::validate_graph(&graph).is_err());
+    }
+}
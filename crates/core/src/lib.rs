@@ -1,15 +1,35 @@
 pub mod brick_traits;
+pub mod brick_registry;
 pub mod flow_runner;
+pub mod graph_runner;
 pub mod mapper;
+pub mod path;
 pub mod quota;
 pub mod types;
 pub mod rules_engine;
+pub mod transform;
+pub mod templating;
 pub mod retry;
+pub mod events;
+pub mod secrets;
+pub mod connections;
+pub mod response;
+pub mod schema;
 
 pub use brick_traits::*;
-pub use flow_runner::{FlowRunner, FlowRunnerContext, FlowError, UsageLogger, ExecutionDataStorage};
+pub use brick_registry::{BrickRegistry, BrickFactory};
+pub use flow_runner::{FlowRunner, FlowRunnerContext, FlowError, UsageLogger, ExecutionDataStorage, SubFlowExecutor};
+pub use graph_runner::GraphRunner;
+pub use events::{ExecutionEvent, ExecutionEventBus, ExecutionEventKind, EventSubscription};
 pub use mapper::*;
+pub use path::{JsonPath, PathError};
 pub use quota::*;
 pub use types::*;
 pub use rules_engine::*;
+pub use transform::TransformError;
+pub use templating::{CompiledTemplate, TemplateContext, TemplateError};
+pub use secrets::{SecretRedactor, SecretResolver};
+pub use connections::{ConnectionResolver, ConnectionSpec, ResolvedConnection};
+pub use response::{FlowResponder, FlowResponse, ResponseBody};
+pub use schema::SchemaError;
 
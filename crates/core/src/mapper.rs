@@ -1,7 +1,9 @@
 use serde_json::{json, Value};
 use thiserror::Error;
 
-use crate::types::{MappingRule, TransformType, MappingDirection, MergeStrategy, SplitStrategy};
+use crate::path::{JsonPath, PathError};
+use crate::transform::{self, TransformError};
+use crate::types::{MappingRule, TransformType, MappingMode, MergeStrategy, SplitStrategy};
 
 #[derive(Debug, Error)]
 pub enum MappingError {
@@ -15,7 +17,19 @@ pub enum MappingError {
     TypeMismatch(String),
     
     #[error("Transform error: {0}")]
-    TransformError(String),
+    TransformError(#[from] TransformError),
+
+    #[error("Mapping cannot be reversed: {0}")]
+    NotInvertible(String),
+}
+
+impl From<PathError> for MappingError {
+    fn from(e: PathError) -> Self {
+        match e {
+            PathError::Syntax { .. } => MappingError::InvalidPath(e.to_string()),
+            PathError::Set { .. } => MappingError::TypeMismatch(e.to_string()),
+        }
+    }
 }
 
 pub struct Mapper;
@@ -23,73 +37,102 @@ pub struct Mapper;
 impl Mapper {
     /// Maps a value from source path to target path in the output JSON
     /// Supports both legacy single-path and new multi-path formats
+    ///
+    /// This runs the rule forward; rules with `MappingDirection::Backward`
+    /// only apply in reverse and are skipped.
     pub fn map_field(
         source: &Value,
         target: &mut Value,
         rule: &MappingRule,
     ) -> Result<(), MappingError> {
-        // Determine which paths to use (backward compatibility)
+        let (source_paths, target_paths) = Self::rule_paths(rule)?;
+        if !rule.direction.applies_to(MappingMode::Forward) {
+            return Ok(());
+        }
+        Self::map_field_forward(source, target, source_paths, target_paths, rule)
+    }
+
+    /// Maps a field between two documents in the given mode
+    ///
+    /// `Forward` writes the target from the source, `Reverse` writes the
+    /// source from the target with the rule's transforms inverted. Rules whose
+    /// direction does not include the mode are skipped.
+    pub fn sync_field(
+        source: &mut Value,
+        target: &mut Value,
+        rule: &MappingRule,
+        mode: MappingMode,
+    ) -> Result<(), MappingError> {
+        let (source_paths, target_paths) = Self::rule_paths(rule)?;
+        if !rule.direction.applies_to(mode) {
+            return Ok(());
+        }
+        match mode {
+            MappingMode::Forward => Self::map_field_forward(source, target, source_paths, target_paths, rule),
+            MappingMode::Reverse => Self::map_field_backward(source, target, source_paths, target_paths, rule),
+        }
+    }
+
+    /// Applies a rule set between two documents in the given mode
+    pub fn sync(
+        source: &mut Value,
+        target: &mut Value,
+        rules: &[MappingRule],
+        mode: MappingMode,
+    ) -> Result<(), MappingError> {
+        for rule in rules {
+            Self::sync_field(source, target, rule, mode)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that a rule can run in reverse
+    ///
+    /// Fails for lossy transforms (such as `string_to_upper`) and for merge
+    /// and split strategies that cannot be undone.
+    pub fn ensure_reversible(rule: &MappingRule) -> Result<(), MappingError> {
+        let (source_paths, target_paths) = Self::rule_paths(rule)?;
+        transform::invert_pipeline(&rule.transform)?;
+        if source_paths.len() > 1 && target_paths.len() == 1 {
+            match rule.merge_strategy {
+                None | Some(MergeStrategy::Array) => {}
+                Some(ref strategy) => {
+                    return Err(MappingError::NotInvertible(format!(
+                        "merge strategy {:?} cannot be reversed",
+                        strategy
+                    )))
+                }
+            }
+        } else if source_paths.len() == 1 && target_paths.len() > 1 {
+            if let Some(SplitStrategy::Extract) = rule.split_strategy {
+                return Err(MappingError::NotInvertible(
+                    "split strategy Extract cannot be reversed".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a rule's source and target paths, accepting both the legacy
+    /// single-path and the multi-path format
+    fn rule_paths(rule: &MappingRule) -> Result<(&[String], &[String]), MappingError> {
         let source_paths = if !rule.source_paths.is_empty() {
-            &rule.source_paths
+            &rule.source_paths[..]
         } else if !rule.source_path.is_empty() {
-            // Legacy format - single source path
-            return Self::map_field_legacy(source, target, rule);
+            std::slice::from_ref(&rule.source_path)
         } else {
             return Err(MappingError::InvalidPath("No source paths specified".to_string()));
         };
 
         let target_paths = if !rule.target_paths.is_empty() {
-            &rule.target_paths
+            &rule.target_paths[..]
         } else if !rule.target_path.is_empty() {
-            // Legacy format - single target path
-            return Self::map_field_legacy(source, target, rule);
+            std::slice::from_ref(&rule.target_path)
         } else {
             return Err(MappingError::InvalidPath("No target paths specified".to_string()));
         };
 
-        // Handle multi-directional mapping
-        match rule.direction {
-            MappingDirection::Forward => {
-                Self::map_field_forward(source, target, source_paths, target_paths, rule)?;
-            }
-            MappingDirection::Backward => {
-                // TODO: Backward mapping requires mutable source access, which current API doesn't support.
-                // For backward mapping, we need mutable access to source.
-                // Since source is immutable, we'll work with a clone for backward mapping.
-                // In practice, backward mapping should be handled at a higher level.
-                // For now, we'll skip backward mapping when source is immutable.
-                // This is a limitation - bidirectional mapping requires both to be mutable.
-                // See map_field_backward() for future implementation.
-                return Err(MappingError::InvalidPath(
-                    "Backward mapping requires mutable source. Use bidirectional mapping at flow level.".to_string()
-                ));
-            }
-            MappingDirection::Bidirectional => {
-                Self::map_field_forward(source, target, source_paths, target_paths, rule)?;
-                // TODO: Bidirectional mapping also requires mutable source.
-                // For now, we'll only do forward mapping.
-                // Full bidirectional support would require refactoring the API.
-                // See map_field_backward() for future implementation.
-            }
-        }
-
-        Ok(())
-    }
-
-    /// Legacy single-path mapping (for backward compatibility)
-    fn map_field_legacy(
-        source: &Value,
-        target: &mut Value,
-        rule: &MappingRule,
-    ) -> Result<(), MappingError> {
-        let source_value = Self::get_value_at_path(source, &rule.source_path)?;
-        let transformed_value = if let Some(ref transform) = rule.transform {
-            Self::apply_transform(source_value, transform)?
-        } else {
-            source_value
-        };
-        Self::set_value_at_path(target, &rule.target_path, transformed_value)?;
-        Ok(())
+        Ok((source_paths, target_paths))
     }
 
     /// Forward mapping (source -> target)
@@ -102,21 +145,13 @@ impl Mapper {
     ) -> Result<(), MappingError> {
         if source_paths.len() == 1 && target_paths.len() == 1 {
             // Single source to single target - move ownership to avoid clone
-            let source_value = Self::get_value_at_path(source, &source_paths[0])?;
-            let transformed_value = if let Some(ref transform) = rule.transform {
-                Self::apply_transform(source_value, transform)?
-            } else {
-                source_value
-            };
+            let source_value = Self::read_source(source, &source_paths[0], rule)?;
+            let transformed_value = Self::transform_source(source_value, &source_paths[0], &rule.transform)?;
             Self::set_value_at_path(target, &target_paths[0], transformed_value)?;
         } else if source_paths.len() > 1 && target_paths.len() == 1 {
             // Multiple sources to single target (merge)
             let merged_value = Self::merge_sources(source, source_paths, rule.merge_strategy.as_ref())?;
-            let transformed_value = if let Some(ref transform) = rule.transform {
-                Self::apply_transform(merged_value, transform)?
-            } else {
-                merged_value
-            };
+            let transformed_value = transform::apply_pipeline(merged_value, &rule.transform)?;
             Self::set_value_at_path(target, &target_paths[0], transformed_value)?;
         } else if source_paths.len() == 1 && target_paths.len() > 1 {
             // Single source to multiple targets (split)
@@ -125,71 +160,124 @@ impl Mapper {
         } else {
             // Multiple sources to multiple targets (1:1 mapping)
             for (source_path, target_path) in source_paths.iter().zip(target_paths.iter()) {
-                let source_value = Self::get_value_at_path(source, source_path)?;
-                // Move ownership to transform if needed, avoiding clone when no transform
-                let transformed_value = if let Some(ref transform) = rule.transform {
-                    Self::apply_transform(source_value, transform)?
-                } else {
-                    source_value
-                };
+                let source_value = Self::read_source(source, source_path, rule)?;
+                let transformed_value = Self::transform_source(source_value, source_path, &rule.transform)?;
                 Self::set_value_at_path(target, target_path, transformed_value)?;
             }
         }
         Ok(())
     }
 
+    /// Reads the value at a source path
+    ///
+    /// A missing value reads as null when the rule's pipeline supplies a
+    /// default for it.
+    fn read_source(source: &Value, source_path: &str, rule: &MappingRule) -> Result<Value, MappingError> {
+        match Self::get_value_at_path(source, source_path) {
+            Err(MappingError::ValueNotFound(_))
+                if rule.transform.iter().any(|t| matches!(t, TransformType::DefaultIfMissing { .. })) =>
+            {
+                Ok(Value::Null)
+            }
+            result => result,
+        }
+    }
+
+    /// Applies a transform pipeline to the value read from a path
+    ///
+    /// Values read through wildcards, slices or filters are transformed
+    /// element-wise, except by transforms that work on whole arrays (such as
+    /// `string_concat` or `array_sort`), which receive the list of matches.
+    fn transform_source(value: Value, path: &str, pipeline: &[TransformType]) -> Result<Value, MappingError> {
+        if pipeline.is_empty() {
+            return Ok(value);
+        }
+
+        let mut element_wise = !JsonPath::cached(path)?.is_singular();
+        let mut value = value;
+        for step in pipeline {
+            value = match value {
+                Value::Array(values) if element_wise && !step.operates_on_arrays() => values
+                    .into_iter()
+                    .map(|v| transform::apply(v, step))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(Value::Array)?,
+                value => {
+                    let value = transform::apply(value, step)?;
+                    element_wise &= value.is_array();
+                    value
+                }
+            };
+        }
+        Ok(value)
+    }
+
     /// Backward mapping (target -> source)
-    /// 
-    /// TODO: This function is kept for future bidirectional mapping support.
-    /// It requires mutable access to source, which the current API doesn't support.
-    /// For full backward/bidirectional support, the API would need to be refactored.
-    /// 
-    /// This is intentionally unused but kept for future feature implementation.
-    #[allow(dead_code)]
+    ///
+    /// Undoes what `map_field_forward` does: transforms are inverted, a merge
+    /// is split back into its sources and a copied split is read back from
+    /// its first target.
     fn map_field_backward(
         source: &mut Value,
-        target: &mut Value,
+        target: &Value,
         source_paths: &[String],
         target_paths: &[String],
         rule: &MappingRule,
     ) -> Result<(), MappingError> {
-        // Reverse the mapping direction
-        if source_paths.len() == 1 && target_paths.len() == 1 {
-            let target_value = Self::get_value_at_path(target, &target_paths[0])?;
-            let transformed_value = if let Some(ref transform) = rule.transform {
-                Self::apply_transform(target_value, transform)?
-            } else {
-                target_value
-            };
-            Self::set_value_at_path(source, &source_paths[0], transformed_value)?;
-        } else if target_paths.len() > 1 && source_paths.len() == 1 {
-            // Multiple targets to single source (merge)
-            let merged_value = Self::merge_sources(target, target_paths, rule.merge_strategy.as_ref())?;
-            let transformed_value = if let Some(ref transform) = rule.transform {
-                Self::apply_transform(merged_value, transform)?
-            } else {
-                merged_value
-            };
-            Self::set_value_at_path(source, &source_paths[0], transformed_value)?;
-        } else if target_paths.len() == 1 && source_paths.len() > 1 {
-            // Single target to multiple sources (split)
+        Self::ensure_reversible(rule)?;
+        let pipeline = transform::invert_pipeline(&rule.transform)?;
+
+        if source_paths.len() > 1 && target_paths.len() == 1 {
+            // Single target back to multiple sources (undo merge)
+            let merged_value = Self::get_value_at_path(target, &target_paths[0])?;
+            let merged_value = transform::apply_pipeline(merged_value, &pipeline)?;
+            let values = Self::unmerge(merged_value, source_paths.len(), rule.merge_strategy.as_ref())?;
+            for (source_path, value) in source_paths.iter().zip(values) {
+                Self::set_value_at_path(source, source_path, value)?;
+            }
+        } else if source_paths.len() == 1 && target_paths.len() > 1 {
+            // Multiple targets back to a single source (undo split); every
+            // target holds a copy of the source value
             let target_value = Self::get_value_at_path(target, &target_paths[0])?;
-            Self::split_to_targets(source, source_paths, target_value, rule.split_strategy.as_ref())?;
+            Self::set_value_at_path(source, &source_paths[0], target_value)?;
         } else {
-            // Multiple targets to multiple sources (1:1 mapping)
+            // 1:1 and N:N mappings
             for (target_path, source_path) in target_paths.iter().zip(source_paths.iter()) {
                 let target_value = Self::get_value_at_path(target, target_path)?;
-                let transformed_value = if let Some(ref transform) = rule.transform {
-                    Self::apply_transform(target_value.clone(), transform)?
-                } else {
-                    target_value
-                };
+                let transformed_value = Self::transform_source(target_value, target_path, &pipeline)?;
                 Self::set_value_at_path(source, source_path, transformed_value)?;
             }
         }
         Ok(())
     }
 
+    /// Splits a merged value back into the values of its sources
+    fn unmerge(
+        merged: Value,
+        count: usize,
+        strategy: Option<&MergeStrategy>,
+    ) -> Result<Vec<Value>, MappingError> {
+        match (strategy, merged) {
+            (Some(MergeStrategy::Array), Value::Array(values)) if values.len() == count => Ok(values),
+            (None, Value::Object(mut fields)) => (0..count)
+                .map(|idx| {
+                    fields.remove(&format!("field_{}", idx)).ok_or_else(|| {
+                        MappingError::ValueNotFound(format!("Merged value has no field_{}", idx))
+                    })
+                })
+                .collect(),
+            (Some(MergeStrategy::Array), _) => Err(MappingError::TypeMismatch(format!(
+                "Expected an array of {} merged values",
+                count
+            ))),
+            (None, _) => Err(MappingError::TypeMismatch("Expected a merged object".to_string())),
+            (Some(strategy), _) => Err(MappingError::NotInvertible(format!(
+                "merge strategy {:?} cannot be reversed",
+                strategy
+            ))),
+        }
+    }
+
     /// Merges multiple source values into a single target
     fn merge_sources(
         source: &Value,
@@ -312,176 +400,63 @@ impl Mapper {
         Ok(output)
     }
 
-    /// Gets a value from a JSON path (e.g., "user.name" or "items[0].title")
-    pub fn get_value_at_path(value: &Value, path: &str) -> Result<Value, MappingError> {
-        let parts: Vec<&str> = path.split('.').collect();
-        let mut current = value;
-
-        for part in parts {
-            if part.contains('[') {
-                // Handle array access like "items[0]"
-                let bracket_pos = part.find('[').ok_or_else(|| {
-                    MappingError::InvalidPath(format!("Invalid array syntax in path: {}", path))
-                })?;
-                let key = &part[..bracket_pos];
-                let index_str = &part[bracket_pos + 1..part.len() - 1];
-                let index: usize = index_str.parse().map_err(|_| {
-                    MappingError::InvalidPath(format!("Invalid array index: {}", index_str))
-                })?;
-
-                current = current
-                    .get(key)
-                    .ok_or_else(|| MappingError::ValueNotFound(format!("Key not found: {}", key)))?
-                    .get(index)
-                    .ok_or_else(|| {
-                        MappingError::ValueNotFound(format!("Index {} not found in array", index))
-                    })?;
-            } else {
-                current = current
-                    .get(part)
-                    .ok_or_else(|| MappingError::ValueNotFound(format!("Path not found: {}", path)))?;
+    /// Runs mapping rules in reverse, rebuilding a source document from a
+    /// target document
+    pub fn apply_mappings_reverse(
+        input: &Value,
+        rules: &[MappingRule],
+    ) -> Result<Value, MappingError> {
+        let mut output = json!({});
+
+        for rule in rules {
+            let (source_paths, target_paths) = Self::rule_paths(rule)?;
+            if rule.direction.applies_to(MappingMode::Reverse) {
+                Self::map_field_backward(&mut output, input, source_paths, target_paths, rule)?;
             }
         }
 
-        Ok(current.clone())
+        Ok(output)
+    }
+
+    /// Gets a value from a JSON path (e.g., "user.name", "items[-1].title"
+    /// or "deals[?(@.amount > 1000)].id"; see `JsonPath` for the syntax)
+    ///
+    /// Paths with wildcards, slices or filters return an array of all
+    /// matching values.
+    pub fn get_value_at_path(value: &Value, path: &str) -> Result<Value, MappingError> {
+        JsonPath::cached(path)?
+            .get(value)
+            .ok_or_else(|| MappingError::ValueNotFound(format!("Path not found: {}", path)))
     }
 
-    /// Sets a value at a JSON path
-    fn set_value_at_path(
+    /// Sets a value at a JSON path, creating missing objects and arrays
+    ///
+    /// Wildcard paths such as "customers[*].name" assign an array value
+    /// element-wise.
+    pub fn set_value_at_path(
         value: &mut Value,
         path: &str,
         new_value: Value,
     ) -> Result<(), MappingError> {
-        let parts: Vec<&str> = path.split('.').collect();
-        let mut current = value;
-
-        for (i, part) in parts.iter().enumerate() {
-            if i == parts.len() - 1 {
-                // Last part - set the value
-                if let Some(obj) = current.as_object_mut() {
-                    obj.insert(part.to_string(), new_value.clone());
-                } else {
-                    return Err(MappingError::TypeMismatch(
-                        "Target path does not point to an object".to_string(),
-                    ));
-                }
-            } else {
-                // Navigate through the path
-                if !current.get(part).is_some() {
-                    if let Some(obj) = current.as_object_mut() {
-                        obj.insert(part.to_string(), json!({}));
-                    }
-                }
-                current = current
-                    .get_mut(part)
-                    .ok_or_else(|| MappingError::InvalidPath(format!("Path not found: {}", path)))?;
-            }
-        }
-
+        JsonPath::cached(path)?.set(value, new_value)?;
         Ok(())
     }
 
     /// Applies a transform to a value
-    fn apply_transform(value: Value, transform: &TransformType) -> Result<Value, MappingError> {
-        match transform {
-            TransformType::StringConcat { separator } => {
-                if let Value::Array(arr) = value {
-                    let strings: Vec<String> = arr
-                        .iter()
-                        .map(|v| v.as_str().unwrap_or("").to_string())
-                        .collect();
-                    Ok(Value::String(strings.join(separator)))
-                } else {
-                    Err(MappingError::TypeMismatch(
-                        "StringConcat requires an array of strings".to_string(),
-                    ))
-                }
-            }
-            TransformType::NumberAdd => {
-                if let Value::Number(n) = value {
-                    Ok(Value::Number(n.clone()))
-                } else {
-                    Err(MappingError::TypeMismatch("NumberAdd requires a number".to_string()))
-                }
-            }
-            TransformType::NumberMultiply => {
-                if let Value::Number(n) = value {
-                    Ok(Value::Number(n.clone()))
-                } else {
-                    Err(MappingError::TypeMismatch(
-                        "NumberMultiply requires a number".to_string(),
-                    ))
-                }
-            }
-            TransformType::StringToUpper => {
-                if let Value::String(s) = value {
-                    Ok(Value::String(s.to_uppercase()))
-                } else {
-                    Err(MappingError::TypeMismatch("StringToUpper requires a string".to_string()))
-                }
-            }
-            TransformType::StringToLower => {
-                if let Value::String(s) = value {
-                    Ok(Value::String(s.to_lowercase()))
-                } else {
-                    Err(MappingError::TypeMismatch("StringToLower requires a string".to_string()))
-                }
-            }
-            TransformType::Conditional {
-                condition,
-                true_value,
-                false_value,
-            } => {
-                // Simple condition evaluation - can be enhanced
-                let condition_met = Self::evaluate_condition(&value, condition)?;
-                Ok(if condition_met {
-                    true_value.clone()
-                } else {
-                    false_value.clone()
-                })
-            }
-        }
+    pub fn apply_transform(value: Value, transform: &TransformType) -> Result<Value, MappingError> {
+        Ok(transform::apply(value, transform)?)
     }
 
     /// Evaluates a simple condition (e.g., "> 1000", "== 'VIP'")
     pub fn evaluate_condition(value: &Value, condition: &str) -> Result<bool, MappingError> {
-        if condition.starts_with("> ") {
-            if let (Some(num), Some(threshold)) = (
-                value.as_f64(),
-                condition[2..].trim().parse::<f64>().ok(),
-            ) {
-                Ok(num > threshold)
-            } else {
-                Err(MappingError::TransformError(
-                    "Invalid numeric condition".to_string(),
-                ))
-            }
-        } else if condition.starts_with("< ") {
-            if let (Some(num), Some(threshold)) = (
-                value.as_f64(),
-                condition[2..].trim().parse::<f64>().ok(),
-            ) {
-                Ok(num < threshold)
-            } else {
-                Err(MappingError::TransformError(
-                    "Invalid numeric condition".to_string(),
-                ))
-            }
-        } else if condition.starts_with("== ") {
-            let expected = condition[3..].trim().trim_matches('"').trim_matches('\'');
-            Ok(value.as_str().map(|s| s == expected).unwrap_or(false))
-        } else {
-            Err(MappingError::TransformError(format!(
-                "Unsupported condition: {}",
-                condition
-            )))
-        }
+        Ok(transform::evaluate_condition(value, condition)?)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::MappingDirection;
     use serde_json::json;
 
     #[test]
@@ -499,7 +474,7 @@ mod tests {
             source_paths: vec![],
             target_paths: vec![],
             direction: MappingDirection::Forward,
-            transform: None,
+            transform: vec![],
             merge_strategy: None,
             split_strategy: None,
         };
@@ -508,6 +483,26 @@ mod tests {
         assert_eq!(output["customer_name"], "John Doe");
     }
 
+    #[test]
+    fn test_map_field_rejects_indices_far_past_the_end() {
+        let input = json!({ "name": "John Doe" });
+        let mut output = json!({ "out": [] });
+        let rule = MappingRule {
+            source_path: "name".to_string(),
+            target_path: "out[4294967295]".to_string(),
+            source_paths: vec![],
+            target_paths: vec![],
+            direction: MappingDirection::Forward,
+            transform: vec![],
+            merge_strategy: None,
+            split_strategy: None,
+        };
+
+        let result = Mapper::map_field(&input, &mut output, &rule);
+        assert!(matches!(result, Err(MappingError::TypeMismatch(_))));
+        assert_eq!(output, json!({ "out": [] }));
+    }
+
     #[test]
     fn test_combine_text() {
         let input = json!({
@@ -521,9 +516,9 @@ mod tests {
             source_paths: vec![],
             target_paths: vec![],
             direction: MappingDirection::Forward,
-            transform: Some(TransformType::StringConcat {
+            transform: vec![TransformType::StringConcat {
                 separator: " ".to_string(),
-            }),
+            }],
             merge_strategy: None,
             split_strategy: None,
         };
@@ -637,7 +632,7 @@ mod tests {
                 source_paths: vec![],
                 target_paths: vec![],
                 direction: MappingDirection::Forward,
-                transform: None,
+                transform: vec![],
                 merge_strategy: None,
                 split_strategy: None,
             },
@@ -647,7 +642,7 @@ mod tests {
                 source_paths: vec![],
                 target_paths: vec![],
                 direction: MappingDirection::Forward,
-                transform: None,
+                transform: vec![],
                 merge_strategy: None,
                 split_strategy: None,
             },
@@ -656,5 +651,134 @@ mod tests {
         assert_eq!(result["customer_name"], "John");
         assert_eq!(result["customer_age"], 30);
     }
-}
 
+    #[test]
+    fn test_wildcard_maps_element_wise() {
+        let input = json!({
+            "contacts": [
+                {"name": "ada", "company": {"name": "Analytical"}},
+                {"name": "grace", "company": {"name": "Navy"}}
+            ]
+        });
+        let rule = |source: &str, target: &str, transform: Vec<TransformType>| MappingRule {
+            source_path: source.to_string(),
+            target_path: target.to_string(),
+            source_paths: vec![],
+            target_paths: vec![],
+            direction: MappingDirection::Forward,
+            transform,
+            merge_strategy: None,
+            split_strategy: None,
+        };
+        let rules = vec![
+            rule("contacts[*].name", "customers[*].name", vec![TransformType::StringToUpper]),
+            rule("contacts[*].company.name", "customers[*].company", vec![]),
+            rule("contacts[-1].name", "last", vec![]),
+            rule("contacts[*].name", "names", vec![TransformType::StringConcat { separator: ", ".to_string() }]),
+        ];
+
+        let result = Mapper::apply_mappings(&input, &rules).unwrap();
+        assert_eq!(result, json!({
+            "customers": [
+                {"name": "ADA", "company": "Analytical"},
+                {"name": "GRACE", "company": "Navy"}
+            ],
+            "last": "grace",
+            "names": "ada, grace"
+        }));
+    }
+
+    #[test]
+    fn test_invalid_path_is_reported() {
+        let result = Mapper::get_value_at_path(&json!({}), "items[");
+        assert!(matches!(result, Err(MappingError::InvalidPath(_))));
+    }
+
+    #[test]
+    fn test_transform_pipeline_from_config() {
+        let input = json!({
+            "order": {"total": "19.994", "items": [{"sku": " a-1 "}, {"sku": "b-2"}, {"sku": "a-1"}]}
+        });
+        let rules: Vec<MappingRule> = serde_json::from_value(json!([
+            {"source_path": "order.total", "target_path": "total",
+             "transform": ["to_number", {"round": {"decimals": 2}}]},
+            {"source_path": "order.items[*].sku", "target_path": "skus",
+             "transform": ["trim", "string_to_upper", "array_unique"]},
+            {"source_path": "order.coupon", "target_path": "coupon",
+             "transform": {"default_if_missing": {"value": "none"}}}
+        ])).unwrap();
+
+        let result = Mapper::apply_mappings(&input, &rules).unwrap();
+        assert_eq!(result, json!({"total": 19.99, "skus": ["A-1", "B-2"], "coupon": "none"}));
+
+        let rules: Vec<MappingRule> = serde_json::from_value(json!([
+            {"source_path": "order.items", "target_path": "n", "transform": "to_number"}
+        ])).unwrap();
+        assert!(matches!(
+            Mapper::apply_mappings(&input, &rules),
+            Err(MappingError::TransformError(TransformError::InvalidInput { transform: "to_number", .. }))
+        ));
+    }
+
+    #[test]
+    fn test_rules_run_in_reverse() {
+        let rules: Vec<MappingRule> = serde_json::from_value(json!([
+            {"source_path": "properties.amount", "target_path": "Amount.number",
+             "direction": "bidirectional", "transform": [{"number_divide": {"operand": 100}}]},
+            {"source_paths": ["properties.firstname", "properties.lastname"], "target_paths": ["Name.parts"],
+             "direction": "bidirectional", "merge_strategy": "array"},
+            {"source_path": "properties.tags", "target_path": "Tags.names",
+             "direction": "bidirectional", "transform": {"split": {"separator": ";"}}},
+            {"source_path": "id", "target_path": "hubspot_id"},
+            {"source_path": "properties.hs_lastmodified", "target_path": "Synced.date", "direction": "backward"}
+        ])).unwrap();
+        let mut hubspot = json!({
+            "id": "42",
+            "properties": {"amount": 125000, "firstname": "Ada", "lastname": "Lovelace", "tags": "vip;eu"}
+        });
+        let mut notion = json!({"Synced": {"date": "2024-05-01"}});
+
+        Mapper::sync(&mut hubspot, &mut notion, &rules, MappingMode::Forward).unwrap();
+        assert_eq!(notion, json!({
+            "Amount": {"number": 1250},
+            "Name": {"parts": ["Ada", "Lovelace"]},
+            "Tags": {"names": ["vip", "eu"]},
+            "hubspot_id": "42",
+            "Synced": {"date": "2024-05-01"}
+        }));
+
+        notion["Amount"]["number"] = json!(1300.5);
+        notion["Tags"]["names"] = json!(["vip"]);
+        let written_back = Mapper::apply_mappings_reverse(&notion, &rules).unwrap();
+        assert_eq!(written_back, json!({
+            "properties": {
+                "amount": 130050,
+                "firstname": "Ada",
+                "lastname": "Lovelace",
+                "tags": "vip",
+                "hs_lastmodified": "2024-05-01"
+            }
+        }));
+
+        Mapper::sync(&mut hubspot, &mut notion, &rules, MappingMode::Reverse).unwrap();
+        assert_eq!(hubspot["properties"]["amount"], json!(130050));
+        assert_eq!(hubspot["id"], json!("42"));
+    }
+
+    #[test]
+    fn test_lossy_rules_are_rejected_in_reverse() {
+        let rules: Vec<MappingRule> = serde_json::from_value(json!([
+            {"source_path": "name", "target_path": "name", "direction": "bidirectional", "transform": "string_to_upper"}
+        ])).unwrap();
+        let result = Mapper::apply_mappings_reverse(&json!({"name": "ADA"}), &rules);
+        assert!(matches!(
+            result,
+            Err(MappingError::TransformError(TransformError::NotInvertible { transform: "string_to_upper" }))
+        ));
+
+        let rules: Vec<MappingRule> = serde_json::from_value(json!([
+            {"source_paths": ["first", "last"], "target_path": "name", "direction": "bidirectional", "merge_strategy": "concat"}
+        ])).unwrap();
+        assert!(matches!(Mapper::ensure_reversible(&rules[0]), Err(MappingError::NotInvertible(_))));
+    }
+}
@@ -0,0 +1,714 @@
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use thiserror::Error;
+
+/// Maximum number of compiled paths kept by `JsonPath::cached`
+const MAX_CACHED_PATHS: usize = 1024;
+
+/// How far past the end of an array an index may be assigned; the gap is
+/// filled with nulls
+const MAX_ASSIGN_GAP: usize = 100;
+
+static PATH_CACHE: OnceLock<Mutex<HashMap<String, Arc<JsonPath>>>> = OnceLock::new();
+
+#[derive(Debug, Error)]
+pub enum PathError {
+    #[error("Invalid path '{path}' at position {position}: {message}")]
+    Syntax {
+        path: String,
+        position: usize,
+        message: String,
+    },
+
+    #[error("Cannot set '{path}': {message}")]
+    Set { path: String, message: String },
+}
+
+/// A compiled JSONPath-style expression
+///
+/// Supported syntax, with an optional leading `$`:
+/// - `user.name`, `user['first.name']`, `user["name"]`: object keys
+/// - `items[0]`, `items[-1]`, `matrix[0][1]`: array indices, negative from the end
+/// - `items[*]`, `user.*`: all array elements or object values
+/// - `items[1:3]`, `items[:2]`, `items[-2:]`, `items[::2]`: array slices
+/// - `deals[?(@.amount > 1000)]`: elements matching a filter, with `==`, `!=`,
+///   `>`, `>=`, `<`, `<=`, `&&`, `||`, `!` and existence checks like `[?(@.email)]`
+///
+/// Paths without wildcards, slices or filters are singular and select at most
+/// one value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(i64),
+    Wildcard,
+    Slice { start: Option<i64>, end: Option<i64>, step: i64 },
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Exists(Vec<Segment>),
+    Compare { left: Operand, op: CompareOp, right: Operand },
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    /// Path relative to the current element (`@`); only keys and indices
+    Current(Vec<Segment>),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl JsonPath {
+    /// Compiles a path expression
+    pub fn compile(path: &str) -> Result<Self, PathError> {
+        let segments = Parser::new(path).parse_path()?;
+        Ok(Self {
+            source: path.to_string(),
+            segments,
+        })
+    }
+
+    /// Compiles a path expression, reusing an earlier compilation of the same path
+    pub fn cached(path: &str) -> Result<Arc<Self>, PathError> {
+        let cache = PATH_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+        if let Some(compiled) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(path) {
+            return Ok(compiled.clone());
+        }
+
+        let compiled = Arc::new(Self::compile(path)?);
+        let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+        if cache.len() >= MAX_CACHED_PATHS {
+            cache.clear();
+        }
+        cache.insert(path.to_string(), compiled.clone());
+        Ok(compiled)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns true if the path selects at most one value
+    pub fn is_singular(&self) -> bool {
+        self.segments.iter().all(|s| matches!(s, Segment::Key(_) | Segment::Index(_)))
+    }
+
+    /// Returns all values the path selects, in document order
+    pub fn query<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        let mut matches = Vec::new();
+        select(&self.segments, value, &mut matches);
+        matches
+    }
+
+    /// Returns the selected value of a singular path, or an array of all
+    /// selected values otherwise
+    ///
+    /// Returns `None` only when a singular path selects nothing.
+    pub fn get(&self, value: &Value) -> Option<Value> {
+        let matches = self.query(value);
+        if self.is_singular() {
+            matches.first().map(|v| (*v).clone())
+        } else {
+            Some(Value::Array(matches.into_iter().cloned().collect()))
+        }
+    }
+
+    /// Sets the value at the path, creating missing objects and arrays
+    ///
+    /// A wildcard assigns element-wise: `new_value` must be an array, and its
+    /// n-th element is set at the n-th element of the target array.
+    pub fn set(&self, target: &mut Value, new_value: Value) -> Result<(), PathError> {
+        assign(&self.segments, target, new_value).map_err(|message| PathError::Set {
+            path: self.source.clone(),
+            message,
+        })
+    }
+}
+
+impl std::str::FromStr for JsonPath {
+    type Err = PathError;
+
+    fn from_str(path: &str) -> Result<Self, Self::Err> {
+        Self::compile(path)
+    }
+}
+
+impl std::fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn select<'a>(segments: &[Segment], value: &'a Value, out: &mut Vec<&'a Value>) {
+    let Some((segment, rest)) = segments.split_first() else {
+        out.push(value);
+        return;
+    };
+
+    match segment {
+        Segment::Key(key) => {
+            if let Some(child) = value.get(key.as_str()) {
+                select(rest, child, out);
+            }
+        }
+        Segment::Index(index) => {
+            if let Some(child) = value.as_array().and_then(|arr| resolve_index(arr.len(), *index)).and_then(|i| value.get(i)) {
+                select(rest, child, out);
+            }
+        }
+        Segment::Wildcard => {
+            for child in children(value) {
+                select(rest, child, out);
+            }
+        }
+        Segment::Slice { start, end, step } => {
+            if let Some(arr) = value.as_array() {
+                let len = arr.len() as i64;
+                let bound = |i: i64| if i < 0 { (len + i).max(0) } else { i.min(len) };
+                let start = start.map(bound).unwrap_or(0);
+                let end = end.map(bound).unwrap_or(len);
+                let mut i = start;
+                while i < end {
+                    select(rest, &arr[i as usize], out);
+                    match i.checked_add(*step) {
+                        Some(next) => i = next,
+                        None => break,
+                    }
+                }
+            }
+        }
+        Segment::Filter(filter) => {
+            for child in children(value) {
+                if filter.matches(child) {
+                    select(rest, child, out);
+                }
+            }
+        }
+    }
+}
+
+fn children(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(arr) => arr.iter().collect(),
+        Value::Object(obj) => obj.values().collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn resolve_index(len: usize, index: i64) -> Option<usize> {
+    let resolved = if index < 0 { len as i64 + index } else { index };
+    (0..len as i64).contains(&resolved).then_some(resolved as usize)
+}
+
+fn assign(segments: &[Segment], current: &mut Value, new_value: Value) -> Result<(), String> {
+    let Some((segment, rest)) = segments.split_first() else {
+        *current = new_value;
+        return Ok(());
+    };
+
+    match segment {
+        Segment::Key(key) => {
+            if current.is_null() {
+                *current = Value::Object(serde_json::Map::new());
+            }
+            let obj = current
+                .as_object_mut()
+                .ok_or_else(|| format!("expected an object at key '{}'", key))?;
+            assign(rest, obj.entry(key.clone()).or_insert(Value::Null), new_value)
+        }
+        Segment::Index(index) => {
+            if current.is_null() {
+                *current = Value::Array(Vec::new());
+            }
+            let arr = current
+                .as_array_mut()
+                .ok_or_else(|| format!("expected an array at index {}", index))?;
+            let position = if *index < 0 {
+                resolve_index(arr.len(), *index).ok_or_else(|| format!("index {} is out of bounds", index))?
+            } else {
+                *index as usize
+            };
+            if position > arr.len() + MAX_ASSIGN_GAP {
+                return Err(format!("index {} is more than {} past the end of the array", index, MAX_ASSIGN_GAP));
+            }
+            if arr.len() <= position {
+                arr.resize(position + 1, Value::Null);
+            }
+            assign(rest, &mut arr[position], new_value)
+        }
+        Segment::Wildcard => {
+            let Value::Array(values) = new_value else {
+                return Err("a wildcard can only be assigned an array".to_string());
+            };
+            if current.is_null() {
+                *current = Value::Array(Vec::new());
+            }
+            let arr = current
+                .as_array_mut()
+                .ok_or_else(|| "expected an array at wildcard".to_string())?;
+            if arr.len() < values.len() {
+                arr.resize(values.len(), Value::Null);
+            }
+            for (element, value) in arr.iter_mut().zip(values) {
+                assign(rest, element, value)?;
+            }
+            Ok(())
+        }
+        Segment::Slice { .. } | Segment::Filter(_) => {
+            Err("slices and filters cannot be assigned".to_string())
+        }
+    }
+}
+
+impl Filter {
+    fn matches(&self, element: &Value) -> bool {
+        match self {
+            Filter::Exists(segments) => resolve(segments, element).is_some(),
+            Filter::Compare { left, op, right } => {
+                match (left.resolve(element), right.resolve(element)) {
+                    (Some(left), Some(right)) => compare(left, *op, right),
+                    _ => false,
+                }
+            }
+            Filter::Not(inner) => !inner.matches(element),
+            Filter::And(a, b) => a.matches(element) && b.matches(element),
+            Filter::Or(a, b) => a.matches(element) || b.matches(element),
+        }
+    }
+}
+
+impl Operand {
+    fn resolve<'a>(&'a self, element: &'a Value) -> Option<&'a Value> {
+        match self {
+            Operand::Current(segments) => resolve(segments, element),
+            Operand::Literal(value) => Some(value),
+        }
+    }
+}
+
+/// Walks a path of keys and indices
+fn resolve<'a>(segments: &[Segment], value: &'a Value) -> Option<&'a Value> {
+    segments.iter().try_fold(value, |current, segment| match segment {
+        Segment::Key(key) => current.get(key.as_str()),
+        Segment::Index(index) => current
+            .as_array()
+            .and_then(|arr| resolve_index(arr.len(), *index))
+            .and_then(|i| current.get(i)),
+        _ => None,
+    })
+}
+
+fn compare(left: &Value, op: CompareOp, right: &Value) -> bool {
+    let ordering = match (left, right) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64().zip(b.as_f64()).and_then(|(a, b)| a.partial_cmp(&b)),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        _ => None,
+    };
+
+    match op {
+        CompareOp::Eq => ordering.map(|o| o == Ordering::Equal).unwrap_or(left == right),
+        CompareOp::Ne => !ordering.map(|o| o == Ordering::Equal).unwrap_or(left == right),
+        CompareOp::Gt => ordering == Some(Ordering::Greater),
+        CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        CompareOp::Lt => ordering == Some(Ordering::Less),
+        CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+    }
+}
+
+struct Parser<'a> {
+    path: &'a str,
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(path: &'a str) -> Self {
+        Self {
+            path,
+            chars: path.chars().collect(),
+            pos: 0,
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, PathError> {
+        Err(PathError::Syntax {
+            path: self.path.to_string(),
+            position: self.pos,
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_str(&mut self, s: &str) -> bool {
+        let matches = s.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c));
+        if matches {
+            self.pos += s.chars().count();
+        }
+        matches
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), PathError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            self.error(format!("expected '{}'", c))
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_path(&mut self) -> Result<Vec<Segment>, PathError> {
+        let mut segments = Vec::new();
+        let rooted = self.eat('$');
+
+        if !rooted && self.peek().is_some_and(|c| c != '.' && c != '[') {
+            segments.push(self.dotted_key()?);
+        }
+
+        while let Some(c) = self.peek() {
+            match c {
+                '.' => {
+                    self.pos += 1;
+                    segments.push(self.dotted_key()?);
+                }
+                '[' => segments.push(self.bracket()?),
+                other => return self.error(format!("unexpected '{}'", other)),
+            }
+        }
+
+        Ok(segments)
+    }
+
+    /// Parses a key after a dot, up to the next `.` or `[`
+    fn dotted_key(&mut self) -> Result<Segment, PathError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c != '.' && c != '[') {
+            self.pos += 1;
+        }
+        let key: String = self.chars[start..self.pos].iter().collect();
+        match key.as_str() {
+            "" => self.error("expected a key"),
+            "*" => Ok(Segment::Wildcard),
+            _ => Ok(Segment::Key(key)),
+        }
+    }
+
+    fn bracket(&mut self) -> Result<Segment, PathError> {
+        self.expect('[')?;
+        self.skip_whitespace();
+        let segment = match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Segment::Wildcard
+            }
+            Some('\'') | Some('"') => Segment::Key(self.quoted()?),
+            Some('?') => {
+                self.pos += 1;
+                Segment::Filter(self.filter_or()?)
+            }
+            Some(c) if c == '-' || c == ':' || c.is_ascii_digit() => self.index_or_slice()?,
+            _ => return self.error("expected an index, slice, quoted key, '*' or filter"),
+        };
+        self.skip_whitespace();
+        self.expect(']')?;
+        Ok(segment)
+    }
+
+    fn index_or_slice(&mut self) -> Result<Segment, PathError> {
+        let start = self.optional_int()?;
+        self.skip_whitespace();
+        if !self.eat(':') {
+            return match start {
+                Some(index) => Ok(Segment::Index(index)),
+                None => self.error("expected an index"),
+            };
+        }
+
+        self.skip_whitespace();
+        let end = self.optional_int()?;
+        self.skip_whitespace();
+        let step = if self.eat(':') {
+            self.skip_whitespace();
+            self.optional_int()?.unwrap_or(1)
+        } else {
+            1
+        };
+        if step <= 0 {
+            return self.error("slice step must be positive");
+        }
+        Ok(Segment::Slice { start, end, step })
+    }
+
+    fn optional_int(&mut self) -> Result<Option<i64>, PathError> {
+        let start = self.pos;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            return Ok(None);
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match text.parse() {
+            Ok(n) => Ok(Some(n)),
+            Err(_) => self.error(format!("invalid integer '{}'", text)),
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, PathError> {
+        let quote = match self.peek() {
+            Some(c @ ('\'' | '"')) => c,
+            _ => return self.error("expected a quoted string"),
+        };
+        self.pos += 1;
+
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => return self.error("unterminated string"),
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(c) => text.push(c),
+                        None => return self.error("unterminated string"),
+                    }
+                }
+                Some(c) if c == quote => break,
+                Some(c) => text.push(c),
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        Ok(text)
+    }
+
+    fn filter_or(&mut self) -> Result<Filter, PathError> {
+        let mut filter = self.filter_and()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat_str("||") {
+                return Ok(filter);
+            }
+            filter = Filter::Or(Box::new(filter), Box::new(self.filter_and()?));
+        }
+    }
+
+    fn filter_and(&mut self) -> Result<Filter, PathError> {
+        let mut filter = self.filter_unary()?;
+        loop {
+            self.skip_whitespace();
+            if !self.eat_str("&&") {
+                return Ok(filter);
+            }
+            filter = Filter::And(Box::new(filter), Box::new(self.filter_unary()?));
+        }
+    }
+
+    fn filter_unary(&mut self) -> Result<Filter, PathError> {
+        self.skip_whitespace();
+        if self.peek() == Some('!') && self.chars.get(self.pos + 1) != Some(&'=') {
+            self.pos += 1;
+            return Ok(Filter::Not(Box::new(self.filter_unary()?)));
+        }
+        if self.eat('(') {
+            let filter = self.filter_or()?;
+            self.skip_whitespace();
+            self.expect(')')?;
+            return Ok(filter);
+        }
+
+        let left = self.operand()?;
+        self.skip_whitespace();
+        let op = if self.eat_str("==") {
+            CompareOp::Eq
+        } else if self.eat_str("!=") {
+            CompareOp::Ne
+        } else if self.eat_str(">=") {
+            CompareOp::Ge
+        } else if self.eat_str("<=") {
+            CompareOp::Le
+        } else if self.eat('>') {
+            CompareOp::Gt
+        } else if self.eat('<') {
+            CompareOp::Lt
+        } else {
+            return match left {
+                Operand::Current(segments) => Ok(Filter::Exists(segments)),
+                Operand::Literal(_) => self.error("expected a comparison operator"),
+            };
+        };
+
+        self.skip_whitespace();
+        let right = self.operand()?;
+        Ok(Filter::Compare { left, op, right })
+    }
+
+    fn operand(&mut self) -> Result<Operand, PathError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('@') => {
+                self.pos += 1;
+                let mut segments = Vec::new();
+                loop {
+                    if self.eat('.') {
+                        let start = self.pos;
+                        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+                            self.pos += 1;
+                        }
+                        if self.pos == start {
+                            return self.error("expected a key");
+                        }
+                        segments.push(Segment::Key(self.chars[start..self.pos].iter().collect()));
+                    } else if self.peek() == Some('[') {
+                        match self.bracket()? {
+                            segment @ (Segment::Key(_) | Segment::Index(_)) => segments.push(segment),
+                            _ => return self.error("filter paths may only contain keys and indices"),
+                        }
+                    } else {
+                        return Ok(Operand::Current(segments));
+                    }
+                }
+            }
+            Some('\'') | Some('"') => Ok(Operand::Literal(Value::String(self.quoted()?))),
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.pos;
+                self.pos += 1;
+                while self.peek().is_some_and(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '+' | '-')) {
+                    self.pos += 1;
+                }
+                let text: String = self.chars[start..self.pos].iter().collect();
+                match serde_json::from_str::<serde_json::Number>(&text) {
+                    Ok(n) => Ok(Operand::Literal(Value::Number(n))),
+                    Err(_) => self.error(format!("invalid number '{}'", text)),
+                }
+            }
+            _ => {
+                for (word, value) in [("true", Value::Bool(true)), ("false", Value::Bool(false)), ("null", Value::Null)] {
+                    if self.eat_str(word) {
+                        return Ok(Operand::Literal(value));
+                    }
+                }
+                self.error("expected '@' or a literal")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn deals() -> Value {
+        json!({
+            "deals": [
+                {"name": "small", "amount": 500, "owner": {"email": "a@example.com"}},
+                {"name": "large", "amount": 5000},
+                {"name": "medium", "amount": 1500, "owner": {"email": "c@example.com"}}
+            ],
+            "matrix": [[1, 2], [3, 4]],
+            "meta": {"first.name": "Ada"}
+        })
+    }
+
+    fn query(path: &str) -> Vec<Value> {
+        let data = deals();
+        JsonPath::compile(path).unwrap().query(&data).into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn test_keys_indices_and_quoted_keys() {
+        assert_eq!(query("deals[0].name"), vec![json!("small")]);
+        assert_eq!(query("$.deals[-1].name"), vec![json!("medium")]);
+        assert_eq!(query("matrix[1][0]"), vec![json!(3)]);
+        assert_eq!(query("meta['first.name']"), vec![json!("Ada")]);
+        assert!(query("deals[7]").is_empty());
+    }
+
+    #[test]
+    fn test_wildcards_slices_and_filters() {
+        assert_eq!(query("deals[*].name"), vec![json!("small"), json!("large"), json!("medium")]);
+        assert_eq!(query("deals[1:].amount"), vec![json!(5000), json!(1500)]);
+        assert_eq!(query("deals[::2].name"), vec![json!("small"), json!("medium")]);
+        assert_eq!(query("deals[1::9223372036854775807].name"), vec![json!("large")]);
+        assert_eq!(query("deals[?(@.amount > 1000)].name"), vec![json!("large"), json!("medium")]);
+        assert_eq!(
+            query("deals[?(@.amount > 1000 && @.owner.email)].name"),
+            vec![json!("medium")]
+        );
+        assert_eq!(query("deals[?(@.name == 'small' || !@.owner)].amount"), vec![json!(500), json!(5000)]);
+    }
+
+    #[test]
+    fn test_get_wraps_non_singular_results() {
+        let data = deals();
+        let path = JsonPath::compile("deals[?(@.amount > 10000)]").unwrap();
+        assert!(!path.is_singular());
+        assert_eq!(path.get(&data), Some(json!([])));
+        assert_eq!(JsonPath::compile("deals[0].missing").unwrap().get(&data), None);
+    }
+
+    #[test]
+    fn test_set_creates_containers_and_assigns_element_wise() {
+        let mut target = json!({});
+        JsonPath::compile("a.b[1].c").unwrap().set(&mut target, json!(1)).unwrap();
+        assert_eq!(target, json!({"a": {"b": [null, {"c": 1}]}}));
+
+        let mut target = json!({"customers": [{"id": 1}, {"id": 2}]});
+        JsonPath::compile("customers[*].name").unwrap()
+            .set(&mut target, json!(["Ada", "Grace"]))
+            .unwrap();
+        assert_eq!(target, json!({"customers": [{"id": 1, "name": "Ada"}, {"id": 2, "name": "Grace"}]}));
+
+        let result = JsonPath::compile("deals[?(@.x)]").unwrap().set(&mut target, json!(1));
+        assert!(matches!(result, Err(PathError::Set { .. })));
+
+        // Indices far past the end would allocate the gap
+        let result = JsonPath::compile("out[4294967295]").unwrap().set(&mut target, json!(1));
+        assert!(matches!(result, Err(PathError::Set { .. })));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        for path in ["a..b", "a[", "a[1:2:0]", "a['x]", "a[?(@.x >)]", "$a"] {
+            assert!(matches!(JsonPath::compile(path), Err(PathError::Syntax { .. })), "{}", path);
+        }
+    }
+}
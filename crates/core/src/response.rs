@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+/// HTTP response a flow returns to the caller of its endpoint
+///
+/// Produced by a `respond` brick through `_respond` metadata in its output.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlowResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: ResponseBody,
+}
+
+/// Body of a `FlowResponse`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum ResponseBody {
+    Json(Value),
+    Text(String),
+    /// No body; the caller is sent to the location with a 3xx status
+    Redirect(String),
+}
+
+/// Headers a flow may not set: they would reach the API's origin, so they
+/// could set cookies for it or weaken the protections of its pages
+const RESERVED_HEADERS: &[&str] = &[
+    "set-cookie",
+    "set-cookie2",
+    "content-security-policy",
+    "content-security-policy-report-only",
+    "x-content-type-options",
+    "x-frame-options",
+    "strict-transport-security",
+    "permissions-policy",
+    "referrer-policy",
+];
+
+/// Prefixes of reserved header families (CORS and cross-origin isolation)
+const RESERVED_HEADER_PREFIXES: &[&str] = &["access-control-", "cross-origin-"];
+
+impl FlowResponse {
+    /// Checks the status code and headers; security headers and cookies are
+    /// reserved
+    pub fn validate(&self) -> Result<(), String> {
+        if !(100..=599).contains(&self.status) {
+            return Err(format!("Invalid status code {}", self.status));
+        }
+        if let ResponseBody::Redirect(location) = &self.body {
+            if !(300..=399).contains(&self.status) {
+                return Err(format!("Redirects need a 3xx status code, got {}", self.status));
+            }
+            if location.is_empty() || location.contains(['\r', '\n']) {
+                return Err("Invalid redirect location".to_string());
+            }
+        }
+        for (name, value) in &self.headers {
+            let valid_name = !name.is_empty() && name.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b));
+            if !valid_name {
+                return Err(format!("Invalid header name '{}'", name));
+            }
+            let lower = name.to_ascii_lowercase();
+            if RESERVED_HEADERS.contains(&lower.as_str()) || RESERVED_HEADER_PREFIXES.iter().any(|prefix| lower.starts_with(prefix)) {
+                return Err(format!("Header '{}' cannot be set by a flow", name));
+            }
+            if value.contains(['\r', '\n', '\0']) {
+                return Err(format!("Invalid value of header '{}'", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hands the first `FlowResponse` of an execution to the caller waiting for it
+///
+/// Cloned into the execution's context; later responses are ignored, so the
+/// caller gets its answer as soon as the first `respond` brick runs while the
+/// remaining bricks keep running.
+#[derive(Clone)]
+pub struct FlowResponder {
+    sender: Arc<Mutex<Option<oneshot::Sender<FlowResponse>>>>,
+}
+
+impl FlowResponder {
+    /// Creates a responder and the receiver the caller waits on
+    pub fn channel() -> (Self, oneshot::Receiver<FlowResponse>) {
+        let (sender, receiver) = oneshot::channel();
+        (Self { sender: Arc::new(Mutex::new(Some(sender))) }, receiver)
+    }
+
+    /// Sends a response; returns false if one was sent already or the caller
+    /// stopped waiting
+    pub fn respond(&self, response: FlowResponse) -> bool {
+        let sender = self.sender.lock().unwrap_or_else(|e| e.into_inner()).take();
+        sender.is_some_and(|sender| sender.send(response).is_ok())
+    }
+
+    /// Whether a response was sent already
+    pub fn has_responded(&self) -> bool {
+        self.sender.lock().unwrap_or_else(|e| e.into_inner()).is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_only_first_response_is_sent() {
+        let (responder, receiver) = FlowResponder::channel();
+        let first = FlowResponse { status: 201, headers: BTreeMap::new(), body: ResponseBody::Json(json!({"id": 1})) };
+        let second = FlowResponse { status: 200, headers: BTreeMap::new(), body: ResponseBody::Text("late".to_string()) };
+
+        assert!(responder.clone().respond(first.clone()));
+        assert!(responder.has_responded());
+        assert!(!responder.respond(second));
+        assert_eq!(receiver.await.unwrap(), first);
+    }
+
+    #[test]
+    fn test_validate() {
+        let redirect = FlowResponse { status: 200, headers: BTreeMap::new(), body: ResponseBody::Redirect("/done".to_string()) };
+        assert!(redirect.validate().is_err());
+        assert!(FlowResponse { status: 303, ..redirect.clone() }.validate().is_ok());
+
+        let headers = BTreeMap::from([("X-Bad\n".to_string(), "1".to_string())]);
+        assert!(FlowResponse { status: 303, headers, ..redirect.clone() }.validate().is_err());
+
+        for name in ["Set-Cookie", "content-security-policy", "Access-Control-Allow-Origin", "X-Content-Type-Options"] {
+            let headers = BTreeMap::from([(name.to_string(), "x".to_string())]);
+            assert!(FlowResponse { status: 303, headers, ..redirect.clone() }.validate().is_err(), "{}", name);
+        }
+        let headers = BTreeMap::from([("X-Lead-Id".to_string(), "1".to_string())]);
+        assert!(FlowResponse { status: 303, headers, ..redirect }.validate().is_ok());
+    }
+}
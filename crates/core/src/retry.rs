@@ -23,12 +23,28 @@ impl Default for RetryConfig {
 
 /// Retries an async operation with exponential backoff
 pub async fn retry_with_backoff<F, T, E>(
-    mut operation: F,
+    operation: F,
     config: RetryConfig,
 ) -> Result<T, E>
 where
     F: FnMut() -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<T, E>> + Send>>,
     E: std::fmt::Display,
+{
+    retry_with_backoff_if(operation, config, |_| true).await
+}
+
+/// Retries an async operation with exponential backoff while `should_retry`
+/// accepts the error
+pub async fn retry_with_backoff_if<F, Fut, T, E, P>(
+    mut operation: F,
+    config: RetryConfig,
+    should_retry: P,
+) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = Result<T, E>>,
+    E: std::fmt::Display,
+    P: Fn(&E) -> bool,
 {
     let mut delay = config.initial_delay;
     let mut last_error = None;
@@ -37,20 +53,25 @@ where
         match operation().await {
             Ok(result) => return Ok(result),
             Err(e) => {
-                last_error = Some(e);
-                if attempt < config.max_retries {
+                let retry = attempt < config.max_retries && should_retry(&e);
+                if retry {
                     tracing::warn!(
                         attempt = attempt + 1,
                         max_retries = config.max_retries,
                         delay_ms = delay.as_millis(),
+                        error = %e,
                         "Retrying operation after error"
                     );
-                    sleep(delay).await;
-                    delay = Duration::from_secs_f64(
-                        (delay.as_secs_f64() * config.backoff_multiplier)
-                            .min(config.max_delay.as_secs_f64()),
-                    );
                 }
+                last_error = Some(e);
+                if !retry {
+                    break;
+                }
+                sleep(delay).await;
+                delay = Duration::from_secs_f64(
+                    (delay.as_secs_f64() * config.backoff_multiplier)
+                        .min(config.max_delay.as_secs_f64()),
+                );
             }
         }
     }
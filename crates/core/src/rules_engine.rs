@@ -1,6 +1,7 @@
 use serde_json::Value;
-use crate::types::{Rule, RuleCondition, RuleAction, Operator};
+use crate::types::{Rule, RuleCondition, RuleAction, Operator, BranchMode};
 use crate::mapper::Mapper;
+use crate::path::JsonPath;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -58,9 +59,33 @@ impl RulesEngine {
         operator: &Operator,
         expected_value: &Value,
     ) -> Result<bool, RulesEngineError> {
-        let field_value = Mapper::get_value_at_path(input, path)
-            .map_err(|_| RulesEngineError::FieldNotFound(path.to_string()))?;
+        let compiled = JsonPath::cached(path)
+            .map_err(|e| RulesEngineError::EvaluationError(e.to_string()))?;
 
+        // Paths selecting several values hold if any selected value matches
+        if !compiled.is_singular() {
+            let matches = compiled.query(input);
+            if matches.is_empty() {
+                return Err(RulesEngineError::FieldNotFound(path.to_string()));
+            }
+            for field_value in matches {
+                if Self::compare_field(field_value.clone(), operator, expected_value)? {
+                    return Ok(true);
+                }
+            }
+            return Ok(false);
+        }
+
+        let field_value = compiled.get(input)
+            .ok_or_else(|| RulesEngineError::FieldNotFound(path.to_string()))?;
+        Self::compare_field(field_value, operator, expected_value)
+    }
+
+    fn compare_field(
+        field_value: Value,
+        operator: &Operator,
+        expected_value: &Value,
+    ) -> Result<bool, RulesEngineError> {
         match operator {
             Operator::Equals => Ok(field_value == *expected_value),
             Operator::NotEquals => Ok(field_value != *expected_value),
@@ -160,8 +185,8 @@ impl RulesEngine {
                     // Transform actions are handled by the mapper
                     results.push(RuleActionResult::Transform);
                 }
-                RuleAction::Branch { flow_id } => {
-                    results.push(RuleActionResult::Branch { flow_id: flow_id.clone() });
+                RuleAction::Branch { flow_id, mode } => {
+                    results.push(RuleActionResult::Branch { flow_id: flow_id.clone(), mode: *mode });
                 }
                 RuleAction::SkipBricks { count } => {
                     results.push(RuleActionResult::SkipBricks { count: *count });
@@ -173,10 +198,10 @@ impl RulesEngine {
     }
 
     /// Evaluate rules and return matching rule with actions
-    pub fn evaluate_rules(
-        rules: &[Rule],
+    pub fn evaluate_rules<'a>(
+        rules: &'a [Rule],
         input: &Value,
-    ) -> Result<Option<&Rule>, RulesEngineError> {
+    ) -> Result<Option<&'a Rule>, RulesEngineError> {
         for rule in rules {
             if Self::evaluate_condition(&rule.condition, input)? {
                 return Ok(Some(rule));
@@ -190,6 +215,30 @@ impl RulesEngine {
 pub enum RuleActionResult {
     SetField { path: String },
     Transform,
-    Branch { flow_id: String },
+    Branch { flow_id: String, mode: BranchMode },
     SkipBricks { count: usize },
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_wildcard_condition_matches_any_element() {
+        let input = json!({"deals": [{"amount": 500}, {"amount": 5000}]});
+        let condition = |path: &str, value: Value| RuleCondition::Field {
+            path: path.to_string(),
+            operator: Operator::GreaterThan,
+            value,
+        };
+
+        assert!(RulesEngine::evaluate_condition(&condition("deals[*].amount", json!(1000)), &input).unwrap());
+        assert!(!RulesEngine::evaluate_condition(&condition("deals[*].amount", json!(10000)), &input).unwrap());
+        assert!(!RulesEngine::evaluate_condition(&condition("deals[0].amount", json!(1000)), &input).unwrap());
+        assert!(matches!(
+            RulesEngine::evaluate_condition(&condition("deals[?(@.amount > 10000)].amount", json!(0)), &input),
+            Err(RulesEngineError::FieldNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,739 @@
+//! JSON Schema validation of payloads
+//!
+//! Covers the assertion keywords of JSON Schema 2020-12 that payload schemas
+//! commonly use:
+//!
+//! - `type`, `enum`, `const`
+//! - `minLength`, `maxLength`, `pattern`, `format` (`date-time`, `date`,
+//!   `email`, `uuid`, `ipv4`; other formats are not checked)
+//! - `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`, `multipleOf`
+//! - `required`, `properties`, `patternProperties`, `additionalProperties`,
+//!   `propertyNames`, `minProperties`, `maxProperties`, `dependentRequired`,
+//!   `dependentSchemas`
+//! - `items`, `prefixItems`, `contains`, `minContains`, `maxContains`,
+//!   `minItems`, `maxItems`, `uniqueItems`
+//! - `allOf`, `anyOf`, `oneOf`, `not`, `if`/`then`/`else`
+//! - `$ref` to a JSON pointer within the same schema, such as `#/$defs/lead`
+//!
+//! `check_schema` rejects schemas using keywords whose meaning is not
+//! implemented (`unevaluatedProperties`, `unevaluatedItems`, anchors and
+//! dynamic references, `$id` below the root, `$ref`s to other documents),
+//! rather than accepting payloads those keywords would refuse.
+//!
+//! Errors locate the invalid value with a JSON pointer into the payload.
+//! `apply_defaults` fills in the `default`s of missing properties.
+
+use regex::Regex;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Maximum number of compiled patterns kept by the pattern cache
+const MAX_CACHED_PATTERNS: usize = 256;
+
+/// Maximum number of nested `$ref`s followed, which stops reference cycles
+const MAX_REF_DEPTH: usize = 64;
+
+/// Maximum number of `$ref`s followed while validating one payload, which
+/// stops schemas that branch into the same reference from taking
+/// exponential time
+const MAX_REF_EXPANSIONS: usize = 100_000;
+
+/// Keywords that constrain payloads but are not implemented by `validate`
+const UNSUPPORTED_KEYWORDS: &[&str] = &[
+    "unevaluatedProperties",
+    "unevaluatedItems",
+    "$anchor",
+    "$dynamicAnchor",
+    "$dynamicRef",
+    "$recursiveAnchor",
+    "$recursiveRef",
+];
+
+/// Whether a number satisfies a bound keyword's limit
+type Bound = fn(f64, f64) -> bool;
+
+static PATTERN_CACHE: OnceLock<Mutex<HashMap<String, Arc<Regex>>>> = OnceLock::new();
+
+/// A value that does not match its schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaError {
+    /// JSON pointer of the value within the validated payload
+    pub instance_path: String,
+    /// JSON pointer of the failing keyword within the schema
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = if self.instance_path.is_empty() { "/" } else { &self.instance_path };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// Checks that a value is a usable schema: an object or boolean whose
+/// patterns compile, whose `$ref`s resolve within it and which uses no
+/// keyword `validate` would ignore
+pub fn check_schema(schema: &Value) -> Result<(), String> {
+    check_subschema(schema, schema, "")
+}
+
+/// Validates a payload against a schema; returns every mismatch found
+pub fn validate(schema: &Value, instance: &Value) -> Result<(), Vec<SchemaError>> {
+    let mut validator = Validator { root: schema, errors: Vec::new(), ref_depth: 0, ref_expansions: 0 };
+    validator.validate(schema, instance, "", "");
+    // References skipped past the limit leave the result undecided
+    if validator.ref_expansions > MAX_REF_EXPANSIONS {
+        validator.error("", "", "Schema follows too many references to validate".to_string());
+    }
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+/// Joins errors into one message, e.g. for an error string
+pub fn describe_errors(errors: &[SchemaError]) -> String {
+    errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")
+}
+
+/// Whether a payload matches a schema
+pub fn is_valid(schema: &Value, instance: &Value) -> bool {
+    validate(schema, instance).is_ok()
+}
+
+fn check_subschema(root: &Value, schema: &Value, path: &str) -> Result<(), String> {
+    let obj = match schema {
+        Value::Bool(_) => return Ok(()),
+        Value::Object(obj) => obj,
+        _ => return Err(format!("{}: schema must be an object or a boolean", display_path(path))),
+    };
+
+    if let Some(keyword) = UNSUPPORTED_KEYWORDS.iter().find(|keyword| obj.contains_key(**keyword)) {
+        return Err(format!("{}/{}: keyword is not supported", path, keyword));
+    }
+    // A nested `$id` would change what the `$ref`s below it point to
+    if !path.is_empty() && obj.contains_key("$id") {
+        return Err(format!("{}/$id: only the root schema may have an `$id`", path));
+    }
+    if let Some(reference) = obj.get("$ref") {
+        let reference = reference.as_str()
+            .ok_or_else(|| format!("{}/$ref: must be a string", path))?;
+        if !reference.starts_with('#') {
+            return Err(format!("{}/$ref: only references within the schema, such as '#/$defs/name', are supported", path));
+        }
+        if resolve_ref(root, reference).is_none() {
+            return Err(format!("{}/$ref: cannot resolve '{}'", path, reference));
+        }
+    }
+    if let Some(pattern) = obj.get("pattern") {
+        let pattern = pattern.as_str().ok_or_else(|| format!("{}/pattern: must be a string", path))?;
+        compile_pattern(pattern).map_err(|e| format!("{}/pattern: {}", path, e))?;
+    }
+    if let Some(types) = obj.get("type") {
+        let valid = match types {
+            Value::String(name) => is_type_name(name),
+            Value::Array(names) => names.iter().all(|n| n.as_str().is_some_and(is_type_name)),
+            _ => false,
+        };
+        if !valid {
+            return Err(format!("{}/type: unknown type {}", path, types));
+        }
+    }
+    if let Some(required) = obj.get("required") {
+        if !required.as_array().is_some_and(|names| names.iter().all(Value::is_string)) {
+            return Err(format!("{}/required: must be an array of strings", path));
+        }
+    }
+
+    for keyword in ["properties", "patternProperties", "$defs", "definitions", "dependentSchemas"] {
+        if let Some(schemas) = obj.get(keyword) {
+            let schemas = schemas.as_object().ok_or_else(|| format!("{}/{}: must be an object", path, keyword))?;
+            for (name, subschema) in schemas {
+                if keyword == "patternProperties" {
+                    compile_pattern(name).map_err(|e| format!("{}/{}: {}", path, keyword, e))?;
+                }
+                check_subschema(root, subschema, &format!("{}/{}/{}", path, keyword, escape_pointer(name)))?;
+            }
+        }
+    }
+    for keyword in ["allOf", "anyOf", "oneOf", "prefixItems"] {
+        if let Some(schemas) = obj.get(keyword) {
+            let schemas = schemas.as_array()
+                .filter(|schemas| !schemas.is_empty() || keyword == "prefixItems")
+                .ok_or_else(|| format!("{}/{}: must be a non-empty array", path, keyword))?;
+            for (index, subschema) in schemas.iter().enumerate() {
+                check_subschema(root, subschema, &format!("{}/{}/{}", path, keyword, index))?;
+            }
+        }
+    }
+    for keyword in ["additionalProperties", "propertyNames", "items", "contains", "not", "if", "then", "else"] {
+        if let Some(subschema) = obj.get(keyword) {
+            check_subschema(root, subschema, &format!("{}/{}", path, keyword))?;
+        }
+    }
+    Ok(())
+}
+
+fn is_type_name(name: &str) -> bool {
+    matches!(name, "null" | "boolean" | "object" | "array" | "number" | "integer" | "string")
+}
+
+struct Validator<'s> {
+    root: &'s Value,
+    errors: Vec<SchemaError>,
+    ref_depth: usize,
+    /// `$ref`s followed so far, including by probes
+    ref_expansions: usize,
+}
+
+impl<'s> Validator<'s> {
+    fn error(&mut self, instance_path: &str, schema_path: &str, message: String) {
+        self.errors.push(SchemaError {
+            instance_path: instance_path.to_string(),
+            schema_path: schema_path.to_string(),
+            message,
+        });
+    }
+
+    /// Validates against a subschema without recording its errors
+    fn matches(&mut self, schema: &'s Value, instance: &Value, instance_path: &str, schema_path: &str) -> bool {
+        let mut probe = Validator {
+            root: self.root,
+            errors: Vec::new(),
+            ref_depth: self.ref_depth,
+            ref_expansions: self.ref_expansions,
+        };
+        probe.validate(schema, instance, instance_path, schema_path);
+        self.ref_expansions = probe.ref_expansions;
+        probe.errors.is_empty()
+    }
+
+    fn validate(&mut self, schema: &'s Value, instance: &Value, instance_path: &str, schema_path: &str) {
+        let obj = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => {
+                self.error(instance_path, schema_path, "No value is allowed here".to_string());
+                return;
+            }
+            Value::Object(obj) => obj,
+            _ => return,
+        };
+
+        if let Some(reference) = obj.get("$ref").and_then(Value::as_str) {
+            let ref_path = format!("{}/$ref", schema_path);
+            match resolve_ref(self.root, reference) {
+                Some(_) if self.ref_depth >= MAX_REF_DEPTH => {
+                    self.error(instance_path, &ref_path, format!("Too many nested references at '{}'", reference));
+                }
+                // Reported once by `validate`
+                Some(_) if self.ref_expansions >= MAX_REF_EXPANSIONS => self.ref_expansions = MAX_REF_EXPANSIONS + 1,
+                Some(target) => {
+                    self.ref_expansions += 1;
+                    self.ref_depth += 1;
+                    self.validate(target, instance, instance_path, &ref_path);
+                    self.ref_depth -= 1;
+                }
+                None => self.error(instance_path, &ref_path, format!("Cannot resolve reference '{}'", reference)),
+            }
+        }
+
+        self.validate_generic(obj, instance, instance_path, schema_path);
+        match instance {
+            Value::String(s) => self.validate_string(obj, s, instance_path, schema_path),
+            Value::Number(_) => self.validate_number(obj, instance, instance_path, schema_path),
+            Value::Object(fields) => self.validate_object(obj, fields, instance_path, schema_path),
+            Value::Array(items) => self.validate_array(obj, items, instance_path, schema_path),
+            _ => {}
+        }
+        self.validate_combinators(obj, instance, instance_path, schema_path);
+    }
+
+    fn validate_generic(&mut self, obj: &'s Map<String, Value>, instance: &Value, instance_path: &str, schema_path: &str) {
+        if let Some(types) = obj.get("type") {
+            let allowed: Vec<&str> = match types {
+                Value::String(name) => vec![name.as_str()],
+                Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            if !allowed.iter().any(|name| has_type(instance, name)) {
+                self.error(
+                    instance_path,
+                    &format!("{}/type", schema_path),
+                    format!("Expected {}, found {}", allowed.join(" or "), type_name(instance)),
+                );
+            }
+        }
+        if let Some(values) = obj.get("enum").and_then(Value::as_array) {
+            if !values.iter().any(|value| json_equal(value, instance)) {
+                self.error(instance_path, &format!("{}/enum", schema_path), format!("{} is not one of the allowed values", instance));
+            }
+        }
+        if let Some(value) = obj.get("const") {
+            if !json_equal(value, instance) {
+                self.error(instance_path, &format!("{}/const", schema_path), format!("Expected {}", value));
+            }
+        }
+    }
+
+    fn validate_string(&mut self, obj: &Map<String, Value>, s: &str, instance_path: &str, schema_path: &str) {
+        let length = s.chars().count() as u64;
+        if let Some(min) = obj.get("minLength").and_then(Value::as_u64) {
+            if length < min {
+                self.error(instance_path, &format!("{}/minLength", schema_path), format!("Must be at least {} characters long", min));
+            }
+        }
+        if let Some(max) = obj.get("maxLength").and_then(Value::as_u64) {
+            if length > max {
+                self.error(instance_path, &format!("{}/maxLength", schema_path), format!("Must be at most {} characters long", max));
+            }
+        }
+        if let Some(pattern) = obj.get("pattern").and_then(Value::as_str) {
+            let matched = compile_pattern(pattern).map(|re| re.is_match(s));
+            if !matched.unwrap_or(false) {
+                self.error(instance_path, &format!("{}/pattern", schema_path), format!("Does not match pattern '{}'", pattern));
+            }
+        }
+        if let Some(format) = obj.get("format").and_then(Value::as_str) {
+            if !matches_format(format, s) {
+                self.error(instance_path, &format!("{}/format", schema_path), format!("Is not a valid {}", format));
+            }
+        }
+    }
+
+    fn validate_number(&mut self, obj: &Map<String, Value>, instance: &Value, instance_path: &str, schema_path: &str) {
+        let Some(n) = instance.as_f64() else { return };
+        let bounds: [(&str, Bound, &str); 4] = [
+            ("minimum", |n, limit| n >= limit, "greater than or equal to"),
+            ("maximum", |n, limit| n <= limit, "less than or equal to"),
+            ("exclusiveMinimum", |n, limit| n > limit, "greater than"),
+            ("exclusiveMaximum", |n, limit| n < limit, "less than"),
+        ];
+        for (keyword, holds, relation) in bounds {
+            if let Some(limit) = obj.get(keyword).and_then(Value::as_f64) {
+                if !holds(n, limit) {
+                    self.error(instance_path, &format!("{}/{}", schema_path, keyword), format!("Must be {} {}", relation, limit));
+                }
+            }
+        }
+        if let Some(divisor) = obj.get("multipleOf").and_then(Value::as_f64).filter(|d| *d > 0.0) {
+            let quotient = n / divisor;
+            if (quotient - quotient.round()).abs() > 1e-9 {
+                self.error(instance_path, &format!("{}/multipleOf", schema_path), format!("Must be a multiple of {}", divisor));
+            }
+        }
+    }
+
+    fn validate_object(&mut self, obj: &'s Map<String, Value>, fields: &Map<String, Value>, instance_path: &str, schema_path: &str) {
+        if let Some(required) = obj.get("required").and_then(Value::as_array) {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    self.error(instance_path, &format!("{}/required", schema_path), format!("Missing required property '{}'", name));
+                }
+            }
+        }
+        if let Some(min) = obj.get("minProperties").and_then(Value::as_u64) {
+            if (fields.len() as u64) < min {
+                self.error(instance_path, &format!("{}/minProperties", schema_path), format!("Must have at least {} properties", min));
+            }
+        }
+        if let Some(max) = obj.get("maxProperties").and_then(Value::as_u64) {
+            if fields.len() as u64 > max {
+                self.error(instance_path, &format!("{}/maxProperties", schema_path), format!("Must have at most {} properties", max));
+            }
+        }
+        if let Some(dependencies) = obj.get("dependentRequired").and_then(Value::as_object) {
+            for (name, required) in dependencies {
+                if !fields.contains_key(name) {
+                    continue;
+                }
+                for dependency in required.as_array().into_iter().flatten().filter_map(Value::as_str) {
+                    if !fields.contains_key(dependency) {
+                        self.error(
+                            instance_path,
+                            &format!("{}/dependentRequired/{}", schema_path, escape_pointer(name)),
+                            format!("Property '{}' requires property '{}'", name, dependency),
+                        );
+                    }
+                }
+            }
+        }
+
+        let properties = obj.get("properties").and_then(Value::as_object);
+        let pattern_properties = obj.get("patternProperties").and_then(Value::as_object);
+        let property_names = obj.get("propertyNames");
+        let additional = obj.get("additionalProperties");
+        for (name, value) in fields {
+            let child_path = format!("{}/{}", instance_path, escape_pointer(name));
+            let mut evaluated = false;
+            if let Some(subschema) = properties.and_then(|p| p.get(name)) {
+                evaluated = true;
+                self.validate(subschema, value, &child_path, &format!("{}/properties/{}", schema_path, escape_pointer(name)));
+            }
+            for (pattern, subschema) in pattern_properties.into_iter().flatten() {
+                if compile_pattern(pattern).is_ok_and(|re| re.is_match(name)) {
+                    evaluated = true;
+                    self.validate(subschema, value, &child_path, &format!("{}/patternProperties/{}", schema_path, escape_pointer(pattern)));
+                }
+            }
+            if let Some(subschema) = property_names {
+                let name_value = Value::String(name.clone());
+                if !self.matches(subschema, &name_value, &child_path, schema_path) {
+                    self.error(&child_path, &format!("{}/propertyNames", schema_path), format!("Property name '{}' is not allowed", name));
+                }
+            }
+            if let (false, Some(subschema)) = (evaluated, additional) {
+                if subschema == &Value::Bool(false) {
+                    self.error(&child_path, &format!("{}/additionalProperties", schema_path), format!("Unexpected property '{}'", name));
+                } else {
+                    self.validate(subschema, value, &child_path, &format!("{}/additionalProperties", schema_path));
+                }
+            }
+        }
+    }
+
+    fn validate_array(&mut self, obj: &'s Map<String, Value>, items: &[Value], instance_path: &str, schema_path: &str) {
+        if let Some(min) = obj.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                self.error(instance_path, &format!("{}/minItems", schema_path), format!("Must have at least {} items", min));
+            }
+        }
+        if let Some(max) = obj.get("maxItems").and_then(Value::as_u64) {
+            if items.len() as u64 > max {
+                self.error(instance_path, &format!("{}/maxItems", schema_path), format!("Must have at most {} items", max));
+            }
+        }
+        if obj.get("uniqueItems") == Some(&Value::Bool(true)) {
+            let duplicate = items.iter().enumerate()
+                .any(|(i, item)| items[..i].iter().any(|earlier| json_equal(earlier, item)));
+            if duplicate {
+                self.error(instance_path, &format!("{}/uniqueItems", schema_path), "Items must be unique".to_string());
+            }
+        }
+
+        let prefix = obj.get("prefixItems").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+        for (index, item) in items.iter().enumerate() {
+            let child_path = format!("{}/{}", instance_path, index);
+            match prefix.get(index) {
+                Some(subschema) => self.validate(subschema, item, &child_path, &format!("{}/prefixItems/{}", schema_path, index)),
+                None => {
+                    if let Some(subschema) = obj.get("items") {
+                        self.validate(subschema, item, &child_path, &format!("{}/items", schema_path));
+                    }
+                }
+            }
+        }
+
+        if let Some(subschema) = obj.get("contains") {
+            let contains_path = format!("{}/contains", schema_path);
+            let count = items.iter().enumerate()
+                .filter(|(index, item)| self.matches(subschema, item, &format!("{}/{}", instance_path, index), &contains_path))
+                .count() as u64;
+            let min = obj.get("minContains").and_then(Value::as_u64).unwrap_or(1);
+            if count < min {
+                self.error(instance_path, &contains_path, format!("Must contain at least {} matching items", min));
+            }
+            if let Some(max) = obj.get("maxContains").and_then(Value::as_u64) {
+                if count > max {
+                    self.error(instance_path, &format!("{}/maxContains", schema_path), format!("Must contain at most {} matching items", max));
+                }
+            }
+        }
+    }
+
+    fn validate_combinators(&mut self, obj: &'s Map<String, Value>, instance: &Value, instance_path: &str, schema_path: &str) {
+        if let Some(schemas) = obj.get("allOf").and_then(Value::as_array) {
+            for (index, subschema) in schemas.iter().enumerate() {
+                self.validate(subschema, instance, instance_path, &format!("{}/allOf/{}", schema_path, index));
+            }
+        }
+        // Applies the whole object to the schemas of the properties it has
+        if let (Some(dependencies), Value::Object(fields)) = (obj.get("dependentSchemas").and_then(Value::as_object), instance) {
+            for (name, subschema) in dependencies {
+                if fields.contains_key(name) {
+                    self.validate(subschema, instance, instance_path, &format!("{}/dependentSchemas/{}", schema_path, escape_pointer(name)));
+                }
+            }
+        }
+        if let Some(schemas) = obj.get("anyOf").and_then(Value::as_array) {
+            let any = schemas.iter().enumerate()
+                .any(|(index, subschema)| self.matches(subschema, instance, instance_path, &format!("{}/anyOf/{}", schema_path, index)));
+            if !any {
+                self.error(instance_path, &format!("{}/anyOf", schema_path), "Does not match any of the allowed schemas".to_string());
+            }
+        }
+        if let Some(schemas) = obj.get("oneOf").and_then(Value::as_array) {
+            let matching = schemas.iter().enumerate()
+                .filter(|(index, subschema)| self.matches(subschema, instance, instance_path, &format!("{}/oneOf/{}", schema_path, index)))
+                .count();
+            if matching != 1 {
+                self.error(
+                    instance_path,
+                    &format!("{}/oneOf", schema_path),
+                    format!("Must match exactly one schema, matches {}", matching),
+                );
+            }
+        }
+        if let Some(subschema) = obj.get("not") {
+            let not_path = format!("{}/not", schema_path);
+            if self.matches(subschema, instance, instance_path, &not_path) {
+                self.error(instance_path, &not_path, "Matches a disallowed schema".to_string());
+            }
+        }
+        if let Some(condition) = obj.get("if") {
+            let (branch, subschema) = if self.matches(condition, instance, instance_path, &format!("{}/if", schema_path)) {
+                ("then", obj.get("then"))
+            } else {
+                ("else", obj.get("else"))
+            };
+            if let Some(subschema) = subschema {
+                self.validate(subschema, instance, instance_path, &format!("{}/{}", schema_path, branch));
+            }
+        }
+    }
+}
+
+/// Inserts the `default` of every property missing from an object
+///
+/// Descends into present properties and array `items`; defaults behind
+/// `$ref`s and combinators are not applied, as which one applies depends on
+/// the value.
+pub fn apply_defaults(schema: &Value, instance: &mut Value) {
+    match instance {
+        Value::Object(fields) => {
+            let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+                return;
+            };
+            for (name, subschema) in properties {
+                match fields.get_mut(name) {
+                    Some(value) => apply_defaults(subschema, value),
+                    None => {
+                        if let Some(default) = subschema.get("default") {
+                            fields.insert(name.clone(), default.clone());
+                        }
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(subschema) = schema.get("items") {
+                items.iter_mut().for_each(|item| apply_defaults(subschema, item));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Resolves a `$ref` of the form `#` or `#/json/pointer` within `root`
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Option<&'a Value> {
+    let pointer = reference.strip_prefix('#')?;
+    if pointer.is_empty() {
+        return Some(root);
+    }
+    root.pointer(pointer)
+}
+
+fn compile_pattern(pattern: &str) -> Result<Arc<Regex>, regex::Error> {
+    let cache = PATTERN_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some(compiled) = cache.lock().unwrap_or_else(|e| e.into_inner()).get(pattern) {
+        return Ok(compiled.clone());
+    }
+
+    let compiled = Arc::new(Regex::new(pattern)?);
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    if cache.len() >= MAX_CACHED_PATTERNS {
+        cache.clear();
+    }
+    cache.insert(pattern.to_string(), compiled.clone());
+    Ok(compiled)
+}
+
+fn has_type(instance: &Value, name: &str) -> bool {
+    match name {
+        "null" => instance.is_null(),
+        "boolean" => instance.is_boolean(),
+        "object" => instance.is_object(),
+        "array" => instance.is_array(),
+        "number" => instance.is_number(),
+        "integer" => instance.is_i64() || instance.is_u64() || instance.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "string" => instance.is_string(),
+        _ => false,
+    }
+}
+
+fn type_name(instance: &Value) -> &'static str {
+    match instance {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+    }
+}
+
+/// Equality where numbers compare by value, so `1` equals `1.0`
+fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x == y || x.as_f64() == y.as_f64(),
+        (Value::Array(x), Value::Array(y)) => x.len() == y.len() && x.iter().zip(y).all(|(x, y)| json_equal(x, y)),
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len() && x.iter().all(|(key, value)| y.get(key).is_some_and(|other| json_equal(value, other)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+fn matches_format(format: &str, s: &str) -> bool {
+    match format {
+        "date-time" => chrono::DateTime::parse_from_rfc3339(s).is_ok(),
+        "date" => chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok(),
+        "email" => s.split_once('@').is_some_and(|(local, domain)| {
+            !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.') && !s.contains(char::is_whitespace)
+        }),
+        "uuid" => uuid::Uuid::parse_str(s).is_ok() && s.len() == 36,
+        "ipv4" => s.parse::<std::net::Ipv4Addr>().is_ok(),
+        _ => true,
+    }
+}
+
+/// Escapes a property name for use in a JSON pointer
+fn escape_pointer(name: &str) -> String {
+    name.replace('~', "~0").replace('/', "~1")
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() { "/" } else { path }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn lead_schema() -> Value {
+        json!({
+            "type": "object",
+            "required": ["email", "source"],
+            "properties": {
+                "email": { "type": "string", "format": "email" },
+                "source": { "enum": ["web", "referral"] },
+                "score": { "type": "integer", "minimum": 0, "maximum": 100 },
+                "tags": { "type": "array", "items": { "$ref": "#/$defs/tag" }, "uniqueItems": true }
+            },
+            "additionalProperties": false,
+            "$defs": {
+                "tag": { "type": "string", "pattern": "^[a-z0-9-]+$" }
+            }
+        })
+    }
+
+    #[test]
+    fn test_valid_payload() {
+        let lead = json!({ "email": "ada@example.com", "source": "web", "score": 80.0, "tags": ["hot", "b2b"] });
+        assert_eq!(validate(&lead_schema(), &lead), Ok(()));
+        assert!(check_schema(&lead_schema()).is_ok());
+    }
+
+    #[test]
+    fn test_errors_point_at_invalid_values() {
+        let lead = json!({ "email": "not-an-email", "score": 120, "tags": ["hot", "Cold", "hot"], "extra": 1 });
+        let errors = validate(&lead_schema(), &lead).unwrap_err();
+        let found: Vec<(&str, &str)> = errors.iter().map(|e| (e.instance_path.as_str(), e.schema_path.as_str())).collect();
+        assert_eq!(found, vec![
+            ("", "/required"),
+            ("/email", "/properties/email/format"),
+            ("/extra", "/additionalProperties"),
+            ("/score", "/properties/score/maximum"),
+            ("/tags", "/properties/tags/uniqueItems"),
+            ("/tags/1", "/properties/tags/items/$ref/pattern"),
+        ]);
+        assert_eq!(errors[0].message, "Missing required property 'source'");
+    }
+
+    #[test]
+    fn test_combinators() {
+        let schema = json!({
+            "oneOf": [
+                { "type": "object", "required": ["id"] },
+                { "type": "string", "minLength": 1 }
+            ],
+            "if": { "type": "object" },
+            "then": { "properties": { "id": { "type": "integer" } } }
+        });
+        assert!(is_valid(&schema, &json!({ "id": 7 })));
+        assert!(is_valid(&schema, &json!("lead-7")));
+        assert!(!is_valid(&schema, &json!({ "id": "7" })));
+        assert!(!is_valid(&schema, &json!("")));
+        assert!(!is_valid(&json!({ "not": { "type": "null" } }), &Value::Null));
+    }
+
+    #[test]
+    fn test_invalid_schemas_are_rejected() {
+        assert!(check_schema(&json!("object")).is_err());
+        assert!(check_schema(&json!({ "type": "text" })).is_err());
+        assert!(check_schema(&json!({ "properties": { "name": { "pattern": "(" } } })).is_err());
+        assert!(check_schema(&json!({ "items": { "$ref": "#/$defs/missing" } })).is_err());
+        assert!(check_schema(&json!(true)).is_ok());
+    }
+
+    #[test]
+    fn test_unsupported_keywords_are_rejected() {
+        assert!(check_schema(&json!({ "unevaluatedProperties": false })).is_err());
+        assert!(check_schema(&json!({ "properties": { "tags": { "unevaluatedItems": false } } })).is_err());
+        assert!(check_schema(&json!({ "$defs": { "lead": { "$anchor": "lead" } } })).is_err());
+        assert!(check_schema(&json!({ "items": { "$dynamicRef": "#meta" } })).is_err());
+        assert!(check_schema(&json!({ "$ref": "https://example.com/lead.json" })).is_err());
+        assert!(check_schema(&json!({ "$ref": "lead.json#/$defs/lead" })).is_err());
+        assert!(check_schema(&json!({ "properties": { "lead": { "$id": "lead.json" } } })).is_err());
+        assert!(check_schema(&json!({ "$id": "https://example.com/signup.json", "$ref": "#/$defs/lead", "$defs": { "lead": {} } })).is_ok());
+    }
+
+    #[test]
+    fn test_branching_references_are_limited() {
+        let schema = json!({ "allOf": [{ "$ref": "#" }, { "$ref": "#" }] });
+        assert!(check_schema(&schema).is_ok());
+        let errors = validate(&schema, &json!({})).unwrap_err();
+        assert!(errors.iter().any(|e| e.message.contains("too many references")));
+
+        // Probes whose errors are dropped still hit the limit
+        let schema = json!({ "not": { "anyOf": [{ "$ref": "#/not" }, { "$ref": "#/not" }] } });
+        assert!(!is_valid(&schema, &json!({})));
+
+        // Reused definitions are fine
+        let schema = json!({
+            "type": "array",
+            "items": { "$ref": "#/$defs/lead" },
+            "$defs": { "lead": { "type": "object", "required": ["email"] } }
+        });
+        assert!(is_valid(&schema, &json!(vec![json!({ "email": "a@example.com" }); 1000])));
+    }
+
+    #[test]
+    fn test_apply_defaults() {
+        let schema = json!({
+            "properties": {
+                "mode": { "type": "string", "default": "forward" },
+                "mappings": {
+                    "type": "array",
+                    "items": { "properties": { "direction": { "default": "forward" } } }
+                }
+            },
+            "dependentSchemas": { "mode": { "required": ["mappings"] } }
+        });
+        let mut config = json!({ "mappings": [{ "source_path": "a" }, { "direction": "backward" }] });
+        apply_defaults(&schema, &mut config);
+        assert_eq!(config, json!({
+            "mode": "forward",
+            "mappings": [{ "source_path": "a", "direction": "forward" }, { "direction": "backward" }]
+        }));
+
+        // The defaulted mode now requires mappings
+        let mut config = json!({});
+        apply_defaults(&schema, &mut config);
+        assert_eq!(validate(&schema, &config).unwrap_err()[0].schema_path, "/dependentSchemas/mode/required");
+    }
+}
@@ -0,0 +1,483 @@
+//! Secret references in brick configs and redaction of secret values
+//!
+//! Brick configs refer to entries of the secrets store with
+//! `{{ secret:name }}`; the flow runner resolves them through a
+//! `SecretResolver` just before a brick runs. Every value resolved during an
+//! execution is added to the execution's `SecretRedactor`, which removes it
+//! from step records, execution data, events and errors.
+
+use async_trait::async_trait;
+use serde_json::{Map, Value};
+use std::sync::RwLock;
+
+use crate::brick_traits::BrickError;
+use crate::flow_runner::FlowError;
+use crate::response::{FlowResponse, ResponseBody};
+use crate::schema::SchemaError;
+use crate::templating::{self, CompiledTemplate};
+use crate::types::{BrickConfig, BrickFallback, Flow, FlowExecution, FlowNodeKind};
+
+/// Replaces secret values in records and API responses
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Config keys whose values are credentials
+const CREDENTIAL_KEYS: &[&str] = &[
+    "api_key",
+    "apikey",
+    "password",
+    "secret",
+    "token",
+    "access_token",
+    "refresh_token",
+    "client_secret",
+    "private_key",
+    "authorization",
+];
+
+/// Trait for resolving secret references (to avoid circular dependencies)
+#[async_trait]
+pub trait SecretResolver: Send + Sync {
+    /// Returns the plaintext value of a secret of the workspace, or `None` if
+    /// it has no secret with the name
+    async fn resolve_secret(
+        &self,
+        workspace_id: Option<&str>,
+        name: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Secret values used by an execution, shared with its sub-flows
+#[derive(Debug, Default)]
+pub struct SecretRedactor {
+    /// Longest first, so a secret containing another is replaced whole
+    values: RwLock<Vec<String>>,
+}
+
+impl SecretRedactor {
+    pub fn add(&self, value: &str) {
+        if value.is_empty() {
+            return;
+        }
+        let mut values = self.values.write().unwrap_or_else(|e| e.into_inner());
+        if !values.iter().any(|v| v == value) {
+            values.push(value.to_string());
+            values.sort_by_key(|v| std::cmp::Reverse(v.len()));
+        }
+    }
+
+    /// Adds the literal credentials of a config (see `is_credential_key`)
+    pub fn add_credentials(&self, config: &Value) {
+        match config {
+            Value::Object(fields) => {
+                for (key, value) in fields {
+                    match value {
+                        Value::String(s) if is_credential_key(key) && s != REDACTED => self.add(s),
+                        value => self.add_credentials(value),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|item| self.add_credentials(item)),
+            _ => {}
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.read().unwrap_or_else(|e| e.into_inner()).is_empty()
+    }
+
+    pub fn redact_str(&self, s: &str) -> String {
+        let values = self.values.read().unwrap_or_else(|e| e.into_inner());
+        values.iter().fold(s.to_string(), |s, value| {
+            if s.contains(value.as_str()) {
+                s.replace(value.as_str(), REDACTED)
+            } else {
+                s
+            }
+        })
+    }
+
+    /// Redacts every string (and object key) in a value
+    pub fn redact(&self, value: &Value) -> Value {
+        if self.is_empty() {
+            return value.clone();
+        }
+        self.redact_value(value)
+    }
+
+    fn redact_value(&self, value: &Value) -> Value {
+        match value {
+            Value::String(s) => Value::String(self.redact_str(s)),
+            Value::Array(items) => Value::Array(items.iter().map(|item| self.redact_value(item)).collect()),
+            Value::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(key, value)| (self.redact_str(key), self.redact_value(value)))
+                    .collect::<Map<_, _>>(),
+            ),
+            value => value.clone(),
+        }
+    }
+
+    /// Redacts the payloads, error and step records of an execution
+    pub fn redact_execution(&self, execution: &mut FlowExecution) {
+        if self.is_empty() {
+            return;
+        }
+        execution.input_payload = self.redact_value(&execution.input_payload);
+        execution.output_payload = execution.output_payload.as_ref().map(|output| self.redact_value(output));
+        execution.error = execution.error.as_deref().map(|error| self.redact_str(error));
+        for step in &mut execution.steps {
+            step.input = self.redact_value(&step.input);
+            step.output = step.output.as_ref().map(|output| self.redact_value(output));
+            step.error = step.error.as_deref().map(|error| self.redact_str(error));
+        }
+    }
+
+    /// Redacts the headers and body of a response sent to an endpoint's caller
+    pub fn redact_response(&self, response: &mut FlowResponse) {
+        if self.is_empty() {
+            return;
+        }
+        for value in response.headers.values_mut() {
+            *value = self.redact_str(value);
+        }
+        response.body = match &response.body {
+            ResponseBody::Json(value) => ResponseBody::Json(self.redact_value(value)),
+            ResponseBody::Text(text) => ResponseBody::Text(self.redact_str(text)),
+            ResponseBody::Redirect(location) => ResponseBody::Redirect(self.redact_str(location)),
+        };
+    }
+
+    /// Redacts the message of a flow error
+    fn redact_schema_errors(&self, errors: Vec<SchemaError>) -> Vec<SchemaError> {
+        errors
+            .into_iter()
+            .map(|e| SchemaError { message: self.redact_str(&e.message), ..e })
+            .collect()
+    }
+
+    pub fn redact_error(&self, error: FlowError) -> FlowError {
+        if self.is_empty() {
+            return error;
+        }
+        let redact = |message: String| self.redact_str(&message);
+        match error {
+            FlowError::BrickError(e) => FlowError::BrickError(match e {
+                BrickError::ConfigError(m) => BrickError::ConfigError(redact(m)),
+                BrickError::ExecutionError(m) => BrickError::ExecutionError(redact(m)),
+                BrickError::QuotaExceeded(m) => BrickError::QuotaExceeded(redact(m)),
+                BrickError::NetworkError(m) => BrickError::NetworkError(redact(m)),
+                BrickError::InvalidInput(m) => BrickError::InvalidInput(redact(m)),
+                BrickError::Unknown(m) => BrickError::Unknown(redact(m)),
+                e @ BrickError::Timeout(_) => e,
+            }),
+            FlowError::ConditionError(m) => FlowError::ConditionError(redact(m)),
+            FlowError::SubFlowError(m) => FlowError::SubFlowError(redact(m)),
+            FlowError::InvalidInput(errors) => FlowError::InvalidInput(self.redact_schema_errors(errors)),
+            FlowError::InvalidOutput(errors) => FlowError::InvalidOutput(self.redact_schema_errors(errors)),
+            error => error,
+        }
+    }
+}
+
+/// Whether a config key holds a credential, e.g. `api_key` or `smtp_password`
+pub fn is_credential_key(key: &str) -> bool {
+    let key = key.to_ascii_lowercase();
+    CREDENTIAL_KEYS
+        .iter()
+        .any(|k| key == *k || key.strip_suffix(k).is_some_and(|prefix| prefix.ends_with('_')))
+}
+
+/// Whether a string is a template referring to a stored secret
+pub fn references_secret(s: &str) -> bool {
+    s.contains("secret:")
+        && templating::is_template(s)
+        && CompiledTemplate::compile(s).is_ok_and(|template| !template.secret_names().is_empty())
+}
+
+/// Masks credentials stored in plain text in a config
+///
+/// Values of credential keys are replaced with `REDACTED` unless they refer
+/// to a stored secret.
+pub fn redact_credentials(config: &mut Value) {
+    match config {
+        Value::Object(fields) => {
+            for (key, value) in fields.iter_mut() {
+                match value {
+                    Value::String(s) if is_credential_key(key) => {
+                        if !s.is_empty() && !references_secret(s) {
+                            *s = REDACTED.to_string();
+                        }
+                    }
+                    value => redact_credentials(value),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact_credentials),
+        _ => {}
+    }
+}
+
+/// Puts back credentials masked by `redact_credentials`, taking them from
+/// the same place in `previous`
+pub fn restore_credentials(config: &mut Value, previous: &Value) {
+    match (config, previous) {
+        (Value::Object(fields), Value::Object(previous_fields)) => {
+            for (key, value) in fields.iter_mut() {
+                if let Some(previous_value) = previous_fields.get(key) {
+                    match value {
+                        Value::String(s) if s == REDACTED && is_credential_key(key) => {
+                            if let Value::String(previous_value) = previous_value {
+                                *s = previous_value.clone();
+                            }
+                        }
+                        value => restore_credentials(value, previous_value),
+                    }
+                }
+            }
+        }
+        (Value::Array(items), Value::Array(previous_items)) => {
+            for (item, previous_item) in items.iter_mut().zip(previous_items) {
+                restore_credentials(item, previous_item);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Masks the plain-text credentials in every brick config of a flow
+pub fn redact_flow_credentials(flow: &mut Flow) {
+    flow.visit_bricks_mut(|brick| redact_credentials(&mut brick.config));
+}
+
+/// Restores credentials masked in an update of a flow from the stored flow
+///
+/// Brick nodes are matched by node id and brick type, so masked credentials
+/// stay with their brick when bricks are reordered; the linear brick list is
+/// then rebuilt from the graph. Credentials are only restored into configs
+/// whose other fields are unchanged, so an update cannot send a stored
+/// credential to a new destination such as another `url`. Fails with the id
+/// of the first node that still holds a masked credential, which would
+/// otherwise be stored as `REDACTED`.
+pub fn restore_flow_credentials(flow: &mut Flow, previous: &Flow) -> Result<(), String> {
+    let Some(graph) = flow.graph.as_mut() else {
+        return Ok(());
+    };
+    let previous_graph = previous.effective_graph();
+    for node in &mut graph.nodes {
+        let FlowNodeKind::Brick(ref mut brick) = node.kind else {
+            continue;
+        };
+        if let Some(FlowNodeKind::Brick(previous)) = previous_graph.node(&node.id).map(|n| &n.kind) {
+            if previous.brick_type == brick.brick_type {
+                restore_brick_credentials(brick, previous);
+            }
+        }
+        let mut masked = false;
+        visit_brick_configs(brick, &mut |config| masked |= contains_masked_credential(config));
+        if masked {
+            return Err(node.id.clone());
+        }
+    }
+    flow.bricks = graph.brick_configs().into_iter().cloned().collect();
+    Ok(())
+}
+
+/// Restores the credentials of a brick and of its fallback brick, where their
+/// configs differ from the previous ones in credentials only
+fn restore_brick_credentials(brick: &mut BrickConfig, previous: &BrickConfig) {
+    if same_apart_from_credentials(&brick.config, &previous.config) {
+        restore_credentials(&mut brick.config, &previous.config);
+    }
+    let previous_fallback = match previous.policy.as_ref().and_then(|p| p.fallback.as_ref()) {
+        Some(BrickFallback::Brick(fallback)) => fallback,
+        _ => return,
+    };
+    if let Some(BrickFallback::Brick(fallback)) = brick.policy.as_mut().and_then(|p| p.fallback.as_mut()) {
+        if fallback.brick_type == previous_fallback.brick_type
+            && same_apart_from_credentials(&fallback.config, &previous_fallback.config)
+        {
+            restore_credentials(&mut fallback.config, &previous_fallback.config);
+        }
+    }
+}
+
+/// Whether two configs are equal once their credentials are masked
+fn same_apart_from_credentials(config: &Value, previous: &Value) -> bool {
+    let (mut config, mut previous) = (config.clone(), previous.clone());
+    redact_credentials(&mut config);
+    redact_credentials(&mut previous);
+    config == previous
+}
+
+/// Calls `f` with the config of a brick and of its fallback brick, if any
+fn visit_brick_configs(brick: &BrickConfig, f: &mut impl FnMut(&Value)) {
+    f(&brick.config);
+    if let Some(BrickFallback::Brick(fallback)) = brick.policy.as_ref().and_then(|p| p.fallback.as_ref()) {
+        f(&fallback.config);
+    }
+}
+
+/// Whether a config still holds a credential masked by `redact_credentials`
+fn contains_masked_credential(config: &Value) -> bool {
+    match config {
+        Value::Object(fields) => fields.iter().any(|(key, value)| match value {
+            Value::String(s) => s == REDACTED && is_credential_key(key),
+            value => contains_masked_credential(value),
+        }),
+        Value::Array(items) => items.iter().any(contains_masked_credential),
+        _ => false,
+    }
+}
+
+/// Whether a stored payload had secret values replaced with `REDACTED`
+///
+/// Such payloads cannot be fed to bricks again.
+pub fn contains_redacted(value: &Value) -> bool {
+    match value {
+        Value::String(s) => s.contains(REDACTED),
+        Value::Array(items) => items.iter().any(contains_redacted),
+        Value::Object(fields) => fields.iter().any(|(key, value)| key.contains(REDACTED) || contains_redacted(value)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_redactor_replaces_values_everywhere() {
+        let redactor = SecretRedactor::default();
+        assert_eq!(redactor.redact(&json!({"a": "sk-123"})), json!({"a": "sk-123"}));
+
+        redactor.add("sk-123");
+        redactor.add("sk-123456");
+        redactor.add("");
+        assert_eq!(redactor.redact_str("key sk-123456 and sk-123"), "key [REDACTED] and [REDACTED]");
+        assert_eq!(
+            redactor.redact(&json!({"sk-123": ["Bearer sk-123", 5]})),
+            json!({"[REDACTED]": ["Bearer [REDACTED]", 5]})
+        );
+
+        let error = redactor.redact_error(FlowError::BrickError(BrickError::NetworkError("bad key sk-123".to_string())));
+        assert_eq!(error.to_string(), "Brick execution error: Network error: bad key [REDACTED]");
+    }
+
+    #[test]
+    fn test_credentials_are_masked_and_restored() {
+        let stored = json!({
+            "api_key": "sk-live",
+            "db_password": "hunter2",
+            "token": "{{ secret:crm-token }}",
+            "nested": [{"authorization": "Basic abc"}, {"authorization": "Bearer {{ secret:crm-token }}"}],
+            "model_name": "gpt-4"
+        });
+        let mut config = stored.clone();
+        redact_credentials(&mut config);
+        assert_eq!(config, json!({
+            "api_key": "[REDACTED]",
+            "db_password": "[REDACTED]",
+            "token": "{{ secret:crm-token }}",
+            "nested": [{"authorization": "[REDACTED]"}, {"authorization": "Bearer {{ secret:crm-token }}"}],
+            "model_name": "gpt-4"
+        }));
+
+        config["db_password"] = json!("changed");
+        restore_credentials(&mut config, &stored);
+        assert_eq!(config["api_key"], "sk-live");
+        assert_eq!(config["db_password"], "changed");
+        assert_eq!(config["nested"][0]["authorization"], "Basic abc");
+
+        let redactor = SecretRedactor::default();
+        redactor.add_credentials(&stored);
+        assert_eq!(redactor.redact_str("sk-live hunter2 gpt-4"), "[REDACTED] [REDACTED] gpt-4");
+    }
+
+    fn openai_flow(nodes: &[(&str, &str)]) -> Flow {
+        let graph = crate::types::FlowGraph::chain(nodes.iter().map(|(id, key)| (Some(id.to_string()), BrickConfig {
+            brick_type: crate::types::BrickType::OpenAi,
+            config: json!({"api_key": key}),
+            policy: None,
+        })).collect());
+        Flow {
+            id: "flow-1".to_string(),
+            name: "Keys".to_string(),
+            description: None,
+            bricks: graph.brick_configs().into_iter().cloned().collect(),
+            graph: Some(graph),
+            retry_policy: None,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: None,
+            active: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_flow_credentials_follow_node_ids() {
+        let stored = openai_flow(&[("first", "sk-first"), ("second", "sk-second")]);
+
+        // Reordered bricks keep their own keys
+        let mut update = openai_flow(&[("second", REDACTED), ("first", REDACTED)]);
+        restore_flow_credentials(&mut update, &stored).unwrap();
+        assert_eq!(update.bricks[0].config["api_key"], "sk-second");
+        assert_eq!(update.bricks[1].config["api_key"], "sk-first");
+        assert_eq!(update.graph.unwrap().brick_configs()[0].config["api_key"], "sk-second");
+
+        // A masked key for a brick that was not stored cannot be restored
+        let mut update = openai_flow(&[("first", REDACTED), ("third", REDACTED)]);
+        assert_eq!(restore_flow_credentials(&mut update, &stored), Err("third".to_string()));
+    }
+
+    #[test]
+    fn test_credentials_are_not_restored_into_changed_configs() {
+        let mut stored = openai_flow(&[("first", "sk-first")]);
+        stored.graph.as_mut().unwrap().nodes[0].kind = FlowNodeKind::Brick(BrickConfig {
+            brick_type: crate::types::BrickType::OpenAi,
+            config: json!({"api_key": "sk-first", "base_url": "https://api.openai.com", "token": "t-1"}),
+            policy: None,
+        });
+
+        // Changing only another credential keeps the masked one
+        let mut update = stored.clone();
+        update.graph.as_mut().unwrap().nodes[0].kind = FlowNodeKind::Brick(BrickConfig {
+            brick_type: crate::types::BrickType::OpenAi,
+            config: json!({"api_key": REDACTED, "base_url": "https://api.openai.com", "token": "t-2"}),
+            policy: None,
+        });
+        restore_flow_credentials(&mut update, &stored).unwrap();
+        assert_eq!(update.bricks[0].config["api_key"], "sk-first");
+
+        // A new destination needs the credential again
+        let mut update = stored.clone();
+        update.graph.as_mut().unwrap().nodes[0].kind = FlowNodeKind::Brick(BrickConfig {
+            brick_type: crate::types::BrickType::OpenAi,
+            config: json!({"api_key": REDACTED, "base_url": "https://attacker.example", "token": "t-1"}),
+            policy: None,
+        });
+        assert_eq!(restore_flow_credentials(&mut update, &stored), Err("first".to_string()));
+    }
+
+    #[test]
+    fn test_redacted_payloads_are_detected() {
+        assert!(contains_redacted(&json!({"auth": "Bearer [REDACTED]"})));
+        assert!(contains_redacted(&json!([{"[REDACTED]": 1}])));
+        assert!(!contains_redacted(&json!({"name": "REDACTED", "count": 3})));
+    }
+
+    #[test]
+    fn test_credential_keys_and_references() {
+        assert!(is_credential_key("api_key"));
+        assert!(is_credential_key("SMTP_Password"));
+        assert!(!is_credential_key("tokens"));
+        assert!(!is_credential_key("max_tokens"));
+        assert!(references_secret("{{secret:openai}}"));
+        assert!(references_secret("Bearer {{ secret:crm.token-2 }}"));
+        assert!(!references_secret("secret:openai"));
+        assert!(!references_secret("{{ vars.openai }}"));
+    }
+}
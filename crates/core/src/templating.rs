@@ -0,0 +1,1043 @@
+//! Template engine for brick configs and prompts
+//!
+//! Templates use a small Jinja-like syntax:
+//!
+//! - `{{ customer.name }}` outputs a value; paths support `.key`, `[0]` and
+//!   `["quoted key"]`
+//! - `{{ name | default("n/a") | upper }}` pipes a value through filters
+//! - `{% if amount > 1000 and not vip %}…{% elif … %}…{% else %}…{% endif %}`
+//! - `{% for item in items %}{{ loop.index }}. {{ item.name }}{% else %}none{% endfor %}`
+//! - `{# comments #}`
+//!
+//! Names resolve against loop variables, then the `payload`, `vars` and
+//! `execution` namespaces, then the fields of the payload itself, so
+//! `{{ customer.name }}` and `{{ payload.customer.name }}` are equivalent.
+//!
+//! `{{ secret:name }}` refers to an entry of the secrets store. Secrets are
+//! resolved by the caller (see `secret_references`) and passed in the context.
+//! They can only be output as is: filters, comparisons, conditions and loops
+//! over a secret would derive values the redactor cannot recognize.
+
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TemplateError {
+    #[error("Template syntax error at position {position}: {message}")]
+    Syntax { position: usize, message: String },
+
+    #[error("Undefined variable '{0}' in template")]
+    Undefined(String),
+
+    #[error("Template filter '{filter}' failed: {message}")]
+    Filter { filter: String, message: String },
+}
+
+/// Values a template can refer to
+pub struct TemplateContext<'a> {
+    /// Input of the brick being rendered
+    pub payload: &'a Value,
+    /// Variables of the flow
+    pub variables: &'a Map<String, Value>,
+    /// Metadata of the execution, such as its id and the brick index
+    pub execution: Value,
+    /// Plaintext values of the secrets the template refers to, by name
+    pub secrets: &'a HashMap<String, String>,
+}
+
+impl<'a> TemplateContext<'a> {
+    /// Context with only a payload
+    pub fn from_payload(payload: &'a Value) -> Self {
+        static NO_VARIABLES: std::sync::OnceLock<Map<String, Value>> = std::sync::OnceLock::new();
+        static NO_SECRETS: std::sync::OnceLock<HashMap<String, String>> = std::sync::OnceLock::new();
+        Self {
+            payload,
+            variables: NO_VARIABLES.get_or_init(Map::new),
+            execution: Value::Object(Map::new()),
+            secrets: NO_SECRETS.get_or_init(HashMap::new),
+        }
+    }
+}
+
+/// Returns true if a string contains template syntax
+pub fn is_template(s: &str) -> bool {
+    s.contains("{{") || s.contains("{%") || s.contains("{#")
+}
+
+/// Renders every templated string in a config
+///
+/// A string consisting of a single `{{ … }}` expression is replaced by the
+/// value itself, so `"{{ invoice.lines }}"` stays an array; other templates
+/// render to strings. Object keys are left as they are.
+pub fn render_config(config: &Value, context: &TemplateContext) -> Result<Value, TemplateError> {
+    match config {
+        Value::String(s) if is_template(s) => CompiledTemplate::compile(s)?.render_value(context),
+        Value::Array(items) => items.iter().map(|item| render_config(item, context)).collect::<Result<_, _>>().map(Value::Array),
+        Value::Object(fields) => fields
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), render_config(value, context)?)))
+            .collect::<Result<Map<_, _>, _>>()
+            .map(Value::Object),
+        value => Ok(value.clone()),
+    }
+}
+
+/// Returns the names of the secrets referenced by the templates in a config
+pub fn secret_references(config: &Value) -> Result<BTreeSet<String>, TemplateError> {
+    let mut names = BTreeSet::new();
+    collect_secret_references(config, &mut names)?;
+    Ok(names)
+}
+
+fn collect_secret_references(config: &Value, names: &mut BTreeSet<String>) -> Result<(), TemplateError> {
+    match config {
+        Value::String(s) if s.contains("secret:") && is_template(s) => {
+            names.extend(CompiledTemplate::compile(s)?.secret_names());
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_secret_references(item, names)?;
+            }
+        }
+        Value::Object(fields) => {
+            for value in fields.values() {
+                collect_secret_references(value, names)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// A parsed template
+#[derive(Debug, Clone)]
+pub struct CompiledTemplate {
+    nodes: Vec<Node>,
+}
+
+impl CompiledTemplate {
+    pub fn compile(source: &str) -> Result<Self, TemplateError> {
+        let tokens = tokenize(source)?;
+        let mut tokens = tokens.into_iter().peekable();
+        let (nodes, end) = parse_nodes(&mut tokens, &[])?;
+        if let Some((tag, position)) = end {
+            return Err(syntax(position, format!("unexpected {{% {} %}}", tag)));
+        }
+        Ok(Self { nodes })
+    }
+
+    /// Renders the template to a string
+    pub fn render(&self, context: &TemplateContext) -> Result<String, TemplateError> {
+        let mut out = String::new();
+        let mut scope = Scope::new(context);
+        render_nodes(&self.nodes, &mut scope, &mut out)?;
+        Ok(out)
+    }
+
+    /// Renders the template, keeping the type of the value when the template
+    /// is a single expression
+    pub fn render_value(&self, context: &TemplateContext) -> Result<Value, TemplateError> {
+        if let [Node::Output(expr)] = self.nodes.as_slice() {
+            let scope = Scope::new(context);
+            return expr.eval(&scope)?.ok_or_else(|| TemplateError::Undefined(expr.describe()));
+        }
+        self.render(context).map(Value::String)
+    }
+
+    /// Names of the secrets the template refers to
+    pub fn secret_names(&self) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        for node in &self.nodes {
+            node.collect_secrets(&mut names);
+        }
+        names
+    }
+}
+
+fn syntax(position: usize, message: impl Into<String>) -> TemplateError {
+    TemplateError::Syntax { position, message: message.into() }
+}
+
+// ---------------------------------------------------------------------------
+// Tokens and nodes
+
+enum Token {
+    Text(String),
+    Output(String, usize),
+    Tag(String, usize),
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Output(Expr),
+    If {
+        branches: Vec<(Expr, Vec<Node>)>,
+        otherwise: Vec<Node>,
+    },
+    For {
+        variable: String,
+        iterable: Expr,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+impl Node {
+    fn collect_secrets(&self, names: &mut BTreeSet<String>) {
+        match self {
+            Node::Text(_) => {}
+            Node::Output(expr) => expr.collect_secrets(names),
+            Node::If { branches, otherwise } => {
+                for (condition, body) in branches {
+                    condition.collect_secrets(names);
+                    body.iter().for_each(|node| node.collect_secrets(names));
+                }
+                otherwise.iter().for_each(|node| node.collect_secrets(names));
+            }
+            Node::For { iterable, body, otherwise, .. } => {
+                iterable.collect_secrets(names);
+                body.iter().chain(otherwise).for_each(|node| node.collect_secrets(names));
+            }
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, TemplateError> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    let mut offset = 0;
+
+    while let Some(start) = rest.find('{').filter(|_| is_template(rest)) {
+        let (open, close) = match rest[start..].get(..2) {
+            Some("{{") => ("{{", "}}"),
+            Some("{%") => ("{%", "%}"),
+            Some("{#") => ("{#", "#}"),
+            _ => {
+                // A lone brace is text
+                let text_end = start + 1;
+                push_text(&mut tokens, &rest[..text_end]);
+                offset += text_end;
+                rest = &rest[text_end..];
+                continue;
+            }
+        };
+        push_text(&mut tokens, &rest[..start]);
+
+        let inner_start = start + open.len();
+        let inner_len = rest[inner_start..]
+            .find(close)
+            .ok_or_else(|| syntax(offset + start, format!("unclosed {}", open)))?;
+        let raw = &rest[inner_start..inner_start + inner_len];
+        let inner = raw.trim().to_string();
+        let position = offset + inner_start + (raw.len() - raw.trim_start().len());
+        match open {
+            "{{" => tokens.push(Token::Output(inner, position)),
+            "{%" => tokens.push(Token::Tag(inner, position)),
+            _ => {}
+        }
+
+        let end = inner_start + inner_len + close.len();
+        offset += end;
+        rest = &rest[end..];
+    }
+    push_text(&mut tokens, rest);
+    Ok(tokens)
+}
+
+fn push_text(tokens: &mut Vec<Token>, text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    match tokens.last_mut() {
+        Some(Token::Text(existing)) => existing.push_str(text),
+        _ => tokens.push(Token::Text(text.to_string())),
+    }
+}
+
+type Tokens = std::iter::Peekable<std::vec::IntoIter<Token>>;
+
+/// A closing tag (with its arguments) and its position
+type EndTag = (String, usize);
+
+/// Parses nodes up to one of the given closing tags, returning the tag that
+/// ended the block (with its arguments) if any
+fn parse_nodes(tokens: &mut Tokens, terminators: &[&str]) -> Result<(Vec<Node>, Option<EndTag>), TemplateError> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        match token {
+            Token::Text(text) => nodes.push(Node::Text(text)),
+            Token::Output(source, position) => {
+                let expr = Expr::parse(&source, position)?;
+                if !matches!(expr, Expr::Secret(_)) {
+                    reject_secrets(&expr, position)?;
+                }
+                nodes.push(Node::Output(expr));
+            }
+            Token::Tag(tag, position) => {
+                let keyword = tag.split_whitespace().next().unwrap_or("");
+                if terminators.contains(&keyword) {
+                    return Ok((nodes, Some((tag, position))));
+                }
+                match keyword {
+                    "if" => nodes.push(parse_if(tokens, &tag, position)?),
+                    "for" => nodes.push(parse_for(tokens, &tag, position)?),
+                    _ => return Err(syntax(position, format!("unexpected {{% {} %}}", tag))),
+                }
+            }
+        }
+    }
+    Ok((nodes, None))
+}
+
+/// Refuses secrets used inside an expression rather than output as is
+fn reject_secrets(expr: &Expr, position: usize) -> Result<(), TemplateError> {
+    let mut names = BTreeSet::new();
+    expr.collect_secrets(&mut names);
+    match names.into_iter().next() {
+        Some(name) => Err(syntax(
+            position,
+            format!("secret:{} can only be output as {{{{ secret:{} }}}}, without filters, comparisons or conditions", name, name),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn tag_argument(tag: &str) -> &str {
+    tag.split_once(char::is_whitespace).map(|(_, rest)| rest.trim()).unwrap_or("")
+}
+
+fn parse_if(tokens: &mut Tokens, tag: &str, position: usize) -> Result<Node, TemplateError> {
+    let mut branches = Vec::new();
+    let mut condition = Expr::parse(tag_argument(tag), position)?;
+    reject_secrets(&condition, position)?;
+    loop {
+        let (body, end) = parse_nodes(tokens, &["elif", "else", "endif"])?;
+        branches.push((condition, body));
+        match end {
+            Some((tag, position)) if tag.starts_with("elif") => {
+                condition = Expr::parse(tag_argument(&tag), position)?;
+                reject_secrets(&condition, position)?;
+            }
+            Some((tag, _)) if tag == "else" => {
+                let (otherwise, end) = parse_nodes(tokens, &["endif"])?;
+                return match end {
+                    Some(_) => Ok(Node::If { branches, otherwise }),
+                    None => Err(syntax(position, "missing {% endif %}")),
+                };
+            }
+            Some((tag, _)) if tag == "endif" => return Ok(Node::If { branches, otherwise: Vec::new() }),
+            _ => return Err(syntax(position, "missing {% endif %}")),
+        }
+    }
+}
+
+fn parse_for(tokens: &mut Tokens, tag: &str, position: usize) -> Result<Node, TemplateError> {
+    let argument = tag_argument(tag);
+    let (variable, iterable) = argument
+        .split_once(" in ")
+        .map(|(variable, iterable)| (variable.trim(), iterable.trim()))
+        .filter(|(variable, _)| is_identifier(variable))
+        .ok_or_else(|| syntax(position, "expected {% for <name> in <expression> %}"))?;
+    let iterable = Expr::parse(iterable, position)?;
+    reject_secrets(&iterable, position)?;
+
+    let (body, end) = parse_nodes(tokens, &["else", "endfor"])?;
+    let otherwise = match end {
+        Some((tag, _)) if tag == "endfor" => Vec::new(),
+        Some(_) => match parse_nodes(tokens, &["endfor"])? {
+            (otherwise, Some(_)) => otherwise,
+            _ => return Err(syntax(position, "missing {% endfor %}")),
+        },
+        None => return Err(syntax(position, "missing {% endfor %}")),
+    };
+
+    Ok(Node::For {
+        variable: variable.to_string(),
+        iterable,
+        body,
+        otherwise,
+    })
+}
+
+/// Characters allowed in secret names
+pub fn is_secret_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+// ---------------------------------------------------------------------------
+// Expressions
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Literal(Value),
+    Path(String, Vec<PathStep>),
+    Secret(String),
+    Filter(Box<Expr>, String, Vec<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Compare(Box<Expr>, CompareOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone)]
+enum PathStep {
+    Key(String),
+    Index(i64),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+const FILTERS: &[&str] = &[
+    "default", "json", "upper", "lower", "trim", "truncate", "length", "join", "first", "last", "replace",
+];
+
+struct ExprParser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    /// Position of the expression within the template
+    offset: usize,
+    source: &'a str,
+}
+
+impl Expr {
+    fn parse(source: &str, offset: usize) -> Result<Expr, TemplateError> {
+        let mut parser = ExprParser { chars: source.chars().collect(), pos: 0, offset, source };
+        if parser.source.trim().is_empty() {
+            return parser.error("expected an expression");
+        }
+        let expr = parser.or()?;
+        parser.skip_whitespace();
+        if parser.pos < parser.chars.len() {
+            return parser.error(format!("unexpected '{}'", parser.chars[parser.pos]));
+        }
+        Ok(expr)
+    }
+
+    /// Readable name of the expression for undefined-variable errors
+    fn describe(&self) -> String {
+        match self {
+            Expr::Path(root, steps) => steps.iter().fold(root.clone(), |mut path, step| {
+                match step {
+                    PathStep::Key(key) => {
+                        path.push('.');
+                        path.push_str(key);
+                    }
+                    PathStep::Index(index) => path.push_str(&format!("[{}]", index)),
+                }
+                path
+            }),
+            Expr::Secret(name) => format!("secret:{}", name),
+            Expr::Filter(inner, ..) => inner.describe(),
+            _ => "expression".to_string(),
+        }
+    }
+
+    fn collect_secrets(&self, names: &mut BTreeSet<String>) {
+        match self {
+            Expr::Secret(name) => {
+                names.insert(name.clone());
+            }
+            Expr::Filter(inner, _, args) => {
+                inner.collect_secrets(names);
+                args.iter().for_each(|arg| arg.collect_secrets(names));
+            }
+            Expr::Not(inner) => inner.collect_secrets(names),
+            Expr::And(a, b) | Expr::Or(a, b) | Expr::Compare(a, _, b) => {
+                a.collect_secrets(names);
+                b.collect_secrets(names);
+            }
+            Expr::Literal(_) | Expr::Path(..) => {}
+        }
+    }
+}
+
+impl ExprParser<'_> {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, TemplateError> {
+        Err(syntax(self.offset + self.pos, message))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, s: &str) -> bool {
+        self.skip_whitespace();
+        let matches = s.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c));
+        if matches {
+            self.pos += s.chars().count();
+        }
+        matches
+    }
+
+    /// Eats a keyword such as `and`, which must not run into an identifier
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.skip_whitespace();
+        let start = self.pos;
+        if self.eat(keyword) && !self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            return true;
+        }
+        self.pos = start;
+        false
+    }
+
+    fn or(&mut self) -> Result<Expr, TemplateError> {
+        let mut expr = self.and()?;
+        while self.eat_keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, TemplateError> {
+        let mut expr = self.not()?;
+        while self.eat_keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not()?));
+        }
+        Ok(expr)
+    }
+
+    fn not(&mut self) -> Result<Expr, TemplateError> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, TemplateError> {
+        let left = self.filtered()?;
+        let op = if self.eat("==") {
+            CompareOp::Eq
+        } else if self.eat("!=") {
+            CompareOp::Ne
+        } else if self.eat(">=") {
+            CompareOp::Ge
+        } else if self.eat("<=") {
+            CompareOp::Le
+        } else if self.eat(">") {
+            CompareOp::Gt
+        } else if self.eat("<") {
+            CompareOp::Lt
+        } else {
+            return Ok(left);
+        };
+        Ok(Expr::Compare(Box::new(left), op, Box::new(self.filtered()?)))
+    }
+
+    fn filtered(&mut self) -> Result<Expr, TemplateError> {
+        let mut expr = self.primary()?;
+        while self.eat("|") {
+            self.skip_whitespace();
+            let position = self.pos;
+            let name = self.identifier()?;
+            if !FILTERS.contains(&name.as_str()) {
+                self.pos = position;
+                return self.error(format!("unknown filter '{}'", name));
+            }
+            let mut args = Vec::new();
+            if self.eat("(") && !self.eat(")") {
+                loop {
+                    args.push(self.or()?);
+                    if self.eat(")") {
+                        break;
+                    }
+                    if !self.eat(",") {
+                        return self.error("expected ',' or ')'");
+                    }
+                }
+            }
+            expr = Expr::Filter(Box::new(expr), name, args);
+        }
+        Ok(expr)
+    }
+
+    fn primary(&mut self) -> Result<Expr, TemplateError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let expr = self.or()?;
+                if !self.eat(")") {
+                    return self.error("expected ')'");
+                }
+                Ok(expr)
+            }
+            Some('"' | '\'') => Ok(Expr::Literal(Value::String(self.string()?))),
+            Some(c) if c.is_ascii_digit() || c == '-' => self.number(),
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.identifier()?;
+                match name.as_str() {
+                    "true" => return Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => return Ok(Expr::Literal(Value::Bool(false))),
+                    "null" | "none" => return Ok(Expr::Literal(Value::Null)),
+                    "secret" if self.peek() == Some(':') => {
+                        self.pos += 1;
+                        return self.secret_name().map(Expr::Secret);
+                    }
+                    _ => {}
+                }
+                let mut steps = Vec::new();
+                loop {
+                    if self.peek() == Some('.') {
+                        self.pos += 1;
+                        steps.push(PathStep::Key(self.identifier()?));
+                    } else if self.peek() == Some('[') {
+                        self.pos += 1;
+                        self.skip_whitespace();
+                        let step = match self.peek() {
+                            Some('"' | '\'') => PathStep::Key(self.string()?),
+                            _ => match self.number()? {
+                                Expr::Literal(Value::Number(n)) if n.is_i64() => PathStep::Index(n.as_i64().unwrap_or_default()),
+                                _ => return self.error("expected an index or a quoted key"),
+                            },
+                        };
+                        if !self.eat("]") {
+                            return self.error("expected ']'");
+                        }
+                        steps.push(step);
+                    } else {
+                        break;
+                    }
+                }
+                Ok(Expr::Path(name, steps))
+            }
+            Some(c) => self.error(format!("unexpected '{}'", c)),
+            None => self.error("unexpected end of expression"),
+        }
+    }
+
+    fn identifier(&mut self) -> Result<String, TemplateError> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return self.error("expected a name");
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn secret_name(&mut self) -> Result<String, TemplateError> {
+        let start = self.pos;
+        while self.peek().is_some_and(is_secret_name_char) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return self.error("expected a secret name after 'secret:'");
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    fn string(&mut self) -> Result<String, TemplateError> {
+        let quote = self.chars[self.pos];
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if c == quote => {
+                    self.pos += 1;
+                    return Ok(s);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('n') => s.push('\n'),
+                        Some('t') => s.push('\t'),
+                        Some(c) => s.push(c),
+                        None => return self.error("unterminated string"),
+                    }
+                    self.pos += 1;
+                }
+                Some(c) => {
+                    s.push(c);
+                    self.pos += 1;
+                }
+                None => return self.error("unterminated string"),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Expr, TemplateError> {
+        let start = self.pos;
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        while self.peek().is_some_and(|c| c.is_ascii_digit() || c == '.') {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        match serde_json::from_str::<Value>(&text) {
+            Ok(value @ Value::Number(_)) => Ok(Expr::Literal(value)),
+            _ => {
+                self.pos = start;
+                self.error(format!("invalid number '{}'", text))
+            }
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Evaluation
+
+struct Scope<'a, 'c> {
+    context: &'a TemplateContext<'c>,
+    variables: Value,
+    /// Loop variables, innermost last
+    locals: Vec<(String, Value)>,
+}
+
+impl<'a, 'c> Scope<'a, 'c> {
+    fn new(context: &'a TemplateContext<'c>) -> Self {
+        Self {
+            context,
+            variables: Value::Object(context.variables.clone()),
+            locals: Vec::new(),
+        }
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Value> {
+        if let Some((_, value)) = self.locals.iter().rev().find(|(local, _)| local == name) {
+            return Some(value);
+        }
+        match name {
+            "payload" => Some(self.context.payload),
+            "vars" => Some(&self.variables),
+            "execution" => Some(&self.context.execution),
+            _ => self.context.payload.get(name),
+        }
+    }
+}
+
+impl Expr {
+    /// Evaluates the expression; `None` means undefined
+    fn eval(&self, scope: &Scope) -> Result<Option<Value>, TemplateError> {
+        match self {
+            Expr::Literal(value) => Ok(Some(value.clone())),
+            Expr::Path(root, steps) => Ok(scope.lookup(root).and_then(|v| walk(v, steps)).cloned()),
+            Expr::Secret(name) => Ok(scope.context.secrets.get(name).map(|value| Value::String(value.clone()))),
+            Expr::Filter(inner, name, args) => {
+                let value = inner.eval(scope)?;
+                let args = args
+                    .iter()
+                    .map(|arg| arg.eval(scope).map(|v| v.unwrap_or(Value::Null)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if name == "default" {
+                    return Ok(Some(match value {
+                        None | Some(Value::Null) => args.into_iter().next().unwrap_or(Value::String(String::new())),
+                        Some(value) => value,
+                    }));
+                }
+                let value = value.ok_or_else(|| TemplateError::Undefined(inner.describe()))?;
+                apply_filter(name, value, &args).map(Some)
+            }
+            Expr::Not(inner) => Ok(Some(Value::Bool(!truthy(inner.eval(scope)?.as_ref())))),
+            Expr::And(a, b) => Ok(Some(Value::Bool(
+                truthy(a.eval(scope)?.as_ref()) && truthy(b.eval(scope)?.as_ref()),
+            ))),
+            Expr::Or(a, b) => Ok(Some(Value::Bool(
+                truthy(a.eval(scope)?.as_ref()) || truthy(b.eval(scope)?.as_ref()),
+            ))),
+            Expr::Compare(a, op, b) => {
+                let (a, b) = (a.eval(scope)?, b.eval(scope)?);
+                Ok(Some(Value::Bool(compare(a.as_ref(), *op, b.as_ref()))))
+            }
+        }
+    }
+}
+
+fn walk<'v>(value: &'v Value, steps: &[PathStep]) -> Option<&'v Value> {
+    steps.iter().try_fold(value, |current, step| match step {
+        PathStep::Key(key) => current.get(key.as_str()),
+        PathStep::Index(index) => {
+            let items = current.as_array()?;
+            let index = if *index < 0 { items.len() as i64 + index } else { *index };
+            usize::try_from(index).ok().and_then(|i| items.get(i))
+        }
+    })
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_f64().is_some_and(|n| n != 0.0),
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(items)) => !items.is_empty(),
+        Some(Value::Object(fields)) => !fields.is_empty(),
+    }
+}
+
+fn compare(a: Option<&Value>, op: CompareOp, b: Option<&Value>) -> bool {
+    let ordering = match (a, b) {
+        (Some(Value::Number(x)), Some(Value::Number(y))) => x.as_f64().zip(y.as_f64()).and_then(|(x, y)| x.partial_cmp(&y)),
+        (Some(Value::String(x)), Some(Value::String(y))) => Some(x.cmp(y)),
+        _ => None,
+    };
+    let equal = ordering.map(|o| o == Ordering::Equal).unwrap_or(a == b);
+    match op {
+        CompareOp::Eq => equal,
+        CompareOp::Ne => !equal,
+        CompareOp::Gt => ordering == Some(Ordering::Greater),
+        CompareOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        CompareOp::Lt => ordering == Some(Ordering::Less),
+        CompareOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+    }
+}
+
+/// Text of a value: strings as-is, null as nothing, anything else as JSON
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    }
+}
+
+fn apply_filter(name: &str, value: Value, args: &[Value]) -> Result<Value, TemplateError> {
+    let error = |message: String| TemplateError::Filter { filter: name.to_string(), message };
+    let string_arg = |index: usize, default: &str| match args.get(index) {
+        Some(Value::String(s)) => Ok(s.clone()),
+        None => Ok(default.to_string()),
+        Some(other) => Err(error(format!("expected a string argument, got {}", other))),
+    };
+
+    Ok(match name {
+        "json" => Value::String(value.to_string()),
+        "upper" => Value::String(text_of(&value).to_uppercase()),
+        "lower" => Value::String(text_of(&value).to_lowercase()),
+        "trim" => Value::String(text_of(&value).trim().to_string()),
+        "truncate" => {
+            let length = args
+                .first()
+                .and_then(Value::as_u64)
+                .ok_or_else(|| error("expected a length, e.g. truncate(100)".to_string()))? as usize;
+            let suffix = string_arg(1, "...")?;
+            let text = text_of(&value);
+            if text.chars().count() <= length {
+                Value::String(text)
+            } else {
+                let kept = length.saturating_sub(suffix.chars().count());
+                Value::String(text.chars().take(kept).collect::<String>() + &suffix)
+            }
+        }
+        "length" => Value::from(match &value {
+            Value::Array(items) => items.len(),
+            Value::Object(fields) => fields.len(),
+            Value::Null => 0,
+            value => text_of(value).chars().count(),
+        }),
+        "join" => {
+            let separator = string_arg(0, ", ")?;
+            match value {
+                Value::Array(items) => Value::String(items.iter().map(text_of).collect::<Vec<_>>().join(&separator)),
+                value => Value::String(text_of(&value)),
+            }
+        }
+        "first" | "last" => match value {
+            Value::Array(items) => {
+                let item = if name == "first" { items.into_iter().next() } else { items.into_iter().next_back() };
+                item.unwrap_or(Value::Null)
+            }
+            other => return Err(error(format!("expected an array, got {}", other))),
+        },
+        "replace" => {
+            let from = string_arg(0, "")?;
+            if from.is_empty() {
+                return Err(error("expected the text to replace, e.g. replace(\"a\", \"b\")".to_string()));
+            }
+            Value::String(text_of(&value).replace(&from, &string_arg(1, "")?))
+        }
+        _ => return Err(error("unknown filter".to_string())),
+    })
+}
+
+fn render_nodes(nodes: &[Node], scope: &mut Scope, out: &mut String) -> Result<(), TemplateError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Output(expr) => {
+                let value = expr.eval(scope)?.ok_or_else(|| TemplateError::Undefined(expr.describe()))?;
+                out.push_str(&text_of(&value));
+            }
+            Node::If { branches, otherwise } => {
+                let mut body = otherwise;
+                for (condition, branch) in branches {
+                    if truthy(condition.eval(scope)?.as_ref()) {
+                        body = branch;
+                        break;
+                    }
+                }
+                render_nodes(body, scope, out)?;
+            }
+            Node::For { variable, iterable, body, otherwise } => {
+                let items: Vec<Value> = match iterable.eval(scope)? {
+                    Some(Value::Array(items)) => items,
+                    Some(Value::Object(fields)) => fields
+                        .into_iter()
+                        .map(|(key, value)| serde_json::json!({ "key": key, "value": value }))
+                        .collect(),
+                    None | Some(Value::Null) => Vec::new(),
+                    Some(other) => {
+                        return Err(TemplateError::Filter {
+                            filter: "for".to_string(),
+                            message: format!("cannot loop over {}", other),
+                        })
+                    }
+                };
+                if items.is_empty() {
+                    render_nodes(otherwise, scope, out)?;
+                    continue;
+                }
+
+                let length = items.len();
+                for (index, item) in items.into_iter().enumerate() {
+                    let loop_info = serde_json::json!({
+                        "index": index + 1,
+                        "index0": index,
+                        "first": index == 0,
+                        "last": index + 1 == length,
+                        "length": length,
+                    });
+                    scope.locals.push(("loop".to_string(), loop_info));
+                    scope.locals.push((variable.clone(), item));
+                    let result = render_nodes(body, scope, out);
+                    scope.locals.truncate(scope.locals.len() - 2);
+                    result?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(template: &str, payload: Value) -> Result<String, TemplateError> {
+        CompiledTemplate::compile(template)?.render(&TemplateContext::from_payload(&payload))
+    }
+
+    #[test]
+    fn test_paths_defaults_and_filters() {
+        let payload = json!({"customer": {"name": "Ada", "tags": ["vip", "eu"]}, "notes": "abcdefghij"});
+        assert_eq!(render("Hi {{ customer.name }} ({{ customer.tags[-1] }})", payload.clone()).unwrap(), "Hi Ada (eu)");
+        assert_eq!(render("{{ customer.title | default(\"n/a\") | upper }}", payload.clone()).unwrap(), "N/A");
+        assert_eq!(render("{{ notes | truncate(6) }}|{{ customer.tags | join(\"+\") }}", payload.clone()).unwrap(), "abc...|vip+eu");
+        assert_eq!(render("{{ payload.customer | json }}", payload.clone()).unwrap(), r#"{"name":"Ada","tags":["vip","eu"]}"#);
+        assert_eq!(
+            render("{{ customer.email }}", payload),
+            Err(TemplateError::Undefined("customer.email".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_conditionals_and_loops() {
+        let template = "{% if amount > 1000 and not draft %}big{% elif amount %}small{% else %}none{% endif %}";
+        assert_eq!(render(template, json!({"amount": 5000, "draft": false})).unwrap(), "big");
+        assert_eq!(render(template, json!({"amount": 5})).unwrap(), "small");
+        assert_eq!(render(template, json!({})).unwrap(), "none");
+
+        let template = "{% for item in items %}{{ loop.index }}. {{ item.name }}{% if not loop.last %}, {% endif %}{% else %}empty{% endfor %}";
+        assert_eq!(render(template, json!({"items": [{"name": "a"}, {"name": "b"}]})).unwrap(), "1. a, 2. b");
+        assert_eq!(render(template, json!({"items": []})).unwrap(), "empty");
+    }
+
+    #[test]
+    fn test_syntax_errors_report_position() {
+        assert_eq!(
+            render("Hello {{ name", json!({})),
+            Err(TemplateError::Syntax { position: 6, message: "unclosed {{".to_string() })
+        );
+        assert!(matches!(render("{{ name | shout }}", json!({})), Err(TemplateError::Syntax { position: 10, .. })));
+        assert!(matches!(render("{% if a %}x", json!({})), Err(TemplateError::Syntax { .. })));
+        assert!(matches!(render("{% endfor %}", json!({})), Err(TemplateError::Syntax { .. })));
+    }
+
+    #[test]
+    fn test_render_config_uses_all_namespaces() {
+        let payload = json!({"invoice": {"lines": [1, 2]}, "vars": "payload field"});
+        let mut variables = Map::new();
+        variables.insert("region".to_string(), json!("eu"));
+        let context = TemplateContext {
+            payload: &payload,
+            variables: &variables,
+            execution: json!({"id": "exec-1"}),
+            secrets: &HashMap::new(),
+        };
+        let config = json!({
+            "url": "https://{{ vars.region }}.example.com/{{ execution.id }}",
+            "lines": "{{ invoice.lines }}",
+            "literal": "no template",
+            "nested": [{"note": "{{ payload.vars }}"}]
+        });
+
+        assert_eq!(render_config(&config, &context).unwrap(), json!({
+            "url": "https://eu.example.com/exec-1",
+            "lines": [1, 2],
+            "literal": "no template",
+            "nested": [{"note": "payload field"}]
+        }));
+    }
+
+    #[test]
+    fn test_secret_references() {
+        let config = json!({
+            "api_key": "{{ secret:openai-key }}",
+            "headers": [{"Authorization": "Bearer {{ secret:crm.token }}"}],
+            "plain": "secret:not-a-reference"
+        });
+        let names = secret_references(&config).unwrap();
+        assert_eq!(names.into_iter().collect::<Vec<_>>(), vec!["crm.token", "openai-key"]);
+
+        let payload = json!({});
+        let secrets = HashMap::from([("openai-key".to_string(), "sk-123".to_string())]);
+        let context = TemplateContext { secrets: &secrets, ..TemplateContext::from_payload(&payload) };
+        assert_eq!(render_config(&json!("{{ secret:openai-key }}"), &context).unwrap(), json!("sk-123"));
+        assert_eq!(
+            render_config(&json!("{{ secret:missing }}"), &context),
+            Err(TemplateError::Undefined("secret:missing".to_string()))
+        );
+        assert!(matches!(render("{{ secret: }}", json!({})), Err(TemplateError::Syntax { .. })));
+    }
+
+    #[test]
+    fn test_secrets_are_only_output_as_is() {
+        for template in [
+            "{{ secret:openai-key | upper }}",
+            "{{ secret:openai-key | truncate(3) }}",
+            "{{ name | default(secret:openai-key) }}",
+            "{{ secret:openai-key == 'sk-123' }}",
+            "{% if secret:openai-key %}set{% endif %}",
+            "{% if name %}{% elif secret:openai-key > 'a' %}{% endif %}",
+            "{% for c in secret:openai-key %}{{ c }}{% endfor %}",
+        ] {
+            assert!(matches!(CompiledTemplate::compile(template), Err(TemplateError::Syntax { .. })), "{}", template);
+            assert!(secret_references(&json!(template)).is_err(), "{}", template);
+        }
+        assert!(CompiledTemplate::compile("Bearer {{ secret:openai-key }} for {{ name | upper }}").is_ok());
+    }
+}
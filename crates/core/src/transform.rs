@@ -0,0 +1,713 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chrono::{format::{Item, StrftimeItems}, DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha512};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use thiserror::Error;
+
+use crate::path::JsonPath;
+use crate::types::{HashAlgorithm, PadSide, RoundingMode, TransformType};
+
+/// Error of a single transform step
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TransformError {
+    #[error("{transform} expects {expected}, got {found}")]
+    InvalidInput {
+        transform: &'static str,
+        expected: &'static str,
+        found: &'static str,
+    },
+
+    #[error("{transform}: invalid argument: {message}")]
+    InvalidArgument {
+        transform: &'static str,
+        message: String,
+    },
+
+    #[error("{transform}: cannot parse {input:?}: {message}")]
+    Parse {
+        transform: &'static str,
+        input: String,
+        message: String,
+    },
+
+    #[error("{transform}: division by zero")]
+    DivisionByZero { transform: &'static str },
+
+    #[error("{transform}: result is not a finite number")]
+    NotFinite { transform: &'static str },
+
+    #[error("lookup: no entry for {key:?}")]
+    LookupMiss { key: String },
+
+    #[error("{transform} is lossy and cannot be reversed")]
+    NotInvertible { transform: &'static str },
+}
+
+impl TransformType {
+    /// Name of the transform, as used in configs
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransformType::StringConcat { .. } => "string_concat",
+            TransformType::StringToUpper => "string_to_upper",
+            TransformType::StringToLower => "string_to_lower",
+            TransformType::Trim => "trim",
+            TransformType::Replace { .. } => "replace",
+            TransformType::RegexExtract { .. } => "regex_extract",
+            TransformType::Split { .. } => "split",
+            TransformType::Pad { .. } => "pad",
+            TransformType::NumberAdd { .. } => "number_add",
+            TransformType::NumberSubtract { .. } => "number_subtract",
+            TransformType::NumberMultiply { .. } => "number_multiply",
+            TransformType::NumberDivide { .. } => "number_divide",
+            TransformType::Round { .. } => "round",
+            TransformType::DateParse { .. } => "date_parse",
+            TransformType::DateFormat { .. } => "date_format",
+            TransformType::DateTimezone { .. } => "date_timezone",
+            TransformType::ToString => "to_string",
+            TransformType::ToNumber => "to_number",
+            TransformType::ToBoolean => "to_boolean",
+            TransformType::DefaultIfMissing { .. } => "default_if_missing",
+            TransformType::Lookup { .. } => "lookup",
+            TransformType::Base64Encode => "base64_encode",
+            TransformType::Base64Decode => "base64_decode",
+            TransformType::Hash { .. } => "hash",
+            TransformType::ArrayMap { .. } => "array_map",
+            TransformType::ArrayFilter { .. } => "array_filter",
+            TransformType::ArraySort { .. } => "array_sort",
+            TransformType::ArrayUnique => "array_unique",
+            TransformType::Conditional { .. } => "conditional",
+        }
+    }
+
+    /// Returns true if the transform works on a whole array rather than on
+    /// single values
+    pub fn operates_on_arrays(&self) -> bool {
+        matches!(
+            self,
+            TransformType::StringConcat { .. }
+                | TransformType::ArrayMap { .. }
+                | TransformType::ArrayFilter { .. }
+                | TransformType::ArraySort { .. }
+                | TransformType::ArrayUnique
+        )
+    }
+
+    /// Returns the transform that undoes this one
+    ///
+    /// Lossy transforms, such as case changes, rounding or hashing, have no
+    /// inverse.
+    pub fn inverse(&self) -> Result<TransformType, TransformError> {
+        let not_invertible = || TransformError::NotInvertible { transform: self.name() };
+        Ok(match self {
+            TransformType::StringConcat { separator } if !separator.is_empty() => {
+                TransformType::Split { separator: separator.clone() }
+            }
+            TransformType::Split { separator } => TransformType::StringConcat { separator: separator.clone() },
+            TransformType::NumberAdd { operand } => TransformType::NumberSubtract { operand: *operand },
+            TransformType::NumberSubtract { operand } => TransformType::NumberAdd { operand: *operand },
+            TransformType::NumberMultiply { operand } if *operand != 0.0 => {
+                TransformType::NumberDivide { operand: *operand }
+            }
+            TransformType::NumberDivide { operand } => TransformType::NumberMultiply { operand: *operand },
+            TransformType::DateParse { format } => TransformType::DateFormat { format: format.clone() },
+            TransformType::DateFormat { format } => TransformType::DateParse { format: format.clone() },
+            TransformType::ToNumber => TransformType::ToString,
+            TransformType::Base64Encode => TransformType::Base64Decode,
+            TransformType::Base64Decode => TransformType::Base64Encode,
+            TransformType::Lookup { table, default: None } => {
+                // Only a one-to-one table can be read backwards
+                let mut inverse = serde_json::Map::new();
+                for (key, value) in table {
+                    if inverse.insert(text_of(value), Value::String(key.clone())).is_some() {
+                        return Err(not_invertible());
+                    }
+                }
+                TransformType::Lookup { table: inverse, default: None }
+            }
+            TransformType::ArrayMap { transforms } => TransformType::ArrayMap {
+                transforms: invert_pipeline(transforms)?,
+            },
+            _ => return Err(not_invertible()),
+        })
+    }
+}
+
+/// Returns the pipeline that undoes a pipeline: the inverses of its
+/// transforms, in reverse order
+pub fn invert_pipeline(pipeline: &[TransformType]) -> Result<Vec<TransformType>, TransformError> {
+    pipeline.iter().rev().map(TransformType::inverse).collect()
+}
+
+/// Applies a pipeline of transforms in order
+pub fn apply_pipeline(value: Value, pipeline: &[TransformType]) -> Result<Value, TransformError> {
+    pipeline.iter().try_fold(value, apply)
+}
+
+/// Applies a single transform to a value
+pub fn apply(value: Value, transform: &TransformType) -> Result<Value, TransformError> {
+    let name = transform.name();
+    match transform {
+        TransformType::StringConcat { separator } => {
+            let items = expect_array(name, value)?;
+            let strings: Vec<String> = items.iter().map(text_of).collect();
+            Ok(Value::String(strings.join(separator)))
+        }
+        TransformType::StringToUpper => Ok(Value::String(expect_string(name, value)?.to_uppercase())),
+        TransformType::StringToLower => Ok(Value::String(expect_string(name, value)?.to_lowercase())),
+        TransformType::Trim => Ok(Value::String(expect_string(name, value)?.trim().to_string())),
+        TransformType::Replace { from, to, regex } => {
+            let s = expect_string(name, value)?;
+            if *regex {
+                Ok(Value::String(compile_regex(name, from)?.replace_all(&s, to.as_str()).into_owned()))
+            } else {
+                Ok(Value::String(s.replace(from.as_str(), to)))
+            }
+        }
+        TransformType::RegexExtract { pattern, group } => {
+            let s = expect_string(name, value)?;
+            // No match yields null, so a following default_if_missing can fill it in
+            Ok(compile_regex(name, pattern)?
+                .captures(&s)
+                .and_then(|captures| captures.get(*group))
+                .map(|m| Value::String(m.as_str().to_string()))
+                .unwrap_or(Value::Null))
+        }
+        TransformType::Split { separator } => {
+            let s = expect_string(name, value)?;
+            if separator.is_empty() {
+                return Err(TransformError::InvalidArgument {
+                    transform: name,
+                    message: "separator must not be empty".to_string(),
+                });
+            }
+            Ok(Value::Array(s.split(separator.as_str()).map(|part| Value::String(part.to_string())).collect()))
+        }
+        TransformType::Pad { width, fill, side } => {
+            let s = text_of(&value);
+            let padding: String = std::iter::repeat_n(*fill, width.saturating_sub(s.chars().count())).collect();
+            Ok(Value::String(match side {
+                PadSide::Left => padding + &s,
+                PadSide::Right => s + &padding,
+            }))
+        }
+        TransformType::NumberAdd { operand } => number(name, expect_number(name, &value)? + operand),
+        TransformType::NumberSubtract { operand } => number(name, expect_number(name, &value)? - operand),
+        TransformType::NumberMultiply { operand } => number(name, expect_number(name, &value)? * operand),
+        TransformType::NumberDivide { operand } => {
+            let n = expect_number(name, &value)?;
+            if *operand == 0.0 {
+                return Err(TransformError::DivisionByZero { transform: name });
+            }
+            number(name, n / operand)
+        }
+        TransformType::Round { decimals, mode } => {
+            let n = expect_number(name, &value)?;
+            let factor = 10f64.powi(*decimals as i32);
+            let scaled = n * factor;
+            let rounded = match mode {
+                RoundingMode::Nearest => scaled.round(),
+                RoundingMode::Floor => scaled.floor(),
+                RoundingMode::Ceil => scaled.ceil(),
+                RoundingMode::Truncate => scaled.trunc(),
+            };
+            number(name, rounded / factor)
+        }
+        TransformType::DateParse { format } => parse_date(name, &value, format).map(|dt| Value::String(dt.to_rfc3339())),
+        TransformType::DateFormat { format } => {
+            let dt = parse_date(name, &value, "rfc3339")?;
+            format_date(name, &dt, format)
+        }
+        TransformType::DateTimezone { timezone } => {
+            let dt = parse_date(name, &value, "rfc3339")?;
+            let offset = parse_offset(timezone).ok_or_else(|| TransformError::InvalidArgument {
+                transform: name,
+                message: format!("unsupported timezone {:?}; use UTC or an offset such as +02:00", timezone),
+            })?;
+            Ok(Value::String(dt.with_timezone(&offset).to_rfc3339()))
+        }
+        TransformType::ToString => match value {
+            Value::Null => Err(invalid_input(name, "a value", &value)),
+            value => Ok(Value::String(text_of(&value))),
+        },
+        TransformType::ToNumber => match value {
+            Value::Number(_) => Ok(value),
+            Value::Bool(b) => Ok(Value::from(b as i64)),
+            Value::String(ref s) => {
+                let n = s.trim().parse::<f64>().map_err(|e| TransformError::Parse {
+                    transform: name,
+                    input: s.clone(),
+                    message: e.to_string(),
+                })?;
+                number(name, n)
+            }
+            _ => Err(invalid_input(name, "a string, number or boolean", &value)),
+        },
+        TransformType::ToBoolean => match value {
+            Value::Bool(_) => Ok(value),
+            Value::Number(ref n) => Ok(Value::Bool(n.as_f64().map(|n| n != 0.0).unwrap_or(false))),
+            Value::String(ref s) => match s.trim().to_ascii_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Ok(Value::Bool(true)),
+                "false" | "no" | "off" | "0" | "" => Ok(Value::Bool(false)),
+                _ => Err(TransformError::Parse {
+                    transform: name,
+                    input: s.clone(),
+                    message: "not a boolean".to_string(),
+                }),
+            },
+            _ => Err(invalid_input(name, "a string, number or boolean", &value)),
+        },
+        TransformType::DefaultIfMissing { value: default } => Ok(match value {
+            Value::Null => default.clone(),
+            value => value,
+        }),
+        TransformType::Lookup { table, default } => {
+            let key = text_of(&value);
+            match table.get(&key).or(default.as_ref()) {
+                Some(found) => Ok(found.clone()),
+                None => Err(TransformError::LookupMiss { key }),
+            }
+        }
+        TransformType::Base64Encode => Ok(Value::String(BASE64.encode(text_of(&value)))),
+        TransformType::Base64Decode => {
+            let s = expect_string(name, value)?;
+            let bytes = BASE64.decode(s.trim()).map_err(|e| TransformError::Parse {
+                transform: name,
+                input: s.clone(),
+                message: e.to_string(),
+            })?;
+            String::from_utf8(bytes).map(Value::String).map_err(|_| TransformError::Parse {
+                transform: name,
+                input: s,
+                message: "decoded bytes are not UTF-8".to_string(),
+            })
+        }
+        TransformType::Hash { algorithm } => {
+            let s = text_of(&value);
+            let digest = match algorithm {
+                HashAlgorithm::Sha256 => hex::encode(Sha256::digest(s.as_bytes())),
+                HashAlgorithm::Sha512 => hex::encode(Sha512::digest(s.as_bytes())),
+            };
+            Ok(Value::String(digest))
+        }
+        TransformType::ArrayMap { transforms } => expect_array(name, value)?
+            .into_iter()
+            .map(|item| apply_pipeline(item, transforms))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        TransformType::ArrayFilter { condition } => {
+            let items = expect_array(name, value)?;
+            let filter = JsonPath::cached(&format!("$[?({})]", condition)).map_err(|e| TransformError::InvalidArgument {
+                transform: name,
+                message: e.to_string(),
+            })?;
+            Ok(filter.get(&Value::Array(items)).unwrap_or_else(|| Value::Array(Vec::new())))
+        }
+        TransformType::ArraySort { by, descending } => {
+            let mut items = expect_array(name, value)?;
+            let by = by
+                .as_deref()
+                .map(JsonPath::cached)
+                .transpose()
+                .map_err(|e| TransformError::InvalidArgument {
+                    transform: name,
+                    message: e.to_string(),
+                })?;
+            let key = |item: &Value| match &by {
+                Some(path) => path.get(item).unwrap_or(Value::Null),
+                None => item.clone(),
+            };
+            items.sort_by(|a, b| {
+                let ordering = compare_values(&key(a), &key(b));
+                if *descending { ordering.reverse() } else { ordering }
+            });
+            Ok(Value::Array(items))
+        }
+        TransformType::ArrayUnique => {
+            let mut seen = HashSet::new();
+            let items = expect_array(name, value)?
+                .into_iter()
+                .filter(|item| seen.insert(item.to_string()))
+                .collect();
+            Ok(Value::Array(items))
+        }
+        TransformType::Conditional {
+            condition,
+            true_value,
+            false_value,
+        } => {
+            // Simple condition evaluation - can be enhanced
+            let condition_met = evaluate_condition(&value, condition)?;
+            Ok(if condition_met {
+                true_value.clone()
+            } else {
+                false_value.clone()
+            })
+        }
+    }
+}
+
+/// Evaluates a simple condition (e.g., "> 1000", "== 'VIP'")
+pub fn evaluate_condition(value: &Value, condition: &str) -> Result<bool, TransformError> {
+    let invalid = |message: &str| TransformError::InvalidArgument {
+        transform: "conditional",
+        message: format!("{}: {}", message, condition),
+    };
+
+    if let Some(threshold) = condition.strip_prefix("> ") {
+        match (value.as_f64(), threshold.trim().parse::<f64>().ok()) {
+            (Some(num), Some(threshold)) => Ok(num > threshold),
+            _ => Err(invalid("Invalid numeric condition")),
+        }
+    } else if let Some(threshold) = condition.strip_prefix("< ") {
+        match (value.as_f64(), threshold.trim().parse::<f64>().ok()) {
+            (Some(num), Some(threshold)) => Ok(num < threshold),
+            _ => Err(invalid("Invalid numeric condition")),
+        }
+    } else if let Some(expected) = condition.strip_prefix("== ") {
+        let expected = expected.trim().trim_matches('"').trim_matches('\'');
+        Ok(value.as_str().map(|s| s == expected).unwrap_or(false))
+    } else {
+        Err(invalid("Unsupported condition"))
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn invalid_input(transform: &'static str, expected: &'static str, value: &Value) -> TransformError {
+    TransformError::InvalidInput {
+        transform,
+        expected,
+        found: type_name(value),
+    }
+}
+
+fn expect_string(transform: &'static str, value: Value) -> Result<String, TransformError> {
+    match value {
+        Value::String(s) => Ok(s),
+        value => Err(invalid_input(transform, "a string", &value)),
+    }
+}
+
+fn expect_number(transform: &'static str, value: &Value) -> Result<f64, TransformError> {
+    value.as_f64().ok_or_else(|| invalid_input(transform, "a number", value))
+}
+
+fn expect_array(transform: &'static str, value: Value) -> Result<Vec<Value>, TransformError> {
+    match value {
+        Value::Array(items) => Ok(items),
+        value => Err(invalid_input(transform, "an array", &value)),
+    }
+}
+
+/// Text of a value: strings as-is, anything else as JSON
+fn text_of(value: &Value) -> String {
+    value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string())
+}
+
+/// Converts an arithmetic result to JSON, keeping whole numbers integral
+fn number(transform: &'static str, n: f64) -> Result<Value, TransformError> {
+    if !n.is_finite() {
+        return Err(TransformError::NotFinite { transform });
+    }
+    if n.fract() == 0.0 && n.abs() < i64::MAX as f64 {
+        return Ok(Value::from(n as i64));
+    }
+    serde_json::Number::from_f64(n)
+        .map(Value::Number)
+        .ok_or(TransformError::NotFinite { transform })
+}
+
+fn compile_regex(transform: &'static str, pattern: &str) -> Result<regex::Regex, TransformError> {
+    regex::Regex::new(pattern).map_err(|e| TransformError::InvalidArgument {
+        transform,
+        message: e.to_string(),
+    })
+}
+
+fn strftime_items<'a>(transform: &'static str, format: &'a str) -> Result<Vec<Item<'a>>, TransformError> {
+    let items: Vec<Item> = StrftimeItems::new(format).collect();
+    if items.iter().any(|item| matches!(item, Item::Error)) {
+        return Err(TransformError::InvalidArgument {
+            transform,
+            message: format!("invalid date format {:?}", format),
+        });
+    }
+    Ok(items)
+}
+
+/// Parses a date; dates and times without an offset are taken as UTC
+fn parse_date(transform: &'static str, value: &Value, format: &str) -> Result<DateTime<FixedOffset>, TransformError> {
+    let parse_error = |message: String| TransformError::Parse {
+        transform,
+        input: text_of(value),
+        message,
+    };
+    let utc = |dt: DateTime<Utc>| dt.fixed_offset();
+
+    match format {
+        "unix" | "unix_ms" => {
+            let n = match value {
+                Value::String(s) => s.trim().parse::<f64>().map_err(|e| parse_error(e.to_string()))?,
+                value => expect_number(transform, value)?,
+            };
+            let millis = if format == "unix" { n * 1000.0 } else { n };
+            Utc.timestamp_millis_opt(millis as i64)
+                .single()
+                .map(utc)
+                .ok_or_else(|| parse_error("timestamp out of range".to_string()))
+        }
+        "rfc3339" => {
+            let s = value.as_str().ok_or_else(|| invalid_input(transform, "a string", value))?;
+            DateTime::parse_from_rfc3339(s).map_err(|e| parse_error(e.to_string()))
+        }
+        "rfc2822" => {
+            let s = value.as_str().ok_or_else(|| invalid_input(transform, "a string", value))?;
+            DateTime::parse_from_rfc2822(s).map_err(|e| parse_error(e.to_string()))
+        }
+        format => {
+            let s = value.as_str().ok_or_else(|| invalid_input(transform, "a string", value))?;
+            strftime_items(transform, format)?;
+            DateTime::parse_from_str(s, format)
+                .or_else(|_| NaiveDateTime::parse_from_str(s, format).map(|dt| utc(dt.and_utc())))
+                .or_else(|_| {
+                    NaiveDate::parse_from_str(s, format)
+                        .map(|d| utc(d.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc()))
+                })
+                .map_err(|e| parse_error(e.to_string()))
+        }
+    }
+}
+
+fn format_date(transform: &'static str, dt: &DateTime<FixedOffset>, format: &str) -> Result<Value, TransformError> {
+    Ok(match format {
+        "unix" => Value::from(dt.timestamp()),
+        "unix_ms" => Value::from(dt.timestamp_millis()),
+        "rfc3339" => Value::String(dt.to_rfc3339()),
+        "rfc2822" => Value::String(dt.to_rfc2822()),
+        format => {
+            let items = strftime_items(transform, format)?;
+            Value::String(dt.format_with_items(items.into_iter()).to_string())
+        }
+    })
+}
+
+/// Parses `UTC`, `Z` or an offset such as `+02:00`, `-0530` or `+09`
+fn parse_offset(timezone: &str) -> Option<FixedOffset> {
+    let timezone = timezone.trim();
+    if timezone.eq_ignore_ascii_case("utc") || timezone.eq_ignore_ascii_case("z") {
+        return FixedOffset::east_opt(0);
+    }
+
+    let sign = match timezone.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let digits: String = timezone[1..].chars().filter(|c| *c != ':').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (digits[..2].parse::<i32>().ok()?, digits[2..].parse::<i32>().ok()?),
+        _ => return None,
+    };
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Orders values for sorting: numbers numerically, strings lexically, and
+/// values of different types by type (null first)
+fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(value: &Value) -> u8 {
+        match value {
+            Value::Null => 0,
+            Value::Bool(_) => 1,
+            Value::Number(_) => 2,
+            Value::String(_) => 3,
+            Value::Array(_) => 4,
+            Value::Object(_) => 5,
+        }
+    }
+
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => x
+            .as_f64()
+            .zip(y.as_f64())
+            .and_then(|(x, y)| x.partial_cmp(&y))
+            .unwrap_or(Ordering::Equal),
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn pipeline(config: Value) -> Vec<TransformType> {
+        TransformType::parse_pipeline(config).unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic_and_rounding() {
+        let transforms = pipeline(json!([
+            {"number_multiply": {"operand": 1.19}},
+            {"number_add": {"operand": 0.5}},
+            {"round": {"decimals": 2}}
+        ]));
+        assert_eq!(apply_pipeline(json!(10), &transforms).unwrap(), json!(12.4));
+        assert_eq!(
+            apply(json!(10), &TransformType::NumberDivide { operand: 0.0 }),
+            Err(TransformError::DivisionByZero { transform: "number_divide" })
+        );
+        assert_eq!(
+            apply(json!(2.5), &TransformType::Round { decimals: 0, mode: RoundingMode::Floor }).unwrap(),
+            json!(2)
+        );
+    }
+
+    #[test]
+    fn test_string_transforms() {
+        let transforms = pipeline(json!([
+            "trim",
+            {"replace": {"from": "\\s+", "to": "-", "regex": true}},
+            {"pad": {"width": 12, "fill": "*"}}
+        ]));
+        assert_eq!(apply_pipeline(json!("  a b   c "), &transforms).unwrap(), json!("*******a-b-c"));
+
+        let extract = TransformType::RegexExtract { pattern: r"#(\d+)".to_string(), group: 1 };
+        assert_eq!(apply(json!("Order #1234"), &extract).unwrap(), json!("1234"));
+        assert_eq!(apply(json!("no order"), &extract).unwrap(), Value::Null);
+
+        let split = TransformType::Split { separator: ",".to_string() };
+        assert_eq!(apply(json!("a,b"), &split).unwrap(), json!(["a", "b"]));
+        assert_eq!(
+            apply(json!(5), &TransformType::StringToUpper),
+            Err(TransformError::InvalidInput { transform: "string_to_upper", expected: "a string", found: "a number" })
+        );
+    }
+
+    #[test]
+    fn test_date_transforms() {
+        let transforms = pipeline(json!([
+            {"date_parse": {"format": "%d/%m/%Y %H:%M"}},
+            {"date_timezone": {"timezone": "+02:00"}},
+            {"date_format": {"format": "%Y-%m-%d %H:%M %z"}}
+        ]));
+        assert_eq!(apply_pipeline(json!("31/12/2024 23:30"), &transforms).unwrap(), json!("2025-01-01 01:30 +0200"));
+
+        let unix = pipeline(json!([{"date_parse": {"format": "unix"}}, {"date_format": {"format": "unix_ms"}}]));
+        assert_eq!(apply_pipeline(json!(1700000000), &unix).unwrap(), json!(1700000000000i64));
+
+        let invalid = TransformType::DateTimezone { timezone: "Europe/Paris".to_string() };
+        assert!(matches!(
+            apply(json!("2024-01-01T00:00:00Z"), &invalid),
+            Err(TransformError::InvalidArgument { transform: "date_timezone", .. })
+        ));
+        assert!(matches!(
+            apply(json!("yesterday"), &TransformType::DateParse { format: "%Y-%m-%d".to_string() }),
+            Err(TransformError::Parse { .. })
+        ));
+    }
+
+    #[test]
+    fn test_coercion_defaults_and_lookups() {
+        assert_eq!(apply(json!(" 42 "), &TransformType::ToNumber).unwrap(), json!(42));
+        assert_eq!(apply(json!("1.5"), &TransformType::ToNumber).unwrap(), json!(1.5));
+        assert_eq!(apply(json!(42), &TransformType::ToString).unwrap(), json!("42"));
+        assert_eq!(apply(json!("Yes"), &TransformType::ToBoolean).unwrap(), json!(true));
+        assert!(matches!(apply(json!("abc"), &TransformType::ToNumber), Err(TransformError::Parse { .. })));
+
+        let default = TransformType::DefaultIfMissing { value: json!("n/a") };
+        assert_eq!(apply(Value::Null, &default).unwrap(), json!("n/a"));
+        assert_eq!(apply(json!(""), &default).unwrap(), json!(""));
+
+        let lookup = pipeline(json!({"lookup": {"table": {"1": "gold", "2": "silver"}}}));
+        assert_eq!(apply_pipeline(json!(1), &lookup).unwrap(), json!("gold"));
+        assert_eq!(
+            apply_pipeline(json!(3), &lookup),
+            Err(TransformError::LookupMiss { key: "3".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_encoding_transforms() {
+        let round_trip = pipeline(json!(["base64_encode", "base64_decode"]));
+        assert_eq!(apply(json!("hello"), &TransformType::Base64Encode).unwrap(), json!("aGVsbG8="));
+        assert_eq!(apply_pipeline(json!("hello"), &round_trip).unwrap(), json!("hello"));
+        assert_eq!(
+            apply(json!("hello"), &TransformType::Hash { algorithm: HashAlgorithm::Sha256 }).unwrap(),
+            json!("2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824")
+        );
+        assert!(matches!(apply(json!("!!"), &TransformType::Base64Decode), Err(TransformError::Parse { .. })));
+    }
+
+    #[test]
+    fn test_array_transforms() {
+        let transforms = pipeline(json!([
+            {"array_filter": {"condition": "@ > 5"}},
+            {"array_sort": {"descending": true}},
+            {"array_map": {"transforms": [{"number_multiply": {"operand": 2}}]}}
+        ]));
+        assert_eq!(apply_pipeline(json!([10, 3, 20, 7]), &transforms).unwrap(), json!([40, 20, 14]));
+
+        let by_price = pipeline(json!({"array_sort": {"by": "price"}}));
+        let items = json!([{"price": 10}, {"price": 3}]);
+        assert_eq!(apply_pipeline(items, &by_price).unwrap(), json!([{"price": 3}, {"price": 10}]));
+
+        let names = pipeline(json!([{"array_map": {"transforms": ["string_to_lower"]}}, "array_unique", {"array_sort": {}}]));
+        assert_eq!(apply_pipeline(json!(["b", "A", "a"]), &names).unwrap(), json!(["a", "b"]));
+        assert!(matches!(
+            apply(json!([1]), &TransformType::ArrayFilter { condition: "@ >".to_string() }),
+            Err(TransformError::InvalidArgument { transform: "array_filter", .. })
+        ));
+    }
+
+    #[test]
+    fn test_inverted_pipeline_round_trips() {
+        let transforms = pipeline(json!([
+            {"number_multiply": {"operand": 100}},
+            {"number_add": {"operand": 1}},
+            "to_string",
+        ]));
+        // to_string is not invertible, but to_number is
+        assert_eq!(invert_pipeline(&transforms), Err(TransformError::NotInvertible { transform: "to_string" }));
+
+        let transforms = pipeline(json!([
+            {"split": {"separator": ";"}},
+            {"array_map": {"transforms": ["base64_encode"]}},
+            {"string_concat": {"separator": ","}},
+            {"lookup": {"table": {"YQ==,Yg==": "ab"}}}
+        ]));
+        let forward = apply_pipeline(json!("a;b"), &transforms).unwrap();
+        assert_eq!(forward, json!("ab"));
+        assert_eq!(apply_pipeline(forward, &invert_pipeline(&transforms).unwrap()).unwrap(), json!("a;b"));
+
+        let prices = pipeline(json!([{"number_multiply": {"operand": 100}}, {"number_add": {"operand": 1}}]));
+        assert_eq!(apply_pipeline(json!(1001), &invert_pipeline(&prices).unwrap()).unwrap(), json!(10));
+
+        for lossy in [json!("string_to_upper"), json!({"number_multiply": {"operand": 0}}),
+                      json!({"lookup": {"table": {"a": 1, "b": 1}}})] {
+            assert!(matches!(
+                invert_pipeline(&pipeline(lossy)),
+                Err(TransformError::NotInvertible { .. })
+            ));
+        }
+    }
+}
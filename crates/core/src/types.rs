@@ -1,8 +1,11 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
-#[serde(rename_all = "snake_case")]
+/// Identifies a brick implementation.
+///
+/// Built-in bricks have dedicated variants; any other id (for example a brick
+/// registered by a plugin) is kept as `Custom`. Serialized as its string id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum BrickType {
     OpenAi,
     Nvidia,
@@ -14,12 +17,14 @@ pub enum BrickType {
     CombineText,
     Conditional,
     RulesEngine,
+    Respond,
+    Custom(String),
 }
 
 impl BrickType {
     /// Returns the string representation of the brick type
     /// This avoids repeated format! allocations
-    pub fn as_str(&self) -> &'static str {
+    pub fn as_str(&self) -> &str {
         match self {
             BrickType::OpenAi => "OpenAi",
             BrickType::Nvidia => "Nvidia",
@@ -31,7 +36,72 @@ impl BrickType {
             BrickType::CombineText => "CombineText",
             BrickType::Conditional => "Conditional",
             BrickType::RulesEngine => "RulesEngine",
+            BrickType::Respond => "Respond",
+            BrickType::Custom(id) => id,
+        }
+    }
+
+    /// Returns the id used in flow definitions and the brick registry
+    pub fn id(&self) -> &str {
+        match self {
+            BrickType::OpenAi => "openai",
+            BrickType::Nvidia => "nvidia",
+            BrickType::HubSpot => "hubspot",
+            BrickType::Notion => "notion",
+            BrickType::Odoo => "odoo",
+            BrickType::N8n => "n8n",
+            BrickType::FieldMapping => "field_mapping",
+            BrickType::CombineText => "combine_text",
+            BrickType::Conditional => "conditional",
+            BrickType::RulesEngine => "rules_engine",
+            BrickType::Respond => "respond",
+            BrickType::Custom(id) => id,
+        }
+    }
+
+    /// Parses a brick id, falling back to `Custom` for unknown ids
+    pub fn from_id(id: &str) -> Self {
+        match id {
+            // `open_ai` and `hub_spot` are the ids older releases serialized
+            "openai" | "open_ai" => BrickType::OpenAi,
+            "nvidia" => BrickType::Nvidia,
+            "hubspot" | "hub_spot" => BrickType::HubSpot,
+            "notion" => BrickType::Notion,
+            "odoo" => BrickType::Odoo,
+            "n8n" => BrickType::N8n,
+            "field_mapping" => BrickType::FieldMapping,
+            "combine_text" => BrickType::CombineText,
+            "conditional" => BrickType::Conditional,
+            "rules_engine" => BrickType::RulesEngine,
+            "respond" => BrickType::Respond,
+            other => BrickType::Custom(other.to_string()),
+        }
+    }
+
+    pub fn is_custom(&self) -> bool {
+        matches!(self, BrickType::Custom(_))
+    }
+}
+
+impl std::fmt::Display for BrickType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+impl Serialize for BrickType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl<'de> Deserialize<'de> for BrickType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        if id.is_empty() {
+            return Err(serde::de::Error::custom("brick type cannot be empty"));
         }
+        Ok(BrickType::from_id(&id))
     }
 }
 
@@ -39,6 +109,92 @@ impl BrickType {
 pub struct BrickConfig {
     pub brick_type: BrickType,
     pub config: Value,
+    /// Retry, timeout and error handling for this brick; without a policy a
+    /// failing brick fails the flow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<BrickPolicy>,
+}
+
+/// Execution policy for a single brick of a flow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BrickPolicy {
+    /// Total attempts including the first one
+    #[serde(default = "BrickPolicy::default_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the second attempt
+    #[serde(default = "BrickPolicy::default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Factor applied to the delay after every attempt
+    #[serde(default = "BrickPolicy::default_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+    /// Upper bound for the delay between attempts
+    #[serde(default = "BrickPolicy::default_max_backoff_ms")]
+    pub max_backoff_ms: u64,
+    /// Time limit for each attempt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    /// Pass the brick's input through unchanged when it (and its fallback) fails
+    #[serde(default)]
+    pub continue_on_error: bool,
+    /// Used once all attempts have failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback: Option<BrickFallback>,
+}
+
+impl BrickPolicy {
+    fn default_max_attempts() -> u32 {
+        1
+    }
+
+    fn default_initial_backoff_ms() -> u64 {
+        1000
+    }
+
+    fn default_backoff_multiplier() -> f64 {
+        2.0
+    }
+
+    fn default_max_backoff_ms() -> u64 {
+        30_000
+    }
+
+    /// Backoff settings for `retry::retry_with_backoff_if`
+    pub fn retry_config(&self) -> crate::retry::RetryConfig {
+        crate::retry::RetryConfig {
+            max_retries: self.max_attempts.saturating_sub(1),
+            initial_delay: std::time::Duration::from_millis(self.initial_backoff_ms),
+            max_delay: std::time::Duration::from_millis(self.max_backoff_ms),
+            backoff_multiplier: self.backoff_multiplier,
+        }
+    }
+
+    pub fn timeout(&self) -> Option<std::time::Duration> {
+        self.timeout_ms.map(std::time::Duration::from_millis)
+    }
+}
+
+impl Default for BrickPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: Self::default_max_attempts(),
+            initial_backoff_ms: Self::default_initial_backoff_ms(),
+            backoff_multiplier: Self::default_backoff_multiplier(),
+            max_backoff_ms: Self::default_max_backoff_ms(),
+            timeout_ms: None,
+            continue_on_error: false,
+            fallback: None,
+        }
+    }
+}
+
+/// Replacement for the output of a brick whose attempts all failed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BrickFallback {
+    /// Used as the brick's output
+    Value(Value),
+    /// Run once with the failed brick's input; its own policy is ignored
+    Brick(Box<BrickConfig>),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,11 +203,283 @@ pub struct Flow {
     pub name: String,
     pub description: Option<String>,
     pub bricks: Vec<BrickConfig>,
+    /// Directed graph form of the flow. When absent the flow runs as a linear
+    /// chain of `bricks` (see `FlowGraph::from_linear`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub graph: Option<FlowGraph>,
+    /// How failed executions of this flow are retried from the dead-letter queue
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_policy: Option<RetryPolicy>,
+    /// Values available to brick config templates as `{{ vars.<name> }}`
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub variables: serde_json::Map<String, Value>,
+    /// JSON Schemas of the payloads the flow accepts and produces
+    #[serde(default, skip_serializing_if = "FlowSchema::is_empty")]
+    pub schema: FlowSchema,
+    /// Workspace that owns the flow; set when the flow is stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<String>,
     pub active: bool,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// JSON Schemas (draft 2020-12) of a flow's input and output payloads
+///
+/// Trigger payloads are validated against `input` before the flow runs and
+/// the output of a completed execution against `output`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FlowSchema {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<Value>,
+}
+
+impl FlowSchema {
+    pub fn is_empty(&self) -> bool {
+        self.input.is_none() && self.output.is_none()
+    }
+
+    /// Checks that both schemas are usable
+    pub fn check(&self) -> Result<(), String> {
+        if let Some(input) = &self.input {
+            crate::schema::check_schema(input).map_err(|e| format!("Invalid input schema: {}", e))?;
+        }
+        if let Some(output) = &self.output {
+            crate::schema::check_schema(output).map_err(|e| format!("Invalid output schema: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// Validates a trigger payload; passes if the flow declares no input schema
+    pub fn validate_input(&self, payload: &Value) -> Result<(), Vec<crate::schema::SchemaError>> {
+        self.input.as_ref().map_or(Ok(()), |schema| crate::schema::validate(schema, payload))
+    }
+
+    /// Validates an execution's output; passes if the flow declares no output schema
+    pub fn validate_output(&self, payload: &Value) -> Result<(), Vec<crate::schema::SchemaError>> {
+        self.output.as_ref().map_or(Ok(()), |schema| crate::schema::validate(schema, payload))
+    }
+}
+
+/// Automatic retry settings for failed executions of a flow
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries before the execution is left in the dead-letter queue (0 disables retries)
+    #[serde(default = "RetryPolicy::default_max_retries")]
+    pub max_retries: u32,
+    /// Delay before the first retry
+    #[serde(default = "RetryPolicy::default_initial_delay_secs")]
+    pub initial_delay_secs: u64,
+    /// Factor applied to the delay after every retry
+    #[serde(default = "RetryPolicy::default_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+    /// Upper bound for the delay between retries
+    #[serde(default = "RetryPolicy::default_max_delay_secs")]
+    pub max_delay_secs: u64,
+}
+
+impl RetryPolicy {
+    fn default_max_retries() -> u32 {
+        3
+    }
+
+    fn default_initial_delay_secs() -> u64 {
+        60
+    }
+
+    fn default_backoff_multiplier() -> f64 {
+        2.0
+    }
+
+    fn default_max_delay_secs() -> u64 {
+        3600
+    }
+
+    /// Delay before retry number `retry_count + 1`
+    pub fn delay(&self, retry_count: u32) -> std::time::Duration {
+        let secs = self.initial_delay_secs as f64 * self.backoff_multiplier.max(1.0).powi(retry_count as i32);
+        std::time::Duration::from_secs_f64(secs.min(self.max_delay_secs as f64))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: Self::default_max_retries(),
+            initial_delay_secs: Self::default_initial_delay_secs(),
+            backoff_multiplier: Self::default_backoff_multiplier(),
+            max_delay_secs: Self::default_max_delay_secs(),
+        }
+    }
+}
+
+impl Flow {
+    /// Returns the flow's graph, deriving one from the linear brick list when
+    /// the flow has not been converted yet
+    pub fn effective_graph(&self) -> FlowGraph {
+        match self.graph {
+            Some(ref graph) => graph.clone(),
+            None => FlowGraph::from_linear(&self.bricks),
+        }
+    }
+
+    /// Calls `f` with every brick of the flow: the linear bricks, then the
+    /// graph's brick nodes, each followed by its fallback brick if any
+    pub fn visit_bricks<'a>(&'a self, mut f: impl FnMut(&'a BrickConfig)) {
+        let graph_bricks = self.graph.iter().flat_map(|graph| graph.brick_configs());
+        for brick in self.bricks.iter().chain(graph_bricks) {
+            f(brick);
+            if let Some(BrickFallback::Brick(ref fallback)) = brick.policy.as_ref().and_then(|p| p.fallback.as_ref()) {
+                f(fallback);
+            }
+        }
+    }
+
+    /// Mutable form of `visit_bricks`
+    pub fn visit_bricks_mut(&mut self, mut f: impl FnMut(&mut BrickConfig)) {
+        let graph_bricks = self.graph.iter_mut().flat_map(|graph| {
+            graph.nodes.iter_mut().filter_map(|node| match node.kind {
+                FlowNodeKind::Brick(ref mut brick) => Some(brick),
+                FlowNodeKind::Join { .. } => None,
+            })
+        });
+        for brick in self.bricks.iter_mut().chain(graph_bricks) {
+            f(brick);
+            if let Some(BrickFallback::Brick(ref mut fallback)) = brick.policy.as_mut().and_then(|p| p.fallback.as_mut()) {
+                f(fallback);
+            }
+        }
+    }
+}
+
+/// A flow expressed as a directed acyclic graph of nodes and edges
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct FlowGraph {
+    pub nodes: Vec<FlowNode>,
+    #[serde(default)]
+    pub edges: Vec<FlowEdge>,
+}
+
+impl FlowGraph {
+    /// Builds a chain graph (`brick_0 -> brick_1 -> ...`) from a linear brick list
+    pub fn from_linear(bricks: &[BrickConfig]) -> Self {
+        Self::chain(bricks.iter().map(|brick| (None, brick.clone())).collect())
+    }
+
+    /// Builds a chain graph from bricks paired with their node ids; bricks
+    /// without an id are named `brick_<index>`
+    pub fn chain(bricks: Vec<(Option<String>, BrickConfig)>) -> Self {
+        let nodes: Vec<FlowNode> = bricks
+            .into_iter()
+            .enumerate()
+            .map(|(index, (id, brick))| FlowNode {
+                id: id.unwrap_or_else(|| format!("brick_{}", index)),
+                kind: FlowNodeKind::Brick(brick),
+            })
+            .collect();
+
+        let edges = nodes
+            .windows(2)
+            .map(|pair| FlowEdge {
+                from: pair[0].id.clone(),
+                to: pair[1].id.clone(),
+                condition: None,
+            })
+            .collect();
+
+        Self { nodes, edges }
+    }
+
+    /// Whether the graph is a plain chain of bricks without joins or
+    /// conditional edges, so it can be rebuilt from its brick list
+    pub fn is_linear(&self) -> bool {
+        self.edges.len() == self.nodes.len().saturating_sub(1)
+            && self.nodes.iter().all(|n| matches!(n.kind, FlowNodeKind::Brick(_)))
+            && self.nodes.windows(2).zip(&self.edges).all(|(pair, edge)| {
+                edge.from == pair[0].id && edge.to == pair[1].id && edge.condition.is_none()
+            })
+    }
+
+    pub fn node(&self, id: &str) -> Option<&FlowNode> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+
+    /// Returns the index of the node at `index` and of every node reachable from it
+    pub fn downstream_of(&self, index: usize) -> std::collections::HashSet<usize> {
+        let mut reached = std::collections::HashSet::new();
+        let mut pending = vec![index];
+        while let Some(current) = pending.pop() {
+            if !reached.insert(current) {
+                continue;
+            }
+            let id = match self.nodes.get(current) {
+                Some(node) => &node.id,
+                None => continue,
+            };
+            for edge in self.edges.iter().filter(|e| &e.from == id) {
+                if let Some(next) = self.nodes.iter().position(|n| n.id == edge.to) {
+                    pending.push(next);
+                }
+            }
+        }
+        reached
+    }
+
+    /// Returns the brick configs of all brick nodes in node order
+    pub fn brick_configs(&self) -> Vec<&BrickConfig> {
+        self.nodes
+            .iter()
+            .filter_map(|n| match n.kind {
+                FlowNodeKind::Brick(ref config) => Some(config),
+                FlowNodeKind::Join { .. } => None,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowNode {
+    pub id: String,
+    #[serde(flatten)]
+    pub kind: FlowNodeKind,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FlowNodeKind {
+    /// Executes a brick with the output of its incoming edge(s)
+    Brick(BrickConfig),
+    /// Waits for every incoming branch and combines their outputs
+    Join {
+        #[serde(default)]
+        strategy: JoinStrategy,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum JoinStrategy {
+    /// Shallow-merges object outputs in edge order (later keys win)
+    #[default]
+    Merge,
+    /// Collects outputs into an array in edge order
+    Array,
+    /// Builds an object keyed by the id of the node each output came from
+    Keyed,
+}
+
+/// Connects two nodes. An edge with a condition is only followed when the
+/// condition holds for the output of the `from` node.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowEdge {
+    pub from: String,
+    pub to: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<RuleCondition>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FlowExecution {
     pub flow_id: String,
@@ -62,6 +490,85 @@ pub struct FlowExecution {
     pub input_payload: Value,
     pub output_payload: Option<Value>,
     pub error: Option<String>,
+    /// Execution that started this one as a sub-flow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_execution_id: Option<String>,
+    /// Failed execution this one replays
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replay_of_execution_id: Option<String>,
+    /// Bricks run by this execution, in the order they finished. Stored with
+    /// the execution but not loaded with it (see `ExecutionStepRepository`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<ExecutionStep>,
+}
+
+/// Record of one brick run within an execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionStep {
+    /// Position of the brick in the flow (the node index for graph flows)
+    pub brick_index: usize,
+    pub brick_type: BrickType,
+    pub status: StepStatus,
+    /// Payload the brick received
+    pub input: Value,
+    /// Payload passed on by the brick
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    /// Attempts made, including retries from the brick's policy
+    pub attempts: u32,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub duration_ms: u64,
+}
+
+impl ExecutionStep {
+    /// Step of a brick skipped through `_skip_bricks`
+    pub fn skipped(brick_index: usize, brick_type: BrickType, input: Value) -> Self {
+        Self {
+            brick_index,
+            brick_type,
+            status: StepStatus::Skipped,
+            output: Some(input.clone()),
+            input,
+            error: None,
+            attempts: 0,
+            started_at: chrono::Utc::now(),
+            duration_ms: 0,
+        }
+    }
+
+    /// Marks the step failed after its brick ran, e.g. when its branch failed
+    pub fn fail(&mut self, error: String) {
+        self.status = StepStatus::Failed;
+        self.output = None;
+        self.error = Some(error);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Succeeded,
+    Failed,
+    Skipped,
+}
+
+impl StepStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepStatus::Succeeded => "succeeded",
+            StepStatus::Failed => "failed",
+            StepStatus::Skipped => "skipped",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "succeeded" => Some(StepStatus::Succeeded),
+            "failed" => Some(StepStatus::Failed),
+            "skipped" => Some(StepStatus::Skipped),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -106,7 +613,10 @@ pub struct MappingRule {
     pub target_paths: Vec<String>,
     #[serde(default)]
     pub direction: MappingDirection,
-    pub transform: Option<TransformType>,
+    /// Transforms applied in order to the mapped value; a single transform
+    /// may be given instead of a list
+    #[serde(default, deserialize_with = "deserialize_pipeline", skip_serializing_if = "Vec::is_empty")]
+    pub transform: Vec<TransformType>,
     #[serde(default)]
     pub merge_strategy: Option<MergeStrategy>,
     #[serde(default)]
@@ -117,6 +627,14 @@ fn default_single_path() -> String {
     String::new()
 }
 
+fn deserialize_pipeline<'de, D>(deserializer: D) -> Result<Vec<TransformType>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Value::deserialize(deserializer)?;
+    TransformType::parse_pipeline(value).map_err(serde::de::Error::custom)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum MappingDirection {
@@ -131,6 +649,29 @@ impl Default for MappingDirection {
     }
 }
 
+impl MappingDirection {
+    /// Returns true if a rule with this direction runs in the given mode
+    pub fn applies_to(&self, mode: MappingMode) -> bool {
+        match self {
+            MappingDirection::Forward => mode == MappingMode::Forward,
+            MappingDirection::Backward => mode == MappingMode::Reverse,
+            MappingDirection::Bidirectional => true,
+        }
+    }
+}
+
+/// Which way a set of mapping rules is run
+///
+/// `Forward` maps source paths to target paths; `Reverse` maps target paths
+/// back to source paths, e.g. to write changes back to the originating system.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MappingMode {
+    #[default]
+    Forward,
+    Reverse,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum MergeStrategy {
@@ -149,17 +690,140 @@ pub enum SplitStrategy {
     TransformEach,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A step of a mapping rule's transform pipeline
+///
+/// See the `transform` module for how each step treats its input.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum TransformType {
+    // Strings
     StringConcat { separator: String },
-    NumberAdd,
-    NumberMultiply,
     StringToUpper,
     StringToLower,
+    Trim,
+    Replace {
+        from: String,
+        to: String,
+        /// Treat `from` as a regular expression; `to` may then use `$1` etc.
+        #[serde(default)]
+        regex: bool,
+    },
+    RegexExtract {
+        pattern: String,
+        /// Capture group to extract; 0 is the whole match
+        #[serde(default)]
+        group: usize,
+    },
+    Split { separator: String },
+    Pad {
+        width: usize,
+        #[serde(default = "default_pad_fill")]
+        fill: char,
+        #[serde(default)]
+        side: PadSide,
+    },
+
+    // Numbers
+    NumberAdd { operand: f64 },
+    NumberSubtract { operand: f64 },
+    NumberMultiply { operand: f64 },
+    NumberDivide { operand: f64 },
+    Round {
+        #[serde(default)]
+        decimals: u32,
+        #[serde(default)]
+        mode: RoundingMode,
+    },
+
+    // Dates
+    /// Parses a date with a strftime format, or `rfc3339`, `rfc2822`,
+    /// `unix` or `unix_ms`, into an RFC 3339 timestamp
+    DateParse { format: String },
+    /// Formats an RFC 3339 timestamp with a strftime format, or `rfc3339`,
+    /// `rfc2822`, `unix` or `unix_ms`
+    DateFormat { format: String },
+    /// Converts an RFC 3339 timestamp to a UTC offset such as `+02:00`
+    DateTimezone { timezone: String },
+
+    // Types
+    ToString,
+    ToNumber,
+    ToBoolean,
+    /// Replaces a missing or null value
+    DefaultIfMissing { value: Value },
+    Lookup {
+        table: serde_json::Map<String, Value>,
+        /// Value for keys missing from the table; without it they are an error
+        #[serde(default)]
+        default: Option<Value>,
+    },
+
+    // Encoding
+    Base64Encode,
+    Base64Decode,
+    Hash {
+        #[serde(default)]
+        algorithm: HashAlgorithm,
+    },
+
+    // Arrays
+    ArrayMap { transforms: Vec<TransformType> },
+    /// Keeps the elements matching a filter expression such as `@.price > 10`
+    ArrayFilter { condition: String },
+    ArraySort {
+        /// Path within each element to sort by; the element itself if unset
+        #[serde(default)]
+        by: Option<String>,
+        #[serde(default)]
+        descending: bool,
+    },
+    ArrayUnique,
+
     Conditional { condition: String, true_value: Value, false_value: Value },
 }
 
+impl TransformType {
+    /// Parses a transform pipeline from either a single transform or a list
+    pub fn parse_pipeline(value: Value) -> Result<Vec<TransformType>, serde_json::Error> {
+        match value {
+            Value::Null => Ok(Vec::new()),
+            Value::Array(_) => serde_json::from_value(value),
+            value => serde_json::from_value(value).map(|transform| vec![transform]),
+        }
+    }
+}
+
+fn default_pad_fill() -> char {
+    ' '
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PadSide {
+    #[default]
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum RoundingMode {
+    /// Round half away from zero
+    #[default]
+    Nearest,
+    Floor,
+    Ceil,
+    Truncate,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Sha512,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Template {
     pub id: String,
@@ -169,6 +833,9 @@ pub struct Template {
     pub flow_config: Flow,
     pub is_system: bool,
     pub created_by: Option<String>,
+    /// Workspace that owns the template; `None` for system templates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
@@ -230,6 +897,8 @@ pub enum RuleAction {
     #[serde(rename = "branch")]
     Branch {
         flow_id: String,
+        #[serde(default)]
+        mode: BranchMode,
     },
     #[serde(rename = "skip_bricks")]
     SkipBricks {
@@ -237,6 +906,17 @@ pub enum RuleAction {
     },
 }
 
+/// How a `Branch` action's sub-flow relates to the rest of the parent flow
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BranchMode {
+    /// Run the sub-flow, merge its output into the payload and continue
+    #[default]
+    Inline,
+    /// Run the sub-flow, merge its output and skip the remaining bricks
+    Replace,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Rule {
     pub name: String,
@@ -0,0 +1,3 @@
+-- Store flows as directed acyclic graphs of nodes and edges.
+-- Rows with a NULL graph are linear flows and are migrated to a chain on read.
+ALTER TABLE flows ADD COLUMN graph TEXT;
@@ -0,0 +1,4 @@
+-- Link sub-flow executions started by a Branch action to their parent execution
+ALTER TABLE executions ADD COLUMN parent_execution_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_executions_parent_execution_id ON executions(parent_execution_id);
@@ -0,0 +1,13 @@
+-- Per-flow retry policy for failed executions
+ALTER TABLE flows ADD COLUMN retry_policy TEXT;
+
+-- Link replayed executions to the failed execution they replay
+ALTER TABLE executions ADD COLUMN replay_of_execution_id TEXT;
+
+-- Dead-letter state: pending, retrying, resolved, exhausted or discarded
+-- next_retry_at is a unix timestamp in milliseconds
+ALTER TABLE failed_executions ADD COLUMN status TEXT NOT NULL DEFAULT 'pending';
+ALTER TABLE failed_executions ADD COLUMN next_retry_at INTEGER;
+ALTER TABLE failed_executions ADD COLUMN last_replay_execution_id TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_failed_executions_status_next_retry_at ON failed_executions(status, next_retry_at);
@@ -0,0 +1,18 @@
+-- Per-brick step records of executions
+-- status is succeeded, failed or skipped; input and output are JSON
+CREATE TABLE IF NOT EXISTS execution_steps (
+    id TEXT PRIMARY KEY,
+    execution_id TEXT NOT NULL,
+    brick_index INTEGER NOT NULL,
+    brick_type TEXT NOT NULL,
+    status TEXT NOT NULL,
+    input TEXT NOT NULL,
+    output TEXT,
+    error TEXT,
+    attempts INTEGER NOT NULL,
+    started_at TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL,
+    FOREIGN KEY (execution_id) REFERENCES executions(execution_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_execution_steps_execution_id ON execution_steps(execution_id);
@@ -0,0 +1,3 @@
+-- Flow variables, available to brick config templates as {{ vars.<name> }}.
+-- Stored as a JSON object; NULL when the flow has no variables.
+ALTER TABLE flows ADD COLUMN variables TEXT;
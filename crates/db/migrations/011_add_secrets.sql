@@ -0,0 +1,12 @@
+-- Secrets referenced from brick configs as {{ secret:<name> }}
+-- Values are encrypted with AES-256-GCM under the master key; nonce and
+-- ciphertext are base64 encoded and the name is bound as associated data
+CREATE TABLE IF NOT EXISTS secrets (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    nonce TEXT NOT NULL,
+    ciphertext TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
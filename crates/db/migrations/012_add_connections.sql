@@ -0,0 +1,16 @@
+-- Saved connections referenced from brick configs by connection_id
+-- Credentials are a JSON object encrypted like secrets, with
+-- "connection:<id>" bound as associated data
+CREATE TABLE IF NOT EXISTS connections (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    connection_type TEXT NOT NULL,
+    base_url TEXT,
+    defaults TEXT NOT NULL DEFAULT '{}',
+    nonce TEXT NOT NULL,
+    ciphertext TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_connections_type ON connections(connection_type);
@@ -0,0 +1,76 @@
+-- Workspaces and their members
+-- Roles, from least to most privileged: viewer, runner, editor, admin, owner
+CREATE TABLE IF NOT EXISTS workspaces (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS workspace_members (
+    workspace_id TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    role TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (workspace_id, user_id),
+    FOREIGN KEY (workspace_id) REFERENCES workspaces(id),
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_workspace_members_user_id ON workspace_members(user_id);
+
+-- Everything owned by a flow records the flow's workspace
+ALTER TABLE flows ADD COLUMN workspace_id TEXT;
+ALTER TABLE executions ADD COLUMN workspace_id TEXT;
+ALTER TABLE usage_logs ADD COLUMN workspace_id TEXT;
+ALTER TABLE scheduled_flows ADD COLUMN workspace_id TEXT;
+ALTER TABLE failed_executions ADD COLUMN workspace_id TEXT;
+ALTER TABLE connections ADD COLUMN workspace_id TEXT;
+-- NULL for system templates, which every workspace sees
+ALTER TABLE templates ADD COLUMN workspace_id TEXT;
+
+-- Secret names are unique per workspace
+ALTER TABLE secrets RENAME TO secrets_legacy;
+
+CREATE TABLE secrets (
+    id TEXT PRIMARY KEY,
+    workspace_id TEXT,
+    name TEXT NOT NULL,
+    description TEXT,
+    nonce TEXT NOT NULL,
+    ciphertext TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    UNIQUE (workspace_id, name)
+);
+
+INSERT INTO secrets (id, name, description, nonce, ciphertext, created_at, updated_at)
+SELECT id, name, description, nonce, ciphertext, created_at, updated_at FROM secrets_legacy;
+
+DROP TABLE secrets_legacy;
+
+CREATE INDEX IF NOT EXISTS idx_flows_workspace_id ON flows(workspace_id);
+CREATE INDEX IF NOT EXISTS idx_executions_workspace_id ON executions(workspace_id);
+CREATE INDEX IF NOT EXISTS idx_usage_logs_workspace_id ON usage_logs(workspace_id);
+CREATE INDEX IF NOT EXISTS idx_scheduled_flows_workspace_id ON scheduled_flows(workspace_id);
+CREATE INDEX IF NOT EXISTS idx_templates_workspace_id ON templates(workspace_id);
+CREATE INDEX IF NOT EXISTS idx_failed_executions_workspace_id ON failed_executions(workspace_id);
+CREATE INDEX IF NOT EXISTS idx_secrets_workspace_id ON secrets(workspace_id);
+CREATE INDEX IF NOT EXISTS idx_connections_workspace_id ON connections(workspace_id);
+
+-- Existing users and data move to a default workspace
+INSERT INTO workspaces (id, name, created_at, updated_at)
+SELECT 'default', 'Default', datetime('now'), datetime('now')
+WHERE EXISTS (SELECT 1 FROM users) OR EXISTS (SELECT 1 FROM flows);
+
+INSERT INTO workspace_members (workspace_id, user_id, role, created_at)
+SELECT 'default', id, 'owner', datetime('now') FROM users;
+
+UPDATE flows SET workspace_id = 'default' WHERE workspace_id IS NULL;
+UPDATE executions SET workspace_id = 'default' WHERE workspace_id IS NULL;
+UPDATE usage_logs SET workspace_id = 'default' WHERE workspace_id IS NULL;
+UPDATE scheduled_flows SET workspace_id = 'default' WHERE workspace_id IS NULL;
+UPDATE failed_executions SET workspace_id = 'default' WHERE workspace_id IS NULL;
+UPDATE secrets SET workspace_id = 'default' WHERE workspace_id IS NULL;
+UPDATE connections SET workspace_id = 'default' WHERE workspace_id IS NULL;
+UPDATE templates SET workspace_id = 'default' WHERE workspace_id IS NULL AND is_system = 0;
@@ -0,0 +1,7 @@
+-- Restrictions on API keys
+-- scopes: JSON array such as ["flows:read", "flow:<id>:trigger"]; NULL grants full access
+-- allowed_ips: JSON array of addresses or CIDR blocks; NULL allows every client
+ALTER TABLE api_keys ADD COLUMN scopes TEXT;
+ALTER TABLE api_keys ADD COLUMN expires_at TEXT;
+ALTER TABLE api_keys ADD COLUMN allowed_ips TEXT;
+ALTER TABLE api_keys ADD COLUMN revoked_at TEXT;
@@ -0,0 +1,26 @@
+-- Refresh tokens, stored as SHA-256 hashes
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    revoked_at TEXT,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user_id ON refresh_tokens(user_id);
+
+-- Access tokens revoked before they expire, by jti; rows can be dropped once expired
+CREATE TABLE IF NOT EXISTS revoked_access_tokens (
+    jti TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
+
+-- Access tokens of a user issued before revoked_at are rejected
+CREATE TABLE IF NOT EXISTS user_token_revocations (
+    user_id TEXT PRIMARY KEY,
+    revoked_at TEXT NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id)
+);
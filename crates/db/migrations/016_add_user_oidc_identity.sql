@@ -0,0 +1,6 @@
+-- Single sign-on identity of users
+-- oidc_subject: "<issuer>|<sub>" of the linked identity provider account
+-- group_names: JSON array of the groups reported at the last sign-on
+ALTER TABLE users ADD COLUMN oidc_subject TEXT;
+ALTER TABLE users ADD COLUMN group_names TEXT;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_oidc_subject ON users(oidc_subject);
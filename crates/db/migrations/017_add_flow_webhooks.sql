@@ -0,0 +1,16 @@
+-- Webhook authentication per flow
+-- auth: JSON such as {"mode": "hmac", "signature_header": "...", "timestamp_header": "...", "tolerance_secs": 300}
+-- nonce/ciphertext: the secret, encrypted with the secrets master key
+-- previous_*: the secret replaced by the last rotation, accepted until previous_expires_at
+CREATE TABLE IF NOT EXISTS flow_webhooks (
+    flow_id TEXT PRIMARY KEY,
+    auth TEXT NOT NULL,
+    nonce TEXT NOT NULL,
+    ciphertext TEXT NOT NULL,
+    previous_nonce TEXT,
+    previous_ciphertext TEXT,
+    previous_expires_at TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    FOREIGN KEY (flow_id) REFERENCES flows(id) ON DELETE CASCADE
+);
@@ -0,0 +1,21 @@
+-- Idempotency keys of triggered executions
+-- scope: workspace of an API call or flow of a webhook; keys are unique per scope
+-- fingerprint: hash of the request, to detect a key reused for another request
+-- status: in_progress until the response is recorded, then completed
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    scope TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    fingerprint TEXT,
+    status TEXT NOT NULL,
+    response_status INTEGER,
+    response_content_type TEXT,
+    response_body TEXT,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    PRIMARY KEY (scope, idempotency_key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_expires_at ON idempotency_keys(expires_at);
+
+-- Payload path of webhook calls holding their idempotency key, e.g. "event.id"
+ALTER TABLE flow_webhooks ADD COLUMN idempotency_key_path TEXT;
@@ -0,0 +1,16 @@
+-- Custom HTTP endpoints of flows, served under /hooks
+-- method: upper-case HTTP method; path: the path after /hooks, such as /acme/leads
+-- request_schema: JSON Schema the request payload must match
+CREATE TABLE IF NOT EXISTS flow_endpoints (
+    flow_id TEXT PRIMARY KEY,
+    method TEXT NOT NULL,
+    path TEXT NOT NULL,
+    request_schema TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    UNIQUE (method, path),
+    FOREIGN KEY (flow_id) REFERENCES flows(id) ON DELETE CASCADE
+);
+
+-- Headers other than Content-Type sent again when a response is replayed
+ALTER TABLE idempotency_keys ADD COLUMN response_headers TEXT;
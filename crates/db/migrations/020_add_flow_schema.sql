@@ -0,0 +1,3 @@
+-- JSON Schemas of a flow's input and output payloads.
+-- Stored as {"input": ..., "output": ...}; NULL when the flow declares neither.
+ALTER TABLE flows ADD COLUMN schema TEXT;
@@ -0,0 +1,3 @@
+-- When the request holding an in_progress idempotency key last renewed its claim;
+-- claims not renewed within the lease are taken over by the next request
+ALTER TABLE idempotency_keys ADD COLUMN claimed_at TEXT;
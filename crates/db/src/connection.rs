@@ -2,6 +2,8 @@ use anyhow::Result;
 use sqlx::{SqlitePool, sqlite::{SqliteConnectOptions, SqlitePoolOptions}};
 use std::str::FromStr;
 
+use crate::repositories::workspace_repository::DEFAULT_WORKSPACE_ID;
+
 pub async fn create_pool(database_url: &str) -> Result<SqlitePool> {
     let mut options = SqliteConnectOptions::from_str(database_url)?
         .create_if_missing(true);
@@ -35,7 +37,7 @@ pub async fn create_pool(database_url: &str) -> Result<SqlitePool> {
     Ok(pool)
 }
 
-async fn init_schema(pool: &SqlitePool) -> Result<()> {
+pub(crate) async fn init_schema(pool: &SqlitePool) -> Result<()> {
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS flows (
@@ -43,6 +45,11 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
             name TEXT NOT NULL,
             description TEXT,
             bricks TEXT NOT NULL,
+            graph TEXT,
+            retry_policy TEXT,
+            variables TEXT,
+            schema TEXT,
+            workspace_id TEXT,
             active INTEGER NOT NULL DEFAULT 1,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL
@@ -52,6 +59,13 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
     .execute(pool)
     .await?;
 
+    // Databases created before flow graphs existed lack the graph column
+    add_column_if_missing(pool, "flows", "graph", "TEXT").await?;
+    add_column_if_missing(pool, "flows", "retry_policy", "TEXT").await?;
+    add_column_if_missing(pool, "flows", "variables", "TEXT").await?;
+    add_column_if_missing(pool, "flows", "schema", "TEXT").await?;
+    add_column_if_missing(pool, "flows", "workspace_id", "TEXT").await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS executions (
@@ -62,13 +76,29 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
             completed_at TEXT,
             input_payload TEXT NOT NULL,
             output_payload TEXT,
-            error TEXT
+            error TEXT,
+            parent_execution_id TEXT,
+            replay_of_execution_id TEXT,
+            workspace_id TEXT
         )
         "#
     )
     .execute(pool)
     .await?;
 
+    add_column_if_missing(pool, "executions", "parent_execution_id", "TEXT").await?;
+    add_column_if_missing(pool, "executions", "replay_of_execution_id", "TEXT").await?;
+    add_column_if_missing(pool, "executions", "workspace_id", "TEXT").await?;
+
+    sqlx::query(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_executions_parent_execution_id
+        ON executions(parent_execution_id)
+        "#
+    )
+    .execute(pool)
+    .await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS usage_logs (
@@ -79,13 +109,16 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
             timestamp TEXT NOT NULL,
             cost_unit REAL NOT NULL,
             token_usage INTEGER,
-            metadata TEXT
+            metadata TEXT,
+            workspace_id TEXT
         )
         "#
     )
     .execute(pool)
     .await?;
 
+    add_column_if_missing(pool, "usage_logs", "workspace_id", "TEXT").await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS users (
@@ -93,13 +126,21 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
             email TEXT NOT NULL UNIQUE,
             password_hash TEXT NOT NULL,
             created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
+            updated_at TEXT NOT NULL,
+            oidc_subject TEXT,
+            group_names TEXT
         )
         "#
     )
     .execute(pool)
     .await?;
 
+    add_column_if_missing(pool, "users", "oidc_subject", "TEXT").await?;
+    add_column_if_missing(pool, "users", "group_names", "TEXT").await?;
+    sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_users_oidc_subject ON users(oidc_subject)")
+        .execute(pool)
+        .await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS api_keys (
@@ -109,6 +150,62 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
             name TEXT,
             created_at TEXT NOT NULL,
             last_used_at TEXT,
+            scopes TEXT,
+            expires_at TEXT,
+            allowed_ips TEXT,
+            revoked_at TEXT,
+            FOREIGN KEY (user_id) REFERENCES users(id)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // Keys created before scopes existed keep full access (NULL scopes)
+    add_column_if_missing(pool, "api_keys", "scopes", "TEXT").await?;
+    add_column_if_missing(pool, "api_keys", "expires_at", "TEXT").await?;
+    add_column_if_missing(pool, "api_keys", "allowed_ips", "TEXT").await?;
+    add_column_if_missing(pool, "api_keys", "revoked_at", "TEXT").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS refresh_tokens (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            token_hash TEXT NOT NULL UNIQUE,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            revoked_at TEXT,
+            FOREIGN KEY (user_id) REFERENCES users(id)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user_id ON refresh_tokens(user_id)")
+        .execute(pool)
+        .await?;
+
+    // Access tokens revoked before they expire, by jti
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS revoked_access_tokens (
+            jti TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            expires_at TEXT NOT NULL
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // Access tokens of a user issued before revoked_at are rejected
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS user_token_revocations (
+            user_id TEXT PRIMARY KEY,
+            revoked_at TEXT NOT NULL,
             FOREIGN KEY (user_id) REFERENCES users(id)
         )
         "#
@@ -137,6 +234,7 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
             id TEXT PRIMARY KEY,
             flow_id TEXT NOT NULL UNIQUE,
             cron_expression TEXT NOT NULL,
+            workspace_id TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL,
             FOREIGN KEY (flow_id) REFERENCES flows(id)
@@ -146,6 +244,8 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
     .execute(pool)
     .await?;
 
+    add_column_if_missing(pool, "scheduled_flows", "workspace_id", "TEXT").await?;
+
     sqlx::query(
         r#"
         CREATE TABLE IF NOT EXISTS execution_data (
@@ -256,6 +356,7 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
             flow_config TEXT NOT NULL,
             is_system INTEGER NOT NULL DEFAULT 0,
             created_by TEXT,
+            workspace_id TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL,
             FOREIGN KEY (created_by) REFERENCES users(id)
@@ -265,6 +366,8 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
     .execute(pool)
     .await?;
 
+    add_column_if_missing(pool, "templates", "workspace_id", "TEXT").await?;
+
     sqlx::query(
         r#"
         CREATE INDEX IF NOT EXISTS idx_templates_category 
@@ -350,6 +453,10 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
             max_retries INTEGER NOT NULL DEFAULT 3,
             last_attempt_at TEXT NOT NULL,
             created_at TEXT NOT NULL,
+            status TEXT NOT NULL DEFAULT 'pending',
+            next_retry_at INTEGER,
+            last_replay_execution_id TEXT,
+            workspace_id TEXT,
             FOREIGN KEY (execution_id) REFERENCES executions(execution_id),
             FOREIGN KEY (flow_id) REFERENCES flows(id)
         )
@@ -358,6 +465,12 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
     .execute(pool)
     .await?;
 
+    // Dead-letter state added after the table was first created
+    add_column_if_missing(pool, "failed_executions", "status", "TEXT NOT NULL DEFAULT 'pending'").await?;
+    add_column_if_missing(pool, "failed_executions", "next_retry_at", "INTEGER").await?;
+    add_column_if_missing(pool, "failed_executions", "last_replay_execution_id", "TEXT").await?;
+    add_column_if_missing(pool, "failed_executions", "workspace_id", "TEXT").await?;
+
     sqlx::query(
         r#"
         CREATE INDEX IF NOT EXISTS idx_failed_executions_flow_id 
@@ -376,6 +489,367 @@ async fn init_schema(pool: &SqlitePool) -> Result<()> {
     .execute(pool)
     .await?;
 
+    sqlx::query(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_failed_executions_status_next_retry_at 
+        ON failed_executions(status, next_retry_at)
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // Durable job queue consumed by flowmason-worker
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS jobs (
+            id TEXT PRIMARY KEY,
+            flow_id TEXT NOT NULL,
+            input_payload TEXT NOT NULL,
+            status TEXT NOT NULL,
+            attempts INTEGER NOT NULL DEFAULT 0,
+            max_attempts INTEGER NOT NULL DEFAULT 3,
+            available_at INTEGER NOT NULL,
+            lease_owner TEXT,
+            lease_expires_at INTEGER,
+            execution_id TEXT,
+            error TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_jobs_status_available_at 
+        ON jobs(status, available_at)
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // Per-brick step records of executions
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS execution_steps (
+            id TEXT PRIMARY KEY,
+            execution_id TEXT NOT NULL,
+            brick_index INTEGER NOT NULL,
+            brick_type TEXT NOT NULL,
+            status TEXT NOT NULL,
+            input TEXT NOT NULL,
+            output TEXT,
+            error TEXT,
+            attempts INTEGER NOT NULL,
+            started_at TEXT NOT NULL,
+            duration_ms INTEGER NOT NULL,
+            FOREIGN KEY (execution_id) REFERENCES executions(execution_id)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE INDEX IF NOT EXISTS idx_execution_steps_execution_id 
+        ON execution_steps(execution_id)
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // Secrets referenced from brick configs, encrypted with the master key
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS secrets (
+            id TEXT PRIMARY KEY,
+            workspace_id TEXT,
+            name TEXT NOT NULL,
+            description TEXT,
+            nonce TEXT NOT NULL,
+            ciphertext TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE (workspace_id, name)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // Secret names were globally unique before workspaces; SQLite cannot
+    // change a constraint in place, so the table is rebuilt
+    if !column_exists(pool, "secrets", "workspace_id").await? {
+        let mut tx = pool.begin().await?;
+        sqlx::query("ALTER TABLE secrets RENAME TO secrets_legacy").execute(&mut *tx).await?;
+        sqlx::query(
+            r#"
+            CREATE TABLE secrets (
+                id TEXT PRIMARY KEY,
+                workspace_id TEXT,
+                name TEXT NOT NULL,
+                description TEXT,
+                nonce TEXT NOT NULL,
+                ciphertext TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                UNIQUE (workspace_id, name)
+            )
+            "#
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO secrets (id, name, description, nonce, ciphertext, created_at, updated_at)
+            SELECT id, name, description, nonce, ciphertext, created_at, updated_at FROM secrets_legacy
+            "#
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query("DROP TABLE secrets_legacy").execute(&mut *tx).await?;
+        tx.commit().await?;
+    }
+
+    // Saved connections, with credentials encrypted like secrets
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS connections (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            connection_type TEXT NOT NULL,
+            base_url TEXT,
+            defaults TEXT NOT NULL DEFAULT '{}',
+            nonce TEXT NOT NULL,
+            ciphertext TEXT NOT NULL,
+            workspace_id TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    add_column_if_missing(pool, "connections", "workspace_id", "TEXT").await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_connections_type ON connections(connection_type)")
+        .execute(pool)
+        .await?;
+
+    // Webhook authentication per flow, with secrets encrypted like secrets
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS flow_webhooks (
+            flow_id TEXT PRIMARY KEY,
+            auth TEXT NOT NULL,
+            nonce TEXT NOT NULL,
+            ciphertext TEXT NOT NULL,
+            previous_nonce TEXT,
+            previous_ciphertext TEXT,
+            previous_expires_at TEXT,
+            idempotency_key_path TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (flow_id) REFERENCES flows(id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    add_column_if_missing(pool, "flow_webhooks", "idempotency_key_path", "TEXT").await?;
+
+    // Idempotency keys of triggered executions with their recorded responses
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS idempotency_keys (
+            scope TEXT NOT NULL,
+            idempotency_key TEXT NOT NULL,
+            fingerprint TEXT,
+            status TEXT NOT NULL,
+            response_status INTEGER,
+            response_content_type TEXT,
+            response_headers TEXT,
+            response_body TEXT,
+            created_at TEXT NOT NULL,
+            claimed_at TEXT,
+            expires_at TEXT NOT NULL,
+            PRIMARY KEY (scope, idempotency_key)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_idempotency_keys_expires_at ON idempotency_keys(expires_at)")
+        .execute(pool)
+        .await?;
+
+    add_column_if_missing(pool, "idempotency_keys", "response_headers", "TEXT").await?;
+    add_column_if_missing(pool, "idempotency_keys", "claimed_at", "TEXT").await?;
+
+    // Custom HTTP endpoints flows are published under
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS flow_endpoints (
+            flow_id TEXT PRIMARY KEY,
+            method TEXT NOT NULL,
+            path TEXT NOT NULL,
+            request_schema TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            UNIQUE (method, path),
+            FOREIGN KEY (flow_id) REFERENCES flows(id) ON DELETE CASCADE
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    // Workspaces own flows and everything related to them
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS workspaces (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS workspace_members (
+            workspace_id TEXT NOT NULL,
+            user_id TEXT NOT NULL,
+            role TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (workspace_id, user_id),
+            FOREIGN KEY (workspace_id) REFERENCES workspaces(id),
+            FOREIGN KEY (user_id) REFERENCES users(id)
+        )
+        "#
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query("CREATE INDEX IF NOT EXISTS idx_workspace_members_user_id ON workspace_members(user_id)")
+        .execute(pool)
+        .await?;
+
+    for table in WORKSPACE_TABLES {
+        sqlx::query(&format!(
+            "CREATE INDEX IF NOT EXISTS idx_{table}_workspace_id ON {table}(workspace_id)"
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    assign_default_workspace(pool).await?;
+
     Ok(())
 }
 
+/// Tables whose rows belong to a workspace
+const WORKSPACE_TABLES: &[&str] = &[
+    "flows",
+    "executions",
+    "usage_logs",
+    "scheduled_flows",
+    "templates",
+    "failed_executions",
+    "secrets",
+    "connections",
+];
+
+/// Moves data stored before workspaces existed into the default workspace
+///
+/// Runs while no workspace exists yet and the database already has users or
+/// data; every existing user becomes an owner of the default workspace.
+/// System templates stay shared by all workspaces.
+async fn assign_default_workspace(pool: &SqlitePool) -> Result<()> {
+    let workspaces: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM workspaces")
+        .fetch_one(pool)
+        .await?;
+    if workspaces > 0 {
+        return Ok(());
+    }
+    let existing: i64 = sqlx::query_scalar(
+        r#"
+        SELECT (SELECT COUNT(*) FROM users) + (SELECT COUNT(*) FROM flows)
+            + (SELECT COUNT(*) FROM secrets) + (SELECT COUNT(*) FROM connections)
+        "#
+    )
+    .fetch_one(pool)
+    .await?;
+    if existing == 0 {
+        return Ok(());
+    }
+
+    let now = chrono::Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await?;
+    sqlx::query("INSERT INTO workspaces (id, name, created_at, updated_at) VALUES (?1, 'Default', ?2, ?2)")
+        .bind(DEFAULT_WORKSPACE_ID)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        r#"
+        INSERT INTO workspace_members (workspace_id, user_id, role, created_at)
+        SELECT ?1, id, 'owner', ?2 FROM users
+        "#
+    )
+    .bind(DEFAULT_WORKSPACE_ID)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await?;
+    for table in WORKSPACE_TABLES {
+        let system_filter = if *table == "templates" { " AND is_system = 0" } else { "" };
+        sqlx::query(&format!(
+            "UPDATE {table} SET workspace_id = ?1 WHERE workspace_id IS NULL{system_filter}"
+        ))
+        .bind(DEFAULT_WORKSPACE_ID)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+
+    tracing::info!("Assigned existing users and data to the default workspace");
+    Ok(())
+}
+
+/// Whether a table has a column
+async fn column_exists(pool: &SqlitePool, table: &str, column: &str) -> Result<bool> {
+    let exists: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2"
+    )
+    .bind(table)
+    .bind(column)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(exists > 0)
+}
+
+/// Adds a column to an existing table unless it is already present
+async fn add_column_if_missing(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    if !column_exists(pool, table, column).await? {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
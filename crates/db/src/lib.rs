@@ -1,6 +1,8 @@
 pub mod repositories;
 pub mod connection;
+pub mod sub_flow;
 
 pub use repositories::*;
 pub use connection::*;
+pub use sub_flow::RepositorySubFlowExecutor;
 
@@ -1,5 +1,9 @@
 use anyhow::Result;
-use sqlx::SqlitePool;
+use flowmason_auth::{IpRange, ScopeSet};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use std::net::IpAddr;
+
+const API_KEY_COLUMNS: &str = "id, user_id, key_hash, name, created_at, last_used_at, scopes, expires_at, allowed_ips, revoked_at";
 
 pub struct ApiKeyRepository {
     pool: SqlitePool,
@@ -13,6 +17,29 @@ pub struct ApiKey {
     pub name: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_used_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// `None` grants the full access of the user
+    pub scopes: Option<ScopeSet>,
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// `None` allows every client
+    pub allowed_ips: Option<Vec<IpRange>>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ApiKey {
+    /// Whether the key is neither revoked nor expired
+    pub fn is_active(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+
+    /// Whether a client may use the key; keys with an allow-list reject
+    /// clients of unknown address
+    pub fn allows_ip(&self, ip: Option<IpAddr>) -> bool {
+        match (&self.allowed_ips, ip) {
+            (None, _) => true,
+            (Some(ranges), Some(ip)) => ranges.iter().any(|range| range.contains(ip)),
+            (Some(_), None) => false,
+        }
+    }
 }
 
 impl ApiKeyRepository {
@@ -20,144 +47,179 @@ impl ApiKeyRepository {
         Self { pool }
     }
 
-    pub async fn create(&self, user_id: &str, key_hash: &str, name: Option<&str>) -> Result<String> {
+    pub async fn create(
+        &self,
+        user_id: &str,
+        key_hash: &str,
+        name: Option<&str>,
+        scopes: Option<&ScopeSet>,
+        expires_at: Option<chrono::DateTime<chrono::Utc>>,
+        allowed_ips: Option<&[IpRange]>,
+    ) -> Result<String> {
         let id = uuid::Uuid::new_v4().to_string();
         let now = chrono::Utc::now().to_rfc3339();
-        
-        sqlx::query!(
+
+        sqlx::query(
             r#"
-            INSERT INTO api_keys (id, user_id, key_hash, name, created_at)
-            VALUES (?1, ?2, ?3, ?4, ?5)
+            INSERT INTO api_keys (id, user_id, key_hash, name, created_at, scopes, expires_at, allowed_ips)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
             "#,
-            id,
-            user_id,
-            key_hash,
-            name,
-            now
         )
+        .bind(&id)
+        .bind(user_id)
+        .bind(key_hash)
+        .bind(name)
+        .bind(&now)
+        .bind(scopes.map(serde_json::to_string).transpose()?)
+        .bind(expires_at.map(|dt| dt.to_rfc3339()))
+        .bind(allowed_ips.map(serde_json::to_string).transpose()?)
         .execute(&self.pool)
         .await?;
-        
+
         Ok(id)
     }
 
     pub async fn get_by_hash(&self, key_hash: &str) -> Result<Option<ApiKey>> {
-        let row = sqlx::query!(
-            r#"
-            SELECT id, user_id, key_hash, name, created_at, last_used_at
-            FROM api_keys
-            WHERE key_hash = ?1
-            "#,
-            key_hash
-        )
-        .fetch_optional(&self.pool)
-        .await?;
-
-        if let Some(row) = row {
-            Ok(Some(ApiKey {
-                id: row.id.expect("id should not be null"),
-                user_id: row.user_id,
-                key_hash: row.key_hash,
-                name: row.name,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
-                    .map_err(|e| anyhow::anyhow!("Failed to parse created_at: {}", e))?
-                    .with_timezone(&chrono::Utc),
-                last_used_at: row.last_used_at.as_ref().map(|s| {
-                    chrono::DateTime::parse_from_rfc3339(s)
-                        .map_err(|e| anyhow::anyhow!("Failed to parse last_used_at: {}", e))
-                        .map(|dt| dt.with_timezone(&chrono::Utc))
-                }).transpose()?,
-            }))
-        } else {
-            Ok(None)
-        }
+        let row = sqlx::query(&format!("SELECT {} FROM api_keys WHERE key_hash = ?1", API_KEY_COLUMNS))
+            .bind(key_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(api_key_from_row).transpose()
     }
 
     pub async fn update_last_used(&self, key_hash: &str) -> Result<()> {
         let now = chrono::Utc::now().to_rfc3339();
-        sqlx::query!(
+        sqlx::query(
             r#"
             UPDATE api_keys
             SET last_used_at = ?1
             WHERE key_hash = ?2
             "#,
-            now,
-            key_hash
         )
+        .bind(&now)
+        .bind(key_hash)
         .execute(&self.pool)
         .await?;
-        
+
         Ok(())
     }
 
     pub async fn list_by_user(&self, user_id: &str) -> Result<Vec<ApiKey>> {
-        let rows = sqlx::query!(
-            r#"
-            SELECT id, user_id, key_hash, name, created_at, last_used_at
-            FROM api_keys
-            WHERE user_id = ?1
-            ORDER BY created_at DESC
-            "#,
-            user_id
-        )
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM api_keys WHERE user_id = ?1 ORDER BY created_at DESC",
+            API_KEY_COLUMNS
+        ))
+        .bind(user_id)
         .fetch_all(&self.pool)
         .await?;
-
-        Ok(rows.into_iter().map(|row| {
-            ApiKey {
-                id: row.id.expect("id should not be null"),
-                user_id: row.user_id,
-                key_hash: row.key_hash,
-                name: row.name,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-                last_used_at: row.last_used_at.as_ref().map(|s| {
-                    chrono::DateTime::parse_from_rfc3339(s)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc)
-                }),
-            }
-        }).collect())
+        rows.iter().map(api_key_from_row).collect()
     }
 
     pub async fn get(&self, id: &str) -> Result<Option<ApiKey>> {
-        let row = sqlx::query!(
-            r#"
-            SELECT id, user_id, key_hash, name, created_at, last_used_at
-            FROM api_keys
-            WHERE id = ?1
-            "#,
-            id
-        )
-        .fetch_optional(&self.pool)
-        .await?;
+        let row = sqlx::query(&format!("SELECT {} FROM api_keys WHERE id = ?1", API_KEY_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(api_key_from_row).transpose()
+    }
 
-        if let Some(row) = row {
-            Ok(Some(ApiKey {
-                id: row.id.expect("id should not be null"),
-                user_id: row.user_id,
-                key_hash: row.key_hash,
-                name: row.name,
-                created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
-                    .map_err(|e| anyhow::anyhow!("Failed to parse created_at: {}", e))?
-                    .with_timezone(&chrono::Utc),
-                last_used_at: row.last_used_at.as_ref().map(|s| {
-                    chrono::DateTime::parse_from_rfc3339(s.as_str())
-                        .map_err(|e| anyhow::anyhow!("Failed to parse last_used_at: {}", e))
-                        .map(|dt| dt.with_timezone(&chrono::Utc))
-                }).transpose()?,
-            }))
-        } else {
-            Ok(None)
-        }
+    /// Marks a key as revoked; returns false if it was already revoked
+    pub async fn revoke(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE api_keys SET revoked_at = ?2 WHERE id = ?1 AND revoked_at IS NULL")
+            .bind(id)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
     }
 
     pub async fn delete(&self, id: &str) -> Result<()> {
-        sqlx::query!("DELETE FROM api_keys WHERE id = ?1", id)
+        sqlx::query("DELETE FROM api_keys WHERE id = ?1")
+            .bind(id)
             .execute(&self.pool)
             .await?;
         Ok(())
     }
 }
 
+fn parse_rfc3339(value: &str, field: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", field, e))
+}
+
+fn parse_optional_rfc3339(value: Option<String>, field: &str) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+    value.map(|value| parse_rfc3339(&value, field)).transpose()
+}
+
+fn api_key_from_row(row: &SqliteRow) -> Result<ApiKey> {
+    let created_at: String = row.try_get("created_at")?;
+    let scopes: Option<String> = row.try_get("scopes")?;
+    let allowed_ips: Option<String> = row.try_get("allowed_ips")?;
+
+    Ok(ApiKey {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        key_hash: row.try_get("key_hash")?,
+        name: row.try_get("name")?,
+        created_at: parse_rfc3339(&created_at, "created_at")?,
+        last_used_at: parse_optional_rfc3339(row.try_get("last_used_at")?, "last_used_at")?,
+        scopes: scopes.map(|s| serde_json::from_str(&s)).transpose()?,
+        expires_at: parse_optional_rfc3339(row.try_get("expires_at")?, "expires_at")?,
+        allowed_ips: allowed_ips.map(|s| serde_json::from_str(&s)).transpose()?,
+        revoked_at: parse_optional_rfc3339(row.try_get("revoked_at")?, "revoked_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flowmason_auth::{ApiKeyScope, ScopeResource};
+
+    async fn test_repo() -> ApiKeyRepository {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        sqlx::query("INSERT INTO users (id, email, password_hash, created_at, updated_at) VALUES ('user-1', 'ci@example.com', 'x', ?1, ?1)")
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&pool)
+            .await
+            .unwrap();
+        ApiKeyRepository::new(pool)
+    }
+
+    #[tokio::test]
+    async fn test_scoped_keys_expire_and_revoke() {
+        let repo = test_repo().await;
+        let scopes = ScopeSet::new(vec![
+            ApiKeyScope::Read(ScopeResource::Flows),
+            ApiKeyScope::TriggerFlow("flow-1".to_string()),
+        ]);
+        let expires_at = chrono::Utc::now() + chrono::Duration::days(30);
+        let allowed_ips: Vec<IpRange> = vec!["10.0.0.0/8".parse().unwrap()];
+        let id = repo
+            .create("user-1", "hash-1", Some("CI"), Some(&scopes), Some(expires_at), Some(&allowed_ips))
+            .await
+            .unwrap();
+
+        let key = repo.get_by_hash("hash-1").await.unwrap().unwrap();
+        assert_eq!(key.scopes.as_ref(), Some(&scopes));
+        assert_eq!(key.allowed_ips.as_deref(), Some(allowed_ips.as_slice()));
+        assert!(key.is_active(chrono::Utc::now()));
+        assert!(!key.is_active(expires_at + chrono::Duration::seconds(1)));
+        assert!(key.allows_ip(Some("10.2.3.4".parse().unwrap())));
+        assert!(!key.allows_ip(Some("192.168.0.1".parse().unwrap())));
+        assert!(!key.allows_ip(None));
+
+        // Keys without restrictions keep full access
+        repo.create("user-1", "hash-2", None, None, None, None).await.unwrap();
+        let unrestricted = repo.get_by_hash("hash-2").await.unwrap().unwrap();
+        assert!(unrestricted.scopes.is_none());
+        assert!(unrestricted.is_active(chrono::Utc::now()));
+        assert!(unrestricted.allows_ip(None));
+
+        assert!(repo.revoke(&id).await.unwrap());
+        assert!(!repo.revoke(&id).await.unwrap());
+        assert!(!repo.get(&id).await.unwrap().unwrap().is_active(chrono::Utc::now()));
+        assert_eq!(repo.list_by_user("user-1").await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,328 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use flowmason_auth::{EncryptedSecret, SecretCipher, SecretError};
+use flowmason_core::{ConnectionResolver, ResolvedConnection};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const CONNECTION_COLUMNS: &str = "id, name, connection_type, base_url, defaults, created_at, updated_at";
+
+/// A saved connection; credentials are never returned
+#[derive(Debug, Clone, Serialize)]
+pub struct Connection {
+    pub id: String,
+    pub name: String,
+    /// Type of the bricks that can use the connection, e.g. `hubspot`
+    pub connection_type: String,
+    pub base_url: Option<String>,
+    pub defaults: Map<String, Value>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Changes to a connection; `None` leaves a field unchanged
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionUpdate {
+    pub name: Option<String>,
+    pub base_url: Option<Option<String>>,
+    /// Replaces all credentials
+    pub credentials: Option<Map<String, Value>>,
+    pub defaults: Option<Map<String, Value>>,
+}
+
+/// Store of saved connections backed by the `connections` table
+///
+/// Connections belong to a workspace. Credentials are encrypted with the secrets master key.
+#[derive(Clone)]
+pub struct ConnectionRepository {
+    pool: SqlitePool,
+    cipher: Option<Arc<SecretCipher>>,
+}
+
+impl ConnectionRepository {
+    pub fn new(pool: SqlitePool, cipher: Option<Arc<SecretCipher>>) -> Self {
+        Self { pool, cipher }
+    }
+
+    /// Whether a master key is configured
+    pub fn is_enabled(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    fn cipher(&self) -> Result<&SecretCipher> {
+        self.cipher
+            .as_deref()
+            .ok_or_else(|| SecretError::MissingMasterKey.into())
+    }
+
+    fn encrypt_credentials(&self, id: &str, credentials: &Map<String, Value>) -> Result<EncryptedSecret> {
+        let plaintext = serde_json::to_string(credentials)?;
+        Ok(self.cipher()?.encrypt(&credentials_aad(id), &plaintext)?)
+    }
+
+    pub async fn create(
+        &self,
+        workspace_id: &str,
+        name: &str,
+        connection_type: &str,
+        base_url: Option<&str>,
+        credentials: &Map<String, Value>,
+        defaults: &Map<String, Value>,
+    ) -> Result<Connection> {
+        let id = Uuid::new_v4().to_string();
+        let encrypted = self.encrypt_credentials(&id, credentials)?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            r#"
+            INSERT INTO connections (id, name, connection_type, base_url, defaults, nonce, ciphertext, created_at, updated_at, workspace_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?9)
+            "#,
+        )
+        .bind(&id)
+        .bind(name)
+        .bind(connection_type)
+        .bind(base_url)
+        .bind(serde_json::to_string(defaults)?)
+        .bind(&encrypted.nonce)
+        .bind(&encrypted.ciphertext)
+        .bind(&now)
+        .bind(workspace_id)
+        .execute(&self.pool)
+        .await?;
+
+        self.get(workspace_id, &id).await?.ok_or_else(|| anyhow::anyhow!("Connection {} not found after insert", id))
+    }
+
+    /// Lists the connections of a workspace, optionally of one type only
+    pub async fn list(&self, workspace_id: &str, connection_type: Option<&str>) -> Result<Vec<Connection>> {
+        let rows = match connection_type {
+            Some(connection_type) => {
+                sqlx::query(&format!(
+                    "SELECT {} FROM connections WHERE workspace_id = ?1 AND connection_type = ?2 ORDER BY name",
+                    CONNECTION_COLUMNS
+                ))
+                .bind(workspace_id)
+                .bind(connection_type)
+                .fetch_all(&self.pool)
+                .await?
+            }
+            None => {
+                sqlx::query(&format!(
+                    "SELECT {} FROM connections WHERE workspace_id = ?1 ORDER BY connection_type, name",
+                    CONNECTION_COLUMNS
+                ))
+                .bind(workspace_id)
+                .fetch_all(&self.pool)
+                .await?
+            }
+        };
+        rows.iter().map(connection_from_row).collect()
+    }
+
+    pub async fn get(&self, workspace_id: &str, id: &str) -> Result<Option<Connection>> {
+        let row = sqlx::query(&format!("SELECT {} FROM connections WHERE workspace_id = ?1 AND id = ?2", CONNECTION_COLUMNS))
+            .bind(workspace_id)
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(connection_from_row).transpose()
+    }
+
+    /// Applies an update; returns `None` if the connection does not exist
+    pub async fn update(&self, workspace_id: &str, id: &str, update: ConnectionUpdate) -> Result<Option<Connection>> {
+        let Some(mut connection) = self.get(workspace_id, id).await? else {
+            return Ok(None);
+        };
+        if let Some(name) = update.name {
+            connection.name = name;
+        }
+        if let Some(base_url) = update.base_url {
+            connection.base_url = base_url;
+        }
+        if let Some(defaults) = update.defaults {
+            connection.defaults = defaults;
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            r#"
+            UPDATE connections
+            SET name = ?2, base_url = ?3, defaults = ?4, updated_at = ?5
+            WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .bind(&connection.name)
+        .bind(&connection.base_url)
+        .bind(serde_json::to_string(&connection.defaults)?)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        if let Some(credentials) = update.credentials {
+            let encrypted = self.encrypt_credentials(id, &credentials)?;
+            sqlx::query("UPDATE connections SET nonce = ?2, ciphertext = ?3 WHERE id = ?1")
+                .bind(id)
+                .bind(&encrypted.nonce)
+                .bind(&encrypted.ciphertext)
+                .execute(&self.pool)
+                .await?;
+        }
+
+        self.get(workspace_id, id).await
+    }
+
+    pub async fn delete(&self, workspace_id: &str, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM connections WHERE workspace_id = ?1 AND id = ?2")
+            .bind(workspace_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Loads a connection with its decrypted credentials
+    pub async fn resolve(&self, workspace_id: &str, id: &str) -> Result<Option<ResolvedConnection>> {
+        let row = sqlx::query(&format!(
+            "SELECT {}, nonce, ciphertext FROM connections WHERE workspace_id = ?1 AND id = ?2",
+            CONNECTION_COLUMNS
+        ))
+        .bind(workspace_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let connection = connection_from_row(&row)?;
+        let encrypted = EncryptedSecret {
+            nonce: row.try_get("nonce")?,
+            ciphertext: row.try_get("ciphertext")?,
+        };
+        let credentials = self.cipher()?.decrypt(&credentials_aad(id), &encrypted)?;
+
+        Ok(Some(ResolvedConnection {
+            id: connection.id,
+            connection_type: connection.connection_type,
+            base_url: connection.base_url,
+            credentials: serde_json::from_str(&credentials)?,
+            defaults: connection.defaults,
+        }))
+    }
+}
+
+#[async_trait]
+impl ConnectionResolver for ConnectionRepository {
+    async fn resolve_connection(
+        &self,
+        workspace_id: Option<&str>,
+        id: &str,
+    ) -> Result<Option<ResolvedConnection>, Box<dyn std::error::Error + Send + Sync>> {
+        // Flows outside any workspace have no connections
+        let Some(workspace_id) = workspace_id else {
+            return Ok(None);
+        };
+        self.resolve(workspace_id, id).await.map_err(|e| e.into())
+    }
+}
+
+/// Associated data binding encrypted credentials to their connection
+fn credentials_aad(id: &str) -> String {
+    format!("connection:{}", id)
+}
+
+fn parse_rfc3339(value: &str, field: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", field, e))
+}
+
+fn connection_from_row(row: &SqliteRow) -> Result<Connection> {
+    let defaults: String = row.try_get("defaults")?;
+    let created_at: String = row.try_get("created_at")?;
+    let updated_at: String = row.try_get("updated_at")?;
+
+    Ok(Connection {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        connection_type: row.try_get("connection_type")?,
+        base_url: row.try_get("base_url")?,
+        defaults: serde_json::from_str(&defaults)?,
+        created_at: parse_rfc3339(&created_at, "created_at")?,
+        updated_at: parse_rfc3339(&updated_at, "updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn test_repo() -> ConnectionRepository {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        let cipher = SecretCipher::new(&[5u8; flowmason_auth::secrets::MASTER_KEY_LEN]).unwrap();
+        ConnectionRepository::new(pool, Some(Arc::new(cipher)))
+    }
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[tokio::test]
+    async fn test_connection_crud_and_resolution() {
+        let repo = test_repo().await;
+        let connection = repo
+            .create(
+                "ws-1",
+                "ERP",
+                "odoo",
+                Some("https://erp.example.com"),
+                &object(json!({"username": "bot", "password": "hunter2"})),
+                &object(json!({"database": "prod"})),
+            )
+            .await
+            .unwrap();
+        assert_eq!(connection.defaults, object(json!({"database": "prod"})));
+
+        let ciphertext: String = sqlx::query_scalar("SELECT ciphertext FROM connections")
+            .fetch_one(&repo.pool)
+            .await
+            .unwrap();
+        assert!(!ciphertext.contains("hunter2"));
+
+        let resolved = repo.resolve_connection(Some("ws-1"), &connection.id).await.unwrap().unwrap();
+        assert_eq!(resolved.credentials["password"], "hunter2");
+        assert_eq!(resolved.base_url.as_deref(), Some("https://erp.example.com"));
+
+        // Credentials are kept unless replaced
+        let update = ConnectionUpdate { name: Some("ERP prod".to_string()), ..Default::default() };
+        assert_eq!(repo.update("ws-1", &connection.id, update).await.unwrap().unwrap().name, "ERP prod");
+        assert_eq!(repo.resolve("ws-1", &connection.id).await.unwrap().unwrap().credentials["password"], "hunter2");
+
+        let update = ConnectionUpdate {
+            credentials: Some(object(json!({"username": "bot", "password": "rotated"}))),
+            ..Default::default()
+        };
+        repo.update("ws-1", &connection.id, update).await.unwrap().unwrap();
+        assert_eq!(repo.resolve("ws-1", &connection.id).await.unwrap().unwrap().credentials["password"], "rotated");
+
+        assert_eq!(repo.list("ws-1", Some("odoo")).await.unwrap().len(), 1);
+        assert!(repo.list("ws-1", Some("hubspot")).await.unwrap().is_empty());
+        assert!(repo.update("ws-1", "missing", ConnectionUpdate::default()).await.unwrap().is_none());
+        assert!(repo.resolve("ws-1", "missing").await.unwrap().is_none());
+
+        // Other workspaces cannot see or change the connection
+        assert!(repo.list("ws-2", None).await.unwrap().is_empty());
+        assert!(repo.resolve_connection(Some("ws-2"), &connection.id).await.unwrap().is_none());
+        assert!(!repo.delete("ws-2", &connection.id).await.unwrap());
+
+        assert!(repo.delete("ws-1", &connection.id).await.unwrap());
+        assert!(repo.get("ws-1", &connection.id).await.unwrap().is_none());
+    }
+}
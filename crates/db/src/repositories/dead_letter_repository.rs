@@ -0,0 +1,501 @@
+use anyhow::Result;
+use flowmason_core::types::{FlowExecution, RetryPolicy};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, Row, SqliteConnection, SqlitePool};
+use std::time::Duration;
+use uuid::Uuid;
+
+const DEAD_LETTER_COLUMNS: &str = "id, execution_id, flow_id, workspace_id, error_message, retry_count, max_retries, \
+    status, next_retry_at, last_attempt_at, last_replay_execution_id, created_at";
+
+/// How long a claimed entry stays hidden from other retriers before it is considered abandoned
+const REPLAY_LEASE: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeadLetterStatus {
+    /// Waiting for an automatic retry at `next_retry_at`
+    Pending,
+    /// A replay is in progress
+    Retrying,
+    /// A replay succeeded
+    Resolved,
+    /// Automatic retries are used up; the entry can still be replayed manually
+    Exhausted,
+    /// Dismissed by a user
+    Discarded,
+}
+
+impl DeadLetterStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeadLetterStatus::Pending => "pending",
+            DeadLetterStatus::Retrying => "retrying",
+            DeadLetterStatus::Resolved => "resolved",
+            DeadLetterStatus::Exhausted => "exhausted",
+            DeadLetterStatus::Discarded => "discarded",
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "pending" => Ok(DeadLetterStatus::Pending),
+            "retrying" => Ok(DeadLetterStatus::Retrying),
+            "resolved" => Ok(DeadLetterStatus::Resolved),
+            "exhausted" => Ok(DeadLetterStatus::Exhausted),
+            "discarded" => Ok(DeadLetterStatus::Discarded),
+            other => Err(anyhow::anyhow!("Unknown dead-letter status: {}", other)),
+        }
+    }
+}
+
+/// A failed execution in the dead-letter queue
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    pub id: String,
+    /// The failed execution that created the entry
+    pub execution_id: String,
+    pub flow_id: String,
+    /// Workspace of the flow
+    pub workspace_id: Option<String>,
+    /// Error of the most recent failed attempt
+    pub error_message: String,
+    pub retry_count: i64,
+    pub max_retries: i64,
+    pub status: DeadLetterStatus,
+    pub next_retry_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_attempt_at: chrono::DateTime<chrono::Utc>,
+    /// Execution started by the most recent replay
+    pub last_replay_execution_id: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Dead-letter queue backed by the `failed_executions` table
+///
+/// Entries are created by `ExecutionRepository::create` for failed top-level
+/// executions. Replays are stored as new executions linked through
+/// `replay_of_execution_id` and update the original entry.
+#[derive(Clone)]
+pub struct DeadLetterRepository {
+    pool: SqlitePool,
+}
+
+impl DeadLetterRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Adds a dead-letter entry for a failed execution using its flow's retry policy
+    pub(crate) async fn insert_for_execution(conn: &mut SqliteConnection, execution: &FlowExecution) -> Result<()> {
+        let flow_row = sqlx::query("SELECT retry_policy, workspace_id FROM flows WHERE id = ?1")
+            .bind(&execution.flow_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        let Some(flow_row) = flow_row else {
+            tracing::warn!(
+                execution_id = %execution.execution_id,
+                flow_id = %execution.flow_id,
+                "Flow no longer exists, failed execution not added to dead-letter queue"
+            );
+            return Ok(());
+        };
+
+        let policy: RetryPolicy = flow_row
+            .try_get::<Option<String>, _>("retry_policy")?
+            .as_deref()
+            .map(serde_json::from_str)
+            .transpose()?
+            .unwrap_or_default();
+        let workspace_id: Option<String> = flow_row.try_get("workspace_id")?;
+
+        let now = chrono::Utc::now();
+        let (status, next_retry_at) = if policy.max_retries > 0 {
+            (DeadLetterStatus::Pending, Some(now.timestamp_millis() + policy.delay(0).as_millis() as i64))
+        } else {
+            (DeadLetterStatus::Exhausted, None)
+        };
+
+        sqlx::query(
+            r#"
+            INSERT INTO failed_executions (id, execution_id, flow_id, error_message, retry_count, max_retries,
+                status, next_retry_at, last_attempt_at, created_at, workspace_id)
+            VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6, ?7, ?8, ?8, ?9)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&execution.execution_id)
+        .bind(&execution.flow_id)
+        .bind(execution.error.as_deref().unwrap_or("Unknown error"))
+        .bind(policy.max_retries as i64)
+        .bind(status.as_str())
+        .bind(next_retry_at)
+        .bind(now.to_rfc3339())
+        .bind(workspace_id)
+        .execute(&mut *conn)
+        .await?;
+
+        tracing::info!(
+            execution_id = %execution.execution_id,
+            flow_id = %execution.flow_id,
+            "Failed execution added to dead-letter queue"
+        );
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<DeadLetter>> {
+        let row = sqlx::query(&format!("SELECT {} FROM failed_executions WHERE id = ?1", DEAD_LETTER_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.as_ref().map(dead_letter_from_row).transpose()
+    }
+
+    /// Gets an entry if it belongs to the workspace
+    pub async fn get_in_workspace(&self, workspace_id: &str, id: &str) -> Result<Option<DeadLetter>> {
+        Ok(self.get(id).await?.filter(|entry| entry.workspace_id.as_deref() == Some(workspace_id)))
+    }
+
+    pub async fn list(
+        &self,
+        workspace_id: &str,
+        status: Option<DeadLetterStatus>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Vec<DeadLetter>> {
+        let limit_val = limit.unwrap_or(100).min(1000) as i64; // Max 1000 items
+        let offset_val = offset.unwrap_or(0) as i64;
+
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT {}
+            FROM failed_executions
+            WHERE workspace_id = ?4 AND (?1 IS NULL OR status = ?1)
+            ORDER BY created_at DESC
+            LIMIT ?2 OFFSET ?3
+            "#,
+            DEAD_LETTER_COLUMNS
+        ))
+        .bind(status.map(|s| s.as_str()))
+        .bind(limit_val)
+        .bind(offset_val)
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(dead_letter_from_row).collect()
+    }
+
+    /// Claims entries whose retry is due, including replays abandoned by a crashed process
+    pub async fn claim_due(&self, limit: u32) -> Result<Vec<DeadLetter>> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let lease_expires_at = now_ms + REPLAY_LEASE.as_millis() as i64;
+
+        let rows = sqlx::query(&format!(
+            r#"
+            UPDATE failed_executions
+            SET status = 'retrying', next_retry_at = ?1
+            WHERE id IN (
+                SELECT id FROM failed_executions
+                WHERE status IN ('pending', 'retrying') AND next_retry_at <= ?2
+                ORDER BY next_retry_at ASC
+                LIMIT ?3
+            )
+            RETURNING {}
+            "#,
+            DEAD_LETTER_COLUMNS
+        ))
+        .bind(lease_expires_at)
+        .bind(now_ms)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(dead_letter_from_row).collect()
+    }
+
+    /// Claims a single entry for a manual replay. Returns `None` if it is resolved,
+    /// discarded or already being replayed.
+    pub async fn claim(&self, id: &str) -> Result<Option<DeadLetter>> {
+        let lease_expires_at = chrono::Utc::now().timestamp_millis() + REPLAY_LEASE.as_millis() as i64;
+
+        let row = sqlx::query(&format!(
+            r#"
+            UPDATE failed_executions
+            SET status = 'retrying', next_retry_at = ?1
+            WHERE id = ?2 AND status IN ('pending', 'exhausted')
+            RETURNING {}
+            "#,
+            DEAD_LETTER_COLUMNS
+        ))
+        .bind(lease_expires_at)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.as_ref().map(dead_letter_from_row).transpose()
+    }
+
+    /// Records the outcome of a replay of a claimed entry
+    ///
+    /// A failed replay schedules the next retry using `policy`, or marks the
+    /// entry exhausted once `max_retries` replays have failed.
+    pub async fn finish_replay(
+        &self,
+        entry: &DeadLetter,
+        replay_execution_id: Option<&str>,
+        error: Option<&str>,
+        policy: &RetryPolicy,
+    ) -> Result<DeadLetterStatus> {
+        let now = chrono::Utc::now();
+        let retry_count = entry.retry_count + 1;
+
+        let (status, next_retry_at) = match error {
+            None => (DeadLetterStatus::Resolved, None),
+            Some(_) if retry_count < entry.max_retries => {
+                let delay = policy.delay(retry_count as u32);
+                (DeadLetterStatus::Pending, Some(now.timestamp_millis() + delay.as_millis() as i64))
+            }
+            Some(_) => (DeadLetterStatus::Exhausted, None),
+        };
+
+        sqlx::query(
+            r#"
+            UPDATE failed_executions
+            SET status = ?1, next_retry_at = ?2, retry_count = ?3, last_attempt_at = ?4,
+                error_message = COALESCE(?5, error_message),
+                last_replay_execution_id = COALESCE(?6, last_replay_execution_id)
+            WHERE id = ?7 AND status = 'retrying'
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(next_retry_at)
+        .bind(retry_count)
+        .bind(now.to_rfc3339())
+        .bind(error)
+        .bind(replay_execution_id)
+        .bind(&entry.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(status)
+    }
+
+    /// Ends the replay of an entry that cannot succeed however often it is
+    /// retried; the entry is exhausted with `error` and not retried again
+    pub async fn abandon_replay(&self, entry: &DeadLetter, error: &str) -> Result<DeadLetterStatus> {
+        let status = DeadLetterStatus::Exhausted;
+        sqlx::query(
+            r#"
+            UPDATE failed_executions
+            SET status = ?1, next_retry_at = NULL, last_attempt_at = ?2, error_message = ?3
+            WHERE id = ?4 AND status = 'retrying'
+            "#,
+        )
+        .bind(status.as_str())
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(error)
+        .bind(&entry.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(status)
+    }
+
+    /// Resolves the entry of a failed execution that was recovered outside the
+    /// queue (e.g. resumed), so it is not replayed again. Entries being
+    /// replayed are left alone.
+    pub async fn resolve_for_execution(&self, execution_id: &str, resolved_by_execution_id: &str) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE failed_executions
+            SET status = 'resolved', next_retry_at = NULL, last_replay_execution_id = ?1
+            WHERE execution_id = ?2 AND status IN ('pending', 'exhausted')
+            "#,
+        )
+        .bind(resolved_by_execution_id)
+        .bind(execution_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Discards an entry so it is never retried. Returns false if it does not
+    /// exist or a replay is in progress.
+    pub async fn discard(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE failed_executions SET status = 'discarded', next_retry_at = NULL WHERE id = ?1 AND status != 'retrying'",
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+fn parse_rfc3339(value: &str, field: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", field, e))
+}
+
+fn dead_letter_from_row(row: &SqliteRow) -> Result<DeadLetter> {
+    let status: String = row.try_get("status")?;
+    let last_attempt_at: String = row.try_get("last_attempt_at")?;
+    let created_at: String = row.try_get("created_at")?;
+
+    Ok(DeadLetter {
+        id: row.try_get("id")?,
+        execution_id: row.try_get("execution_id")?,
+        flow_id: row.try_get("flow_id")?,
+        workspace_id: row.try_get("workspace_id")?,
+        error_message: row.try_get("error_message")?,
+        retry_count: row.try_get("retry_count")?,
+        max_retries: row.try_get("max_retries")?,
+        status: DeadLetterStatus::parse(&status)?,
+        next_retry_at: row.try_get::<Option<i64>, _>("next_retry_at")?
+            .and_then(chrono::DateTime::from_timestamp_millis),
+        last_attempt_at: parse_rfc3339(&last_attempt_at, "last_attempt_at")?,
+        last_replay_execution_id: row.try_get("last_replay_execution_id")?,
+        created_at: parse_rfc3339(&created_at, "created_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::{ExecutionRepository, FlowRepository};
+    use flowmason_core::types::{ExecutionStatus, Flow};
+    use serde_json::json;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        pool
+    }
+
+    async fn create_flow(pool: &SqlitePool, retry_policy: Option<RetryPolicy>) {
+        let flow = Flow {
+            id: "flow-1".to_string(),
+            name: "Flow".to_string(),
+            description: None,
+            bricks: vec![],
+            graph: None,
+            retry_policy,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: Some("ws-1".to_string()),
+            active: true,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+        };
+        FlowRepository::new(pool.clone()).create(&flow).await.unwrap();
+    }
+
+    fn failed_execution(execution_id: &str) -> FlowExecution {
+        FlowExecution {
+            flow_id: "flow-1".to_string(),
+            execution_id: execution_id.to_string(),
+            status: ExecutionStatus::Failed,
+            started_at: chrono::Utc::now(),
+            completed_at: Some(chrono::Utc::now()),
+            input_payload: json!({}),
+            output_payload: None,
+            error: Some("boom".to_string()),
+            parent_execution_id: None,
+            replay_of_execution_id: None,
+            steps: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_execution_is_dead_lettered() {
+        let pool = create_test_pool().await;
+        create_flow(&pool, Some(RetryPolicy { max_retries: 2, ..RetryPolicy::default() })).await;
+        let execution_repo = ExecutionRepository::new(pool.clone());
+        let repo = DeadLetterRepository::new(pool);
+
+        execution_repo.create(&failed_execution("exec-1")).await.unwrap();
+
+        // Replays of a dead-lettered execution do not create new entries
+        let mut replay = failed_execution("exec-2");
+        replay.replay_of_execution_id = Some("exec-1".to_string());
+        execution_repo.create(&replay).await.unwrap();
+
+        let entries = repo.list("ws-1", None, None, None).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.execution_id, "exec-1");
+        assert_eq!(entry.status, DeadLetterStatus::Pending);
+        assert_eq!(entry.max_retries, 2);
+        assert_eq!(entry.error_message, "boom");
+        assert!(entry.next_retry_at.is_some());
+        // Entries belong to the workspace of their flow
+        assert_eq!(entry.workspace_id.as_deref(), Some("ws-1"));
+        assert!(repo.list("ws-2", None, None, None).await.unwrap().is_empty());
+        assert!(repo.get_in_workspace("ws-2", &entry.id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_outcomes_update_entry() {
+        let pool = create_test_pool().await;
+        create_flow(&pool, Some(RetryPolicy { max_retries: 2, ..RetryPolicy::default() })).await;
+        ExecutionRepository::new(pool.clone()).create(&failed_execution("exec-1")).await.unwrap();
+        let repo = DeadLetterRepository::new(pool);
+        let policy = RetryPolicy::default();
+
+        let id = repo.list("ws-1", None, None, None).await.unwrap()[0].id.clone();
+        let entry = repo.claim(&id).await.unwrap().unwrap();
+        assert_eq!(entry.status, DeadLetterStatus::Retrying);
+        // Entries being replayed cannot be claimed or discarded
+        assert!(repo.claim(&id).await.unwrap().is_none());
+        assert!(!repo.discard(&id).await.unwrap());
+
+        let status = repo.finish_replay(&entry, Some("exec-2"), Some("still failing"), &policy).await.unwrap();
+        assert_eq!(status, DeadLetterStatus::Pending);
+
+        let entry = repo.claim(&id).await.unwrap().unwrap();
+        assert_eq!(entry.retry_count, 1);
+        assert_eq!(entry.last_replay_execution_id.as_deref(), Some("exec-2"));
+        let status = repo.finish_replay(&entry, Some("exec-3"), Some("still failing"), &policy).await.unwrap();
+        assert_eq!(status, DeadLetterStatus::Exhausted);
+
+        // Exhausted entries can still be replayed manually
+        let entry = repo.claim(&id).await.unwrap().unwrap();
+        let status = repo.finish_replay(&entry, Some("exec-4"), None, &policy).await.unwrap();
+        assert_eq!(status, DeadLetterStatus::Resolved);
+        assert!(repo.claim(&id).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_replays_are_not_retried() {
+        let pool = create_test_pool().await;
+        create_flow(&pool, Some(RetryPolicy { max_retries: 2, initial_delay_secs: 0, ..RetryPolicy::default() })).await;
+        ExecutionRepository::new(pool.clone()).create(&failed_execution("exec-1")).await.unwrap();
+        let repo = DeadLetterRepository::new(pool);
+
+        let entry = repo.claim_due(10).await.unwrap().remove(0);
+        let status = repo.abandon_replay(&entry, "cannot be replayed").await.unwrap();
+        assert_eq!(status, DeadLetterStatus::Exhausted);
+
+        let entry = repo.get(&entry.id).await.unwrap().unwrap();
+        assert_eq!(entry.status, DeadLetterStatus::Exhausted);
+        assert_eq!(entry.error_message, "cannot be replayed");
+        assert!(entry.next_retry_at.is_none());
+        assert!(repo.claim_due(10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_due_entries_are_claimed_once() {
+        let pool = create_test_pool().await;
+        create_flow(&pool, Some(RetryPolicy { initial_delay_secs: 0, ..RetryPolicy::default() })).await;
+        ExecutionRepository::new(pool.clone()).create(&failed_execution("exec-1")).await.unwrap();
+        let repo = DeadLetterRepository::new(pool);
+
+        assert_eq!(repo.claim_due(10).await.unwrap().len(), 1);
+        assert!(repo.claim_due(10).await.unwrap().is_empty());
+    }
+}
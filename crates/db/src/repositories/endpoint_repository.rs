@@ -0,0 +1,168 @@
+use anyhow::Result;
+use serde::Serialize;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+
+const ENDPOINT_COLUMNS: &str = "flow_id, method, path, request_schema, created_at, updated_at";
+
+/// HTTP method and path a flow is published under
+#[derive(Debug, Clone, Serialize)]
+pub struct FlowEndpoint {
+    pub flow_id: String,
+    /// Upper-case HTTP method
+    pub method: String,
+    /// Path below `/hooks`, starting with `/`
+    pub path: String,
+    /// JSON Schema the request payload must match
+    pub request_schema: Option<serde_json::Value>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Custom flow endpoints backed by the `flow_endpoints` table
+///
+/// A flow has at most one endpoint, and a method and path lead to at most
+/// one flow.
+#[derive(Clone)]
+pub struct EndpointRepository {
+    pool: SqlitePool,
+}
+
+impl EndpointRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn get(&self, flow_id: &str) -> Result<Option<FlowEndpoint>> {
+        let row = sqlx::query(&format!("SELECT {} FROM flow_endpoints WHERE flow_id = ?1", ENDPOINT_COLUMNS))
+            .bind(flow_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(endpoint_from_row).transpose()
+    }
+
+    /// Finds the endpoint serving a method and path
+    pub async fn find(&self, method: &str, path: &str) -> Result<Option<FlowEndpoint>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM flow_endpoints WHERE method = ?1 AND path = ?2",
+            ENDPOINT_COLUMNS
+        ))
+        .bind(method)
+        .bind(path)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(endpoint_from_row).transpose()
+    }
+
+    /// Publishes a flow under a method and path, replacing its previous endpoint
+    ///
+    /// Returns `None` if another flow already uses the method and path.
+    pub async fn configure(
+        &self,
+        flow_id: &str,
+        method: &str,
+        path: &str,
+        request_schema: Option<&serde_json::Value>,
+    ) -> Result<Option<FlowEndpoint>> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let request_schema = request_schema.map(serde_json::to_string).transpose()?;
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO flow_endpoints (flow_id, method, path, request_schema, created_at, updated_at)
+            SELECT ?1, ?2, ?3, ?4, ?5, ?5
+            WHERE NOT EXISTS (
+                SELECT 1 FROM flow_endpoints WHERE method = ?2 AND path = ?3 AND flow_id != ?1
+            )
+            ON CONFLICT(flow_id) DO UPDATE SET
+                method = excluded.method,
+                path = excluded.path,
+                request_schema = excluded.request_schema,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(flow_id)
+        .bind(method)
+        .bind(path)
+        .bind(request_schema)
+        .bind(&now)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get(flow_id).await
+    }
+
+    pub async fn delete(&self, flow_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM flow_endpoints WHERE flow_id = ?1")
+            .bind(flow_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+fn parse_rfc3339(value: &str, field: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", field, e))
+}
+
+fn endpoint_from_row(row: &SqliteRow) -> Result<FlowEndpoint> {
+    let request_schema: Option<String> = row.try_get("request_schema")?;
+    let created_at: String = row.try_get("created_at")?;
+    let updated_at: String = row.try_get("updated_at")?;
+
+    Ok(FlowEndpoint {
+        flow_id: row.try_get("flow_id")?,
+        method: row.try_get("method")?,
+        path: row.try_get("path")?,
+        request_schema: request_schema.map(|schema| serde_json::from_str(&schema)).transpose()?,
+        created_at: parse_rfc3339(&created_at, "created_at")?,
+        updated_at: parse_rfc3339(&updated_at, "updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_repo() -> EndpointRepository {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        for flow_id in ["flow-1", "flow-2"] {
+            sqlx::query("INSERT INTO flows (id, name, bricks, created_at, updated_at) VALUES (?1, 'Leads', '[]', ?2, ?2)")
+                .bind(flow_id)
+                .bind(chrono::Utc::now().to_rfc3339())
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        EndpointRepository::new(pool)
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_lifecycle() {
+        let repo = test_repo().await;
+        let schema = serde_json::json!({"type": "object", "required": ["email"]});
+        assert!(repo.configure("flow-1", "POST", "/acme/leads", Some(&schema)).await.unwrap().is_some());
+
+        let endpoint = repo.find("POST", "/acme/leads").await.unwrap().unwrap();
+        assert_eq!(endpoint.flow_id, "flow-1");
+        assert_eq!(endpoint.request_schema, Some(schema));
+        assert!(repo.find("GET", "/acme/leads").await.unwrap().is_none());
+
+        // Another flow cannot take the same method and path
+        assert!(repo.configure("flow-2", "POST", "/acme/leads", None).await.unwrap().is_none());
+        assert!(repo.get("flow-2").await.unwrap().is_none());
+
+        // Reconfiguring moves the flow's endpoint
+        repo.configure("flow-1", "PUT", "/acme/leads", None).await.unwrap();
+        assert!(repo.find("POST", "/acme/leads").await.unwrap().is_none());
+        assert!(repo.get("flow-1").await.unwrap().unwrap().request_schema.is_none());
+
+        assert!(repo.delete("flow-1").await.unwrap());
+        assert!(repo.get("flow-1").await.unwrap().is_none());
+    }
+}
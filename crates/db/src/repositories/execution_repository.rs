@@ -1,6 +1,7 @@
 use anyhow::Result;
 use sqlx::SqlitePool;
-use flowmason_core::types::FlowExecution;
+use flowmason_core::types::{ExecutionStatus, FlowExecution};
+use crate::repositories::{DeadLetterRepository, ExecutionStepRepository};
 use serde_json::Value;
 
 /// Parses JSON string with error logging on failure
@@ -36,10 +37,12 @@ impl ExecutionRepository {
         
         let output_payload_str = output_payload_json.as_deref();
         
+        let mut tx = self.pool.begin().await?;
+
         sqlx::query!(
             r#"
-            INSERT INTO executions (execution_id, flow_id, status, started_at, completed_at, input_payload, output_payload, error)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            INSERT INTO executions (execution_id, flow_id, status, started_at, completed_at, input_payload, output_payload, error, parent_execution_id, replay_of_execution_id, workspace_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, (SELECT workspace_id FROM flows WHERE id = ?2))
             "#,
             execution.execution_id,
             execution.flow_id,
@@ -48,10 +51,25 @@ impl ExecutionRepository {
             completed_at_str,
             input_payload_json,
             output_payload_str,
-            execution.error
+            execution.error,
+            execution.parent_execution_id,
+            execution.replay_of_execution_id
         )
-        .execute(&self.pool)
+        .execute(&mut *tx)
         .await?;
+
+        ExecutionStepRepository::insert_all(&mut tx, &execution.execution_id, &execution.steps).await?;
+
+        // Failed top-level executions go to the dead-letter queue. Sub-flow failures
+        // surface in their parent, and replays update the entry they replay.
+        if execution.status == ExecutionStatus::Failed
+            && execution.parent_execution_id.is_none()
+            && execution.replay_of_execution_id.is_none()
+        {
+            DeadLetterRepository::insert_for_execution(&mut tx, execution).await?;
+        }
+
+        tx.commit().await?;
         
         Ok(())
     }
@@ -59,7 +77,7 @@ impl ExecutionRepository {
     pub async fn get(&self, execution_id: &str) -> Result<Option<FlowExecution>> {
         let row = sqlx::query!(
             r#"
-            SELECT execution_id, flow_id, status, started_at, completed_at, input_payload, output_payload, error
+            SELECT execution_id, flow_id, status, started_at, completed_at, input_payload, output_payload, error, parent_execution_id, replay_of_execution_id
             FROM executions
             WHERE execution_id = ?1
             "#,
@@ -84,19 +102,38 @@ impl ExecutionRepository {
                 input_payload: serde_json::from_str(&row.input_payload)?,
                 output_payload: row.output_payload.as_ref().map(|s| parse_json_with_logging(s, "output_payload")),
                 error: row.error,
+                parent_execution_id: row.parent_execution_id,
+                replay_of_execution_id: row.replay_of_execution_id,
+                steps: Vec::new(),
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Gets an execution if it belongs to the workspace
+    pub async fn get_in_workspace(&self, workspace_id: &str, execution_id: &str) -> Result<Option<FlowExecution>> {
+        let in_workspace = sqlx::query!(
+            "SELECT execution_id FROM executions WHERE execution_id = ?1 AND workspace_id = ?2",
+            execution_id,
+            workspace_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match in_workspace {
+            Some(_) => self.get(execution_id).await,
+            None => Ok(None),
+        }
+    }
+
     pub async fn list_by_flow(&self, flow_id: &str, limit: Option<u32>, offset: Option<u32>) -> Result<Vec<FlowExecution>> {
         let limit_val = limit.unwrap_or(100).min(1000) as i64; // Max 1000 items
         let offset_val = offset.unwrap_or(0) as i64;
         
         let rows = sqlx::query!(
             r#"
-            SELECT execution_id, flow_id, status, started_at, completed_at, input_payload, output_payload, error
+            SELECT execution_id, flow_id, status, started_at, completed_at, input_payload, output_payload, error, parent_execution_id, replay_of_execution_id
             FROM executions
             WHERE flow_id = ?1
             ORDER BY started_at DESC
@@ -126,25 +163,31 @@ impl ExecutionRepository {
                 input_payload: serde_json::from_str(&row.input_payload)?,
                 output_payload: row.output_payload.as_ref().map(|s| parse_json_with_logging(s, "output_payload")),
                 error: row.error,
+                parent_execution_id: row.parent_execution_id,
+                replay_of_execution_id: row.replay_of_execution_id,
+                steps: Vec::new(),
             });
         }
 
         Ok(executions)
     }
 
-    pub async fn list_all(&self, limit: Option<u32>, offset: Option<u32>) -> Result<Vec<FlowExecution>> {
+    /// Lists the executions of all flows of a workspace
+    pub async fn list_all(&self, workspace_id: &str, limit: Option<u32>, offset: Option<u32>) -> Result<Vec<FlowExecution>> {
         let limit_val = limit.unwrap_or(100).min(1000) as i64; // Max 1000 items
         let offset_val = offset.unwrap_or(0) as i64;
         
         let rows = sqlx::query!(
             r#"
-            SELECT execution_id, flow_id, status, started_at, completed_at, input_payload, output_payload, error
+            SELECT execution_id, flow_id, status, started_at, completed_at, input_payload, output_payload, error, parent_execution_id, replay_of_execution_id
             FROM executions
+            WHERE workspace_id = ?3
             ORDER BY started_at DESC
             LIMIT ?1 OFFSET ?2
             "#,
             limit_val,
-            offset_val
+            offset_val,
+            workspace_id
         )
         .fetch_all(&self.pool)
         .await?;
@@ -166,10 +209,52 @@ impl ExecutionRepository {
                 input_payload: serde_json::from_str(&row.input_payload)?,
                 output_payload: row.output_payload.as_ref().map(|s| parse_json_with_logging(s, "output_payload")),
                 error: row.error,
+                parent_execution_id: row.parent_execution_id,
+                replay_of_execution_id: row.replay_of_execution_id,
+                steps: Vec::new(),
             });
         }
 
         Ok(executions)
     }
-}
 
+    /// Lists sub-flow executions started by the given execution
+    pub async fn list_by_parent(&self, parent_execution_id: &str) -> Result<Vec<FlowExecution>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT execution_id, flow_id, status, started_at, completed_at, input_payload, output_payload, error, parent_execution_id, replay_of_execution_id
+            FROM executions
+            WHERE parent_execution_id = ?1
+            ORDER BY started_at ASC
+            "#,
+            parent_execution_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut executions = Vec::new();
+        for row in rows {
+            executions.push(FlowExecution {
+                flow_id: row.flow_id,
+                execution_id: row.execution_id.expect("execution_id should not be null"),
+                status: serde_json::from_str(&row.status)?,
+                started_at: chrono::DateTime::parse_from_rfc3339(&row.started_at)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse started_at: {}", e))?
+                    .with_timezone(&chrono::Utc),
+                completed_at: row.completed_at.as_ref().map(|s| {
+                    chrono::DateTime::parse_from_rfc3339(s)
+                        .map_err(|e| anyhow::anyhow!("Failed to parse completed_at: {}", e))
+                        .map(|dt| dt.with_timezone(&chrono::Utc))
+                }).transpose()?,
+                input_payload: serde_json::from_str(&row.input_payload)?,
+                output_payload: row.output_payload.as_ref().map(|s| parse_json_with_logging(s, "output_payload")),
+                error: row.error,
+                parent_execution_id: row.parent_execution_id,
+                replay_of_execution_id: row.replay_of_execution_id,
+                steps: Vec::new(),
+            });
+        }
+
+        Ok(executions)
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use flowmason_core::types::{BrickType, ExecutionStep, StepStatus};
+use sqlx::{sqlite::SqliteRow, Row, SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+/// Step records of executions, one per brick that ran or was skipped
+///
+/// Steps are written by `ExecutionRepository::create` together with their
+/// execution.
+#[derive(Clone)]
+pub struct ExecutionStepRepository {
+    pool: SqlitePool,
+}
+
+impl ExecutionStepRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Inserts the steps of an execution
+    pub(crate) async fn insert_all(
+        conn: &mut SqliteConnection,
+        execution_id: &str,
+        steps: &[ExecutionStep],
+    ) -> Result<()> {
+        for step in steps {
+            let output = step.output.as_ref().map(serde_json::to_string).transpose()?;
+
+            sqlx::query(
+                r#"
+                INSERT INTO execution_steps (id, execution_id, brick_index, brick_type, status, input, output,
+                    error, attempts, started_at, duration_ms)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(execution_id)
+            .bind(step.brick_index as i64)
+            .bind(step.brick_type.id())
+            .bind(step.status.as_str())
+            .bind(serde_json::to_string(&step.input)?)
+            .bind(output)
+            .bind(&step.error)
+            .bind(step.attempts as i64)
+            .bind(step.started_at.to_rfc3339())
+            .bind(step.duration_ms as i64)
+            .execute(&mut *conn)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists the steps of an execution in the order they started
+    pub async fn list_by_execution(&self, execution_id: &str) -> Result<Vec<ExecutionStep>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT brick_index, brick_type, status, input, output, error, attempts, started_at, duration_ms
+            FROM execution_steps
+            WHERE execution_id = ?1
+            ORDER BY started_at ASC, brick_index ASC
+            "#,
+        )
+        .bind(execution_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter().map(step_from_row).collect()
+    }
+}
+
+fn step_from_row(row: &SqliteRow) -> Result<ExecutionStep> {
+    let brick_type: String = row.try_get("brick_type")?;
+    let status: String = row.try_get("status")?;
+    let input: String = row.try_get("input")?;
+    let output: Option<String> = row.try_get("output")?;
+    let started_at: String = row.try_get("started_at")?;
+
+    Ok(ExecutionStep {
+        brick_index: row.try_get::<i64, _>("brick_index")? as usize,
+        brick_type: BrickType::from_id(&brick_type),
+        status: StepStatus::parse(&status)
+            .ok_or_else(|| anyhow::anyhow!("Unknown step status: {}", status))?,
+        input: serde_json::from_str(&input)?,
+        output: output.as_deref().map(serde_json::from_str).transpose()?,
+        error: row.try_get("error")?,
+        attempts: row.try_get::<i64, _>("attempts")? as u32,
+        started_at: chrono::DateTime::parse_from_rfc3339(&started_at)
+            .map(|dt| dt.with_timezone(&chrono::Utc))
+            .map_err(|e| anyhow::anyhow!("Failed to parse started_at: {}", e))?,
+        duration_ms: row.try_get::<i64, _>("duration_ms")? as u64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::repositories::ExecutionRepository;
+    use flowmason_core::types::{ExecutionStatus, FlowExecution};
+    use serde_json::json;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        pool
+    }
+
+    #[tokio::test]
+    async fn test_steps_are_stored_with_execution() {
+        let pool = create_test_pool().await;
+        let started_at = chrono::Utc::now();
+
+        let mut first = ExecutionStep::skipped(0, BrickType::FieldMapping, json!({"a": 1}));
+        first.started_at = started_at;
+        let second = ExecutionStep {
+            brick_index: 1,
+            brick_type: BrickType::Custom("echo".to_string()),
+            status: StepStatus::Failed,
+            input: json!({"a": 1}),
+            output: None,
+            error: Some("boom".to_string()),
+            attempts: 3,
+            started_at: started_at + chrono::Duration::milliseconds(5),
+            duration_ms: 42,
+        };
+
+        let execution = FlowExecution {
+            flow_id: "flow-1".to_string(),
+            execution_id: "exec-1".to_string(),
+            status: ExecutionStatus::Failed,
+            started_at,
+            completed_at: Some(chrono::Utc::now()),
+            input_payload: json!({"a": 1}),
+            output_payload: None,
+            error: Some("boom".to_string()),
+            parent_execution_id: None,
+            replay_of_execution_id: None,
+            steps: vec![second, first],
+        };
+        ExecutionRepository::new(pool.clone()).create(&execution).await.unwrap();
+
+        let steps = ExecutionStepRepository::new(pool).list_by_execution("exec-1").await.unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].status, StepStatus::Skipped);
+        assert_eq!(steps[0].output, Some(json!({"a": 1})));
+        assert_eq!(steps[1].brick_type, BrickType::Custom("echo".to_string()));
+        assert_eq!(steps[1].error.as_deref(), Some("boom"));
+        assert_eq!((steps[1].attempts, steps[1].duration_ms), (3, 42));
+    }
+}
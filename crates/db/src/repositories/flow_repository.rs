@@ -1,6 +1,6 @@
 use anyhow::Result;
 use sqlx::SqlitePool;
-use flowmason_core::types::Flow;
+use flowmason_core::types::{BrickConfig, Flow, FlowGraph};
 
 #[derive(Clone)]
 pub struct FlowRepository {
@@ -14,22 +14,31 @@ impl FlowRepository {
 
     pub async fn create(&self, flow: &Flow) -> Result<()> {
         let bricks_json = serde_json::to_string(&flow.bricks)?;
+        let graph_json = serde_json::to_string(&flow.effective_graph())?;
+        let retry_policy_json = flow.retry_policy.as_ref().map(serde_json::to_string).transpose()?;
+        let variables_json = Self::variables_json(flow)?;
+        let schema_json = Self::schema_json(flow)?;
         let created_at_str = flow.created_at.to_rfc3339();
         let updated_at_str = flow.updated_at.to_rfc3339();
         let active_i64 = flow.active as i64;
         
         sqlx::query!(
             r#"
-            INSERT INTO flows (id, name, description, bricks, active, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+            INSERT INTO flows (id, name, description, bricks, graph, retry_policy, variables, schema, active, created_at, updated_at, workspace_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
             "#,
             flow.id,
             flow.name,
             flow.description,
             bricks_json,
+            graph_json,
+            retry_policy_json,
+            variables_json,
+            schema_json,
             active_i64,
             created_at_str,
-            updated_at_str
+            updated_at_str,
+            flow.workspace_id
         )
         .execute(&self.pool)
         .await?;
@@ -37,10 +46,11 @@ impl FlowRepository {
         Ok(())
     }
 
+    /// Gets a flow of any workspace, e.g. to run it from a trigger that names it by id
     pub async fn get(&self, id: &str) -> Result<Option<Flow>> {
         let row = sqlx::query!(
             r#"
-            SELECT id, name, description, bricks, active, created_at, updated_at
+            SELECT id, name, description, bricks, graph, retry_policy, variables, schema, workspace_id, active, created_at, updated_at
             FROM flows
             WHERE id = ?1
             "#,
@@ -51,11 +61,18 @@ impl FlowRepository {
 
         if let Some(row) = row {
             // Parse directly to Vec<BrickConfig> to avoid redundant parsing
+            let bricks: Vec<BrickConfig> = serde_json::from_str(&row.bricks)?;
+            let graph = Self::parse_graph(row.graph.as_deref(), &bricks)?;
             Ok(Some(Flow {
                 id: row.id.expect("id should not be null"),
                 name: row.name,
                 description: row.description,
-                bricks: serde_json::from_str(&row.bricks)?,
+                bricks,
+                graph: Some(graph),
+                retry_policy: row.retry_policy.as_deref().map(serde_json::from_str).transpose()?,
+                variables: row.variables.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default(),
+                schema: row.schema.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default(),
+                workspace_id: row.workspace_id,
                 active: row.active != 0,
                 created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
                     .map_err(|e| anyhow::anyhow!("Failed to parse created_at: {}", e))?
@@ -69,19 +86,26 @@ impl FlowRepository {
         }
     }
 
-    pub async fn list(&self, limit: Option<u32>, offset: Option<u32>) -> Result<Vec<Flow>> {
+    /// Gets a flow if it belongs to the workspace
+    pub async fn get_in_workspace(&self, workspace_id: &str, id: &str) -> Result<Option<Flow>> {
+        Ok(self.get(id).await?.filter(|flow| flow.workspace_id.as_deref() == Some(workspace_id)))
+    }
+
+    pub async fn list(&self, workspace_id: &str, limit: Option<u32>, offset: Option<u32>) -> Result<Vec<Flow>> {
         let limit_val = limit.unwrap_or(100).min(1000) as i64; // Max 1000 items
         let offset_val = offset.unwrap_or(0) as i64;
         
         let rows = sqlx::query!(
             r#"
-            SELECT id, name, description, bricks, active, created_at, updated_at
+            SELECT id, name, description, bricks, graph, retry_policy, variables, schema, workspace_id, active, created_at, updated_at
             FROM flows
+            WHERE workspace_id = ?3
             ORDER BY created_at DESC
             LIMIT ?1 OFFSET ?2
             "#,
             limit_val,
-            offset_val
+            offset_val,
+            workspace_id
         )
         .fetch_all(&self.pool)
         .await?;
@@ -89,11 +113,18 @@ impl FlowRepository {
         let mut flows = Vec::new();
         for row in rows {
             // Parse directly to Vec<BrickConfig> to avoid redundant parsing
+            let bricks: Vec<BrickConfig> = serde_json::from_str(&row.bricks)?;
+            let graph = Self::parse_graph(row.graph.as_deref(), &bricks)?;
             flows.push(Flow {
                 id: row.id.expect("id should not be null"),
                 name: row.name,
                 description: row.description,
-                bricks: serde_json::from_str(&row.bricks)?,
+                bricks,
+                graph: Some(graph),
+                retry_policy: row.retry_policy.as_deref().map(serde_json::from_str).transpose()?,
+                variables: row.variables.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default(),
+                schema: row.schema.as_deref().map(serde_json::from_str).transpose()?.unwrap_or_default(),
+                workspace_id: row.workspace_id,
                 active: row.active != 0,
                 created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
                     .map_err(|e| anyhow::anyhow!("Failed to parse created_at: {}", e))?
@@ -109,13 +140,17 @@ impl FlowRepository {
 
     pub async fn update(&self, flow: &Flow) -> Result<()> {
         let bricks_json = serde_json::to_string(&flow.bricks)?;
+        let graph_json = serde_json::to_string(&flow.effective_graph())?;
+        let retry_policy_json = flow.retry_policy.as_ref().map(serde_json::to_string).transpose()?;
+        let variables_json = Self::variables_json(flow)?;
+        let schema_json = Self::schema_json(flow)?;
         let updated_at_str = flow.updated_at.to_rfc3339();
         let active_i64 = flow.active as i64;
         
         sqlx::query!(
             r#"
             UPDATE flows
-            SET name = ?2, description = ?3, bricks = ?4, active = ?5, updated_at = ?6
+            SET name = ?2, description = ?3, bricks = ?4, graph = ?7, retry_policy = ?8, variables = ?9, schema = ?10, active = ?5, updated_at = ?6
             WHERE id = ?1
             "#,
             flow.id,
@@ -123,7 +158,11 @@ impl FlowRepository {
             flow.description,
             bricks_json,
             active_i64,
-            updated_at_str
+            updated_at_str,
+            graph_json,
+            retry_policy_json,
+            variables_json,
+            schema_json
         )
         .execute(&self.pool)
         .await?;
@@ -131,8 +170,65 @@ impl FlowRepository {
         Ok(())
     }
 
-    pub async fn delete(&self, id: &str) -> Result<()> {
-        sqlx::query!("DELETE FROM flows WHERE id = ?1", id)
+    /// Serializes a flow's variables, storing NULL when there are none
+    fn variables_json(flow: &Flow) -> Result<Option<String>> {
+        if flow.variables.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::to_string(&flow.variables)?))
+    }
+
+    /// Serializes a flow's payload schemas, storing NULL when there are none
+    fn schema_json(flow: &Flow) -> Result<Option<String>> {
+        if flow.schema.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::to_string(&flow.schema)?))
+    }
+
+    /// Persists a graph for every flow that was stored before graphs existed
+    ///
+    /// Linear flows become a chain of brick nodes. Returns the number of migrated flows.
+    pub async fn migrate_linear_flows(&self) -> Result<u64> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, bricks
+            FROM flows
+            WHERE graph IS NULL
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut migrated = 0;
+        for row in rows {
+            let id = row.id.expect("id should not be null");
+            let bricks: Vec<BrickConfig> = serde_json::from_str(&row.bricks)?;
+            let graph_json = serde_json::to_string(&FlowGraph::from_linear(&bricks))?;
+
+            sqlx::query!(
+                "UPDATE flows SET graph = ?2 WHERE id = ?1 AND graph IS NULL",
+                id,
+                graph_json
+            )
+            .execute(&self.pool)
+            .await?;
+            migrated += 1;
+        }
+
+        Ok(migrated)
+    }
+
+    /// Parses a stored graph, falling back to a linear chain for unmigrated rows
+    fn parse_graph(graph: Option<&str>, bricks: &[BrickConfig]) -> Result<FlowGraph> {
+        match graph {
+            Some(json) => Ok(serde_json::from_str(json)?),
+            None => Ok(FlowGraph::from_linear(bricks)),
+        }
+    }
+
+    pub async fn delete(&self, workspace_id: &str, id: &str) -> Result<()> {
+        sqlx::query!("DELETE FROM flows WHERE id = ?1 AND workspace_id = ?2", id, workspace_id)
             .execute(&self.pool)
             .await?;
         
@@ -165,7 +261,13 @@ mod tests {
             bricks: vec![BrickConfig {
                 brick_type: BrickType::FieldMapping,
                 config: json!({}),
+                policy: None,
             }],
+            graph: None,
+            retry_policy: None,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: None,
             active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -190,6 +292,11 @@ mod tests {
             name: "Test Flow 1".to_string(),
             description: None,
             bricks: vec![],
+            graph: None,
+            retry_policy: None,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: Some("ws-1".to_string()),
             active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -200,6 +307,11 @@ mod tests {
             name: "Test Flow 2".to_string(),
             description: None,
             bricks: vec![],
+            graph: None,
+            retry_policy: None,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: Some("ws-2".to_string()),
             active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
@@ -208,8 +320,11 @@ mod tests {
         repo.create(&flow1).await.unwrap();
         repo.create(&flow2).await.unwrap();
         
-        let flows = repo.list().await.unwrap();
-        assert!(flows.len() >= 2);
+        let flows = repo.list("ws-1", None, None).await.unwrap();
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].id, "test-flow-1");
+        assert!(repo.get_in_workspace("ws-2", "test-flow-2").await.unwrap().is_some());
+        assert!(repo.get_in_workspace("ws-1", "test-flow-2").await.unwrap().is_none());
     }
 
     #[tokio::test]
@@ -222,18 +337,26 @@ mod tests {
             name: "Test Flow".to_string(),
             description: None,
             bricks: vec![],
+            graph: None,
+            retry_policy: None,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: None,
             active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
 
         repo.create(&flow).await.unwrap();
+        assert!(repo.get("test-flow-1").await.unwrap().unwrap().schema.is_empty());
         flow.name = "Updated Flow".to_string();
+        flow.schema.input = Some(json!({"type": "object", "required": ["email"]}));
         flow.updated_at = Utc::now();
         
         repo.update(&flow).await.unwrap();
         let retrieved = repo.get("test-flow-1").await.unwrap().unwrap();
         assert_eq!(retrieved.name, "Updated Flow");
+        assert_eq!(retrieved.schema, flow.schema);
     }
 
     #[tokio::test]
@@ -246,16 +369,54 @@ mod tests {
             name: "Test Flow".to_string(),
             description: None,
             bricks: vec![],
+            graph: None,
+            retry_policy: None,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: Some("ws-1".to_string()),
             active: true,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
 
         repo.create(&flow).await.unwrap();
-        repo.delete("test-flow-1").await.unwrap();
+        // Flows of other workspaces are left alone
+        repo.delete("ws-2", "test-flow-1").await.unwrap();
+        assert!(repo.get("test-flow-1").await.unwrap().is_some());
+        repo.delete("ws-1", "test-flow-1").await.unwrap();
         
         let retrieved = repo.get("test-flow-1").await.unwrap();
         assert!(retrieved.is_none());
     }
-}
 
+    #[tokio::test]
+    async fn test_linear_flow_is_migrated_to_graph() {
+        let pool = create_test_pool().await;
+        let repo = FlowRepository::new(pool.clone());
+
+        // Simulate a flow stored before the graph column existed
+        sqlx::query(
+            r#"
+            INSERT INTO flows (id, name, description, bricks, active, created_at, updated_at)
+            VALUES (?1, 'Legacy', NULL, ?2, 1, ?3, ?3)
+            "#
+        )
+        .bind("legacy-flow")
+        .bind(json!([
+            { "brick_type": "field_mapping", "config": {} },
+            { "brick_type": "field_mapping", "config": {} }
+        ]).to_string())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        let flow = repo.get("legacy-flow").await.unwrap().unwrap();
+        let graph = flow.graph.unwrap();
+        assert_eq!(graph.nodes.len(), 2);
+        assert_eq!(graph.edges.len(), 1);
+
+        assert_eq!(repo.migrate_linear_flows().await.unwrap(), 1);
+        assert_eq!(repo.migrate_linear_flows().await.unwrap(), 0);
+    }
+}
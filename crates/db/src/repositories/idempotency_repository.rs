@@ -0,0 +1,255 @@
+use anyhow::Result;
+use sqlx::{Row, SqlitePool};
+
+const STATUS_IN_PROGRESS: &str = "in_progress";
+const STATUS_COMPLETED: &str = "completed";
+
+/// A response recorded for an idempotency key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    /// Other headers set by the handler, such as `Location`
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// Outcome of claiming an idempotency key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyClaim {
+    /// The key is new; the caller handles the request and records its response
+    Acquired,
+    /// A request with the key is still being handled
+    InProgress,
+    /// The key was used before; the recorded response is to be returned
+    Completed(StoredResponse),
+    /// The key was used before for a different request
+    Mismatch,
+}
+
+/// Idempotency keys of triggered executions, kept until they expire
+///
+/// Keys are unique within a scope, such as the workspace of an API call or
+/// the flow of a webhook.
+#[derive(Clone)]
+pub struct IdempotencyRepository {
+    pool: SqlitePool,
+}
+
+impl IdempotencyRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Claims a key for a request
+    ///
+    /// `fingerprint` identifies the request; a later request with the same key
+    /// but another fingerprint is a `Mismatch`. Without one, any request with
+    /// the key counts as a repeat.
+    ///
+    /// A claim not renewed within `lease` is stale, e.g. because the process
+    /// handling the request stopped, and is taken over by the next request.
+    pub async fn claim(
+        &self,
+        scope: &str,
+        key: &str,
+        fingerprint: Option<&str>,
+        ttl: chrono::Duration,
+        lease: chrono::Duration,
+    ) -> Result<IdempotencyClaim> {
+        let now = chrono::Utc::now();
+        self.purge_expired().await?;
+
+        let inserted = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO idempotency_keys (scope, idempotency_key, fingerprint, status, created_at, claimed_at, expires_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6)
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(fingerprint)
+        .bind(STATUS_IN_PROGRESS)
+        .bind(now.to_rfc3339())
+        .bind((now + ttl).to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        if inserted.rows_affected() == 1 {
+            return Ok(IdempotencyClaim::Acquired);
+        }
+
+        let reclaimed = sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET fingerprint = ?3, created_at = ?4, claimed_at = ?4, expires_at = ?5
+            WHERE scope = ?1 AND idempotency_key = ?2 AND status = ?6 AND COALESCE(claimed_at, created_at) < ?7
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(fingerprint)
+        .bind(now.to_rfc3339())
+        .bind((now + ttl).to_rfc3339())
+        .bind(STATUS_IN_PROGRESS)
+        .bind((now - lease).to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+        if reclaimed.rows_affected() == 1 {
+            return Ok(IdempotencyClaim::Acquired);
+        }
+
+        let row = sqlx::query(
+            r#"
+            SELECT fingerprint, status, response_status, response_content_type, response_headers, response_body
+            FROM idempotency_keys
+            WHERE scope = ?1 AND idempotency_key = ?2
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+        // Released between the insert and the lookup
+        let Some(row) = row else {
+            return Ok(IdempotencyClaim::InProgress);
+        };
+
+        let stored_fingerprint: Option<String> = row.try_get("fingerprint")?;
+        if fingerprint.is_some() && stored_fingerprint.as_deref() != fingerprint {
+            return Ok(IdempotencyClaim::Mismatch);
+        }
+        let status: String = row.try_get("status")?;
+        if status != STATUS_COMPLETED {
+            return Ok(IdempotencyClaim::InProgress);
+        }
+
+        let response_status: i64 = row.try_get("response_status")?;
+        let response_headers: Option<String> = row.try_get("response_headers")?;
+        Ok(IdempotencyClaim::Completed(StoredResponse {
+            status: u16::try_from(response_status)?,
+            content_type: row.try_get("response_content_type")?,
+            headers: response_headers.map(|headers| serde_json::from_str(&headers)).transpose()?.unwrap_or_default(),
+            body: row.try_get::<Option<String>, _>("response_body")?.unwrap_or_default(),
+        }))
+    }
+
+    /// Records the response of a claimed key
+    pub async fn complete(&self, scope: &str, key: &str, response: &StoredResponse) -> Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET status = ?3, response_status = ?4, response_content_type = ?5, response_body = ?6, response_headers = ?7
+            WHERE scope = ?1 AND idempotency_key = ?2
+            "#,
+        )
+        .bind(scope)
+        .bind(key)
+        .bind(STATUS_COMPLETED)
+        .bind(i64::from(response.status))
+        .bind(&response.content_type)
+        .bind(&response.body)
+        .bind(serde_json::to_string(&response.headers)?)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Extends the lease of a claimed key while its request is handled
+    pub async fn renew(&self, scope: &str, key: &str) -> Result<()> {
+        sqlx::query("UPDATE idempotency_keys SET claimed_at = ?3 WHERE scope = ?1 AND idempotency_key = ?2 AND status = ?4")
+            .bind(scope)
+            .bind(key)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .bind(STATUS_IN_PROGRESS)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Frees a claimed key without a response, so the request can be retried
+    pub async fn release(&self, scope: &str, key: &str) -> Result<()> {
+        sqlx::query("DELETE FROM idempotency_keys WHERE scope = ?1 AND idempotency_key = ?2 AND status = ?3")
+            .bind(scope)
+            .bind(key)
+            .bind(STATUS_IN_PROGRESS)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes expired keys; returns how many were deleted
+    pub async fn purge_expired(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM idempotency_keys WHERE expires_at < ?1")
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_repo() -> IdempotencyRepository {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        IdempotencyRepository::new(pool)
+    }
+
+    #[tokio::test]
+    async fn test_claim_lifecycle() {
+        let repo = test_repo().await;
+        let ttl = chrono::Duration::hours(1);
+        let lease = chrono::Duration::minutes(5);
+        assert_eq!(repo.claim("ws-1", "key-1", Some("a"), ttl, lease).await.unwrap(), IdempotencyClaim::Acquired);
+        assert_eq!(repo.claim("ws-1", "key-1", Some("a"), ttl, lease).await.unwrap(), IdempotencyClaim::InProgress);
+        // Scopes keep keys apart
+        assert_eq!(repo.claim("ws-2", "key-1", Some("a"), ttl, lease).await.unwrap(), IdempotencyClaim::Acquired);
+
+        let response = StoredResponse {
+            status: 200,
+            content_type: Some("application/json".to_string()),
+            headers: vec![("location".to_string(), "/leads/1".to_string())],
+            body: r#"{"execution_id":"exec-1"}"#.to_string(),
+        };
+        repo.complete("ws-1", "key-1", &response).await.unwrap();
+        assert_eq!(repo.claim("ws-1", "key-1", Some("a"), ttl, lease).await.unwrap(), IdempotencyClaim::Completed(response.clone()));
+        assert_eq!(repo.claim("ws-1", "key-1", None, ttl, lease).await.unwrap(), IdempotencyClaim::Completed(response));
+        assert_eq!(repo.claim("ws-1", "key-1", Some("b"), ttl, lease).await.unwrap(), IdempotencyClaim::Mismatch);
+
+        // Released keys can be claimed again
+        repo.release("ws-2", "key-1").await.unwrap();
+        assert_eq!(repo.claim("ws-2", "key-1", Some("a"), ttl, lease).await.unwrap(), IdempotencyClaim::Acquired);
+    }
+
+    #[tokio::test]
+    async fn test_expired_keys_are_reused() {
+        let repo = test_repo().await;
+        let lease = chrono::Duration::minutes(5);
+        assert_eq!(repo.claim("ws-1", "key-1", None, chrono::Duration::seconds(-1), lease).await.unwrap(), IdempotencyClaim::Acquired);
+        assert_eq!(repo.claim("ws-1", "key-1", None, chrono::Duration::hours(1), lease).await.unwrap(), IdempotencyClaim::Acquired);
+        assert_eq!(repo.purge_expired().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_stale_claims_are_taken_over() {
+        let repo = test_repo().await;
+        let ttl = chrono::Duration::hours(1);
+        let lease = chrono::Duration::minutes(5);
+        assert_eq!(repo.claim("ws-1", "key-1", Some("a"), ttl, lease).await.unwrap(), IdempotencyClaim::Acquired);
+
+        // A renewed claim is still held
+        repo.renew("ws-1", "key-1").await.unwrap();
+        assert_eq!(repo.claim("ws-1", "key-1", Some("a"), ttl, lease).await.unwrap(), IdempotencyClaim::InProgress);
+
+        // One not renewed within the lease is taken over, even for another request
+        assert_eq!(repo.claim("ws-1", "key-1", Some("b"), ttl, chrono::Duration::seconds(-1)).await.unwrap(), IdempotencyClaim::Acquired);
+        assert_eq!(repo.claim("ws-1", "key-1", Some("a"), ttl, lease).await.unwrap(), IdempotencyClaim::Mismatch);
+
+        // Completed keys are never taken over
+        let response = StoredResponse { status: 200, content_type: None, headers: Vec::new(), body: String::new() };
+        repo.complete("ws-1", "key-1", &response).await.unwrap();
+        assert_eq!(repo.claim("ws-1", "key-1", Some("b"), ttl, chrono::Duration::seconds(-1)).await.unwrap(), IdempotencyClaim::Completed(response));
+    }
+}
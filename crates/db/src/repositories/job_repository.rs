@@ -0,0 +1,373 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::SqlitePool;
+use std::time::Duration;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Waiting to be claimed once `available_at` has passed
+    Queued,
+    /// Leased by a worker until `lease_expires_at`
+    Running,
+    Completed,
+    /// Failed on its last allowed attempt
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Completed => "completed",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Result<Self> {
+        match value {
+            "queued" => Ok(JobStatus::Queued),
+            "running" => Ok(JobStatus::Running),
+            "completed" => Ok(JobStatus::Completed),
+            "failed" => Ok(JobStatus::Failed),
+            other => Err(anyhow::anyhow!("Unknown job status: {}", other)),
+        }
+    }
+}
+
+/// A queued flow execution
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: String,
+    pub flow_id: String,
+    pub input_payload: Value,
+    pub status: JobStatus,
+    /// Number of times the job has been claimed
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub available_at: chrono::DateTime<chrono::Utc>,
+    pub lease_owner: Option<String>,
+    pub lease_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Execution recorded by the attempt that finished the job
+    pub execution_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Durable job queue backed by the `jobs` table
+///
+/// Workers claim a job by taking a lease on it. A job whose lease expires
+/// (for example because its worker crashed) becomes visible again and is
+/// claimed by the next poll.
+#[derive(Clone)]
+pub struct JobRepository {
+    pool: SqlitePool,
+}
+
+impl JobRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Adds a job that can be claimed immediately
+    pub async fn enqueue(&self, flow_id: &str, input_payload: &Value, max_attempts: u32) -> Result<Job> {
+        let id = Uuid::new_v4().to_string();
+        let now = chrono::Utc::now();
+        let now_str = now.to_rfc3339();
+        let input_payload_json = serde_json::to_string(input_payload)?;
+
+        let max_attempts = max_attempts.max(1) as i64;
+        let available_at = now.timestamp_millis();
+
+        let record = sqlx::query_as!(
+            JobRecord,
+            r#"
+            INSERT INTO jobs (id, flow_id, input_payload, status, attempts, max_attempts, available_at, created_at, updated_at)
+            VALUES (?1, ?2, ?3, 'queued', 0, ?4, ?5, ?6, ?6)
+            RETURNING id as "id!", flow_id as "flow_id!", input_payload as "input_payload!", status as "status!",
+                attempts as "attempts!: i64", max_attempts as "max_attempts!: i64", available_at as "available_at!: i64",
+                lease_owner, lease_expires_at as "lease_expires_at: i64", execution_id, error,
+                created_at as "created_at!", updated_at as "updated_at!"
+            "#,
+            id,
+            flow_id,
+            input_payload_json,
+            max_attempts,
+            available_at,
+            now_str
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let job = Job::try_from(record)?;
+        tracing::info!(job_id = %job.id, flow_id = %flow_id, "Job enqueued");
+        Ok(job)
+    }
+
+    pub async fn get(&self, job_id: &str) -> Result<Option<Job>> {
+        let record = sqlx::query_as!(
+            JobRecord,
+            r#"
+            SELECT id as "id!", flow_id, input_payload, status, attempts, max_attempts, available_at,
+                lease_owner, lease_expires_at, execution_id, error, created_at, updated_at
+            FROM jobs
+            WHERE id = ?1
+            "#,
+            job_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        record.map(Job::try_from).transpose()
+    }
+
+    /// Claims the oldest visible job for `lease_owner`
+    ///
+    /// Visible jobs are queued jobs whose `available_at` has passed and running
+    /// jobs whose lease has expired. The claim is a single UPDATE, so concurrent
+    /// workers never receive the same job.
+    pub async fn claim(&self, lease_owner: &str, lease: Duration) -> Result<Option<Job>> {
+        let now = chrono::Utc::now();
+        let now_ms = now.timestamp_millis();
+        let lease_expires_at = now_ms + lease.as_millis() as i64;
+
+        let updated_at = now.to_rfc3339();
+
+        let record = sqlx::query_as!(
+            JobRecord,
+            r#"
+            UPDATE jobs
+            SET status = 'running', lease_owner = ?1, lease_expires_at = ?2,
+                attempts = attempts + 1, updated_at = ?3
+            WHERE id = (
+                SELECT id FROM jobs
+                WHERE (status = 'queued' AND available_at <= ?4)
+                   OR (status = 'running' AND lease_expires_at <= ?4)
+                ORDER BY available_at ASC, created_at ASC
+                LIMIT 1
+            )
+            RETURNING id as "id!", flow_id as "flow_id!", input_payload as "input_payload!", status as "status!",
+                attempts as "attempts!: i64", max_attempts as "max_attempts!: i64", available_at as "available_at!: i64",
+                lease_owner, lease_expires_at as "lease_expires_at: i64", execution_id, error,
+                created_at as "created_at!", updated_at as "updated_at!"
+            "#,
+            lease_owner,
+            lease_expires_at,
+            updated_at,
+            now_ms
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        record.map(Job::try_from).transpose()
+    }
+
+    /// Extends the lease of a running job. Returns false if the lease was lost.
+    pub async fn extend_lease(&self, job_id: &str, lease_owner: &str, lease: Duration) -> Result<bool> {
+        let now = chrono::Utc::now();
+        let lease_expires_at = now.timestamp_millis() + lease.as_millis() as i64;
+
+        let updated_at = now.to_rfc3339();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE jobs SET lease_expires_at = ?1, updated_at = ?2
+            WHERE id = ?3 AND lease_owner = ?4 AND status = 'running'
+            "#,
+            lease_expires_at,
+            updated_at,
+            job_id,
+            lease_owner
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Acknowledges a job. Returns false if the lease was lost to another worker.
+    pub async fn complete(&self, job_id: &str, lease_owner: &str, execution_id: &str) -> Result<bool> {
+        let updated_at = chrono::Utc::now().to_rfc3339();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'completed', execution_id = ?1, error = NULL,
+                lease_owner = NULL, lease_expires_at = NULL, updated_at = ?2
+            WHERE id = ?3 AND lease_owner = ?4 AND status = 'running'
+            "#,
+            execution_id,
+            updated_at,
+            job_id,
+            lease_owner
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Returns a job to the queue, visible again after `delay`
+    pub async fn retry(&self, job_id: &str, lease_owner: &str, error: &str, delay: Duration) -> Result<bool> {
+        let now = chrono::Utc::now();
+        let available_at = now.timestamp_millis() + delay.as_millis() as i64;
+        let updated_at = now.to_rfc3339();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'queued', available_at = ?1, error = ?2,
+                lease_owner = NULL, lease_expires_at = NULL, updated_at = ?3
+            WHERE id = ?4 AND lease_owner = ?5 AND status = 'running'
+            "#,
+            available_at,
+            error,
+            updated_at,
+            job_id,
+            lease_owner
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Marks a job as permanently failed, linking the failed execution if the flow ran
+    pub async fn fail(&self, job_id: &str, lease_owner: &str, execution_id: Option<&str>, error: &str) -> Result<bool> {
+        let updated_at = chrono::Utc::now().to_rfc3339();
+
+        let result = sqlx::query!(
+            r#"
+            UPDATE jobs
+            SET status = 'failed', execution_id = ?1, error = ?2,
+                lease_owner = NULL, lease_expires_at = NULL, updated_at = ?3
+            WHERE id = ?4 AND lease_owner = ?5 AND status = 'running'
+            "#,
+            execution_id,
+            error,
+            updated_at,
+            job_id,
+            lease_owner
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+}
+
+fn parse_millis(millis: i64) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::from_timestamp_millis(millis)
+        .ok_or_else(|| anyhow::anyhow!("Invalid timestamp: {}", millis))
+}
+
+fn parse_rfc3339(value: &str, field: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", field, e))
+}
+
+/// A `jobs` row as read by the queries above
+struct JobRecord {
+    id: String,
+    flow_id: String,
+    input_payload: String,
+    status: String,
+    attempts: i64,
+    max_attempts: i64,
+    available_at: i64,
+    lease_owner: Option<String>,
+    lease_expires_at: Option<i64>,
+    execution_id: Option<String>,
+    error: Option<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+impl TryFrom<JobRecord> for Job {
+    type Error = anyhow::Error;
+
+    fn try_from(record: JobRecord) -> Result<Self> {
+        Ok(Job {
+            input_payload: serde_json::from_str(&record.input_payload)?,
+            status: JobStatus::parse(&record.status)?,
+            attempts: record.attempts,
+            max_attempts: record.max_attempts,
+            available_at: parse_millis(record.available_at)?,
+            lease_expires_at: record.lease_expires_at.map(parse_millis).transpose()?,
+            created_at: parse_rfc3339(&record.created_at, "created_at")?,
+            updated_at: parse_rfc3339(&record.updated_at, "updated_at")?,
+            id: record.id,
+            flow_id: record.flow_id,
+            lease_owner: record.lease_owner,
+            execution_id: record.execution_id,
+            error: record.error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    async fn test_repo() -> JobRepository {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        JobRepository::new(pool)
+    }
+
+    #[tokio::test]
+    async fn test_claim_leases_job_once() {
+        let repo = test_repo().await;
+        let job = repo.enqueue("flow-1", &json!({"a": 1}), 3).await.unwrap();
+        assert_eq!(job.status, JobStatus::Queued);
+
+        let claimed = repo.claim("worker-1", Duration::from_secs(30)).await.unwrap().unwrap();
+        assert_eq!(claimed.id, job.id);
+        assert_eq!(claimed.status, JobStatus::Running);
+        assert_eq!(claimed.attempts, 1);
+        assert_eq!(claimed.lease_owner.as_deref(), Some("worker-1"));
+
+        // Leased jobs are invisible to other workers
+        assert!(repo.claim("worker-2", Duration::from_secs(30)).await.unwrap().is_none());
+
+        assert!(repo.complete(&job.id, "worker-1", "exec-1").await.unwrap());
+        let done = repo.get(&job.id).await.unwrap().unwrap();
+        assert_eq!(done.status, JobStatus::Completed);
+        assert_eq!(done.execution_id.as_deref(), Some("exec-1"));
+    }
+
+    #[tokio::test]
+    async fn test_expired_lease_is_reclaimed() {
+        let repo = test_repo().await;
+        let job = repo.enqueue("flow-1", &json!({}), 3).await.unwrap();
+
+        repo.claim("worker-1", Duration::ZERO).await.unwrap().unwrap();
+        let reclaimed = repo.claim("worker-2", Duration::from_secs(30)).await.unwrap().unwrap();
+        assert_eq!(reclaimed.id, job.id);
+        assert_eq!(reclaimed.attempts, 2);
+
+        // The first worker lost its lease and can no longer ack
+        assert!(!repo.complete(&job.id, "worker-1", "exec-1").await.unwrap());
+        assert!(repo.complete(&job.id, "worker-2", "exec-2").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_retry_delays_visibility() {
+        let repo = test_repo().await;
+        let job = repo.enqueue("flow-1", &json!({}), 3).await.unwrap();
+
+        repo.claim("worker-1", Duration::from_secs(30)).await.unwrap().unwrap();
+        assert!(repo.retry(&job.id, "worker-1", "boom", Duration::from_secs(60)).await.unwrap());
+        assert!(repo.claim("worker-1", Duration::from_secs(30)).await.unwrap().is_none());
+
+        let queued = repo.get(&job.id).await.unwrap().unwrap();
+        assert_eq!(queued.status, JobStatus::Queued);
+        assert_eq!(queued.error.as_deref(), Some("boom"));
+    }
+}
@@ -6,6 +6,16 @@ pub mod api_key_repository;
 pub mod scheduled_flow_repository;
 pub mod execution_data_repository;
 pub mod template_repository;
+pub mod job_repository;
+pub mod dead_letter_repository;
+pub mod execution_step_repository;
+pub mod secret_repository;
+pub mod connection_repository;
+pub mod workspace_repository;
+pub mod token_repository;
+pub mod webhook_repository;
+pub mod idempotency_repository;
+pub mod endpoint_repository;
 
 pub use flow_repository::FlowRepository;
 pub use execution_repository::ExecutionRepository;
@@ -15,4 +25,13 @@ pub use api_key_repository::ApiKeyRepository;
 pub use scheduled_flow_repository::{ScheduledFlowRepository, ScheduledFlow};
 pub use execution_data_repository::{ExecutionDataRepository, ExecutionData, ExecutionDataSummary};
 pub use template_repository::TemplateRepository;
-
+pub use job_repository::{JobRepository, Job, JobStatus};
+pub use dead_letter_repository::{DeadLetterRepository, DeadLetter, DeadLetterStatus};
+pub use execution_step_repository::ExecutionStepRepository;
+pub use secret_repository::{SecretRepository, Secret};
+pub use connection_repository::{ConnectionRepository, Connection, ConnectionUpdate};
+pub use workspace_repository::{WorkspaceRepository, Workspace, WorkspaceMember, DEFAULT_WORKSPACE_ID};
+pub use token_repository::{TokenRepository, RefreshToken};
+pub use webhook_repository::{WebhookRepository, WebhookSettings, ResolvedWebhook};
+pub use idempotency_repository::{IdempotencyRepository, IdempotencyClaim, StoredResponse};
+pub use endpoint_repository::{EndpointRepository, FlowEndpoint};
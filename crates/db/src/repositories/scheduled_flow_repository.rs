@@ -26,8 +26,8 @@ impl ScheduledFlowRepository {
         
         sqlx::query!(
             r#"
-            INSERT INTO scheduled_flows (id, flow_id, cron_expression, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5)
+            INSERT INTO scheduled_flows (id, flow_id, cron_expression, created_at, updated_at, workspace_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, (SELECT workspace_id FROM flows WHERE id = ?2))
             "#,
             id,
             flow_id,
@@ -96,6 +96,38 @@ impl ScheduledFlowRepository {
         }).collect())
     }
 
+    /// Lists the scheduled flows of a workspace
+    pub async fn list_by_workspace(&self, workspace_id: &str) -> Result<Vec<ScheduledFlow>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, flow_id, cron_expression, created_at, updated_at
+            FROM scheduled_flows
+            WHERE workspace_id = ?1
+            ORDER BY created_at DESC
+            "#,
+            workspace_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut scheduled_flows = Vec::new();
+        for row in rows {
+            scheduled_flows.push(ScheduledFlow {
+                id: row.id.expect("id should not be null"),
+                flow_id: row.flow_id,
+                cron_expression: row.cron_expression,
+                created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse created_at: {}", e))?
+                    .with_timezone(&chrono::Utc),
+                updated_at: chrono::DateTime::parse_from_rfc3339(&row.updated_at)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse updated_at: {}", e))?
+                    .with_timezone(&chrono::Utc),
+            });
+        }
+
+        Ok(scheduled_flows)
+    }
+
     pub async fn delete(&self, flow_id: &str) -> Result<()> {
         sqlx::query!(
             r#"
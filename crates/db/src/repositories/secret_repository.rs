@@ -0,0 +1,252 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use flowmason_auth::{EncryptedSecret, SecretCipher, SecretError};
+use flowmason_core::SecretResolver;
+use serde::Serialize;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const SECRET_COLUMNS: &str = "id, name, description, created_at, updated_at";
+
+/// Metadata of a stored secret; the value itself is never returned
+#[derive(Debug, Clone, Serialize)]
+pub struct Secret {
+    pub id: String,
+    /// Name used in `{{ secret:<name> }}` references
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Secrets store backed by the `secrets` table
+///
+/// Secrets belong to a workspace; names are unique within it. Values are encrypted with AES-256-GCM under the master key. Without a
+/// master key secrets can be listed and deleted, but not written or read.
+#[derive(Clone)]
+pub struct SecretRepository {
+    pool: SqlitePool,
+    cipher: Option<Arc<SecretCipher>>,
+}
+
+impl SecretRepository {
+    pub fn new(pool: SqlitePool, cipher: Option<Arc<SecretCipher>>) -> Self {
+        Self { pool, cipher }
+    }
+
+    /// Creates the store with the master key from the environment (see
+    /// `SecretCipher::from_env`)
+    pub fn from_env(pool: SqlitePool) -> Self {
+        let cipher = match SecretCipher::from_env() {
+            Ok(cipher) => Some(Arc::new(cipher)),
+            Err(SecretError::MissingMasterKey) => {
+                tracing::warn!("FLOWMASON_MASTER_KEY not set; secret values cannot be stored or resolved");
+                None
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to load the secrets master key; secret values cannot be stored or resolved");
+                None
+            }
+        };
+        Self::new(pool, cipher)
+    }
+
+    /// Whether a master key is configured
+    pub fn is_enabled(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    /// The master key cipher, shared with other stores of encrypted data
+    pub fn shared_cipher(&self) -> Option<Arc<SecretCipher>> {
+        self.cipher.clone()
+    }
+
+    fn cipher(&self) -> Result<&SecretCipher> {
+        self.cipher
+            .as_deref()
+            .ok_or_else(|| SecretError::MissingMasterKey.into())
+    }
+
+    pub async fn create(&self, workspace_id: &str, name: &str, value: &str, description: Option<&str>) -> Result<Secret> {
+        let encrypted = self.cipher()?.encrypt(name, value)?;
+        let id = Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            r#"
+            INSERT INTO secrets (id, name, description, nonce, ciphertext, created_at, updated_at, workspace_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, ?7)
+            "#,
+        )
+        .bind(&id)
+        .bind(name)
+        .bind(description)
+        .bind(&encrypted.nonce)
+        .bind(&encrypted.ciphertext)
+        .bind(&now)
+        .bind(workspace_id)
+        .execute(&self.pool)
+        .await?;
+
+        self.get(workspace_id, name).await?.ok_or_else(|| anyhow::anyhow!("Secret {} not found after insert", name))
+    }
+
+    pub async fn list(&self, workspace_id: &str) -> Result<Vec<Secret>> {
+        let rows = sqlx::query(&format!("SELECT {} FROM secrets WHERE workspace_id = ?1 ORDER BY name", SECRET_COLUMNS))
+            .bind(workspace_id)
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter().map(secret_from_row).collect()
+    }
+
+    pub async fn get(&self, workspace_id: &str, name: &str) -> Result<Option<Secret>> {
+        let row = sqlx::query(&format!("SELECT {} FROM secrets WHERE workspace_id = ?1 AND name = ?2", SECRET_COLUMNS))
+            .bind(workspace_id)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(secret_from_row).transpose()
+    }
+
+    /// Replaces the value and description of a secret; returns `None` if it does not exist
+    pub async fn update(&self, workspace_id: &str, name: &str, value: &str, description: Option<&str>) -> Result<Option<Secret>> {
+        let encrypted = self.cipher()?.encrypt(name, value)?;
+        let result = sqlx::query(
+            r#"
+            UPDATE secrets
+            SET nonce = ?2, ciphertext = ?3, description = ?4, updated_at = ?5
+            WHERE name = ?1 AND workspace_id = ?6
+            "#,
+        )
+        .bind(name)
+        .bind(&encrypted.nonce)
+        .bind(&encrypted.ciphertext)
+        .bind(description)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(workspace_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get(workspace_id, name).await
+    }
+
+    pub async fn delete(&self, workspace_id: &str, name: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM secrets WHERE workspace_id = ?1 AND name = ?2")
+            .bind(workspace_id)
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Decrypts the value of a secret
+    pub async fn reveal(&self, workspace_id: &str, name: &str) -> Result<Option<String>> {
+        let row = sqlx::query("SELECT nonce, ciphertext FROM secrets WHERE workspace_id = ?1 AND name = ?2")
+            .bind(workspace_id)
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let encrypted = EncryptedSecret {
+            nonce: row.try_get("nonce")?,
+            ciphertext: row.try_get("ciphertext")?,
+        };
+        Ok(Some(self.cipher()?.decrypt(name, &encrypted)?))
+    }
+}
+
+#[async_trait]
+impl SecretResolver for SecretRepository {
+    async fn resolve_secret(
+        &self,
+        workspace_id: Option<&str>,
+        name: &str,
+    ) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+        // Flows outside any workspace have no secrets
+        let Some(workspace_id) = workspace_id else {
+            return Ok(None);
+        };
+        self.reveal(workspace_id, name).await.map_err(|e| e.into())
+    }
+}
+
+fn parse_rfc3339(value: &str, field: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", field, e))
+}
+
+fn secret_from_row(row: &SqliteRow) -> Result<Secret> {
+    let created_at: String = row.try_get("created_at")?;
+    let updated_at: String = row.try_get("updated_at")?;
+
+    Ok(Secret {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        description: row.try_get("description")?,
+        created_at: parse_rfc3339(&created_at, "created_at")?,
+        updated_at: parse_rfc3339(&updated_at, "updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_repo() -> SecretRepository {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        let cipher = SecretCipher::new(&[3u8; flowmason_auth::secrets::MASTER_KEY_LEN]).unwrap();
+        SecretRepository::new(pool, Some(Arc::new(cipher)))
+    }
+
+    #[tokio::test]
+    async fn test_values_are_encrypted_at_rest() {
+        let repo = test_repo().await;
+        let secret = repo.create("ws-1", "openai", "sk-123", Some("OpenAI key")).await.unwrap();
+        assert_eq!(secret.description.as_deref(), Some("OpenAI key"));
+
+        let ciphertext: String = sqlx::query_scalar("SELECT ciphertext FROM secrets WHERE name = 'openai'")
+            .fetch_one(&repo.pool)
+            .await
+            .unwrap();
+        assert!(!ciphertext.contains("sk-123"));
+        assert_eq!(repo.resolve_secret(Some("ws-1"), "openai").await.unwrap().as_deref(), Some("sk-123"));
+        assert_eq!(repo.resolve_secret(Some("ws-1"), "missing").await.unwrap(), None);
+
+        // Names are unique within a workspace, and other workspaces cannot see them
+        assert!(repo.create("ws-1", "openai", "sk-456", None).await.is_err());
+        assert_eq!(repo.resolve_secret(Some("ws-2"), "openai").await.unwrap(), None);
+        assert_eq!(repo.resolve_secret(None, "openai").await.unwrap(), None);
+        repo.create("ws-2", "openai", "sk-789", None).await.unwrap();
+        assert_eq!(repo.reveal("ws-2", "openai").await.unwrap().as_deref(), Some("sk-789"));
+
+        repo.update("ws-1", "openai", "sk-456", None).await.unwrap().unwrap();
+        assert_eq!(repo.reveal("ws-1", "openai").await.unwrap().as_deref(), Some("sk-456"));
+        assert!(repo.update("ws-1", "missing", "x", None).await.unwrap().is_none());
+
+        assert_eq!(repo.list("ws-1").await.unwrap().len(), 1);
+        assert!(repo.delete("ws-1", "openai").await.unwrap());
+        assert!(repo.get("ws-1", "openai").await.unwrap().is_none());
+        assert!(repo.get("ws-2", "openai").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_without_master_key() {
+        let repo = test_repo().await;
+        repo.create("ws-1", "openai", "sk-123", None).await.unwrap();
+
+        let locked = SecretRepository::new(repo.pool.clone(), None);
+        assert!(!locked.is_enabled());
+        assert_eq!(locked.list("ws-1").await.unwrap().len(), 1);
+        assert!(locked.reveal("ws-1", "openai").await.is_err());
+        assert!(locked.create("ws-1", "other", "x", None).await.is_err());
+    }
+}
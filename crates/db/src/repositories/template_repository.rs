@@ -20,8 +20,8 @@ impl TemplateRepository {
         
         sqlx::query!(
             r#"
-            INSERT INTO templates (id, name, description, category, flow_config, is_system, created_by, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            INSERT INTO templates (id, name, description, category, flow_config, is_system, created_by, workspace_id, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
             "#,
             template.id,
             template.name,
@@ -30,6 +30,7 @@ impl TemplateRepository {
             flow_config_json,
             is_system_i64,
             template.created_by,
+            template.workspace_id,
             created_at_str,
             updated_at_str
         )
@@ -42,7 +43,7 @@ impl TemplateRepository {
     pub async fn get(&self, id: &str) -> Result<Option<Template>> {
         let row = sqlx::query!(
             r#"
-            SELECT id, name, description, category, flow_config, is_system, created_by, created_at, updated_at
+            SELECT id, name, description, category, flow_config, is_system, created_by, workspace_id, created_at, updated_at
             FROM templates
             WHERE id = ?1
             "#,
@@ -60,6 +61,7 @@ impl TemplateRepository {
                 flow_config: serde_json::from_str(&row.flow_config)?,
                 is_system: row.is_system != 0,
                 created_by: row.created_by,
+                workspace_id: row.workspace_id,
                 created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
                     .map_err(|e| anyhow::anyhow!("Failed to parse created_at: {}", e))?
                     .with_timezone(&chrono::Utc),
@@ -72,72 +74,43 @@ impl TemplateRepository {
         }
     }
 
-    pub async fn list(&self, category: Option<&str>, include_system: bool, limit: Option<u32>, offset: Option<u32>) -> Result<Vec<Template>> {
+    /// Gets a template if the workspace can see it: a system template or one of its own
+    pub async fn get_in_workspace(&self, workspace_id: &str, id: &str) -> Result<Option<Template>> {
+        Ok(self.get(id).await?.filter(|template| {
+            template.is_system || template.workspace_id.as_deref() == Some(workspace_id)
+        }))
+    }
+
+    /// Lists the templates of a workspace, optionally with the system templates
+    pub async fn list(
+        &self,
+        workspace_id: &str,
+        category: Option<&str>,
+        include_system: bool,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> Result<Vec<Template>> {
         let limit_val = limit.unwrap_or(100).min(1000) as i64;
         let offset_val = offset.unwrap_or(0) as i64;
+        let include_system_i64 = include_system as i64;
         
-        let rows = if let Some(cat) = category {
-            if include_system {
-                sqlx::query!(
-                    r#"
-                    SELECT id, name, description, category, flow_config, is_system, created_by, created_at, updated_at
-                    FROM templates
-                    WHERE category = ?1
-                    ORDER BY is_system DESC, created_at DESC
-                    LIMIT ?2 OFFSET ?3
-                    "#,
-                    cat,
-                    limit_val,
-                    offset_val
-                )
-                .fetch_all(&self.pool)
-                .await?
-            } else {
-                sqlx::query!(
-                    r#"
-                    SELECT id, name, description, category, flow_config, is_system, created_by, created_at, updated_at
-                    FROM templates
-                    WHERE category = ?1 AND is_system = 0
-                    ORDER BY created_at DESC
-                    LIMIT ?2 OFFSET ?3
-                    "#,
-                    cat,
-                    limit_val,
-                    offset_val
-                )
-                .fetch_all(&self.pool)
-                .await?
-            }
-        } else {
-            if include_system {
-                sqlx::query!(
-                    r#"
-                    SELECT id, name, description, category, flow_config, is_system, created_by, created_at, updated_at
-                    FROM templates
-                    ORDER BY is_system DESC, created_at DESC
-                    LIMIT ?1 OFFSET ?2
-                    "#,
-                    limit_val,
-                    offset_val
-                )
-                .fetch_all(&self.pool)
-                .await?
-            } else {
-                sqlx::query!(
-                    r#"
-                    SELECT id, name, description, category, flow_config, is_system, created_by, created_at, updated_at
-                    FROM templates
-                    WHERE is_system = 0
-                    ORDER BY created_at DESC
-                    LIMIT ?1 OFFSET ?2
-                    "#,
-                    limit_val,
-                    offset_val
-                )
-                .fetch_all(&self.pool)
-                .await?
-            }
-        };
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, name, description, category, flow_config, is_system, created_by, workspace_id, created_at, updated_at
+            FROM templates
+            WHERE (workspace_id = ?1 OR (is_system = 1 AND ?2 = 1))
+            AND (?3 IS NULL OR category = ?3)
+            ORDER BY is_system DESC, created_at DESC
+            LIMIT ?4 OFFSET ?5
+            "#,
+            workspace_id,
+            include_system_i64,
+            category,
+            limit_val,
+            offset_val
+        )
+        .fetch_all(&self.pool)
+        .await?;
 
         let mut templates = Vec::new();
         for row in rows {
@@ -149,6 +122,7 @@ impl TemplateRepository {
                 flow_config: serde_json::from_str(&row.flow_config)?,
                 is_system: row.is_system != 0,
                 created_by: row.created_by,
+                workspace_id: row.workspace_id,
                 created_at: chrono::DateTime::parse_from_rfc3339(&row.created_at)
                     .map_err(|e| anyhow::anyhow!("Failed to parse created_at: {}", e))?
                     .with_timezone(&chrono::Utc),
@@ -202,13 +176,16 @@ impl TemplateRepository {
         Ok(())
     }
 
-    pub async fn list_categories(&self) -> Result<Vec<String>> {
+    /// Lists the categories of the system templates and those of a workspace
+    pub async fn list_categories(&self, workspace_id: &str) -> Result<Vec<String>> {
         let rows = sqlx::query!(
             r#"
             SELECT DISTINCT category
             FROM templates
+            WHERE is_system = 1 OR workspace_id = ?1
             ORDER BY category
-            "#
+            "#,
+            workspace_id
         )
         .fetch_all(&self.pool)
         .await?;
@@ -0,0 +1,228 @@
+use anyhow::Result;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use uuid::Uuid;
+
+const REFRESH_TOKEN_COLUMNS: &str = "id, user_id, created_at, expires_at, revoked_at";
+
+/// A refresh token; the token itself is only stored as a hash
+#[derive(Debug, Clone)]
+pub struct RefreshToken {
+    pub id: String,
+    pub user_id: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl RefreshToken {
+    pub fn is_expired(&self, now: chrono::DateTime<chrono::Utc>) -> bool {
+        now >= self.expires_at
+    }
+}
+
+/// Refresh tokens and revoked access tokens
+///
+/// Access tokens are revoked one by one through their `jti`, or all at once
+/// per user by recording the time of revocation.
+#[derive(Clone)]
+pub struct TokenRepository {
+    pool: SqlitePool,
+}
+
+impl TokenRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create_refresh_token(
+        &self,
+        user_id: &str,
+        token_hash: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<RefreshToken> {
+        let id = Uuid::new_v4().to_string();
+        let now = chrono::Utc::now();
+
+        sqlx::query(
+            r#"
+            INSERT INTO refresh_tokens (id, user_id, token_hash, created_at, expires_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+        )
+        .bind(&id)
+        .bind(user_id)
+        .bind(token_hash)
+        .bind(now.to_rfc3339())
+        .bind(expires_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(RefreshToken {
+            id,
+            user_id: user_id.to_string(),
+            created_at: now,
+            expires_at,
+            revoked_at: None,
+        })
+    }
+
+    pub async fn get_refresh_token(&self, token_hash: &str) -> Result<Option<RefreshToken>> {
+        let row = sqlx::query(&format!("SELECT {} FROM refresh_tokens WHERE token_hash = ?1", REFRESH_TOKEN_COLUMNS))
+            .bind(token_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(refresh_token_from_row).transpose()
+    }
+
+    /// Revokes a refresh token; returns false if it was already revoked
+    pub async fn revoke_refresh_token(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("UPDATE refresh_tokens SET revoked_at = ?2 WHERE id = ?1 AND revoked_at IS NULL")
+            .bind(id)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Denies an access token until it expires
+    pub async fn revoke_access_token(&self, jti: &str, user_id: &str, expires_at: chrono::DateTime<chrono::Utc>) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        // Entries are only needed until their token expires
+        sqlx::query("DELETE FROM revoked_access_tokens WHERE expires_at < ?1")
+            .bind(&now)
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("INSERT OR IGNORE INTO revoked_access_tokens (jti, user_id, expires_at) VALUES (?1, ?2, ?3)")
+            .bind(jti)
+            .bind(user_id)
+            .bind(expires_at.to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Revokes every refresh token of a user and every access token issued so far
+    pub async fn revoke_all(&self, user_id: &str) -> Result<()> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("UPDATE refresh_tokens SET revoked_at = ?2 WHERE user_id = ?1 AND revoked_at IS NULL")
+            .bind(user_id)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO user_token_revocations (user_id, revoked_at) VALUES (?1, ?2)
+            ON CONFLICT(user_id) DO UPDATE SET revoked_at = excluded.revoked_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Whether an access token was revoked by `jti` or by revoking all tokens
+    /// of its user
+    ///
+    /// Token issue times have second precision, so tokens issued in the
+    /// second of a revoke-all stay valid.
+    pub async fn is_access_token_revoked(&self, user_id: &str, jti: &str, issued_at: i64) -> Result<bool> {
+        if !jti.is_empty() {
+            let denied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM revoked_access_tokens WHERE jti = ?1")
+                .bind(jti)
+                .fetch_one(&self.pool)
+                .await?;
+            if denied > 0 {
+                return Ok(true);
+            }
+        }
+
+        let revoked_at: Option<String> = sqlx::query_scalar("SELECT revoked_at FROM user_token_revocations WHERE user_id = ?1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        match revoked_at {
+            Some(revoked_at) => Ok(issued_at < parse_rfc3339(&revoked_at, "revoked_at")?.timestamp()),
+            None => Ok(false),
+        }
+    }
+}
+
+fn parse_rfc3339(value: &str, field: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", field, e))
+}
+
+fn refresh_token_from_row(row: &SqliteRow) -> Result<RefreshToken> {
+    let created_at: String = row.try_get("created_at")?;
+    let expires_at: String = row.try_get("expires_at")?;
+    let revoked_at: Option<String> = row.try_get("revoked_at")?;
+
+    Ok(RefreshToken {
+        id: row.try_get("id")?,
+        user_id: row.try_get("user_id")?,
+        created_at: parse_rfc3339(&created_at, "created_at")?,
+        expires_at: parse_rfc3339(&expires_at, "expires_at")?,
+        revoked_at: revoked_at.map(|value| parse_rfc3339(&value, "revoked_at")).transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_repo() -> TokenRepository {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        sqlx::query("INSERT INTO users (id, email, password_hash, created_at, updated_at) VALUES ('user-1', 'user@example.com', 'x', ?1, ?1)")
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&pool)
+            .await
+            .unwrap();
+        TokenRepository::new(pool)
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_revocation() {
+        let repo = test_repo().await;
+        let expires_at = chrono::Utc::now() + chrono::Duration::days(1);
+        let token = repo.create_refresh_token("user-1", "hash-1", expires_at).await.unwrap();
+        repo.create_refresh_token("user-1", "hash-2", expires_at).await.unwrap();
+
+        let stored = repo.get_refresh_token("hash-1").await.unwrap().unwrap();
+        assert_eq!(stored.id, token.id);
+        assert!(!stored.is_expired(chrono::Utc::now()));
+        assert!(stored.is_expired(expires_at));
+        assert!(repo.get_refresh_token("missing").await.unwrap().is_none());
+
+        assert!(repo.revoke_refresh_token(&token.id).await.unwrap());
+        assert!(!repo.revoke_refresh_token(&token.id).await.unwrap());
+
+        repo.revoke_all("user-1").await.unwrap();
+        assert!(repo.get_refresh_token("hash-2").await.unwrap().unwrap().revoked_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_access_token_denylist() {
+        let repo = test_repo().await;
+        let now = chrono::Utc::now();
+        assert!(!repo.is_access_token_revoked("user-1", "jti-1", now.timestamp()).await.unwrap());
+
+        repo.revoke_access_token("jti-1", "user-1", now + chrono::Duration::hours(1)).await.unwrap();
+        assert!(repo.is_access_token_revoked("user-1", "jti-1", now.timestamp()).await.unwrap());
+        assert!(!repo.is_access_token_revoked("user-1", "jti-2", now.timestamp()).await.unwrap());
+
+        // Revoking all tokens rejects tokens issued earlier, whatever their jti
+        repo.revoke_all("user-1").await.unwrap();
+        assert!(repo.is_access_token_revoked("user-1", "jti-2", now.timestamp() - 10).await.unwrap());
+        assert!(repo.is_access_token_revoked("user-1", "", now.timestamp() - 10).await.unwrap());
+        assert!(!repo.is_access_token_revoked("user-1", "jti-3", now.timestamp() + 10).await.unwrap());
+    }
+}
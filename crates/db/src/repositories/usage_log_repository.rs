@@ -26,8 +26,8 @@ impl UsageLogRepository {
         
         sqlx::query!(
             r#"
-            INSERT INTO usage_logs (id, brick_name, flow_id, execution_id, timestamp, cost_unit, token_usage, metadata)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            INSERT INTO usage_logs (id, brick_name, flow_id, execution_id, timestamp, cost_unit, token_usage, metadata, workspace_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, (SELECT workspace_id FROM flows WHERE id = ?3))
             "#,
             log.id,
             log.brick_name,
@@ -106,6 +106,39 @@ impl UsageLogRepository {
         Ok(logs)
     }
 
+    /// Lists the usage logs of the flows of a workspace
+    pub async fn list_by_workspace(&self, workspace_id: &str) -> Result<Vec<UsageLog>> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT id, brick_name, flow_id, execution_id, timestamp, cost_unit, token_usage, metadata
+            FROM usage_logs
+            WHERE workspace_id = ?1
+            ORDER BY timestamp DESC
+            "#,
+            workspace_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut logs = Vec::new();
+        for row in rows {
+            logs.push(UsageLog {
+                id: row.id.expect("id should not be null"),
+                brick_name: row.brick_name,
+                flow_id: row.flow_id,
+                execution_id: row.execution_id,
+                timestamp: chrono::DateTime::parse_from_rfc3339(&row.timestamp)
+                    .map_err(|e| anyhow::anyhow!("Failed to parse timestamp: {}", e))?
+                    .with_timezone(&chrono::Utc),
+                cost_unit: row.cost_unit,
+                token_usage: row.token_usage,
+                metadata: row.metadata.as_ref().map(|s| parse_json_with_logging(s, "metadata")),
+            });
+        }
+
+        Ok(logs)
+    }
+
     pub async fn list_by_brick_type(&self, brick_type: &BrickType) -> Result<Vec<UsageLog>> {
         let brick_name = brick_type.as_str().to_lowercase();
         let rows = sqlx::query!(
@@ -191,14 +224,42 @@ impl UsageLogRepository {
         Ok(count.count as u64)
     }
 
-    /// Get all unique brick names from usage_logs table
-    pub async fn get_unique_brick_names(&self) -> Result<Vec<String>> {
+    /// Get daily usage count of a brick by name in one workspace
+    pub async fn get_workspace_daily_usage_count(&self, workspace_id: &str, brick_name: &str) -> Result<u64> {
+        let today = Utc::now().date_naive();
+        let today_start_str = today.and_hms_opt(0, 0, 0).unwrap().and_utc().to_rfc3339();
+        let today_end_str = today.and_hms_opt(23, 59, 59).unwrap().and_utc().to_rfc3339();
+
+        let count = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count
+            FROM usage_logs
+            WHERE workspace_id = ?1
+            AND brick_name = ?2
+            AND timestamp >= ?3
+            AND timestamp <= ?4
+            "#,
+            workspace_id,
+            brick_name,
+            today_start_str,
+            today_end_str
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count.count as u64)
+    }
+
+    /// Get the unique brick names used by a workspace
+    pub async fn get_unique_brick_names(&self, workspace_id: &str) -> Result<Vec<String>> {
         let rows = sqlx::query!(
             r#"
             SELECT DISTINCT brick_name
             FROM usage_logs
+            WHERE workspace_id = ?1
             ORDER BY brick_name
-            "#
+            "#,
+            workspace_id
         )
         .fetch_all(&self.pool)
         .await?;
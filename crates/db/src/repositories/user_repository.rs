@@ -2,6 +2,8 @@ use anyhow::Result;
 use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
 use flowmason_auth::User;
 
+const USER_COLUMNS: &str = "id, email, password_hash, group_names, created_at, updated_at";
+
 pub struct UserRepository {
     pool: SqlitePool,
 }
@@ -33,13 +35,7 @@ impl UserRepository {
     }
 
     pub async fn get_by_email(&self, email: &str) -> Result<Option<User>> {
-        let row = sqlx::query(
-            r#"
-            SELECT id, email, password_hash, created_at, updated_at
-            FROM users
-            WHERE email = ?1
-            "#
-        )
+        let row = sqlx::query(&format!("SELECT {} FROM users WHERE email = ?1", USER_COLUMNS))
         .bind(email)
         .fetch_optional(&self.pool)
         .await?;
@@ -52,13 +48,7 @@ impl UserRepository {
     }
 
     pub async fn get_by_id(&self, id: &str) -> Result<Option<User>> {
-        let row = sqlx::query(
-            r#"
-            SELECT id, email, password_hash, created_at, updated_at
-            FROM users
-            WHERE id = ?1
-            "#
-        )
+        let row = sqlx::query(&format!("SELECT {} FROM users WHERE id = ?1", USER_COLUMNS))
         .bind(id)
         .fetch_optional(&self.pool)
         .await?;
@@ -70,6 +60,36 @@ impl UserRepository {
         }
     }
 
+    /// Finds the user linked to an identity provider account
+    pub async fn get_by_oidc_subject(&self, subject: &str) -> Result<Option<User>> {
+        let row = sqlx::query(&format!("SELECT {} FROM users WHERE oidc_subject = ?1", USER_COLUMNS))
+            .bind(subject)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(Self::row_to_user).transpose()
+    }
+
+    /// The identity provider account linked to a user, if any
+    pub async fn get_oidc_subject(&self, id: &str) -> Result<Option<String>> {
+        let subject: Option<Option<String>> = sqlx::query_scalar("SELECT oidc_subject FROM users WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(subject.flatten())
+    }
+
+    /// Links a user to an identity provider account and records its groups
+    pub async fn set_oidc_identity(&self, id: &str, subject: &str, groups: &[String]) -> Result<()> {
+        sqlx::query("UPDATE users SET oidc_subject = ?2, group_names = ?3, updated_at = ?4 WHERE id = ?1")
+            .bind(id)
+            .bind(subject)
+            .bind(serde_json::to_string(groups)?)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn update(&self, user: &User) -> Result<()> {
         let updated_at_str = chrono::Utc::now().to_rfc3339();
 
@@ -105,13 +125,7 @@ impl UserRepository {
     }
 
     pub async fn list_all(&self) -> Result<Vec<User>> {
-        let rows = sqlx::query(
-            r#"
-            SELECT id, email, password_hash, created_at, updated_at
-            FROM users
-            ORDER BY created_at DESC
-            "#
-        )
+        let rows = sqlx::query(&format!("SELECT {} FROM users ORDER BY created_at DESC", USER_COLUMNS))
         .fetch_all(&self.pool)
         .await?;
 
@@ -124,6 +138,7 @@ impl UserRepository {
         let password_hash: String = row.try_get("password_hash")?;
         let created_at_str: String = row.try_get("created_at")?;
         let updated_at_str: String = row.try_get("updated_at")?;
+        let group_names: Option<String> = row.try_get("group_names")?;
 
         let created_at = chrono::DateTime::parse_from_rfc3339(&created_at_str)
             .map_err(|e| anyhow::anyhow!("Failed to parse created_at: {}", e))?
@@ -136,9 +151,34 @@ impl UserRepository {
             id,
             email,
             password_hash,
+            groups: group_names.map(|s| serde_json::from_str(&s)).transpose()?.unwrap_or_default(),
             created_at,
             updated_at,
         })
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_oidc_identity() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        let repo = UserRepository::new(pool);
+        let user = User::new("alice@example.com".to_string(), "x".to_string());
+        repo.create(&user).await.unwrap();
+        assert!(repo.get_by_id(&user.id).await.unwrap().unwrap().groups.is_empty());
+        assert!(repo.get_oidc_subject(&user.id).await.unwrap().is_none());
+
+        let groups = vec!["engineering".to_string()];
+        repo.set_oidc_identity(&user.id, "https://idp.example.com|alice", &groups).await.unwrap();
+        let linked = repo.get_by_oidc_subject("https://idp.example.com|alice").await.unwrap().unwrap();
+        assert_eq!(linked.id, user.id);
+        assert_eq!(linked.groups, groups);
+        assert_eq!(repo.get_oidc_subject(&user.id).await.unwrap().as_deref(), Some("https://idp.example.com|alice"));
+        assert!(repo.get_by_oidc_subject("https://idp.example.com|bob").await.unwrap().is_none());
+    }
+}
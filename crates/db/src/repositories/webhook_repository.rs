@@ -0,0 +1,252 @@
+use anyhow::Result;
+use flowmason_auth::{EncryptedSecret, SecretCipher, SecretError, WebhookAuth};
+use serde::Serialize;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use std::sync::Arc;
+
+const WEBHOOK_COLUMNS: &str = "flow_id, auth, previous_expires_at, idempotency_key_path, created_at, updated_at";
+
+/// Webhook authentication of a flow; the secret is never returned
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookSettings {
+    pub flow_id: String,
+    #[serde(flatten)]
+    pub auth: WebhookAuth,
+    /// Until when the secret replaced by the last rotation is still accepted
+    pub previous_secret_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Payload path of the idempotency key of calls without an
+    /// `Idempotency-Key` header
+    pub idempotency_key_path: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Webhook authentication of a flow with the secrets it accepts
+#[derive(Debug, Clone)]
+pub struct ResolvedWebhook {
+    pub auth: WebhookAuth,
+    /// The current secret, followed by the previous one during its grace period
+    pub secrets: Vec<String>,
+    pub idempotency_key_path: Option<String>,
+}
+
+/// Per-flow webhook secrets backed by the `flow_webhooks` table
+///
+/// Secrets are encrypted with the secrets master key.
+#[derive(Clone)]
+pub struct WebhookRepository {
+    pool: SqlitePool,
+    cipher: Option<Arc<SecretCipher>>,
+}
+
+impl WebhookRepository {
+    pub fn new(pool: SqlitePool, cipher: Option<Arc<SecretCipher>>) -> Self {
+        Self { pool, cipher }
+    }
+
+    /// Whether a master key is configured
+    pub fn is_enabled(&self) -> bool {
+        self.cipher.is_some()
+    }
+
+    fn cipher(&self) -> Result<&SecretCipher> {
+        self.cipher
+            .as_deref()
+            .ok_or_else(|| SecretError::MissingMasterKey.into())
+    }
+
+    pub async fn get(&self, flow_id: &str) -> Result<Option<WebhookSettings>> {
+        let row = sqlx::query(&format!("SELECT {} FROM flow_webhooks WHERE flow_id = ?1", WEBHOOK_COLUMNS))
+            .bind(flow_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(webhook_from_row).transpose()
+    }
+
+    /// Sets the authentication and secret of a flow's webhook, dropping any
+    /// previous secret
+    pub async fn configure(
+        &self,
+        flow_id: &str,
+        auth: &WebhookAuth,
+        secret: &str,
+        idempotency_key_path: Option<&str>,
+    ) -> Result<WebhookSettings> {
+        let encrypted = self.cipher()?.encrypt(&secret_aad(flow_id), secret)?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        sqlx::query(
+            r#"
+            INSERT INTO flow_webhooks (flow_id, auth, nonce, ciphertext, idempotency_key_path, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?6, ?5, ?5)
+            ON CONFLICT(flow_id) DO UPDATE SET
+                auth = excluded.auth,
+                idempotency_key_path = excluded.idempotency_key_path,
+                nonce = excluded.nonce,
+                ciphertext = excluded.ciphertext,
+                previous_nonce = NULL,
+                previous_ciphertext = NULL,
+                previous_expires_at = NULL,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(flow_id)
+        .bind(serde_json::to_string(auth)?)
+        .bind(&encrypted.nonce)
+        .bind(&encrypted.ciphertext)
+        .bind(&now)
+        .bind(idempotency_key_path)
+        .execute(&self.pool)
+        .await?;
+
+        self.get(flow_id).await?.ok_or_else(|| anyhow::anyhow!("Webhook of flow {} not found after insert", flow_id))
+    }
+
+    /// Replaces the secret; the replaced secret stays valid for `grace_period`
+    ///
+    /// Returns `None` if the flow has no webhook authentication.
+    pub async fn rotate(&self, flow_id: &str, secret: &str, grace_period: chrono::Duration) -> Result<Option<WebhookSettings>> {
+        let encrypted = self.cipher()?.encrypt(&secret_aad(flow_id), secret)?;
+        let now = chrono::Utc::now();
+        let previous_expires_at = (grace_period > chrono::Duration::zero()).then(|| (now + grace_period).to_rfc3339());
+
+        let result = sqlx::query(
+            r#"
+            UPDATE flow_webhooks SET
+                previous_nonce = CASE WHEN ?4 IS NULL THEN NULL ELSE nonce END,
+                previous_ciphertext = CASE WHEN ?4 IS NULL THEN NULL ELSE ciphertext END,
+                previous_expires_at = ?4,
+                nonce = ?2,
+                ciphertext = ?3,
+                updated_at = ?5
+            WHERE flow_id = ?1
+            "#,
+        )
+        .bind(flow_id)
+        .bind(&encrypted.nonce)
+        .bind(&encrypted.ciphertext)
+        .bind(previous_expires_at)
+        .bind(now.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get(flow_id).await
+    }
+
+    pub async fn delete(&self, flow_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM flow_webhooks WHERE flow_id = ?1")
+            .bind(flow_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Loads a flow's webhook authentication with the decrypted secrets it accepts
+    pub async fn resolve(&self, flow_id: &str) -> Result<Option<ResolvedWebhook>> {
+        let row = sqlx::query(&format!(
+            "SELECT {}, nonce, ciphertext, previous_nonce, previous_ciphertext FROM flow_webhooks WHERE flow_id = ?1",
+            WEBHOOK_COLUMNS
+        ))
+        .bind(flow_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let settings = webhook_from_row(&row)?;
+        let cipher = self.cipher()?;
+        let aad = secret_aad(flow_id);
+        let mut secrets = vec![cipher.decrypt(&aad, &EncryptedSecret {
+            nonce: row.try_get("nonce")?,
+            ciphertext: row.try_get("ciphertext")?,
+        })?];
+
+        let previous_nonce: Option<String> = row.try_get("previous_nonce")?;
+        let previous_ciphertext: Option<String> = row.try_get("previous_ciphertext")?;
+        let in_grace_period = settings.previous_secret_expires_at.is_some_and(|expires_at| chrono::Utc::now() < expires_at);
+        if let (Some(nonce), Some(ciphertext), true) = (previous_nonce, previous_ciphertext, in_grace_period) {
+            secrets.push(cipher.decrypt(&aad, &EncryptedSecret { nonce, ciphertext })?);
+        }
+
+        Ok(Some(ResolvedWebhook {
+            auth: settings.auth,
+            secrets,
+            idempotency_key_path: settings.idempotency_key_path,
+        }))
+    }
+}
+
+/// Associated data binding an encrypted secret to its flow
+fn secret_aad(flow_id: &str) -> String {
+    format!("webhook:{}", flow_id)
+}
+
+fn parse_rfc3339(value: &str, field: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", field, e))
+}
+
+fn webhook_from_row(row: &SqliteRow) -> Result<WebhookSettings> {
+    let auth: String = row.try_get("auth")?;
+    let previous_expires_at: Option<String> = row.try_get("previous_expires_at")?;
+    let created_at: String = row.try_get("created_at")?;
+    let updated_at: String = row.try_get("updated_at")?;
+
+    Ok(WebhookSettings {
+        flow_id: row.try_get("flow_id")?,
+        auth: serde_json::from_str(&auth)?,
+        previous_secret_expires_at: previous_expires_at
+            .map(|value| parse_rfc3339(&value, "previous_expires_at"))
+            .transpose()?,
+        idempotency_key_path: row.try_get("idempotency_key_path")?,
+        created_at: parse_rfc3339(&created_at, "created_at")?,
+        updated_at: parse_rfc3339(&updated_at, "updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn test_repo() -> WebhookRepository {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        sqlx::query("INSERT INTO flows (id, name, bricks, created_at, updated_at) VALUES ('flow-1', 'Deals', '[]', ?1, ?1)")
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&pool)
+            .await
+            .unwrap();
+        let cipher = SecretCipher::new(&[7u8; flowmason_auth::secrets::MASTER_KEY_LEN]).unwrap();
+        WebhookRepository::new(pool, Some(Arc::new(cipher)))
+    }
+
+    #[tokio::test]
+    async fn test_secret_rotation() {
+        let repo = test_repo().await;
+        let auth = WebhookAuth::Token { header: "X-Token".to_string() };
+        assert!(repo.resolve("flow-1").await.unwrap().is_none());
+        assert!(repo.rotate("flow-1", "s2", chrono::Duration::zero()).await.unwrap().is_none());
+
+        repo.configure("flow-1", &auth, "s1", Some("event.id")).await.unwrap();
+        let resolved = repo.resolve("flow-1").await.unwrap().unwrap();
+        assert_eq!(resolved.auth, auth);
+        assert_eq!(resolved.idempotency_key_path.as_deref(), Some("event.id"));
+        assert_eq!(resolved.secrets, vec!["s1".to_string()]);
+
+        // The old secret is accepted during the grace period only
+        let settings = repo.rotate("flow-1", "s2", chrono::Duration::hours(1)).await.unwrap().unwrap();
+        assert!(settings.previous_secret_expires_at.is_some());
+        assert_eq!(repo.resolve("flow-1").await.unwrap().unwrap().secrets, vec!["s2".to_string(), "s1".to_string()]);
+
+        repo.rotate("flow-1", "s3", chrono::Duration::zero()).await.unwrap();
+        assert_eq!(repo.resolve("flow-1").await.unwrap().unwrap().secrets, vec!["s3".to_string()]);
+
+        assert!(repo.delete("flow-1").await.unwrap());
+        assert!(repo.get("flow-1").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,359 @@
+use anyhow::Result;
+use flowmason_auth::WorkspaceRole;
+use serde::Serialize;
+use sqlx::{sqlite::SqliteRow, Row, SqlitePool};
+use uuid::Uuid;
+
+/// Workspace that receives users and data stored before workspaces existed
+pub const DEFAULT_WORKSPACE_ID: &str = "default";
+
+/// Name of the workspace created for users without one
+const PERSONAL_WORKSPACE_NAME: &str = "Personal";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A member of a workspace, with the email of the user
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceMember {
+    pub workspace_id: String,
+    pub user_id: String,
+    pub email: String,
+    pub role: WorkspaceRole,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Store of workspaces and their members
+#[derive(Clone)]
+pub struct WorkspaceRepository {
+    pool: SqlitePool,
+}
+
+impl WorkspaceRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Creates a workspace owned by `owner_id`
+    pub async fn create(&self, name: &str, owner_id: &str) -> Result<Workspace> {
+        let id = Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("INSERT INTO workspaces (id, name, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)")
+            .bind(&id)
+            .bind(name)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO workspace_members (workspace_id, user_id, role, created_at) VALUES (?1, ?2, ?3, ?4)")
+            .bind(&id)
+            .bind(owner_id)
+            .bind(WorkspaceRole::Owner.as_str())
+            .bind(&now)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        self.get(&id).await?.ok_or_else(|| anyhow::anyhow!("Workspace {} not found after insert", id))
+    }
+
+    pub async fn get(&self, id: &str) -> Result<Option<Workspace>> {
+        let row = sqlx::query("SELECT id, name, created_at, updated_at FROM workspaces WHERE id = ?1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(workspace_from_row).transpose()
+    }
+
+    /// Lists the workspaces of a user with the user's role, oldest membership first
+    pub async fn list_for_user(&self, user_id: &str) -> Result<Vec<(Workspace, WorkspaceRole)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT w.id, w.name, w.created_at, w.updated_at, m.role
+            FROM workspaces w
+            JOIN workspace_members m ON m.workspace_id = w.id
+            WHERE m.user_id = ?1
+            ORDER BY m.created_at, w.name
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| Ok((workspace_from_row(row)?, role_from_row(row)?)))
+            .collect()
+    }
+
+    /// Renames a workspace; returns `None` if it does not exist
+    pub async fn rename(&self, id: &str, name: &str) -> Result<Option<Workspace>> {
+        let result = sqlx::query("UPDATE workspaces SET name = ?2, updated_at = ?3 WHERE id = ?1")
+            .bind(id)
+            .bind(name)
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+        self.get(id).await
+    }
+
+    /// Number of flows in a workspace
+    pub async fn count_flows(&self, id: &str) -> Result<u64> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM flows WHERE workspace_id = ?1")
+            .bind(id)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(count as u64)
+    }
+
+    /// Deletes a workspace with its members, secrets, connections and templates
+    ///
+    /// Callers must make sure the workspace has no flows left.
+    pub async fn delete(&self, id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        for table in ["workspace_members", "secrets", "connections", "templates"] {
+            sqlx::query(&format!("DELETE FROM {} WHERE workspace_id = ?1", table))
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let result = sqlx::query("DELETE FROM workspaces WHERE id = ?1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Role of a user in a workspace, or `None` if the user is not a member
+    pub async fn get_role(&self, workspace_id: &str, user_id: &str) -> Result<Option<WorkspaceRole>> {
+        let role: Option<String> = sqlx::query_scalar(
+            "SELECT role FROM workspace_members WHERE workspace_id = ?1 AND user_id = ?2",
+        )
+        .bind(workspace_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        role.map(|role| parse_role(&role)).transpose()
+    }
+
+    /// The workspace used when a request names none: the user's oldest
+    /// membership, or a new personal workspace for users without any
+    pub async fn default_for_user(&self, user_id: &str) -> Result<(Workspace, WorkspaceRole)> {
+        if let Some(membership) = self.list_for_user(user_id).await?.into_iter().next() {
+            return Ok(membership);
+        }
+        let workspace = self.create(PERSONAL_WORKSPACE_NAME, user_id).await?;
+        Ok((workspace, WorkspaceRole::Owner))
+    }
+
+    /// Adds a member; fails if the user is already a member
+    pub async fn add_member(&self, workspace_id: &str, user_id: &str, role: WorkspaceRole) -> Result<()> {
+        sqlx::query("INSERT INTO workspace_members (workspace_id, user_id, role, created_at) VALUES (?1, ?2, ?3, ?4)")
+            .bind(workspace_id)
+            .bind(user_id)
+            .bind(role.as_str())
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Changes the role of a member; returns false if the user is not a member
+    pub async fn update_member(&self, workspace_id: &str, user_id: &str, role: WorkspaceRole) -> Result<bool> {
+        let result = sqlx::query("UPDATE workspace_members SET role = ?3 WHERE workspace_id = ?1 AND user_id = ?2")
+            .bind(workspace_id)
+            .bind(user_id)
+            .bind(role.as_str())
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn remove_member(&self, workspace_id: &str, user_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM workspace_members WHERE workspace_id = ?1 AND user_id = ?2")
+            .bind(workspace_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    pub async fn get_member(&self, workspace_id: &str, user_id: &str) -> Result<Option<WorkspaceMember>> {
+        let row = sqlx::query(
+            r#"
+            SELECT m.workspace_id, m.user_id, u.email, m.role, m.created_at
+            FROM workspace_members m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.workspace_id = ?1 AND m.user_id = ?2
+            "#,
+        )
+        .bind(workspace_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(member_from_row).transpose()
+    }
+
+    pub async fn list_members(&self, workspace_id: &str) -> Result<Vec<WorkspaceMember>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT m.workspace_id, m.user_id, u.email, m.role, m.created_at
+            FROM workspace_members m
+            JOIN users u ON u.id = m.user_id
+            WHERE m.workspace_id = ?1
+            ORDER BY m.created_at, u.email
+            "#,
+        )
+        .bind(workspace_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter().map(member_from_row).collect()
+    }
+
+    /// Number of owners of a workspace
+    pub async fn count_owners(&self, workspace_id: &str) -> Result<u64> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM workspace_members WHERE workspace_id = ?1 AND role = ?2",
+        )
+        .bind(workspace_id)
+        .bind(WorkspaceRole::Owner.as_str())
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(count as u64)
+    }
+}
+
+fn parse_rfc3339(value: &str, field: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.with_timezone(&chrono::Utc))
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", field, e))
+}
+
+fn parse_role(value: &str) -> Result<WorkspaceRole> {
+    value.parse().map_err(|e: String| anyhow::anyhow!(e))
+}
+
+fn role_from_row(row: &SqliteRow) -> Result<WorkspaceRole> {
+    let role: String = row.try_get("role")?;
+    parse_role(&role)
+}
+
+fn workspace_from_row(row: &SqliteRow) -> Result<Workspace> {
+    let created_at: String = row.try_get("created_at")?;
+    let updated_at: String = row.try_get("updated_at")?;
+
+    Ok(Workspace {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        created_at: parse_rfc3339(&created_at, "created_at")?,
+        updated_at: parse_rfc3339(&updated_at, "updated_at")?,
+    })
+}
+
+fn member_from_row(row: &SqliteRow) -> Result<WorkspaceMember> {
+    let created_at: String = row.try_get("created_at")?;
+
+    Ok(WorkspaceMember {
+        workspace_id: row.try_get("workspace_id")?,
+        user_id: row.try_get("user_id")?,
+        email: row.try_get("email")?,
+        role: role_from_row(row)?,
+        created_at: parse_rfc3339(&created_at, "created_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn create_test_pool() -> SqlitePool {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        pool
+    }
+
+    async fn create_user(pool: &SqlitePool, id: &str) {
+        let now = chrono::Utc::now().to_rfc3339();
+        sqlx::query("INSERT INTO users (id, email, password_hash, created_at, updated_at) VALUES (?1, ?2, 'x', ?3, ?3)")
+            .bind(id)
+            .bind(format!("{}@example.com", id))
+            .bind(&now)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_workspaces_and_members() {
+        let pool = create_test_pool().await;
+        let repo = WorkspaceRepository::new(pool.clone());
+        create_user(&pool, "alice").await;
+        create_user(&pool, "bob").await;
+
+        // Users without a workspace get a personal one, once
+        let (personal, role) = repo.default_for_user("alice").await.unwrap();
+        assert_eq!(personal.name, "Personal");
+        assert_eq!(role, WorkspaceRole::Owner);
+        assert_eq!(repo.default_for_user("alice").await.unwrap().0.id, personal.id);
+
+        let team = repo.create("Team", "alice").await.unwrap();
+        repo.add_member(&team.id, "bob", WorkspaceRole::Runner).await.unwrap();
+        assert!(repo.add_member(&team.id, "bob", WorkspaceRole::Viewer).await.is_err());
+        assert_eq!(repo.get_role(&team.id, "bob").await.unwrap(), Some(WorkspaceRole::Runner));
+        assert_eq!(repo.get_role(&personal.id, "bob").await.unwrap(), None);
+        assert_eq!(repo.default_for_user("bob").await.unwrap().0.id, team.id);
+
+        assert!(repo.update_member(&team.id, "bob", WorkspaceRole::Admin).await.unwrap());
+        let members = repo.list_members(&team.id).await.unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members[1].email, "bob@example.com");
+        assert_eq!(members[1].role, WorkspaceRole::Admin);
+        assert_eq!(repo.count_owners(&team.id).await.unwrap(), 1);
+        assert_eq!(repo.list_for_user("alice").await.unwrap().len(), 2);
+
+        assert!(repo.remove_member(&team.id, "bob").await.unwrap());
+        assert!(!repo.remove_member(&team.id, "bob").await.unwrap());
+        assert!(repo.delete(&team.id).await.unwrap());
+        assert!(repo.get(&team.id).await.unwrap().is_none());
+        assert_eq!(repo.list_for_user("alice").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_existing_data_moves_to_default_workspace() {
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        sqlx::query("DELETE FROM workspaces").execute(&pool).await.unwrap();
+        create_user(&pool, "alice").await;
+        sqlx::query(
+            r#"
+            INSERT INTO flows (id, name, bricks, active, created_at, updated_at)
+            VALUES ('legacy-flow', 'Legacy', '[]', 1, ?1, ?1)
+            "#,
+        )
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        // Schema initialization on the next start assigns the default workspace
+        crate::connection::init_schema(&pool).await.unwrap();
+        let repo = WorkspaceRepository::new(pool.clone());
+        assert_eq!(repo.get_role(DEFAULT_WORKSPACE_ID, "alice").await.unwrap(), Some(WorkspaceRole::Owner));
+        assert_eq!(repo.count_flows(DEFAULT_WORKSPACE_ID).await.unwrap(), 1);
+
+        // It only runs once
+        let workspace = repo.create("Other", "alice").await.unwrap();
+        crate::connection::init_schema(&pool).await.unwrap();
+        assert_eq!(repo.list_for_user("alice").await.unwrap().len(), 2);
+        assert!(repo.get(&workspace.id).await.unwrap().is_some());
+    }
+}
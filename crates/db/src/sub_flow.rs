@@ -0,0 +1,63 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::sync::Arc;
+
+use flowmason_core::{BrickRegistry, FlowError, FlowRunner, FlowRunnerContext, SubFlowExecutor};
+use crate::repositories::{ExecutionRepository, FlowRepository};
+
+/// Runs flows targeted by RulesEngine `Branch` actions and records them as
+/// child executions of the execution that branched
+pub struct RepositorySubFlowExecutor {
+    flow_repo: Arc<FlowRepository>,
+    execution_repo: Arc<ExecutionRepository>,
+    brick_registry: Arc<BrickRegistry>,
+}
+
+impl RepositorySubFlowExecutor {
+    pub fn new(
+        flow_repo: Arc<FlowRepository>,
+        execution_repo: Arc<ExecutionRepository>,
+        brick_registry: Arc<BrickRegistry>,
+    ) -> Self {
+        Self { flow_repo, execution_repo, brick_registry }
+    }
+}
+
+#[async_trait]
+impl SubFlowExecutor for RepositorySubFlowExecutor {
+    async fn execute_sub_flow(
+        &self,
+        flow_id: &str,
+        payload: Value,
+        parent: &FlowRunnerContext,
+    ) -> Result<Value, FlowError> {
+        let context = parent.child(flow_id)?;
+
+        let flow = self.flow_repo.get(flow_id).await
+            .map_err(|e| FlowError::SubFlowError(format!("Failed to load flow {}: {}", flow_id, e)))?
+            .ok_or_else(|| FlowError::FlowNotFound(flow_id.to_string()))?;
+
+        // Flows can only branch to flows of their own workspace
+        if parent.workspace_id.is_some() && flow.workspace_id != parent.workspace_id {
+            return Err(FlowError::FlowNotFound(flow_id.to_string()));
+        }
+
+        if !flow.active {
+            return Err(FlowError::SubFlowError(format!("Flow {} is not active", flow_id)));
+        }
+
+        let bricks = self.brick_registry.create_all(&flow.bricks)?;
+        let execution = FlowRunner::execute_flow_with_tracking(&flow, bricks, payload, Some(context)).await?;
+
+        if let Err(e) = self.execution_repo.create(&execution).await {
+            tracing::warn!(
+                error = %e,
+                execution_id = %execution.execution_id,
+                parent_execution_id = %parent.execution_id,
+                "Failed to store sub-flow execution"
+            );
+        }
+
+        Ok(execution.output_payload.unwrap_or(Value::Null))
+    }
+}
@@ -0,0 +1,75 @@
+;; Sample FlowMason plugin brick.
+;;
+;; Wraps the brick input and config into `{"input": ..., "config": ...}`.
+;; Plugins exchange JSON through linear memory:
+;;   alloc(len) -> ptr                      host writes input/config here
+;;   name() -> i64                          packed (ptr << 32 | len) of a UTF-8 id
+;;   config_schema() -> i64                 packed JSON Schema
+;;   execute(in, in_len, cfg, cfg_len) -> i64
+;;                                          packed `{"ok": <output>}` or `{"error": "<message>"}`
+(module
+  (memory (export "memory") 1)
+  (global $heap (mut i32) (i32.const 4096))
+
+  (data (i32.const 0) "wasm_wrap")
+  (data (i32.const 64) "{\"type\":\"object\",\"properties\":{\"label\":{\"type\":\"string\",\"description\":\"Optional label, returned unchanged in the output\"}}}")
+  (data (i32.const 1024) "{\"ok\":{\"input\":")
+  (data (i32.const 1088) ",\"config\":")
+  (data (i32.const 1152) "}}")
+
+  ;; Bump allocator; memory is discarded after every call
+  (func $alloc (export "alloc") (param $len i32) (result i32)
+    (local $ptr i32)
+    (local $end i32)
+    (local $available i32)
+    (local.set $ptr (global.get $heap))
+    (local.set $end (i32.add (local.get $ptr) (local.get $len)))
+    (local.set $available (i32.mul (memory.size) (i32.const 65536)))
+    (if (i32.gt_u (local.get $end) (local.get $available))
+      (then
+        (if (i32.eq
+              (memory.grow
+                (i32.div_u
+                  (i32.add (i32.sub (local.get $end) (local.get $available)) (i32.const 65535))
+                  (i32.const 65536)))
+              (i32.const -1))
+          (then unreachable))))
+    (global.set $heap (i32.and (i32.add (local.get $end) (i32.const 7)) (i32.const -8)))
+    (local.get $ptr))
+
+  (func $pack (param $ptr i32) (param $len i32) (result i64)
+    (i64.or
+      (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+      (i64.extend_i32_u (local.get $len))))
+
+  (func (export "name") (result i64)
+    (call $pack (i32.const 0) (i32.const 9)))
+
+  (func (export "config_schema") (result i64)
+    (call $pack (i32.const 64) (i32.const 123)))
+
+  (func (export "execute")
+    (param $in i32) (param $in_len i32) (param $cfg i32) (param $cfg_len i32)
+    (result i64)
+    (local $out i32)
+    (local $len i32)
+    (local $pos i32)
+    (local.set $len
+      (i32.add
+        (i32.add (local.get $in_len) (local.get $cfg_len))
+        (i32.const 27)))
+    (local.set $out (call $alloc (local.get $len)))
+    (local.set $pos (local.get $out))
+
+    (memory.copy (local.get $pos) (i32.const 1024) (i32.const 15))
+    (local.set $pos (i32.add (local.get $pos) (i32.const 15)))
+    (memory.copy (local.get $pos) (local.get $in) (local.get $in_len))
+    (local.set $pos (i32.add (local.get $pos) (local.get $in_len)))
+    (memory.copy (local.get $pos) (i32.const 1088) (i32.const 10))
+    (local.set $pos (i32.add (local.get $pos) (i32.const 10)))
+    (memory.copy (local.get $pos) (local.get $cfg) (local.get $cfg_len))
+    (local.set $pos (i32.add (local.get $pos) (local.get $cfg_len)))
+    (memory.copy (local.get $pos) (i32.const 1152) (i32.const 2))
+
+    (call $pack (local.get $out) (local.get $len)))
+)
@@ -0,0 +1,263 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use flowmason_core::BrickRegistry;
+use serde_json::Value;
+use thiserror::Error;
+use wasmtime::{Config, Engine, Instance, Linker, Memory, Module, Store, StoreLimits, StoreLimitsBuilder, Trap, TypedFunc};
+
+use crate::wasm_brick::WasmBrick;
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Failed to compile plugin {path}: {message}")]
+    Compile { path: String, message: String },
+
+    #[error("Plugin ABI error: {0}")]
+    Abi(String),
+
+    #[error("Plugin exceeded its fuel limit")]
+    OutOfFuel,
+
+    #[error("Plugin trapped: {0}")]
+    Trap(String),
+}
+
+/// Resource limits applied to every plugin call
+#[derive(Debug, Clone, Copy)]
+pub struct PluginLimits {
+    /// Fuel units available to a single call (roughly one per wasm instruction)
+    pub fuel: u64,
+    /// Maximum linear memory a plugin instance may grow to
+    pub max_memory_bytes: usize,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 10_000_000,
+            max_memory_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+impl PluginLimits {
+    /// Reads `PLUGIN_FUEL` and `PLUGIN_MAX_MEMORY_BYTES`, falling back to defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            fuel: std::env::var("PLUGIN_FUEL")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.fuel),
+            max_memory_bytes: std::env::var("PLUGIN_MAX_MEMORY_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_memory_bytes),
+        }
+    }
+}
+
+/// A compiled plugin module together with the metadata it reported at load time
+pub(crate) struct LoadedPlugin {
+    pub(crate) id: String,
+    pub(crate) name: &'static str,
+    pub(crate) config_schema: Value,
+    engine: Engine,
+    module: Module,
+    limits: PluginLimits,
+}
+
+struct PluginState {
+    limits: StoreLimits,
+}
+
+/// A fresh, sandboxed instance of a plugin used for a single call
+struct PluginInstance {
+    store: Store<PluginState>,
+    instance: Instance,
+    memory: Memory,
+}
+
+/// Loads WebAssembly plugin bricks and registers them in a `BrickRegistry`
+pub struct PluginHost {
+    engine: Engine,
+    limits: PluginLimits,
+}
+
+impl PluginHost {
+    pub fn new(limits: PluginLimits) -> Result<Self, PluginError> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| PluginError::Abi(e.to_string()))?;
+        Ok(Self { engine, limits })
+    }
+
+    /// Loads a single `.wasm` (or `.wat`) module as a brick
+    pub fn load_file(&self, path: &Path) -> Result<WasmBrick, PluginError> {
+        let module = Module::from_file(&self.engine, path).map_err(|e| PluginError::Compile {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        self.load_module(module)
+    }
+
+    /// Loads a module from raw `.wasm` bytes or `.wat` text
+    pub fn load_bytes(&self, bytes: &[u8]) -> Result<WasmBrick, PluginError> {
+        let module = Module::new(&self.engine, bytes).map_err(|e| PluginError::Compile {
+            path: "<memory>".to_string(),
+            message: e.to_string(),
+        })?;
+        self.load_module(module)
+    }
+
+    fn load_module(&self, module: Module) -> Result<WasmBrick, PluginError> {
+        let mut plugin = LoadedPlugin {
+            id: String::new(),
+            name: "",
+            config_schema: Value::Null,
+            engine: self.engine.clone(),
+            module,
+            limits: self.limits,
+        };
+
+        let id = plugin.call_string_export("name")?;
+        if id.is_empty() {
+            return Err(PluginError::Abi("Plugin name cannot be empty".to_string()));
+        }
+        let schema = plugin.call_string_export("config_schema")?;
+        plugin.config_schema = serde_json::from_str(&schema)
+            .map_err(|e| PluginError::Abi(format!("config_schema is not valid JSON: {}", e)))?;
+        // Brick names are 'static; plugins are loaded once at startup
+        plugin.name = Box::leak(id.clone().into_boxed_str());
+        plugin.id = id;
+
+        Ok(WasmBrick::new(Arc::new(plugin)))
+    }
+
+    /// Loads every `.wasm` (or `.wat`) module in a directory and registers it under its plugin name.
+    /// Modules that fail to load are logged and skipped. Returns the registered ids.
+    pub fn register_dir(&self, dir: &Path, registry: &mut BrickRegistry) -> Result<Vec<String>, PluginError> {
+        let mut registered = Vec::new();
+        if !dir.exists() {
+            return Ok(registered);
+        }
+
+        let mut paths: Vec<_> = std::fs::read_dir(dir)?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| matches!(path.extension().and_then(|ext| ext.to_str()), Some("wasm" | "wat")))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match self.load_file(&path) {
+                Ok(brick) => {
+                    let id = brick.id().to_string();
+                    if registry.contains(&flowmason_core::BrickType::from_id(&id)) {
+                        tracing::warn!(plugin = %id, path = %path.display(), "Plugin overrides an already registered brick");
+                    }
+                    registry.register(id.clone(), move || Box::new(brick.clone()));
+                    tracing::info!(plugin = %id, path = %path.display(), "Registered plugin brick");
+                    registered.push(id);
+                }
+                Err(e) => {
+                    tracing::error!(path = %path.display(), error = %e, "Failed to load plugin");
+                }
+            }
+        }
+
+        Ok(registered)
+    }
+}
+
+impl LoadedPlugin {
+    fn instantiate(&self) -> Result<PluginInstance, PluginError> {
+        let state = PluginState {
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.limits.max_memory_bytes)
+                .instances(1)
+                .build(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.limits.fuel).map_err(|e| PluginError::Abi(e.to_string()))?;
+
+        // Plugins get no imports: no filesystem, network or clock access
+        let linker: Linker<PluginState> = Linker::new(&self.engine);
+        let instance = linker.instantiate(&mut store, &self.module).map_err(map_trap)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| PluginError::Abi("Plugin must export `memory`".to_string()))?;
+
+        Ok(PluginInstance { store, instance, memory })
+    }
+
+    fn call_string_export(&self, export: &str) -> Result<String, PluginError> {
+        let mut instance = self.instantiate()?;
+        let func: TypedFunc<(), i64> = instance.typed_func(export)?;
+        let packed = func.call(&mut instance.store, ()).map_err(map_trap)?;
+        let bytes = instance.read_packed(packed)?;
+        String::from_utf8(bytes).map_err(|e| PluginError::Abi(format!("{} is not UTF-8: {}", export, e)))
+    }
+
+    /// Calls `execute` with JSON input and config and returns the raw JSON response
+    pub(crate) fn call_execute(&self, input: &[u8], config: &[u8]) -> Result<Vec<u8>, PluginError> {
+        let mut instance = self.instantiate()?;
+        let input_ptr = instance.write(input)?;
+        let config_ptr = instance.write(config)?;
+
+        let execute: TypedFunc<(i32, i32, i32, i32), i64> = instance.typed_func("execute")?;
+        let packed = execute
+            .call(
+                &mut instance.store,
+                (input_ptr, input.len() as i32, config_ptr, config.len() as i32),
+            )
+            .map_err(map_trap)?;
+
+        instance.read_packed(packed)
+    }
+}
+
+impl PluginInstance {
+    fn typed_func<P, R>(&mut self, name: &str) -> Result<TypedFunc<P, R>, PluginError>
+    where
+        P: wasmtime::WasmParams,
+        R: wasmtime::WasmResults,
+    {
+        self.instance
+            .get_typed_func::<P, R>(&mut self.store, name)
+            .map_err(|e| PluginError::Abi(format!("Invalid `{}` export: {}", name, e)))
+    }
+
+    /// Copies bytes into plugin memory using its `alloc` export
+    fn write(&mut self, bytes: &[u8]) -> Result<i32, PluginError> {
+        let alloc: TypedFunc<i32, i32> = self.typed_func("alloc")?;
+        let ptr = alloc.call(&mut self.store, bytes.len() as i32).map_err(map_trap)?;
+        self.memory
+            .write(&mut self.store, ptr as u32 as usize, bytes)
+            .map_err(|e| PluginError::Abi(format!("alloc returned an invalid pointer: {}", e)))?;
+        Ok(ptr)
+    }
+
+    /// Reads a `(ptr << 32) | len` region from plugin memory
+    fn read_packed(&mut self, packed: i64) -> Result<Vec<u8>, PluginError> {
+        let ptr = ((packed as u64) >> 32) as usize;
+        let len = (packed as u64 & 0xffff_ffff) as usize;
+        let mut buffer = vec![0u8; len];
+        self.memory
+            .read(&self.store, ptr, &mut buffer)
+            .map_err(|e| PluginError::Abi(format!("Plugin returned an out-of-bounds region: {}", e)))?;
+        Ok(buffer)
+    }
+}
+
+fn map_trap(error: wasmtime::Error) -> PluginError {
+    match error.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => PluginError::OutOfFuel,
+        Some(trap) => PluginError::Trap(trap.to_string()),
+        None => PluginError::Trap(error.to_string()),
+    }
+}
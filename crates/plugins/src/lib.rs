@@ -0,0 +1,26 @@
+pub mod host;
+pub mod wasm_brick;
+
+pub use host::{PluginError, PluginHost, PluginLimits};
+pub use wasm_brick::WasmBrick;
+
+use flowmason_core::BrickRegistry;
+
+/// Registers WebAssembly plugin bricks found in `PLUGINS_DIR` (default `plugins`),
+/// using limits from `PluginLimits::from_env`. Failures are logged, not returned.
+pub fn register_from_env(registry: &mut BrickRegistry) {
+    let dir = std::env::var("PLUGINS_DIR").unwrap_or_else(|_| "plugins".to_string());
+    let host = match PluginHost::new(PluginLimits::from_env()) {
+        Ok(host) => host,
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to initialize plugin host");
+            return;
+        }
+    };
+
+    match host.register_dir(std::path::Path::new(&dir), registry) {
+        Ok(ids) if !ids.is_empty() => tracing::info!(plugins = ?ids, dir = %dir, "Loaded plugin bricks"),
+        Ok(_) => {}
+        Err(e) => tracing::error!(error = %e, dir = %dir, "Failed to read plugins directory"),
+    }
+}
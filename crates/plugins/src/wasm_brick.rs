@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use flowmason_core::{Brick, BrickError, BrickType};
+use serde_json::Value;
+
+use crate::host::LoadedPlugin;
+
+/// A brick backed by a WebAssembly plugin
+///
+/// Every call runs in a fresh instance with the host's fuel and memory limits,
+/// on a blocking thread so long-running plugins do not stall the runtime.
+#[derive(Clone)]
+pub struct WasmBrick {
+    plugin: Arc<LoadedPlugin>,
+}
+
+impl WasmBrick {
+    pub(crate) fn new(plugin: Arc<LoadedPlugin>) -> Self {
+        Self { plugin }
+    }
+
+    /// Id the plugin reported through its `name` export
+    pub fn id(&self) -> &str {
+        &self.plugin.id
+    }
+}
+
+#[async_trait]
+impl Brick for WasmBrick {
+    fn name(&self) -> &'static str {
+        self.plugin.name
+    }
+
+    fn brick_type(&self) -> BrickType {
+        BrickType::Custom(self.plugin.id.clone())
+    }
+
+    fn config_schema(&self) -> Value {
+        self.plugin.config_schema.clone()
+    }
+
+    async fn execute(&self, input: Value, config: Value) -> Result<Value, BrickError> {
+        let input = serde_json::to_vec(&input)
+            .map_err(|e| BrickError::InvalidInput(e.to_string()))?;
+        let config = serde_json::to_vec(&config)
+            .map_err(|e| BrickError::ConfigError(e.to_string()))?;
+        let plugin = self.plugin.clone();
+
+        let response = tokio::task::spawn_blocking(move || plugin.call_execute(&input, &config))
+            .await
+            .map_err(|e| BrickError::ExecutionError(format!("Plugin task failed: {}", e)))?
+            .map_err(|e| BrickError::ExecutionError(e.to_string()))?;
+
+        let mut response: Value = serde_json::from_slice(&response)
+            .map_err(|e| BrickError::ExecutionError(format!("Plugin returned invalid JSON: {}", e)))?;
+
+        if let Some(message) = response.get("error") {
+            let message = message.as_str().map(str::to_string).unwrap_or_else(|| message.to_string());
+            return Err(BrickError::ExecutionError(message));
+        }
+
+        response
+            .get_mut("ok")
+            .map(Value::take)
+            .ok_or_else(|| BrickError::ExecutionError(
+                "Plugin response must contain `ok` or `error`".to_string(),
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::{PluginHost, PluginLimits};
+    use flowmason_core::{BrickRegistry, FlowRunner};
+    use serde_json::json;
+    use std::path::Path;
+
+    fn samples_dir() -> &'static Path {
+        Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/samples"))
+    }
+
+    #[tokio::test]
+    async fn test_sample_plugin_runs_in_flow_runner() {
+        let host = PluginHost::new(PluginLimits::default()).unwrap();
+        let mut registry = BrickRegistry::new();
+        let ids = host.register_dir(samples_dir(), &mut registry).unwrap();
+        assert_eq!(ids, vec!["wasm_wrap".to_string()]);
+
+        let brick_type = BrickType::from_id("wasm_wrap");
+        let brick = registry.create(&brick_type).unwrap();
+        assert_eq!(brick.name(), "wasm_wrap");
+        assert_eq!(brick.brick_type(), brick_type);
+        assert_eq!(brick.config_schema()["properties"]["label"]["type"], "string");
+
+        let output = FlowRunner::execute_flow(
+            vec![brick],
+            vec![json!({"label": "sample"})],
+            json!({"message": "hello"}),
+            None,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            output,
+            json!({"input": {"message": "hello"}, "config": {"label": "sample"}})
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fuel_limit_stops_runaway_plugin() {
+        let wat = r#"
+            (module
+              (memory (export "memory") 1)
+              (data (i32.const 0) "spin")
+              (data (i32.const 16) "{}")
+              (func (export "alloc") (param i32) (result i32) i32.const 1024)
+              (func (export "name") (result i64) i64.const 4)
+              (func (export "config_schema") (result i64) i64.const 0x1000000002)
+              (func (export "execute") (param i32 i32 i32 i32) (result i64)
+                (loop $forever (br $forever))
+                i64.const 0))
+        "#;
+        let host = PluginHost::new(PluginLimits { fuel: 100_000, ..PluginLimits::default() }).unwrap();
+        let brick = host.load_bytes(wat.as_bytes()).unwrap();
+        assert_eq!(brick.id(), "spin");
+
+        let result = brick.execute(json!({}), json!({})).await;
+        assert!(matches!(result, Err(BrickError::ExecutionError(msg)) if msg.contains("fuel")));
+    }
+
+    #[tokio::test]
+    async fn test_memory_limit_is_enforced() {
+        let wat = r#"
+            (module
+              (memory (export "memory") 1)
+              (data (i32.const 0) "grow")
+              (data (i32.const 16) "{}")
+              (func (export "alloc") (param i32) (result i32) i32.const 1024)
+              (func (export "name") (result i64) i64.const 4)
+              (func (export "config_schema") (result i64) i64.const 0x1000000002)
+              (func (export "execute") (param i32 i32 i32 i32) (result i64)
+                (if (i32.eq (memory.grow (i32.const 64)) (i32.const -1))
+                  (then unreachable))
+                i64.const 0))
+        "#;
+        let host = PluginHost::new(PluginLimits {
+            max_memory_bytes: 2 * 65536,
+            ..PluginLimits::default()
+        })
+        .unwrap();
+        let brick = host.load_bytes(wat.as_bytes()).unwrap();
+
+        let result = brick.execute(json!({}), json!({})).await;
+        assert!(matches!(result, Err(BrickError::ExecutionError(_))));
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+
+use flowmason_core::quota::QuotaManager;
+use flowmason_core::secrets::contains_redacted;
+use flowmason_core::types::FlowExecution;
+use flowmason_core::{
+    BrickRegistry, ConnectionResolver, ExecutionDataStorage, FlowRunner, FlowRunnerContext, SecretResolver, SubFlowExecutor,
+    UsageLogger,
+};
+use flowmason_db::repositories::{
+    DeadLetter, DeadLetterRepository, DeadLetterStatus, ExecutionDataRepository, ExecutionRepository, FlowRepository,
+};
+
+/// Number of due dead-letter entries replayed per retrier tick
+const RETRY_BATCH_SIZE: u32 = 10;
+
+/// Result of replaying a dead-letter entry
+pub struct ReplayOutcome {
+    /// Status of the entry after the replay
+    pub status: DeadLetterStatus,
+    /// The replayed execution, if the flow could be started
+    pub execution: Option<FlowExecution>,
+}
+
+/// Replays failed executions from the dead-letter queue
+///
+/// Each replay runs the original input through the current version of the
+/// flow and is stored as a new execution linked to the failed one.
+pub struct DeadLetterReplayer {
+    pub flow_repo: Arc<FlowRepository>,
+    pub execution_repo: Arc<ExecutionRepository>,
+    pub execution_data_repo: Arc<ExecutionDataRepository>,
+    pub dead_letter_repo: Arc<DeadLetterRepository>,
+    pub quota_manager: Arc<dyn QuotaManager>,
+    pub usage_logger: Arc<dyn UsageLogger>,
+    pub sub_flow_executor: Arc<dyn SubFlowExecutor>,
+    pub secret_resolver: Arc<dyn SecretResolver>,
+    pub connection_resolver: Arc<dyn ConnectionResolver>,
+    pub brick_registry: Arc<BrickRegistry>,
+}
+
+impl DeadLetterReplayer {
+    /// Replays an entry previously claimed with `DeadLetterRepository::claim`
+    /// or `claim_due`, and records the outcome on the entry
+    pub async fn replay(&self, entry: &DeadLetter) -> Result<ReplayOutcome> {
+        let flow = self.flow_repo.get(&entry.flow_id).await?;
+        let policy = flow.as_ref().and_then(|f| f.retry_policy.clone()).unwrap_or_default();
+
+        let original = self.execution_repo.get(&entry.execution_id).await?;
+        let (flow, original) = match (flow, original) {
+            (Some(flow), Some(original)) => (flow, original),
+            (None, _) => {
+                let status = self.dead_letter_repo
+                    .finish_replay(entry, None, Some("Flow no longer exists"), &policy)
+                    .await?;
+                return Ok(ReplayOutcome { status, execution: None });
+            }
+            (_, None) => {
+                let status = self.dead_letter_repo
+                    .finish_replay(entry, None, Some("Original execution no longer exists"), &policy)
+                    .await?;
+                return Ok(ReplayOutcome { status, execution: None });
+            }
+        };
+
+        // Secret values were replaced in the stored input; replaying it would
+        // send `[REDACTED]` on as data, however often it is retried
+        if contains_redacted(&original.input_payload) {
+            tracing::warn!(dead_letter_id = %entry.id, execution_id = %entry.execution_id, "Cannot replay an execution whose stored input had secrets redacted");
+            let status = self.dead_letter_repo
+                .abandon_replay(entry, "The input of the execution had secret values redacted and cannot be replayed; start a new execution instead")
+                .await?;
+            return Ok(ReplayOutcome { status, execution: None });
+        }
+
+        let bricks = match self.brick_registry.create_all(&flow.bricks) {
+            Ok(bricks) => bricks,
+            Err(e) => {
+                let status = self.dead_letter_repo
+                    .finish_replay(entry, None, Some(&e.to_string()), &policy)
+                    .await?;
+                return Ok(ReplayOutcome { status, execution: None });
+            }
+        };
+
+        let execution_data_storage: Arc<dyn ExecutionDataStorage> = self.execution_data_repo.clone();
+        let context = FlowRunnerContext {
+            quota_manager: Some(self.quota_manager.clone()),
+            usage_logger: Some(self.usage_logger.clone()),
+            execution_data_storage: Some(execution_data_storage),
+            sub_flow_executor: Some(self.sub_flow_executor.clone()),
+            brick_registry: Some(self.brick_registry.clone()),
+            event_bus: None,
+            flow_id: flow.id.clone(),
+            execution_id: String::new(), // Will be set in execute_flow_recorded
+            workspace_id: flow.workspace_id.clone(),
+            parent_execution_id: None,
+            depth: 0,
+            variables: Default::default(),
+            secret_resolver: Some(self.secret_resolver.clone()),
+            connection_resolver: Some(self.connection_resolver.clone()),
+            redactor: Default::default(),
+            responder: None,
+        };
+
+        let mut execution = FlowRunner::execute_flow_recorded(&flow, bricks, original.input_payload, Some(context)).await;
+        execution.replay_of_execution_id = Some(entry.execution_id.clone());
+        self.execution_repo.create(&execution).await?;
+
+        let status = self.dead_letter_repo
+            .finish_replay(entry, Some(&execution.execution_id), execution.error.as_deref(), &policy)
+            .await?;
+
+        tracing::info!(
+            dead_letter_id = %entry.id,
+            execution_id = %entry.execution_id,
+            replay_execution_id = %execution.execution_id,
+            status = status.as_str(),
+            "Replayed dead-lettered execution"
+        );
+
+        Ok(ReplayOutcome { status, execution: Some(execution) })
+    }
+
+    /// Replays due entries every `interval` in the background
+    pub fn spawn_retrier(self: Arc<Self>, interval: Duration) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+
+                let entries = match self.dead_letter_repo.claim_due(RETRY_BATCH_SIZE).await {
+                    Ok(entries) => entries,
+                    Err(e) => {
+                        tracing::error!(error = %e, "Failed to claim due dead-letter entries");
+                        continue;
+                    }
+                };
+
+                for entry in entries {
+                    if let Err(e) = self.replay(&entry).await {
+                        tracing::error!(error = %e, dead_letter_id = %entry.id, "Failed to replay dead-lettered execution");
+                    }
+                }
+            }
+        });
+    }
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use flowmason_core::connections::ConnectionOption;
 use flowmason_core::types::BrickType;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,6 +8,12 @@ pub struct BrickSchemaResponse {
     pub brick_type: BrickType,
     pub name: String,
     pub config_schema: Value,
+    /// Type of the saved connections the brick accepts as `connection_id`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_type: Option<String>,
+    /// Saved connections of that type
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub connections: Vec<ConnectionOption>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
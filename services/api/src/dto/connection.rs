@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use flowmason_db::repositories::Connection;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateConnectionRequest {
+    pub name: String,
+    /// Type of the bricks that can use the connection, e.g. `hubspot`
+    pub connection_type: String,
+    pub base_url: Option<String>,
+    #[serde(default)]
+    pub credentials: Map<String, Value>,
+    /// Config values used when a brick config does not set them
+    #[serde(default)]
+    pub defaults: Map<String, Value>,
+}
+
+/// Omitted fields are left unchanged; `credentials` replaces all credentials
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateConnectionRequest {
+    pub name: Option<String>,
+    /// An empty string removes the base URL
+    pub base_url: Option<String>,
+    pub credentials: Option<Map<String, Value>>,
+    pub defaults: Option<Map<String, Value>>,
+}
+
+/// A saved connection; credentials are write-only and never returned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionResponse {
+    pub id: String,
+    pub name: String,
+    pub connection_type: String,
+    pub base_url: Option<String>,
+    pub defaults: Map<String, Value>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<Connection> for ConnectionResponse {
+    fn from(connection: Connection) -> Self {
+        Self {
+            id: connection.id,
+            name: connection.name,
+            connection_type: connection.connection_type,
+            base_url: connection.base_url,
+            defaults: connection.defaults,
+            created_at: connection.created_at.to_rfc3339(),
+            updated_at: connection.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConnectionListQuery {
+    pub connection_type: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionTestResponse {
+    pub success: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use flowmason_db::repositories::{DeadLetter, DeadLetterStatus};
+
+use crate::dto::FlowExecutionResponse;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterResponse {
+    pub id: String,
+    pub execution_id: String,
+    pub flow_id: String,
+    pub error_message: String,
+    pub status: DeadLetterStatus,
+    pub retry_count: i64,
+    pub max_retries: i64,
+    pub next_retry_at: Option<String>,
+    pub last_attempt_at: String,
+    pub last_replay_execution_id: Option<String>,
+    pub created_at: String,
+}
+
+impl From<DeadLetter> for DeadLetterResponse {
+    fn from(entry: DeadLetter) -> Self {
+        Self {
+            id: entry.id,
+            execution_id: entry.execution_id,
+            flow_id: entry.flow_id,
+            error_message: entry.error_message,
+            status: entry.status,
+            retry_count: entry.retry_count,
+            max_retries: entry.max_retries,
+            next_retry_at: entry.next_retry_at.map(|d| d.to_rfc3339()),
+            last_attempt_at: entry.last_attempt_at.to_rfc3339(),
+            last_replay_execution_id: entry.last_replay_execution_id,
+            created_at: entry.created_at.to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplayResponse {
+    pub dead_letter: DeadLetterResponse,
+    /// The replayed execution; absent when the flow could not be started
+    pub execution: Option<FlowExecutionResponse>,
+}
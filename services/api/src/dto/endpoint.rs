@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use flowmason_db::repositories::FlowEndpoint;
+
+/// Method and path to publish a flow under, e.g. `POST /acme/leads`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConfigureEndpointRequest {
+    pub method: String,
+    /// Path below `/hooks`
+    pub path: String,
+    /// JSON Schema requests must match; calls that do not are rejected with 422
+    pub request_schema: Option<serde_json::Value>,
+}
+
+/// Custom endpoint of a flow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndpointResponse {
+    pub flow_id: String,
+    pub method: String,
+    pub path: String,
+    /// Path the endpoint is served at, including the `/hooks` prefix
+    pub url: String,
+    pub request_schema: Option<serde_json::Value>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<FlowEndpoint> for EndpointResponse {
+    fn from(endpoint: FlowEndpoint) -> Self {
+        Self {
+            url: format!("/hooks{}", endpoint.path),
+            flow_id: endpoint.flow_id,
+            method: endpoint.method,
+            path: endpoint.path,
+            request_schema: endpoint.request_schema,
+            created_at: endpoint.created_at.to_rfc3339(),
+            updated_at: endpoint.updated_at.to_rfc3339(),
+        }
+    }
+}
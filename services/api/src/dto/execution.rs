@@ -1,11 +1,76 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use flowmason_core::types::{ExecutionStatus, FlowExecution as CoreFlowExecution};
+use flowmason_core::types::{BrickType, ExecutionStatus, ExecutionStep, FlowExecution as CoreFlowExecution, StepStatus};
+use flowmason_db::repositories::{Job, JobStatus};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExecuteFlowRequest {
     pub flow_id: String,
     pub input_payload: Value,
+    #[serde(default)]
+    pub mode: ExecutionMode,
+}
+
+/// Options for resuming a failed execution
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResumeExecutionRequest {
+    /// Brick whose config is replaced; defaults to the brick that failed
+    #[serde(default)]
+    pub brick_index: Option<usize>,
+    /// Config used for the brick in the resumed execution only
+    #[serde(default)]
+    pub config: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionMode {
+    /// Run the flow within the request and return the execution
+    #[default]
+    Sync,
+    /// Enqueue the flow for a worker and return the job id immediately
+    Async,
+    /// Run the flow in the API process and return the execution id
+    /// immediately; progress is streamed from `/executions/:id/events`
+    Background,
+}
+
+/// Execution started in `background` mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundExecutionResponse {
+    pub execution_id: String,
+    pub flow_id: String,
+    /// Server-Sent Events stream of the execution's progress
+    pub events_url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobResponse {
+    pub job_id: String,
+    pub flow_id: String,
+    pub status: JobStatus,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub execution_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<Job> for JobResponse {
+    fn from(job: Job) -> Self {
+        Self {
+            job_id: job.id,
+            flow_id: job.flow_id,
+            status: job.status,
+            attempts: job.attempts,
+            max_attempts: job.max_attempts,
+            execution_id: job.execution_id,
+            error: job.error,
+            created_at: job.created_at.to_rfc3339(),
+            updated_at: job.updated_at.to_rfc3339(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +83,13 @@ pub struct FlowExecutionResponse {
     pub input_payload: Value,
     pub output_payload: Option<Value>,
     pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_execution_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replay_of_execution_id: Option<String>,
+    /// Step records; only present for executions that ran in the request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<ExecutionStepResponse>,
 }
 
 impl From<CoreFlowExecution> for FlowExecutionResponse {
@@ -31,6 +103,38 @@ impl From<CoreFlowExecution> for FlowExecutionResponse {
             input_payload: exec.input_payload,
             output_payload: exec.output_payload,
             error: exec.error,
+            parent_execution_id: exec.parent_execution_id,
+            replay_of_execution_id: exec.replay_of_execution_id,
+            steps: exec.steps.into_iter().map(ExecutionStepResponse::from).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecutionStepResponse {
+    pub brick_index: usize,
+    pub brick_type: BrickType,
+    pub status: StepStatus,
+    pub input: Value,
+    pub output: Option<Value>,
+    pub error: Option<String>,
+    pub attempts: u32,
+    pub started_at: String,
+    pub duration_ms: u64,
+}
+
+impl From<ExecutionStep> for ExecutionStepResponse {
+    fn from(step: ExecutionStep) -> Self {
+        Self {
+            brick_index: step.brick_index,
+            brick_type: step.brick_type,
+            status: step.status,
+            input: step.input,
+            output: step.output,
+            error: step.error,
+            attempts: step.attempts,
+            started_at: step.started_at.to_rfc3339(),
+            duration_ms: step.duration_ms,
         }
     }
 }
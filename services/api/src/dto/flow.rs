@@ -1,12 +1,26 @@
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
-use flowmason_core::types::{BrickType, Flow as CoreFlow};
+use serde_json::{Map, Value};
+use flowmason_core::secrets::redact_flow_credentials;
+use flowmason_core::types::{BrickPolicy, BrickType, Flow as CoreFlow, FlowGraph, FlowNodeKind, FlowSchema, RetryPolicy};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateFlowRequest {
     pub name: String,
     pub description: Option<String>,
+    #[serde(default)]
     pub bricks: Vec<BrickConfigDto>,
+    /// Graph form of the flow; when set, `bricks` is derived from its brick nodes
+    #[serde(default)]
+    pub graph: Option<FlowGraph>,
+    /// Retry settings for failed executions; defaults apply when absent
+    #[serde(default)]
+    pub retry_policy: Option<RetryPolicy>,
+    /// Flow variables, available to brick config templates as `vars`
+    #[serde(default)]
+    pub variables: Map<String, Value>,
+    /// JSON Schemas of the flow's input and output payloads
+    #[serde(default)]
+    pub schema: FlowSchema,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,13 +28,30 @@ pub struct UpdateFlowRequest {
     pub name: Option<String>,
     pub description: Option<String>,
     pub bricks: Option<Vec<BrickConfigDto>>,
+    #[serde(default)]
+    pub graph: Option<FlowGraph>,
+    #[serde(default)]
+    pub retry_policy: Option<RetryPolicy>,
+    /// Replaces the flow variables when set
+    #[serde(default)]
+    pub variables: Option<Map<String, Value>>,
+    /// Replaces the payload schemas when set
+    #[serde(default)]
+    pub schema: Option<FlowSchema>,
     pub active: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrickConfigDto {
+    /// Id of the brick's graph node; sending it back keeps masked
+    /// credentials with their brick when bricks are reordered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
     pub brick_type: BrickType,
     pub config: Value,
+    /// Retries, timeout and error handling for the brick
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<BrickPolicy>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,21 +60,49 @@ pub struct FlowResponse {
     pub name: String,
     pub description: Option<String>,
     pub bricks: Vec<BrickConfigDto>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub graph: Option<FlowGraph>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_policy: Option<RetryPolicy>,
+    #[serde(skip_serializing_if = "Map::is_empty")]
+    pub variables: Map<String, Value>,
+    #[serde(skip_serializing_if = "FlowSchema::is_empty")]
+    pub schema: FlowSchema,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workspace_id: Option<String>,
     pub active: bool,
     pub created_at: String,
     pub updated_at: String,
 }
 
+/// Credentials stored in plain text in brick configs are masked; secret
+/// references are returned as they are
 impl From<CoreFlow> for FlowResponse {
-    fn from(flow: CoreFlow) -> Self {
+    fn from(mut flow: CoreFlow) -> Self {
+        redact_flow_credentials(&mut flow);
+        // `bricks` lists the graph's brick nodes in order
+        let node_ids: Vec<Option<String>> = match flow.graph {
+            Some(ref graph) if graph.brick_configs().len() == flow.bricks.len() => graph.nodes.iter()
+                .filter(|node| matches!(node.kind, FlowNodeKind::Brick(_)))
+                .map(|node| Some(node.id.clone()))
+                .collect(),
+            _ => vec![None; flow.bricks.len()],
+        };
         Self {
             id: flow.id,
             name: flow.name,
             description: flow.description,
-            bricks: flow.bricks.into_iter().map(|b| BrickConfigDto {
+            bricks: flow.bricks.into_iter().zip(node_ids).map(|(b, id)| BrickConfigDto {
+                id,
                 brick_type: b.brick_type,
                 config: b.config,
+                policy: b.policy,
             }).collect(),
+            graph: flow.graph,
+            retry_policy: flow.retry_policy,
+            variables: flow.variables,
+            schema: flow.schema,
+            workspace_id: flow.workspace_id,
             active: flow.active,
             created_at: flow.created_at.to_rfc3339(),
             updated_at: flow.updated_at.to_rfc3339(),
@@ -51,3 +110,23 @@ impl From<CoreFlow> for FlowResponse {
     }
 }
 
+
+/// Input and output schemas of a flow, for generating clients
+///
+/// A missing schema is returned as `null`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowSchemaResponse {
+    pub flow_id: String,
+    pub input: Option<Value>,
+    pub output: Option<Value>,
+}
+
+impl From<CoreFlow> for FlowSchemaResponse {
+    fn from(flow: CoreFlow) -> Self {
+        Self {
+            flow_id: flow.id,
+            input: flow.schema.input,
+            output: flow.schema.output,
+        }
+    }
+}
@@ -5,6 +5,12 @@ pub mod usage;
 pub mod scheduler;
 pub mod pagination;
 pub mod template;
+pub mod dead_letter;
+pub mod secret;
+pub mod connection;
+pub mod workspace;
+pub mod webhook;
+pub mod endpoint;
 
 pub use flow::*;
 pub use brick::*;
@@ -13,4 +19,9 @@ pub use usage::*;
 pub use scheduler::*;
 pub use pagination::*;
 pub use template::*;
-
+pub use dead_letter::*;
+pub use secret::*;
+pub use connection::*;
+pub use workspace::*;
+pub use webhook::*;
+pub use endpoint::*;
@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+use flowmason_db::repositories::Secret;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateSecretRequest {
+    /// Name used in `{{ secret:<name> }}` references
+    pub name: String,
+    pub value: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateSecretRequest {
+    pub value: String,
+    pub description: Option<String>,
+}
+
+/// A stored secret; values are write-only and never returned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecretResponse {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Template expression referring to the secret
+    pub reference: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<Secret> for SecretResponse {
+    fn from(secret: Secret) -> Self {
+        Self {
+            reference: format!("{{{{ secret:{} }}}}", secret.name),
+            id: secret.id,
+            name: secret.name,
+            description: secret.description,
+            created_at: secret.created_at.to_rfc3339(),
+            updated_at: secret.updated_at.to_rfc3339(),
+        }
+    }
+}
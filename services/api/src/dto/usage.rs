@@ -47,18 +47,7 @@ pub struct UsageStatsResponse {
 
 impl UsageStatsResponse {
     pub fn from_brick_type(brick_type: BrickType, daily_usage: u64, daily_limit: u64, monthly_usage: Option<u64>, monthly_limit: Option<u64>) -> Self {
-        // Convert enum to snake_case string
-        let brick_type_str = match brick_type {
-            BrickType::OpenAi => "openai",
-            BrickType::Nvidia => "nvidia",
-            BrickType::HubSpot => "hubspot",
-            BrickType::Notion => "notion",
-            BrickType::Odoo => "odoo",
-            BrickType::N8n => "n8n",
-            BrickType::FieldMapping => "field_mapping",
-            BrickType::CombineText => "combine_text",
-            BrickType::Conditional => "conditional",
-        }.to_string();
+        let brick_type_str = brick_type.id().to_string();
         
         Self {
             brick_type: brick_type_str,
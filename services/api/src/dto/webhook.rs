@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use flowmason_auth::WebhookAuth;
+use flowmason_db::repositories::WebhookSettings;
+
+/// Authentication mode and settings, e.g. `{"mode": "hmac", "tolerance_secs": 300}`
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConfigureWebhookRequest {
+    #[serde(flatten)]
+    pub auth: WebhookAuth,
+    /// Secret issued by the calling service; generated when omitted
+    pub secret: Option<String>,
+    /// Payload path holding the idempotency key of calls without an
+    /// `Idempotency-Key` header, e.g. `event.id`
+    pub idempotency_key_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RotateWebhookSecretRequest {
+    /// New secret; generated when omitted
+    pub secret: Option<String>,
+    /// How long the replaced secret is still accepted
+    #[serde(default)]
+    pub grace_period_secs: u64,
+}
+
+/// Webhook authentication of a flow; the secret is only returned when set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookResponse {
+    pub flow_id: String,
+    #[serde(flatten)]
+    pub auth: WebhookAuth,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    pub previous_secret_expires_at: Option<String>,
+    pub idempotency_key_path: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<WebhookSettings> for WebhookResponse {
+    fn from(settings: WebhookSettings) -> Self {
+        Self {
+            flow_id: settings.flow_id,
+            auth: settings.auth,
+            secret: None,
+            previous_secret_expires_at: settings.previous_secret_expires_at.map(|d| d.to_rfc3339()),
+            idempotency_key_path: settings.idempotency_key_path,
+            created_at: settings.created_at.to_rfc3339(),
+            updated_at: settings.updated_at.to_rfc3339(),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use flowmason_auth::WorkspaceRole;
+use flowmason_db::repositories::{Workspace, WorkspaceMember};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CreateWorkspaceRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateWorkspaceRequest {
+    pub name: String,
+}
+
+/// A workspace with the caller's role in it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceResponse {
+    pub id: String,
+    pub name: String,
+    pub role: WorkspaceRole,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl WorkspaceResponse {
+    pub fn new(workspace: Workspace, role: WorkspaceRole) -> Self {
+        Self {
+            id: workspace.id,
+            name: workspace.name,
+            role,
+            created_at: workspace.created_at.to_rfc3339(),
+            updated_at: workspace.updated_at.to_rfc3339(),
+        }
+    }
+}
+
+/// Adds an existing user to a workspace
+#[derive(Debug, Clone, Deserialize)]
+pub struct AddMemberRequest {
+    pub email: String,
+    pub role: WorkspaceRole,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdateMemberRequest {
+    pub role: WorkspaceRole,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemberResponse {
+    pub user_id: String,
+    pub email: String,
+    pub role: WorkspaceRole,
+    pub created_at: String,
+}
+
+impl From<WorkspaceMember> for MemberResponse {
+    fn from(member: WorkspaceMember) -> Self {
+        Self {
+            user_id: member.user_id,
+            email: member.email,
+            role: member.role,
+            created_at: member.created_at.to_rfc3339(),
+        }
+    }
+}
@@ -3,6 +3,7 @@ use axum::{
     response::{IntoResponse, Response},
     Json,
 };
+use flowmason_core::SchemaError;
 use serde_json::json;
 use thiserror::Error;
 
@@ -28,11 +29,24 @@ pub enum ApiError {
     
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
+
+    /// A payload that does not match its JSON Schema; every mismatch is listed
+    #[error("Validation failed: {0}")]
+    Validation(String, Vec<SchemaError>),
 }
 
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         let (status, error_message) = match self {
+            ApiError::Validation(msg, errors) => {
+                let status = StatusCode::UNPROCESSABLE_ENTITY;
+                let body = Json(json!({
+                    "error": msg,
+                    "status": status.as_u16(),
+                    "errors": errors,
+                }));
+                return (status, body).into_response();
+            }
             ApiError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             ApiError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             ApiError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
@@ -0,0 +1,181 @@
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::Response,
+};
+use flowmason_db::repositories::{IdempotencyClaim, IdempotencyRepository, StoredResponse};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::sync::Arc;
+
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+/// Set on responses returned for a repeated idempotency key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+const MAX_KEY_LEN: usize = 255;
+const DEFAULT_TTL_SECS: i64 = 24 * 60 * 60;
+const DEFAULT_CLAIM_LEASE_SECS: i64 = 60;
+
+/// How long idempotency keys are kept, from `IDEMPOTENCY_KEY_TTL_SECS`
+pub fn key_ttl() -> chrono::Duration {
+    let secs = std::env::var("IDEMPOTENCY_KEY_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_TTL_SECS);
+    chrono::Duration::seconds(secs)
+}
+
+/// How long a key being handled stays claimed without renewal, from
+/// `IDEMPOTENCY_CLAIM_LEASE_SECS`; renewed while the request is handled
+pub fn claim_lease() -> chrono::Duration {
+    let secs = std::env::var("IDEMPOTENCY_CLAIM_LEASE_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_CLAIM_LEASE_SECS);
+    chrono::Duration::seconds(secs)
+}
+
+/// The `Idempotency-Key` header of a request; fails with 400 Bad Request if
+/// it is empty, too long or not printable ASCII
+pub fn key_from_headers(headers: &HeaderMap) -> Result<Option<String>, StatusCode> {
+    match headers.get(IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => validate_key(value.to_str().map_err(|_| StatusCode::BAD_REQUEST)?).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Checks a key taken from a header or payload
+pub fn validate_key(key: &str) -> Result<String, StatusCode> {
+    let key = key.trim();
+    if key.is_empty() || key.len() > MAX_KEY_LEN || !key.chars().all(|c| c.is_ascii_graphic() || c == ' ') {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(key.to_string())
+}
+
+/// Hash identifying a request, to detect a key reused for another request
+pub fn fingerprint(request: &[u8]) -> String {
+    hex::encode(Sha256::digest(request))
+}
+
+/// Handles a request at most once per idempotency key
+///
+/// Repeats of a handled request get the recorded response with an
+/// `Idempotent-Replayed` header, repeats arriving while it is handled get 409
+/// Conflict, and a key reused for another request gets 422 Unprocessable
+/// Entity. Server errors are recorded like responses, as the flow may have
+/// run; other errors free the key so a corrected request can use it.
+///
+/// The request is handled in its own task, so it completes and is recorded
+/// even if the client disconnects. Its claim is renewed meanwhile; if the
+/// process stops before the response is recorded, the key is free again once
+/// the claim lease runs out.
+pub async fn run_once<F>(
+    repo: Arc<IdempotencyRepository>,
+    scope: String,
+    key: String,
+    fingerprint: Option<String>,
+    handle: F,
+) -> Result<Response, StatusCode>
+where
+    F: Future<Output = Result<Response, StatusCode>> + Send + 'static,
+{
+    let lease = claim_lease();
+    let claim = repo.claim(&scope, &key, fingerprint.as_deref(), key_ttl(), lease).await.map_err(|e| {
+        tracing::error!(error = %e, scope = %scope, "Failed to claim idempotency key");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    match claim {
+        IdempotencyClaim::Acquired => {}
+        IdempotencyClaim::InProgress => return Err(StatusCode::CONFLICT),
+        IdempotencyClaim::Mismatch => return Err(StatusCode::UNPROCESSABLE_ENTITY),
+        IdempotencyClaim::Completed(stored) => return Ok(replay(stored)),
+    }
+
+    let task = tokio::spawn(async move {
+        // Keep the claim alive while the request is handled
+        let heartbeat = {
+            let (repo, scope, key) = (repo.clone(), scope.clone(), key.clone());
+            let period = lease.to_std().unwrap_or_default() / 3;
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(period);
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if let Err(e) = repo.renew(&scope, &key).await {
+                        tracing::warn!(error = %e, scope = %scope, "Failed to renew idempotency key claim");
+                    }
+                }
+            })
+        };
+
+        let result = handle.await;
+        heartbeat.abort();
+        record(&repo, &scope, &key, result).await
+    });
+    task.await.unwrap_or_else(|e| {
+        tracing::error!(error = %e, "Idempotent request handler panicked");
+        Err(StatusCode::INTERNAL_SERVER_ERROR)
+    })
+}
+
+async fn record(
+    repo: &IdempotencyRepository,
+    scope: &str,
+    key: &str,
+    result: Result<Response, StatusCode>,
+) -> Result<Response, StatusCode> {
+    let stored = match result {
+        Ok(response) => {
+            let (parts, body) = response.into_parts();
+            let bytes = axum::body::to_bytes(body, usize::MAX).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            StoredResponse {
+                status: parts.status.as_u16(),
+                content_type: parts.headers.get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).map(str::to_string),
+                headers: parts.headers.iter()
+                    .filter(|(name, _)| *name != header::CONTENT_TYPE && *name != header::CONTENT_LENGTH)
+                    .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                    .collect(),
+                body: String::from_utf8_lossy(&bytes).into_owned(),
+            }
+        }
+        Err(status) if status.is_server_error() => StoredResponse {
+            status: status.as_u16(),
+            content_type: None,
+            headers: Vec::new(),
+            body: String::new(),
+        },
+        Err(status) => {
+            if let Err(e) = repo.release(scope, key).await {
+                tracing::error!(error = %e, scope = %scope, "Failed to release idempotency key");
+            }
+            return Err(status);
+        }
+    };
+
+    if let Err(e) = repo.complete(scope, key, &stored).await {
+        tracing::error!(error = %e, scope = %scope, "Failed to record idempotent response");
+    }
+    Ok(to_response(stored))
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = to_response(stored);
+    response.headers_mut().insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+fn to_response(stored: StoredResponse) -> Response {
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = StatusCode::from_u16(stored.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    if let Some(content_type) = stored.content_type.and_then(|v| HeaderValue::from_str(&v).ok()) {
+        response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+    }
+    for (name, value) in stored.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(&value)) {
+            response.headers_mut().append(name, value);
+        }
+    }
+    response
+}
@@ -5,3 +5,5 @@ pub mod templates;
 pub mod middleware;
 pub mod validation;
 pub mod audit;
+pub mod dead_letter;
+pub mod idempotency;
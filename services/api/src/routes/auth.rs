@@ -1,6 +1,6 @@
 use axum::{
     extract::Path,
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::Json,
     routing::{post, delete, get},
     Router,
@@ -8,7 +8,7 @@ use axum::{
 use serde::{Deserialize, Serialize};
 
 use crate::routes::AuthState;
-use flowmason_auth::{JwtService, ApiKeyService, AuthContext};
+use flowmason_auth::{JwtService, ApiKeyService, AuthContext, Claims, IpRange, ScopeResource, ScopeSet, WorkspaceRole};
 use axum::extract::Extension;
 
 #[derive(Deserialize)]
@@ -23,9 +23,24 @@ pub struct LoginRequest {
     pub password: String,
 }
 
+#[derive(Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Deserialize)]
+pub struct LogoutRequest {
+    /// Refresh token to revoke along with the access token
+    pub refresh_token: Option<String>,
+}
+
 #[derive(Serialize)]
 pub struct AuthResponse {
+    /// Access token
     pub token: String,
+    pub refresh_token: String,
+    /// Seconds until the access token expires
+    pub expires_in: i64,
     pub user_id: String,
     pub email: String,
 }
@@ -36,6 +51,10 @@ pub struct ApiKeyResponse {
     pub key: String,
     pub name: Option<String>,
     pub created_at: String,
+    /// `None` grants the full access of the user
+    pub scopes: Option<ScopeSet>,
+    pub expires_at: Option<String>,
+    pub allowed_ips: Option<Vec<IpRange>>,
 }
 
 #[derive(Serialize)]
@@ -49,24 +68,44 @@ pub struct ApiKeyInfo {
     pub name: Option<String>,
     pub created_at: String,
     pub last_used_at: Option<String>,
+    pub scopes: Option<ScopeSet>,
+    pub expires_at: Option<String>,
+    pub allowed_ips: Option<Vec<IpRange>>,
+    pub revoked_at: Option<String>,
 }
 
 #[derive(Serialize)]
 pub struct MeResponse {
     pub user_id: String,
     pub email: String,
+    /// Workspace the request acted on
+    pub workspace_id: String,
+    pub role: WorkspaceRole,
+    /// Identity provider groups; empty without single sign-on
+    pub groups: Vec<String>,
 }
 
 pub fn routes() -> Router<AuthState> {
     Router::new()
         .route("/register", post(register))
         .route("/login", post(login))
+        .route("/refresh", post(refresh))
+        .nest("/oidc", crate::routes::oidc::routes())
         .route("/me", get(get_me)
             .layer(axum::middleware::from_fn(flowmason_auth::auth_middleware)))
+        .route("/logout", post(logout)
+            .layer(axum::middleware::from_fn(flowmason_auth::auth_middleware)))
+        .route("/revoke-all", post(revoke_all)
+            .layer(axum::middleware::from_fn(flowmason_auth::auth_middleware)))
         .nest("/api-keys", Router::new()
             .route("/", post(create_api_key).get(list_api_keys))
             .route("/:id", delete(delete_api_key))
-            .layer(axum::middleware::from_fn(flowmason_auth::auth_middleware)))
+            .route("/:id/revoke", post(revoke_api_key))
+            .layer(axum::middleware::from_fn(flowmason_auth::auth_middleware))
+            .layer(axum::middleware::from_fn(|mut request: axum::extract::Request, next: axum::middleware::Next| async move {
+                request.extensions_mut().insert(ScopeResource::ApiKeys);
+                next.run(request).await
+            })))
 }
 
 async fn get_me(
@@ -75,6 +114,9 @@ async fn get_me(
     Ok(Json(MeResponse {
         user_id: auth_context.user_id,
         email: auth_context.email,
+        workspace_id: auth_context.workspace_id,
+        role: auth_context.role,
+        groups: auth_context.groups,
     }))
 }
 
@@ -100,15 +142,7 @@ async fn register(
     state.user_repo.create(&user).await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    let jwt_service = JwtService::from_env();
-    let token = jwt_service.generate_token(&user.id, &user.email)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    Ok(Json(AuthResponse {
-        token,
-        user_id: user.id,
-        email: user.email,
-    }))
+    Ok(Json(issue_tokens(&state, user.id, user.email, user.groups).await?))
 }
 
 async fn login(
@@ -119,20 +153,118 @@ async fn login(
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
-    if !bcrypt::verify(&payload.password, &user.password_hash)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+    // Users provisioned by single sign-on have no usable password hash
+    if !bcrypt::verify(&payload.password, &user.password_hash).unwrap_or(false) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    Ok(Json(issue_tokens(&state, user.id, user.email, user.groups).await?))
+}
+
+/// Exchanges a refresh token for new tokens
+///
+/// Refresh tokens are single use: each refresh revokes the token it used.
+/// Presenting a revoked token again means it was stolen or replayed, so all
+/// tokens of the user are revoked.
+async fn refresh(
+    axum::extract::State(state): axum::extract::State<AuthState>,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<Json<AuthResponse>, StatusCode> {
+    let token_hash = JwtService::hash_refresh_token(&payload.refresh_token);
+    let refresh_token = state.token_repo.get_refresh_token(&token_hash).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    if refresh_token.is_expired(chrono::Utc::now()) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let rotated = refresh_token.revoked_at.is_none()
+        && state.token_repo.revoke_refresh_token(&refresh_token.id).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !rotated {
+        tracing::warn!(user_id = %refresh_token.user_id, "Revoked refresh token reused; revoking all tokens of the user");
+        state.token_repo.revoke_all(&refresh_token.user_id).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    let jwt_service = JwtService::from_env();
-    let token = jwt_service.generate_token(&user.id, &user.email)
+    let user = state.user_repo.get_by_id(&refresh_token.user_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+    Ok(Json(issue_tokens(&state, user.id, user.email, user.groups).await?))
+}
+
+/// Revokes the access token of the request and, if given, a refresh token
+async fn logout(
+    axum::extract::State(state): axum::extract::State<AuthState>,
+    Extension(auth_context): Extension<AuthContext>,
+    headers: HeaderMap,
+    payload: Option<Json<LogoutRequest>>,
+) -> Result<StatusCode, StatusCode> {
+    let claims = bearer_claims(&state, &headers)?;
+    if !claims.jti.is_empty() {
+        let expires_at = chrono::DateTime::from_timestamp(claims.exp, 0).ok_or(StatusCode::BAD_REQUEST)?;
+        state.token_repo.revoke_access_token(&claims.jti, &claims.sub, expires_at).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
+    if let Some(refresh_token) = payload.and_then(|Json(payload)| payload.refresh_token) {
+        let token_hash = JwtService::hash_refresh_token(&refresh_token);
+        let stored = state.token_repo.get_refresh_token(&token_hash).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some(stored) = stored.filter(|stored| stored.user_id == auth_context.user_id) {
+            state.token_repo.revoke_refresh_token(&stored.id).await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        }
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Revokes every access and refresh token of the caller, e.g. after a leak
+async fn revoke_all(
+    axum::extract::State(state): axum::extract::State<AuthState>,
+    Extension(auth_context): Extension<AuthContext>,
+    headers: HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    bearer_claims(&state, &headers)?;
+    state.token_repo.revoke_all(&auth_context.user_id).await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    tracing::info!(user_id = %auth_context.user_id, "All tokens revoked");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Claims of the access token of a request; API keys are rejected with 400
+/// Bad Request as they have no session to end
+fn bearer_claims(state: &AuthState, headers: &HeaderMap) -> Result<Claims, StatusCode> {
+    let token = headers.get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    state.jwt.verify_token(token).map_err(|_| StatusCode::BAD_REQUEST)
+}
 
-    Ok(Json(AuthResponse {
+/// Issues an access token and a refresh token for a user
+pub(crate) async fn issue_tokens(state: &AuthState, user_id: String, email: String, groups: Vec<String>) -> Result<AuthResponse, StatusCode> {
+    let token = state.jwt.generate_token_with_groups(&user_id, &email, &groups)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let refresh_token = JwtService::generate_refresh_token();
+    let expires_at = chrono::Utc::now() + chrono::Duration::seconds(state.jwt.refresh_token_ttl());
+    state.token_repo.create_refresh_token(&user_id, &JwtService::hash_refresh_token(&refresh_token), expires_at).await
+        .map_err(|e| {
+            tracing::error!(error = %e, user_id = %user_id, "Failed to store refresh token");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(AuthResponse {
         token,
-        user_id: user.id,
-        email: user.email,
-    }))
+        refresh_token,
+        expires_in: state.jwt.access_token_ttl(),
+        user_id,
+        email,
+    })
 }
 
 async fn create_api_key(
@@ -140,6 +272,34 @@ async fn create_api_key(
     Extension(auth_context): Extension<AuthContext>,
     Json(payload): Json<CreateApiKeyRequest>,
 ) -> Result<Json<ApiKeyResponse>, StatusCode> {
+    if payload.scopes.as_ref().is_some_and(ScopeSet::is_empty)
+        || payload.allowed_ips.as_ref().is_some_and(Vec::is_empty)
+        || payload.expires_at.is_some_and(|expires_at| expires_at <= chrono::Utc::now())
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    // Scoped keys can only create keys with a subset of their own scopes
+    if let Some(caller_scopes) = &auth_context.scopes {
+        match &payload.scopes {
+            Some(scopes) if caller_scopes.covers(scopes) => {}
+            _ => return Err(StatusCode::FORBIDDEN),
+        }
+    }
+    // Nor can they outlive the caller's key or widen its IP allow-list
+    if let Some(caller_expires_at) = auth_context.key_expires_at {
+        if payload.expires_at.is_none_or(|expires_at| expires_at > caller_expires_at) {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+    if let Some(caller_ips) = &auth_context.key_allowed_ips {
+        let within = payload.allowed_ips.as_ref().is_some_and(|ips| {
+            ips.iter().all(|ip| caller_ips.iter().any(|caller_ip| caller_ip.covers(ip)))
+        });
+        if !within {
+            return Err(StatusCode::FORBIDDEN);
+        }
+    }
+
     let user_id = auth_context.user_id;
     
     let api_key = ApiKeyService::generate();
@@ -148,7 +308,10 @@ async fn create_api_key(
     let id = state.api_key_repo.create(
         &user_id,
         &key_hash,
-        payload.name.as_deref()
+        payload.name.as_deref(),
+        payload.scopes.as_ref(),
+        payload.expires_at,
+        payload.allowed_ips.as_deref(),
     ).await
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
@@ -157,6 +320,9 @@ async fn create_api_key(
         key: api_key,
         name: payload.name,
         created_at: chrono::Utc::now().to_rfc3339(),
+        scopes: payload.scopes,
+        expires_at: payload.expires_at.map(|d| d.to_rfc3339()),
+        allowed_ips: payload.allowed_ips,
     }))
 }
 
@@ -175,6 +341,10 @@ async fn list_api_keys(
             name: k.name,
             created_at: k.created_at.to_rfc3339(),
             last_used_at: k.last_used_at.map(|d| d.to_rfc3339()),
+            scopes: k.scopes,
+            expires_at: k.expires_at.map(|d| d.to_rfc3339()),
+            allowed_ips: k.allowed_ips,
+            revoked_at: k.revoked_at.map(|d| d.to_rfc3339()),
         }).collect(),
     }))
 }
@@ -202,8 +372,36 @@ async fn delete_api_key(
     Ok(StatusCode::NO_CONTENT)
 }
 
+/// Revokes a key while keeping it listed; revoked keys no longer authenticate
+async fn revoke_api_key(
+    axum::extract::State(state): axum::extract::State<AuthState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let api_key = state.api_key_repo.get(&id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if api_key.user_id != auth_context.user_id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    state.api_key_repo.revoke(&id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    tracing::info!(api_key_id = %id, user_id = %auth_context.user_id, "API key revoked");
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
 #[derive(Deserialize)]
 struct CreateApiKeyRequest {
     pub name: Option<String>,
+    /// Omit for a key with the full access of the user
+    #[serde(default)]
+    pub scopes: Option<ScopeSet>,
+    #[serde(default)]
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Addresses or CIDR blocks the key may be used from
+    #[serde(default)]
+    pub allowed_ips: Option<Vec<IpRange>>,
 }
 
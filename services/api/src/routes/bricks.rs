@@ -1,135 +1,104 @@
 use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
     response::Json,
     routing::get,
     Router,
 };
 
 use crate::dto::{BrickListResponse, BrickSchemaResponse};
-use flowmason_bricks::*;
+use crate::routes::BrickState;
+use flowmason_auth::AuthContext;
+use flowmason_core::connections::{with_connection_schema, ConnectionOption};
 use flowmason_core::types::BrickType;
 use flowmason_core::Brick;
+use flowmason_db::repositories::Connection;
 
-pub fn routes() -> Router {
+pub fn routes() -> Router<BrickState> {
     Router::new()
         .route("/", get(list_bricks))
         .route("/:brick_type/schema", get(get_brick_schema))
 }
 
-async fn list_bricks() -> Json<BrickListResponse> {
-    let openai = OpenAiBrick;
-    let nvidia = NvidiaBrick;
-    let hubspot = HubSpotBrick;
-    let notion = NotionBrick;
-    let odoo = OdooBrick;
-    let n8n = N8nBrick;
-    let field_mapping = FieldMappingBrick;
-    let combine_text = CombineTextBrick;
-    let conditional = ConditionalBrick;
-    let rules_engine = RulesEngineBrick;
-    
-    let bricks = vec![
-        BrickSchemaResponse {
-            brick_type: BrickType::OpenAi,
-            name: "openai".to_string(),
-            config_schema: openai.config_schema(),
-        },
-        BrickSchemaResponse {
-            brick_type: BrickType::Nvidia,
-            name: "nvidia".to_string(),
-            config_schema: nvidia.config_schema(),
-        },
-        BrickSchemaResponse {
-            brick_type: BrickType::HubSpot,
-            name: "hubspot".to_string(),
-            config_schema: hubspot.config_schema(),
-        },
-        BrickSchemaResponse {
-            brick_type: BrickType::Notion,
-            name: "notion".to_string(),
-            config_schema: notion.config_schema(),
-        },
-        BrickSchemaResponse {
-            brick_type: BrickType::Odoo,
-            name: "odoo".to_string(),
-            config_schema: odoo.config_schema(),
-        },
-        BrickSchemaResponse {
-            brick_type: BrickType::N8n,
-            name: "n8n".to_string(),
-            config_schema: n8n.config_schema(),
-        },
-        BrickSchemaResponse {
-            brick_type: BrickType::FieldMapping,
-            name: "field_mapping".to_string(),
-            config_schema: field_mapping.config_schema(),
-        },
-        BrickSchemaResponse {
-            brick_type: BrickType::CombineText,
-            name: "combine_text".to_string(),
-            config_schema: combine_text.config_schema(),
-        },
-        BrickSchemaResponse {
-            brick_type: BrickType::Conditional,
-            name: "conditional".to_string(),
-            config_schema: conditional.config_schema(),
-        },
-        BrickSchemaResponse {
-            brick_type: BrickType::RulesEngine,
-            name: "rules_engine".to_string(),
-            config_schema: rules_engine.config_schema(),
-        },
-    ];
-    
+async fn list_bricks(
+    State(state): State<BrickState>,
+    auth_context: Option<Extension<AuthContext>>,
+) -> Json<BrickListResponse> {
+    let connections = list_connections(&state, auth_context.as_deref(), None).await;
+    let mut bricks = Vec::new();
+
+    for id in state.brick_registry.ids() {
+        let brick_type = BrickType::from_id(id);
+        if let Ok(brick) = state.brick_registry.create(&brick_type) {
+            bricks.push(brick_schema(id, brick_type, brick.as_ref(), &connections));
+        }
+    }
+
     Json(BrickListResponse { bricks })
 }
 
 async fn get_brick_schema(
-    axum::extract::Path(brick_type): axum::extract::Path<String>,
-) -> Result<Json<serde_json::Value>, axum::http::StatusCode> {
-    let schema = match brick_type.as_str() {
-        "openai" => {
-            let brick = OpenAiBrick;
-            brick.config_schema()
-        }
-        "nvidia" => {
-            let brick = NvidiaBrick;
-            brick.config_schema()
-        }
-        "hubspot" => {
-            let brick = HubSpotBrick;
-            brick.config_schema()
-        }
-        "notion" => {
-            let brick = NotionBrick;
-            brick.config_schema()
-        }
-        "odoo" => {
-            let brick = OdooBrick;
-            brick.config_schema()
-        }
-        "n8n" => {
-            let brick = N8nBrick;
-            brick.config_schema()
-        }
-        "field_mapping" => {
-            let brick = FieldMappingBrick;
-            brick.config_schema()
-        }
-        "combine_text" => {
-            let brick = CombineTextBrick;
-            brick.config_schema()
-        }
-        "conditional" => {
-            let brick = ConditionalBrick;
-            brick.config_schema()
-        }
-        "rules_engine" => {
-            let brick = RulesEngineBrick;
-            brick.config_schema()
+    State(state): State<BrickState>,
+    auth_context: Option<Extension<AuthContext>>,
+    Path(brick_type): Path<String>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let brick = state.brick_registry.create(&BrickType::from_id(&brick_type))
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+
+    let schema = match brick.connection_spec() {
+        Some(spec) => {
+            let connections = list_connections(&state, auth_context.as_deref(), Some(spec.connection_type)).await;
+            with_connection_schema(brick.config_schema(), &spec, &connection_options(&connections, spec.connection_type))
         }
-        _ => return Err(axum::http::StatusCode::NOT_FOUND),
+        None => brick.config_schema(),
     };
-    
     Ok(Json(schema))
 }
 
+/// Builds a brick's entry, offering the saved connections it can use
+fn brick_schema(id: &str, brick_type: BrickType, brick: &dyn Brick, connections: &[Connection]) -> BrickSchemaResponse {
+    match brick.connection_spec() {
+        Some(spec) => {
+            let options = connection_options(connections, spec.connection_type);
+            BrickSchemaResponse {
+                name: id.to_string(),
+                config_schema: with_connection_schema(brick.config_schema(), &spec, &options),
+                brick_type,
+                connection_type: Some(spec.connection_type.to_string()),
+                connections: options,
+            }
+        }
+        None => BrickSchemaResponse {
+            name: id.to_string(),
+            config_schema: brick.config_schema(),
+            brick_type,
+            connection_type: None,
+            connections: Vec::new(),
+        },
+    }
+}
+
+fn connection_options(connections: &[Connection], connection_type: &str) -> Vec<ConnectionOption> {
+    connections
+        .iter()
+        .filter(|c| c.connection_type == connection_type)
+        .map(|c| ConnectionOption { id: c.id.clone(), name: c.name.clone() })
+        .collect()
+}
+
+/// Connections of the caller's workspace; anonymous callers see none
+///
+/// Schemas are still served if connections cannot be listed
+async fn list_connections(
+    state: &BrickState,
+    auth_context: Option<&AuthContext>,
+    connection_type: Option<&str>,
+) -> Vec<Connection> {
+    let Some(auth_context) = auth_context else {
+        return Vec::new();
+    };
+    state.connection_repo.list(&auth_context.workspace_id, connection_type).await.unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "Failed to list connections for brick schemas");
+        Vec::new()
+    })
+}
@@ -0,0 +1,201 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use std::time::{Duration, Instant};
+
+use crate::dto::{
+    ConnectionListQuery, ConnectionResponse, ConnectionTestResponse, CreateConnectionRequest, UpdateConnectionRequest,
+};
+use crate::routes::ConnectionState;
+use flowmason_auth::{AuthContext, WorkspaceRole};
+use flowmason_core::{Brick, BrickRegistry, SecretRedactor};
+use flowmason_core::types::BrickType;
+use flowmason_db::repositories::ConnectionUpdate;
+
+/// How long a connection test may take
+const CONNECTION_TEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub fn routes() -> Router<ConnectionState> {
+    Router::new()
+        .route("/", get(list_connections).post(create_connection))
+        .route("/:id", get(get_connection).put(update_connection).delete(delete_connection))
+        .route("/:id/test", post(test_connection))
+}
+
+async fn list_connections(
+    State(state): State<ConnectionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Query(query): Query<ConnectionListQuery>,
+) -> Result<Json<Vec<ConnectionResponse>>, StatusCode> {
+    let connections = state.connection_repo.list(&auth_context.workspace_id, query.connection_type.as_deref()).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(connections.into_iter().map(ConnectionResponse::from).collect()))
+}
+
+async fn get_connection(
+    State(state): State<ConnectionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<ConnectionResponse>, StatusCode> {
+    let connection = state.connection_repo.get(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(ConnectionResponse::from(connection)))
+}
+
+async fn create_connection(
+    State(state): State<ConnectionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(payload): Json<CreateConnectionRequest>,
+) -> Result<Json<ConnectionResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    ensure_enabled(&state)?;
+    if payload.name.trim().is_empty() || brick_for_connection(&state.brick_registry, &payload.connection_type).is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let base_url = payload.base_url.filter(|url| !url.is_empty());
+    if base_url.as_deref().is_some_and(|url| !is_valid_base_url(url)) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let connection = state.connection_repo
+        .create(&auth_context.workspace_id, &payload.name, &payload.connection_type, base_url.as_deref(), &payload.credentials, &payload.defaults)
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to create connection");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    tracing::info!(connection_id = %connection.id, connection_type = %connection.connection_type, "Connection created");
+
+    Ok(Json(ConnectionResponse::from(connection)))
+}
+
+async fn update_connection(
+    State(state): State<ConnectionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateConnectionRequest>,
+) -> Result<Json<ConnectionResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    if payload.credentials.is_some() {
+        ensure_enabled(&state)?;
+    }
+    if payload.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let base_url = payload.base_url.map(|url| Some(url).filter(|url| !url.is_empty()));
+    if let Some(Some(ref url)) = base_url {
+        if !is_valid_base_url(url) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    // The stored credentials were entered for the current host; moving them
+    // to another one needs someone who knows them
+    if payload.credentials.is_none() {
+        if let Some(ref url) = base_url {
+            let current = state.connection_repo.get(&auth_context.workspace_id, &id).await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .ok_or(StatusCode::NOT_FOUND)?;
+            if *url != current.base_url {
+                return Err(StatusCode::BAD_REQUEST);
+            }
+        }
+    }
+
+    let update = ConnectionUpdate {
+        name: payload.name,
+        base_url,
+        credentials: payload.credentials,
+        defaults: payload.defaults,
+    };
+    let connection = state.connection_repo.update(&auth_context.workspace_id, &id, update).await
+        .map_err(|e| {
+            tracing::error!(error = %e, connection_id = %id, "Failed to update connection");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    tracing::info!(connection_id = %connection.id, "Connection updated");
+
+    Ok(Json(ConnectionResponse::from(connection)))
+}
+
+async fn delete_connection(
+    State(state): State<ConnectionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    if !state.connection_repo.delete(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    tracing::info!(connection_id = %id, "Connection deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Checks a connection with the brick of its type
+///
+/// A failed check is reported in the response body; credentials are removed
+/// from the error.
+async fn test_connection(
+    State(state): State<ConnectionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<ConnectionTestResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Runner)?;
+    ensure_enabled(&state)?;
+    let connection = state.connection_repo.resolve(&auth_context.workspace_id, &id).await
+        .map_err(|e| {
+            tracing::error!(error = %e, connection_id = %id, "Failed to load connection");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let brick = brick_for_connection(&state.brick_registry, &connection.connection_type)
+        .ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+    let spec = brick.connection_spec().ok_or(StatusCode::UNPROCESSABLE_ENTITY)?;
+
+    let started = Instant::now();
+    let result = tokio::time::timeout(CONNECTION_TEST_TIMEOUT, brick.test_connection(connection.test_config(&spec))).await;
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    let error = match result {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => {
+            let redactor = SecretRedactor::default();
+            connection.credential_values().for_each(|value| redactor.add(value));
+            Some(redactor.redact_str(&e.to_string()))
+        }
+        Err(_) => Some(format!("Timed out after {} ms", CONNECTION_TEST_TIMEOUT.as_millis())),
+    };
+    tracing::info!(connection_id = %id, success = error.is_none(), "Connection tested");
+
+    Ok(Json(ConnectionTestResponse {
+        success: error.is_none(),
+        error,
+        duration_ms,
+    }))
+}
+
+/// Finds the registered brick that accepts connections of a type
+fn brick_for_connection(registry: &BrickRegistry, connection_type: &str) -> Option<Box<dyn Brick>> {
+    registry
+        .ids()
+        .into_iter()
+        .filter_map(|id| registry.create(&BrickType::from_id(id)).ok())
+        .find(|brick| brick.connection_spec().is_some_and(|spec| spec.connection_type == connection_type))
+}
+
+/// Connection settings cannot be written without a master key
+fn ensure_enabled(state: &ConnectionState) -> Result<(), StatusCode> {
+    if state.connection_repo.is_enabled() {
+        Ok(())
+    } else {
+        Err(StatusCode::SERVICE_UNAVAILABLE)
+    }
+}
+
+fn is_valid_base_url(url: &str) -> bool {
+    url::Url::parse(url).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.has_host())
+}
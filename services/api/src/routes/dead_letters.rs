@@ -0,0 +1,106 @@
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, post},
+    Router,
+};
+use serde::Deserialize;
+
+use crate::dto::{DeadLetterResponse, FlowExecutionResponse, PaginatedResponse, ReplayResponse};
+use crate::routes::DeadLetterState;
+use flowmason_auth::{AuthContext, WorkspaceRole};
+use flowmason_db::repositories::DeadLetterStatus;
+
+pub fn routes() -> Router<DeadLetterState> {
+    Router::new()
+        .route("/", get(list_dead_letters))
+        .route("/:id", get(get_dead_letter).delete(discard_dead_letter))
+        .route("/:id/replay", post(replay_dead_letter))
+}
+
+#[derive(Debug, Deserialize)]
+struct DeadLetterListParams {
+    status: Option<DeadLetterStatus>,
+    #[serde(default = "default_limit")]
+    limit: u32,
+    #[serde(default)]
+    offset: u32,
+}
+
+fn default_limit() -> u32 {
+    100
+}
+
+async fn list_dead_letters(
+    State(state): State<DeadLetterState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Query(params): Query<DeadLetterListParams>,
+) -> Result<Json<PaginatedResponse<DeadLetterResponse>>, StatusCode> {
+    let entries = state.dead_letter_repo.list(&auth_context.workspace_id, params.status, Some(params.limit), Some(params.offset))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let items = entries.into_iter().map(DeadLetterResponse::from).collect();
+    Ok(Json(PaginatedResponse::new(items, params.limit, params.offset)))
+}
+
+async fn get_dead_letter(
+    State(state): State<DeadLetterState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<DeadLetterResponse>, StatusCode> {
+    let entry = state.dead_letter_repo.get_in_workspace(&auth_context.workspace_id, &id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(DeadLetterResponse::from(entry)))
+}
+
+async fn replay_dead_letter(
+    State(state): State<DeadLetterState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<ReplayResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Runner)?;
+    ensure_in_workspace(&state, &auth_context, &id).await?;
+    let entry = match state.dead_letter_repo.claim(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+        Some(entry) => entry,
+        // Resolved, discarded or already being replayed
+        None => return Err(StatusCode::CONFLICT),
+    };
+
+    let outcome = state.replayer.replay(&entry).await.map_err(|e| {
+        tracing::error!(error = %e, dead_letter_id = %id, "Failed to replay dead-lettered execution");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    let entry = state.dead_letter_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(ReplayResponse {
+        dead_letter: DeadLetterResponse::from(entry),
+        execution: outcome.execution.map(FlowExecutionResponse::from),
+    }))
+}
+
+async fn discard_dead_letter(
+    State(state): State<DeadLetterState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    auth_context.require(WorkspaceRole::Runner)?;
+    ensure_in_workspace(&state, &auth_context, &id).await?;
+    if state.dead_letter_repo.discard(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+        return Ok(StatusCode::NO_CONTENT);
+    }
+    // Already resolved, discarded or being replayed
+    Err(StatusCode::CONFLICT)
+}
+
+/// Entries of other workspaces are reported as missing
+async fn ensure_in_workspace(state: &DeadLetterState, auth_context: &AuthContext, id: &str) -> Result<(), StatusCode> {
+    state.dead_letter_repo.get_in_workspace(&auth_context.workspace_id, id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(())
+}
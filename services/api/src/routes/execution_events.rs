@@ -0,0 +1,140 @@
+use axum::{
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Extension, Path, State},
+    http::StatusCode,
+    response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Response},
+};
+use futures::stream::{self, BoxStream, StreamExt};
+use flowmason_core::{ExecutionEvent, ExecutionEventKind};
+use tokio::sync::broadcast::error::RecvError;
+use crate::routes::ExecutionState;
+use crate::routes::executions::execution_in_workspace;
+use flowmason_auth::AuthContext;
+
+/// Streams the events of an execution as Server-Sent Events
+///
+/// The stream replays the events published so far and ends after
+/// `execution_finished`. Each SSE event is named after the event type and
+/// carries the event's sequence number as its id.
+pub async fn stream_events(
+    State(state): State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(execution_id): Path<String>,
+) -> Result<Response, StatusCode> {
+    let events = execution_events(&state, &auth_context, &execution_id).await?
+        .map(|event| Event::default()
+            .event(event.kind.name())
+            .id(event.sequence.to_string())
+            .json_data(&event));
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()).into_response())
+}
+
+/// WebSocket subprotocol of the event stream
+///
+/// Browser clients, which cannot set the Authorization header, request it
+/// together with a `flowmason.bearer.<token>` subprotocol carrying their
+/// credentials.
+pub const EVENTS_WS_PROTOCOL: &str = "flowmason.events";
+
+/// Streams the events of an execution over a WebSocket
+///
+/// Every event is sent as a JSON text message; the socket is closed after
+/// `execution_finished`.
+pub async fn stream_events_ws(
+    State(state): State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(execution_id): Path<String>,
+    ws: WebSocketUpgrade,
+) -> Result<Response, StatusCode> {
+    let events = execution_events(&state, &auth_context, &execution_id).await?;
+    Ok(ws.protocols([EVENTS_WS_PROTOCOL]).on_upgrade(move |socket| forward_events(socket, events)))
+}
+
+async fn forward_events(mut socket: WebSocket, mut events: BoxStream<'static, ExecutionEvent>) {
+    while let Some(event) = events.next().await {
+        let message = match serde_json::to_string(&event) {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to serialize execution event");
+                break;
+            }
+        };
+        if socket.send(Message::Text(message)).await.is_err() {
+            // Client went away
+            return;
+        }
+    }
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+/// Returns the events of an execution: those published so far, followed by
+/// live events until the execution finishes
+///
+/// Executions the event bus no longer knows (finished long ago, or run by a
+/// worker process) yield a single `execution_finished` event built from the
+/// stored execution. Only executions of the caller's workspace are streamed.
+async fn execution_events(
+    state: &ExecutionState,
+    auth_context: &AuthContext,
+    execution_id: &str,
+) -> Result<BoxStream<'static, ExecutionEvent>, StatusCode> {
+    let Some(subscription) = state.event_bus.subscribe(execution_id) else {
+        let execution = execution_in_workspace(state, auth_context, execution_id).await?;
+        let event = ExecutionEvent {
+            execution_id: execution.execution_id,
+            sequence: 0,
+            timestamp: execution.completed_at.unwrap_or(execution.started_at),
+            kind: ExecutionEventKind::ExecutionFinished {
+                status: execution.status,
+                error: execution.error,
+            },
+        };
+        return Ok(stream::once(async move { event }).boxed());
+    };
+
+    // Running executions are not stored yet; their workspace is recorded when
+    // they are tracked, otherwise their flow is named by the first event
+    match &subscription.workspace_id {
+        Some(workspace_id) if *workspace_id != auth_context.workspace_id => return Err(StatusCode::NOT_FOUND),
+        Some(_) => {}
+        None => {
+            let flow_id = subscription.history.iter().find_map(|event| match &event.kind {
+                ExecutionEventKind::ExecutionStarted { flow_id, .. } => Some(flow_id.clone()),
+                _ => None,
+            }).ok_or(StatusCode::NOT_FOUND)?;
+            state.flow_repo.get_in_workspace(&auth_context.workspace_id, &flow_id).await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                .ok_or(StatusCode::NOT_FOUND)?;
+        }
+    }
+
+    let next_sequence = subscription.history.len() as u64;
+    let finished = subscription.history.iter().any(ExecutionEvent::is_finished);
+    let history = stream::iter(subscription.history);
+    if finished {
+        return Ok(history.boxed());
+    }
+
+    let execution_id = execution_id.to_string();
+    let live = stream::unfold(Some(subscription.receiver), move |receiver| {
+        let execution_id = execution_id.clone();
+        async move {
+            let mut receiver = receiver?;
+            loop {
+                match receiver.recv().await {
+                    Ok(event) if event.execution_id == execution_id && event.sequence >= next_sequence => {
+                        let receiver = (!event.is_finished()).then_some(receiver);
+                        return Some((event, receiver));
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(execution_id = %execution_id, skipped, "Execution event subscriber lagged");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+
+    Ok(history.chain(live).boxed())
+}
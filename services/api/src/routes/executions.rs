@@ -1,92 +1,330 @@
 use axum::{
-    extract::{Path, Query},
-    http::StatusCode,
-    response::Json,
+    extract::{Extension, Path, Query},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::{get, post, delete},
     Router,
 };
 use serde::Serialize;
-use crate::dto::{ExecuteFlowRequest, FlowExecutionResponse, PaginationParams, PaginatedResponse};
+use crate::dto::{BackgroundExecutionResponse, ExecuteFlowRequest, ExecutionMode, ExecutionStepResponse, FlowExecutionResponse, JobResponse, PaginationParams, PaginatedResponse, ResumeExecutionRequest};
+use crate::error::ApiError;
+use crate::idempotency;
 use crate::routes::ExecutionState;
+use crate::routes::execution_events::{stream_events, stream_events_ws};
+use flowmason_auth::{AuthContext, WorkspaceRole};
+use flowmason_core::types::{ExecutionStatus, Flow, FlowExecution, FlowNodeKind};
+use flowmason_core::secrets::contains_redacted;
 use flowmason_core::{FlowRunner, FlowRunnerContext};
-use flowmason_bricks::*;
-use flowmason_bricks::RulesEngineBrick;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 pub fn routes() -> Router<ExecutionState> {
     Router::new()
         .route("/", post(execute_flow).get(list_executions))
         .route("/:execution_id", get(get_execution))
+        .route("/:execution_id/children", get(list_child_executions))
+        .route("/:execution_id/steps", get(list_execution_steps))
+        .route("/:execution_id/events", get(stream_events))
+        .route("/:execution_id/events/ws", get(stream_events_ws))
+        .route("/:execution_id/resume", post(resume_execution))
         .route("/:execution_id/data", get(get_execution_data).delete(delete_execution_data))
         .route("/:execution_id/data/brick/:brick_index", get(get_brick_data_by_path))
         .route("/:execution_id/data/fetched", get(get_fetched_data))
         .route("/:execution_id/data/intermediate", get(get_intermediate_data))
         .route("/flow/:flow_id", get(list_flow_executions))
+        .route("/jobs/:job_id", get(get_job))
 }
 
+/// Attempts a queued execution gets before it is marked failed
+fn job_max_attempts() -> u32 {
+    std::env::var("JOB_MAX_ATTEMPTS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3)
+}
+
+/// The 422 response for a trigger payload that does not match the flow's
+/// input schema, listing every mismatch
+///
+/// Lets callers learn about bad payloads before an execution is started.
+pub(crate) fn reject_invalid_input(flow: &Flow, input_payload: &serde_json::Value) -> Option<Response> {
+    let errors = flow.schema.validate_input(input_payload).err()?;
+    tracing::warn!(flow_id = %flow.id, errors = errors.len(), "Payload does not match the flow's input schema");
+    Some(ApiError::Validation("Payload does not match the flow's input schema".to_string(), errors).into_response())
+}
+
+/// Runs a flow; with an `Idempotency-Key` header, repeats of the request
+/// return the response of the first instead of running the flow again
 async fn execute_flow(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    headers: HeaderMap,
     Json(payload): Json<ExecuteFlowRequest>,
-) -> Result<Json<FlowExecutionResponse>, StatusCode> {
-    // Get flow from store
-    let flow = state.flow_repo.get(&payload.flow_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+) -> Result<Response, StatusCode> {
+    auth_context.require(WorkspaceRole::Runner)?;
+    auth_context.require_trigger(&payload.flow_id)?;
+    let key = idempotency::key_from_headers(&headers)?;
+
+    // Rejected requests are checked before a key is claimed, so they do not use it up
+    let flow = state.flow_repo.get_in_workspace(&auth_context.workspace_id, &payload.flow_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
-    
+    if let Some(response) = reject_invalid_input(&flow, &payload.input_payload) {
+        return Ok(response);
+    }
+
+    match key {
+        Some(key) => {
+            let scope = format!("executions:{}", auth_context.workspace_id);
+            let request = serde_json::to_vec(&payload).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            let repo = state.idempotency_repo.clone();
+            idempotency::run_once(repo, scope, key, Some(idempotency::fingerprint(&request)), start_execution(state, auth_context, flow, payload)).await
+        }
+        None => start_execution(state, auth_context, flow, payload).await,
+    }
+}
+
+async fn start_execution(
+    state: ExecutionState,
+    auth_context: AuthContext,
+    flow: Flow,
+    payload: ExecuteFlowRequest,
+) -> Result<Response, StatusCode> {
     // Create brick instances based on flow configuration
-    // This is simplified - in production, you'd have a registry
-    let mut bricks: Vec<Box<dyn flowmason_core::Brick>> = Vec::new();
-    
-    for brick_config in &flow.bricks {
-        let brick: Box<dyn flowmason_core::Brick> = match brick_config.brick_type {
-            flowmason_core::types::BrickType::OpenAi => Box::new(OpenAiBrick),
-            flowmason_core::types::BrickType::Nvidia => Box::new(NvidiaBrick),
-            flowmason_core::types::BrickType::HubSpot => Box::new(HubSpotBrick),
-            flowmason_core::types::BrickType::Notion => Box::new(NotionBrick),
-            flowmason_core::types::BrickType::Odoo => Box::new(OdooBrick),
-            flowmason_core::types::BrickType::N8n => Box::new(N8nBrick),
-            flowmason_core::types::BrickType::FieldMapping => Box::new(FieldMappingBrick),
-            flowmason_core::types::BrickType::CombineText => Box::new(CombineTextBrick),
-            flowmason_core::types::BrickType::Conditional => Box::new(ConditionalBrick),
-            flowmason_core::types::BrickType::RulesEngine => Box::new(RulesEngineBrick),
-        };
-        bricks.push(brick);
+    let bricks = state.brick_registry.create_all(&flow.bricks).map_err(|e| {
+        tracing::warn!(error = %e, flow_id = %payload.flow_id, "Failed to resolve flow bricks");
+        StatusCode::BAD_REQUEST
+    })?;
+
+    // Async executions are persisted and picked up by flowmason-worker
+    if payload.mode == ExecutionMode::Async {
+        let job = state.job_repo.enqueue(&flow.id, &payload.input_payload, job_max_attempts())
+            .await
+            .map_err(|e| {
+                tracing::error!(error = %e, flow_id = %payload.flow_id, "Failed to enqueue execution");
+                StatusCode::INTERNAL_SERVER_ERROR
+            })?;
+        return Ok((StatusCode::ACCEPTED, Json(JobResponse::from(job))).into_response());
     }
     
     // Create execution context with quota manager and usage logger
     // Wrap ExecutionDataRepository in Arc<dyn ExecutionDataStorage>
     let execution_data_storage: Arc<dyn flowmason_core::ExecutionDataStorage> = state.execution_data_repo.clone();
-    let context = FlowRunnerContext {
+    let mut context = FlowRunnerContext {
         quota_manager: Some(state.quota_manager.clone()),
         usage_logger: Some(state.usage_logger.clone()),
         execution_data_storage: Some(execution_data_storage),
+        sub_flow_executor: Some(state.sub_flow_executor.clone()),
+        brick_registry: Some(state.brick_registry.clone()),
+        event_bus: Some(state.event_bus.clone()),
         flow_id: flow.id.clone(),
-        execution_id: String::new(), // Will be set in execute_flow_with_tracking
+        execution_id: String::new(), // Will be set in execute_flow_recorded
+        workspace_id: flow.workspace_id.clone(),
+        parent_execution_id: None,
+        depth: 0,
+        variables: Default::default(),
+        secret_resolver: Some(state.secret_resolver.clone()),
+        connection_resolver: Some(state.connection_resolver.clone()),
+        redactor: Default::default(),
+        responder: None,
     };
+
+    // Background executions run in this process and are followed through their events
+    if payload.mode == ExecutionMode::Background {
+        let execution_id = uuid::Uuid::new_v4().to_string();
+        context.execution_id = execution_id.clone();
+        state.event_bus.track(&execution_id, &auth_context.workspace_id);
+
+        let response = BackgroundExecutionResponse {
+            events_url: format!("/api/v1/executions/{}/events", execution_id),
+            execution_id,
+            flow_id: flow.id.clone(),
+        };
+        tokio::spawn(async move {
+            let execution = FlowRunner::execute_flow_recorded(&flow, bricks, payload.input_payload, Some(context)).await;
+            if let Err(e) = state.execution_repo.create(&execution).await {
+                tracing::error!(error = %e, execution_id = %execution.execution_id, "Failed to store background execution");
+            }
+        });
+        return Ok((StatusCode::ACCEPTED, Json(response)).into_response());
+    }
     
     // Execute flow
-    let execution = FlowRunner::execute_flow_with_tracking(
+    let execution = FlowRunner::execute_flow_recorded(
         &flow,
         bricks,
         payload.input_payload,
         Some(context),
     )
-    .await
-    .map_err(|e| {
-        tracing::error!(error = %e, flow_id = %payload.flow_id, "Flow execution error");
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    .await;
     
-    // Store execution in history
+    // Store execution in history; failed executions land in the dead-letter queue
     state.execution_repo.create(&execution).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
+    if let Some(error) = &execution.error {
+        tracing::error!(error = %error, flow_id = %payload.flow_id, execution_id = %execution.execution_id, "Flow execution error");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    
+    Ok(Json(FlowExecutionResponse::from(execution)).into_response())
+}
+
+/// Resumes a failed execution from the brick that failed
+///
+/// Bricks that completed in the failed execution are not run again; their
+/// stored `intermediate` outputs are reused. The resumed execution is stored
+/// as a new execution linked through `replay_of_execution_id`.
+async fn resume_execution(
+    axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(execution_id): Path<String>,
+    payload: Option<Json<ResumeExecutionRequest>>,
+) -> Result<Json<FlowExecutionResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Runner)?;
+    let original = execution_in_workspace(&state, &auth_context, &execution_id).await?;
+    auth_context.require_trigger(&original.flow_id)?;
+    if original.status != ExecutionStatus::Failed {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let mut flow = state.flow_repo.get_in_workspace(&auth_context.workspace_id, &original.flow_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Rebuild brick outputs and find the brick that failed
+    let data = state.execution_data_repo.get_by_execution(&execution_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut completed: HashMap<usize, serde_json::Value> = HashMap::new();
+    let mut failed_index = None;
+    for d in data {
+        match d.data_type.as_str() {
+            "intermediate" => {
+                completed.insert(d.brick_index as usize, d.data_value);
+            }
+            "error" => failed_index = Some(d.brick_index as usize),
+            _ => {}
+        }
+    }
+
+    let Json(request) = payload.unwrap_or_default();
+    if let Some(config) = request.config {
+        let brick_index = request.brick_index.or(failed_index).ok_or(StatusCode::BAD_REQUEST)?;
+        if !replace_brick_config(&mut flow, brick_index, config) {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        // The edited brick and everything after it run again
+        let rerun = match flow.graph {
+            Some(ref graph) => graph.downstream_of(brick_index),
+            None => (brick_index..flow.bricks.len()).collect(),
+        };
+        completed.retain(|index, _| !rerun.contains(index));
+    }
+
+    // Secret values were replaced in stored payloads; running bricks on them
+    // would pass `[REDACTED]` on as data
+    if contains_redacted(&original.input_payload) || completed.values().any(contains_redacted) {
+        tracing::warn!(execution_id = %execution_id, "Cannot resume an execution whose stored data had secrets redacted");
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let bricks = state.brick_registry.create_all(&flow.bricks).map_err(|e| {
+        tracing::warn!(error = %e, flow_id = %flow.id, "Failed to resolve flow bricks");
+        StatusCode::BAD_REQUEST
+    })?;
+
+    let execution_data_storage: Arc<dyn flowmason_core::ExecutionDataStorage> = state.execution_data_repo.clone();
+    let context = FlowRunnerContext {
+        quota_manager: Some(state.quota_manager.clone()),
+        usage_logger: Some(state.usage_logger.clone()),
+        execution_data_storage: Some(execution_data_storage),
+        sub_flow_executor: Some(state.sub_flow_executor.clone()),
+        brick_registry: Some(state.brick_registry.clone()),
+        event_bus: Some(state.event_bus.clone()),
+        flow_id: flow.id.clone(),
+        execution_id: String::new(), // Will be set in resume_flow_recorded
+        workspace_id: flow.workspace_id.clone(),
+        parent_execution_id: None,
+        depth: 0,
+        variables: Default::default(),
+        secret_resolver: Some(state.secret_resolver.clone()),
+        connection_resolver: Some(state.connection_resolver.clone()),
+        redactor: Default::default(),
+        responder: None,
+    };
+
+    let mut execution = FlowRunner::resume_flow_recorded(
+        &flow,
+        bricks,
+        original.input_payload,
+        completed,
+        Some(context),
+    )
+    .await;
+    execution.replay_of_execution_id = Some(execution_id.clone());
+
+    state.execution_repo.create(&execution).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // A resumed execution that succeeded settles the original's dead-letter entry
+    if execution.status == ExecutionStatus::Completed {
+        if let Err(e) = state.dead_letter_repo.resolve_for_execution(&execution_id, &execution.execution_id).await {
+            tracing::warn!(error = %e, execution_id = %execution_id, "Failed to resolve dead-letter entry");
+        }
+    }
+
     Ok(Json(FlowExecutionResponse::from(execution)))
 }
 
+/// Loads an execution of a flow in the caller's workspace
+pub(crate) async fn execution_in_workspace(
+    state: &ExecutionState,
+    auth_context: &AuthContext,
+    execution_id: &str,
+) -> Result<FlowExecution, StatusCode> {
+    state.execution_repo.get_in_workspace(&auth_context.workspace_id, execution_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Replaces the config of the brick at `brick_index` (the node index for graph
+/// flows). Returns false if there is no such brick.
+fn replace_brick_config(flow: &mut Flow, brick_index: usize, config: serde_json::Value) -> bool {
+    match flow.graph {
+        Some(ref mut graph) => match graph.nodes.get_mut(brick_index).map(|n| &mut n.kind) {
+            Some(FlowNodeKind::Brick(brick_config)) => {
+                brick_config.config = config;
+                true
+            }
+            _ => false,
+        },
+        None => match flow.bricks.get_mut(brick_index) {
+            Some(brick_config) => {
+                brick_config.config = config;
+                true
+            }
+            None => false,
+        },
+    }
+}
+
+async fn get_job(
+    axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(job_id): Path<String>,
+) -> Result<Json<JobResponse>, StatusCode> {
+    let job = state.job_repo.get(&job_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    state.flow_repo.get_in_workspace(&auth_context.workspace_id, &job.flow_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    Ok(Json(JobResponse::from(job)))
+}
+
 async fn list_executions(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
     Query(params): Query<PaginationParams>,
 ) -> Result<Json<PaginatedResponse<FlowExecutionResponse>>, StatusCode> {
-    let exec_list = state.execution_repo.list_all(Some(params.limit), Some(params.offset))
+    let exec_list = state.execution_repo.list_all(&auth_context.workspace_id, Some(params.limit), Some(params.offset))
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let items = exec_list.into_iter().map(FlowExecutionResponse::from).collect();
@@ -95,19 +333,48 @@ async fn list_executions(
 
 async fn get_execution(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(execution_id): Path<String>,
 ) -> Result<Json<FlowExecutionResponse>, StatusCode> {
-    let execution = state.execution_repo.get(&execution_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+    let execution = execution_in_workspace(&state, &auth_context, &execution_id).await?;
     
     Ok(Json(FlowExecutionResponse::from(execution)))
 }
 
+async fn list_execution_steps(
+    axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(execution_id): Path<String>,
+) -> Result<Json<Vec<ExecutionStepResponse>>, StatusCode> {
+    execution_in_workspace(&state, &auth_context, &execution_id).await?;
+
+    let steps = state.step_repo.list_by_execution(&execution_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(steps.into_iter().map(ExecutionStepResponse::from).collect()))
+}
+
+async fn list_child_executions(
+    axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(execution_id): Path<String>,
+) -> Result<Json<Vec<FlowExecutionResponse>>, StatusCode> {
+    execution_in_workspace(&state, &auth_context, &execution_id).await?;
+    let children = state.execution_repo.list_by_parent(&execution_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(children.into_iter().map(FlowExecutionResponse::from).collect()))
+}
+
 async fn list_flow_executions(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(flow_id): Path<String>,
     Query(params): Query<PaginationParams>,
 ) -> Result<Json<PaginatedResponse<FlowExecutionResponse>>, StatusCode> {
+    state.flow_repo.get_in_workspace(&auth_context.workspace_id, &flow_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
     let exec_list = state.execution_repo.list_by_flow(&flow_id, Some(params.limit), Some(params.offset))
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -129,8 +396,10 @@ struct ExecutionDataResponse {
 
 async fn get_execution_data(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(execution_id): Path<String>,
 ) -> Result<Json<Vec<ExecutionDataResponse>>, StatusCode> {
+    execution_in_workspace(&state, &auth_context, &execution_id).await?;
     let data = state.execution_data_repo.get_by_execution(&execution_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -155,8 +424,10 @@ struct BrickDataPath {
 
 async fn get_brick_data_by_path(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(params): Path<BrickDataPath>,
 ) -> Result<Json<Vec<ExecutionDataResponse>>, StatusCode> {
+    execution_in_workspace(&state, &auth_context, &params.execution_id).await?;
     let data = state.execution_data_repo.get_by_brick(&params.execution_id, params.brick_index)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -175,8 +446,10 @@ async fn get_brick_data_by_path(
 
 async fn get_fetched_data(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(execution_id): Path<String>,
 ) -> Result<Json<Vec<ExecutionDataResponse>>, StatusCode> {
+    execution_in_workspace(&state, &auth_context, &execution_id).await?;
     let data = state.execution_data_repo.get_by_data_type(&execution_id, "fetched")
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -195,8 +468,10 @@ async fn get_fetched_data(
 
 async fn get_intermediate_data(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(execution_id): Path<String>,
 ) -> Result<Json<Vec<ExecutionDataResponse>>, StatusCode> {
+    execution_in_workspace(&state, &auth_context, &execution_id).await?;
     let data = state.execution_data_repo.get_by_data_type(&execution_id, "intermediate")
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -215,8 +490,11 @@ async fn get_intermediate_data(
 
 async fn delete_execution_data(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(execution_id): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    execution_in_workspace(&state, &auth_context, &execution_id).await?;
     state.execution_data_repo.delete_by_execution(&execution_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
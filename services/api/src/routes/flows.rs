@@ -1,33 +1,58 @@
 use axum::{
-    extract::{Path, Query},
+    extract::{Extension, Path, Query},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
     routing::{get, post, put, delete},
     Router,
 };
 use uuid::Uuid;
 
-use crate::dto::{CreateFlowRequest, FlowResponse, UpdateFlowRequest, PaginationParams, PaginatedResponse};
+use crate::dto::{BrickConfigDto, CreateFlowRequest, FlowResponse, UpdateFlowRequest, PaginationParams, PaginatedResponse, ConfigureWebhookRequest, RotateWebhookSecretRequest, WebhookResponse, ConfigureEndpointRequest, EndpointResponse, FlowSchemaResponse};
+use crate::error::ApiError;
 use crate::routes::FlowState;
+use crate::routes::hooks;
 use crate::validation::validate_webhook_url;
-use flowmason_core::types::{BrickConfig, Flow, BrickType};
+use flowmason_auth::{generate_webhook_secret, AuthContext, WorkspaceRole};
+use flowmason_core::secrets::{redact_flow_credentials, restore_flow_credentials};
+use flowmason_core::types::{BrickConfig, Flow, BrickType, FlowGraph, FlowNodeKind, FlowSchema, RetryPolicy};
+use flowmason_core::{check_stored_config, GraphRunner, JsonPath, SchemaError};
 use serde_json::{Value, json};
 
 pub fn routes() -> Router<FlowState> {
     Router::new()
         .route("/", post(create_flow).get(list_flows))
         .route("/:id", get(get_flow).put(update_flow).delete(delete_flow))
+        .route("/:id/schema", get(get_flow_schema))
         .route("/:id/duplicate", post(duplicate_flow))
         .route("/:id/export", get(export_flow))
         .route("/import", post(import_flow))
+        .route("/:id/webhook", get(get_webhook).put(configure_webhook).delete(delete_webhook))
+        .route("/:id/webhook/rotate", post(rotate_webhook_secret))
+        .route("/:id/endpoint", get(get_endpoint).put(configure_endpoint).delete(delete_endpoint))
 }
 
+/// Longest grace period for a rotated webhook secret
+const MAX_WEBHOOK_GRACE_PERIOD_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// Creates a flow
+///
+/// Brick configs that do not match their brick's config schema are rejected
+/// with 422 and the list of mismatches.
 async fn create_flow(
     axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
     Json(payload): Json<CreateFlowRequest>,
-) -> Result<Json<FlowResponse>, StatusCode> {
+) -> Result<Response, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    let as_graph = payload.graph.is_some();
+    let (bricks, graph) = resolve_flow_structure(bricks_with_ids(payload.bricks), payload.graph)?;
+    check_flow_schema(&payload.schema)?;
+    if let Err(errors) = check_brick_configs(&state, &graph, as_graph) {
+        return Ok(invalid_brick_configs(errors));
+    }
+
     // Validate webhook URLs in brick configs
-    for brick in &payload.bricks {
+    for brick in &bricks {
         if brick.brick_type == BrickType::N8n {
             if let Some(webhook_url) = brick.config.get("webhook_url").and_then(|v| v.as_str()) {
                 if let Err(e) = validate_webhook_url(webhook_url, None) {
@@ -45,10 +70,12 @@ async fn create_flow(
         id,
         name: payload.name,
         description: payload.description,
-        bricks: payload.bricks.into_iter().map(|b| BrickConfig {
-            brick_type: b.brick_type,
-            config: b.config,
-        }).collect(),
+        bricks,
+        graph: Some(graph),
+        retry_policy: payload.retry_policy,
+        variables: payload.variables,
+        schema: payload.schema,
+        workspace_id: Some(auth_context.workspace_id),
         active: true,
         created_at: now,
         updated_at: now,
@@ -56,14 +83,15 @@ async fn create_flow(
     
     state.flow_repo.create(&flow).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
-    Ok(Json(FlowResponse::from(flow)))
+    Ok(Json(FlowResponse::from(flow)).into_response())
 }
 
 async fn list_flows(
     axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
     Query(params): Query<PaginationParams>,
 ) -> Result<Json<PaginatedResponse<FlowResponse>>, StatusCode> {
-    let flows = state.flow_repo.list(Some(params.limit), Some(params.offset))
+    let flows = state.flow_repo.list(&auth_context.workspace_id, Some(params.limit), Some(params.offset))
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let items = flows.into_iter().map(FlowResponse::from).collect();
@@ -72,20 +100,35 @@ async fn list_flows(
 
 async fn get_flow(
     axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(id): Path<String>,
 ) -> Result<Json<FlowResponse>, StatusCode> {
-    let flow = state.flow_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    let flow = state.flow_repo.get_in_workspace(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
     Ok(Json(FlowResponse::from(flow)))
 }
 
+/// Input and output schemas of the flow, for generating clients
+async fn get_flow_schema(
+    axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<FlowSchemaResponse>, StatusCode> {
+    let flow = state.flow_repo.get_in_workspace(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(FlowSchemaResponse::from(flow)))
+}
+
 async fn update_flow(
     axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(id): Path<String>,
     Json(payload): Json<UpdateFlowRequest>,
-) -> Result<Json<FlowResponse>, StatusCode> {
-    let mut flow = state.flow_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+) -> Result<Response, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    let mut flow = state.flow_repo.get_in_workspace(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    let previous = flow.clone();
 
     if let Some(name) = payload.name {
         flow.name = name;
@@ -93,7 +136,16 @@ async fn update_flow(
     if let Some(description) = payload.description {
         flow.description = Some(description);
     }
-    if let Some(bricks) = payload.bricks {
+    if payload.bricks.is_some() || payload.graph.is_some() {
+        let as_graph = payload.graph.is_some();
+        // A brick list can only replace a chain; branches and joins need the graph
+        if !as_graph && flow.graph.as_ref().is_some_and(|graph| !graph.is_linear()) {
+            tracing::warn!(flow_id = %id, "Rejected brick list update of a non-linear flow graph");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let bricks = payload.bricks.map(bricks_with_ids).unwrap_or_default();
+        let (bricks, graph) = resolve_flow_structure(bricks, payload.graph)?;
+
         // Validate webhook URLs in brick configs
         for brick in &bricks {
             if brick.brick_type == BrickType::N8n {
@@ -105,10 +157,32 @@ async fn update_flow(
                 }
             }
         }
-        flow.bricks = bricks.into_iter().map(|b| BrickConfig {
-            brick_type: b.brick_type,
-            config: b.config,
-        }).collect();
+        flow.bricks = bricks;
+        flow.graph = Some(graph);
+        // Credentials masked in responses are sent back unchanged
+        if let Err(node_id) = restore_flow_credentials(&mut flow, &previous) {
+            tracing::warn!(flow_id = %id, node_id = %node_id, "Masked credentials that cannot be restored");
+            return Ok(ApiError::BadRequest(format!(
+                "Brick '{}' has a masked credential that cannot be kept, because the brick is new or its other settings changed; \
+                 enter the credential again or use a {{{{ secret:name }}}} reference",
+                node_id
+            )).into_response());
+        }
+        if let Some(graph) = &flow.graph {
+            if let Err(errors) = check_brick_configs(&state, graph, as_graph) {
+                return Ok(invalid_brick_configs(errors));
+            }
+        }
+    }
+    if let Some(retry_policy) = payload.retry_policy {
+        flow.retry_policy = Some(retry_policy);
+    }
+    if let Some(variables) = payload.variables {
+        flow.variables = variables;
+    }
+    if let Some(schema) = payload.schema {
+        check_flow_schema(&schema)?;
+        flow.schema = schema;
     }
     if let Some(active) = payload.active {
         flow.active = active;
@@ -117,27 +191,31 @@ async fn update_flow(
 
     state.flow_repo.update(&flow).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
-    Ok(Json(FlowResponse::from(flow)))
+    Ok(Json(FlowResponse::from(flow)).into_response())
 }
 
 async fn delete_flow(
     axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(id): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
-    let existing = state.flow_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    auth_context.require(WorkspaceRole::Editor)?;
+    let existing = state.flow_repo.get_in_workspace(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     if existing.is_none() {
         return Err(StatusCode::NOT_FOUND);
     }
 
-    state.flow_repo.delete(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    state.flow_repo.delete(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     Ok(StatusCode::NO_CONTENT)
 }
 
 async fn duplicate_flow(
     axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(id): Path<String>,
 ) -> Result<Json<FlowResponse>, StatusCode> {
-    let original_flow = state.flow_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    auth_context.require(WorkspaceRole::Editor)?;
+    let original_flow = state.flow_repo.get_in_workspace(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
     
     let new_id = Uuid::new_v4().to_string();
@@ -148,6 +226,11 @@ async fn duplicate_flow(
         name: format!("{} (Copy)", original_flow.name),
         description: original_flow.description.clone(), // Clone needed for Option<String>
         bricks: original_flow.bricks.clone(), // Clone needed for Vec<BrickConfig>
+        graph: original_flow.graph.clone(),
+        retry_policy: original_flow.retry_policy.clone(),
+        variables: original_flow.variables.clone(),
+        schema: original_flow.schema.clone(),
+        workspace_id: original_flow.workspace_id.clone(),
         active: false, // Duplicated flows start as inactive
         created_at: now,
         updated_at: now,
@@ -160,10 +243,13 @@ async fn duplicate_flow(
 
 async fn export_flow(
     axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(id): Path<String>,
 ) -> Result<Json<serde_json::Value>, StatusCode> {
-    let flow = state.flow_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    let mut flow = state.flow_repo.get_in_workspace(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    // Exports carry secret references, never credentials
+    redact_flow_credentials(&mut flow);
     
     let export_data = json!({
         "version": "1.0",
@@ -172,6 +258,10 @@ async fn export_flow(
             "name": flow.name,
             "description": flow.description,
             "bricks": flow.bricks,
+            "graph": flow.graph,
+            "retry_policy": flow.retry_policy,
+            "variables": flow.variables,
+            "schema": flow.schema,
             "active": flow.active,
         }
     });
@@ -186,8 +276,10 @@ struct ImportFlowRequest {
 
 async fn import_flow(
     axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
     Json(payload): Json<ImportFlowRequest>,
-) -> Result<Json<FlowResponse>, StatusCode> {
+) -> Result<Response, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
     let flow_data = payload.flow;
     
     let name = flow_data.get("name")
@@ -198,11 +290,46 @@ async fn import_flow(
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
     
-    let bricks_json = flow_data.get("bricks")
-        .ok_or(StatusCode::BAD_REQUEST)?;
-    
-    let bricks: Vec<BrickConfig> = serde_json::from_value(bricks_json.clone())
-        .map_err(|_| StatusCode::BAD_REQUEST)?;
+    let graph: Option<FlowGraph> = match flow_data.get("graph") {
+        Some(graph_json) if !graph_json.is_null() => Some(
+            serde_json::from_value(graph_json.clone()).map_err(|_| StatusCode::BAD_REQUEST)?,
+        ),
+        _ => None,
+    };
+
+    // Exports from before flow graphs only carry the linear brick list
+    let bricks: Vec<BrickConfig> = match flow_data.get("bricks") {
+        Some(bricks_json) => serde_json::from_value(bricks_json.clone())
+            .map_err(|_| StatusCode::BAD_REQUEST)?,
+        None if graph.is_some() => Vec::new(),
+        None => return Err(StatusCode::BAD_REQUEST),
+    };
+    let as_graph = graph.is_some();
+    let bricks = bricks.into_iter().map(|brick| (None, brick)).collect();
+    let (bricks, graph) = resolve_flow_structure(bricks, graph)?;
+    if let Err(errors) = check_brick_configs(&state, &graph, as_graph) {
+        return Ok(invalid_brick_configs(errors));
+    }
+
+    let retry_policy: Option<RetryPolicy> = match flow_data.get("retry_policy") {
+        Some(policy_json) if !policy_json.is_null() => Some(
+            serde_json::from_value(policy_json.clone()).map_err(|_| StatusCode::BAD_REQUEST)?,
+        ),
+        _ => None,
+    };
+
+    let variables: serde_json::Map<String, Value> = match flow_data.get("variables") {
+        Some(variables_json) if !variables_json.is_null() => serde_json::from_value(variables_json.clone())
+            .map_err(|_| StatusCode::BAD_REQUEST)?,
+        _ => Default::default(),
+    };
+
+    let schema: FlowSchema = match flow_data.get("schema") {
+        Some(schema_json) if !schema_json.is_null() => serde_json::from_value(schema_json.clone())
+            .map_err(|_| StatusCode::BAD_REQUEST)?,
+        _ => Default::default(),
+    };
+    check_flow_schema(&schema)?;
     
     // Validate webhook URLs in imported bricks
     for brick in &bricks {
@@ -223,6 +350,11 @@ async fn import_flow(
         name: name.to_string(),
         description,
         bricks,
+        graph: Some(graph),
+        retry_policy,
+        variables,
+        schema,
+        workspace_id: Some(auth_context.workspace_id),
         active: false, // Imported flows start as inactive
         created_at: now,
         updated_at: now,
@@ -230,6 +362,241 @@ async fn import_flow(
     
     state.flow_repo.create(&flow).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
-    Ok(Json(FlowResponse::from(flow)))
+    Ok(Json(FlowResponse::from(flow)).into_response())
+}
+
+async fn get_webhook(
+    axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<WebhookResponse>, StatusCode> {
+    ensure_flow_exists(&state, &auth_context, &id).await?;
+    let settings = state.webhook_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(WebhookResponse::from(settings)))
+}
+
+/// Sets how webhook calls of the flow authenticate; returns the secret once
+async fn configure_webhook(
+    axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+    Json(payload): Json<ConfigureWebhookRequest>,
+) -> Result<Json<WebhookResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    ensure_webhooks_enabled(&state)?;
+    ensure_flow_exists(&state, &auth_context, &id).await?;
+    if let Err(e) = payload.auth.validate() {
+        tracing::warn!(error = %e, flow_id = %id, "Invalid webhook settings");
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let idempotency_key_path = payload.idempotency_key_path.filter(|path| !path.is_empty());
+    if let Some(path) = &idempotency_key_path {
+        JsonPath::cached(path).map_err(|_| StatusCode::BAD_REQUEST)?;
+    }
+    let secret = webhook_secret(payload.secret)?;
+
+    let settings = state.webhook_repo.configure(&id, &payload.auth, &secret, idempotency_key_path.as_deref()).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    tracing::info!(flow_id = %id, user_id = %auth_context.user_id, "Webhook authentication configured");
+    Ok(Json(WebhookResponse { secret: Some(secret), ..WebhookResponse::from(settings) }))
+}
+
+/// Replaces the webhook secret; the old one may stay valid for a grace period
+async fn rotate_webhook_secret(
+    axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+    payload: Option<Json<RotateWebhookSecretRequest>>,
+) -> Result<Json<WebhookResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    ensure_webhooks_enabled(&state)?;
+    ensure_flow_exists(&state, &auth_context, &id).await?;
+    let payload = payload.map(|Json(payload)| payload).unwrap_or_default();
+    if payload.grace_period_secs > MAX_WEBHOOK_GRACE_PERIOD_SECS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let secret = webhook_secret(payload.secret)?;
+
+    let grace_period = chrono::Duration::seconds(payload.grace_period_secs as i64);
+    let settings = state.webhook_repo.rotate(&id, &secret, grace_period).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    tracing::info!(flow_id = %id, user_id = %auth_context.user_id, grace_period_secs = payload.grace_period_secs, "Webhook secret rotated");
+    Ok(Json(WebhookResponse { secret: Some(secret), ..WebhookResponse::from(settings) }))
+}
+
+/// Removes the webhook authentication; webhook calls of the flow are then rejected
+async fn delete_webhook(
+    axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    ensure_flow_exists(&state, &auth_context, &id).await?;
+    if !state.webhook_repo.delete(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_endpoint(
+    axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<EndpointResponse>, StatusCode> {
+    ensure_flow_exists(&state, &auth_context, &id).await?;
+    let endpoint = state.endpoint_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(EndpointResponse::from(endpoint)))
 }
 
+/// Publishes the flow under a custom method and path below `/hooks`
+///
+/// Calls authenticate with the flow's webhook secret. Fails with 409 Conflict
+/// if another flow uses the method and path.
+async fn configure_endpoint(
+    axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+    Json(payload): Json<ConfigureEndpointRequest>,
+) -> Result<Json<EndpointResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    ensure_flow_exists(&state, &auth_context, &id).await?;
+    let method = hooks::normalize_method(&payload.method).ok_or(StatusCode::BAD_REQUEST)?;
+    let path = hooks::normalize_path(&payload.path).ok_or(StatusCode::BAD_REQUEST)?;
+    if let Some(schema) = &payload.request_schema {
+        if let Err(e) = flowmason_core::schema::check_schema(schema) {
+            tracing::warn!(error = %e, flow_id = %id, "Invalid endpoint request schema");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+
+    let endpoint = state.endpoint_repo.configure(&id, &method, &path, payload.request_schema.as_ref()).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::CONFLICT)?;
+    tracing::info!(flow_id = %id, user_id = %auth_context.user_id, method = %method, path = %path, "Flow endpoint configured");
+    Ok(Json(EndpointResponse::from(endpoint)))
+}
+
+async fn delete_endpoint(
+    axum::extract::State(state): axum::extract::State<FlowState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    ensure_flow_exists(&state, &auth_context, &id).await?;
+    if !state.endpoint_repo.delete(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Checks every brick config of a flow against its brick's config schema
+///
+/// Errors locate the config in the request: under `/graph/nodes/<index>` for
+/// flows sent as a graph, under `/bricks/<index>` otherwise. Schema paths
+/// point into the brick's config schema (see `GET /bricks/:type/schema`).
+fn check_brick_configs(state: &FlowState, graph: &FlowGraph, as_graph: bool) -> Result<(), Vec<SchemaError>> {
+    let brick_nodes = graph.nodes.iter().enumerate().filter_map(|(index, node)| match &node.kind {
+        FlowNodeKind::Brick(brick) => Some((index, brick)),
+        FlowNodeKind::Join { .. } => None,
+    });
+
+    let mut errors = Vec::new();
+    for (brick_index, (node_index, brick)) in brick_nodes.enumerate() {
+        let location = if as_graph {
+            format!("/graph/nodes/{}", node_index)
+        } else {
+            format!("/bricks/{}", brick_index)
+        };
+        let Ok(instance) = state.brick_registry.create(&brick.brick_type) else {
+            errors.push(SchemaError {
+                instance_path: format!("{}/brick_type", location),
+                schema_path: String::new(),
+                message: format!("Unknown brick type '{}'", brick.brick_type),
+            });
+            continue;
+        };
+        if let Err(config_errors) = check_stored_config(instance.as_ref(), &brick.config) {
+            errors.extend(config_errors.into_iter().map(|e| SchemaError {
+                instance_path: format!("{}/config{}", location, e.instance_path),
+                ..e
+            }));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn invalid_brick_configs(errors: Vec<SchemaError>) -> Response {
+    tracing::warn!(errors = errors.len(), "Invalid brick configuration");
+    ApiError::Validation("Invalid brick configuration".to_string(), errors).into_response()
+}
+
+/// Rejects payload schemas that cannot be used for validation
+fn check_flow_schema(schema: &FlowSchema) -> Result<(), StatusCode> {
+    schema.check().map_err(|e| {
+        tracing::warn!(error = %e, "Invalid flow schema");
+        StatusCode::BAD_REQUEST
+    })
+}
+
+async fn ensure_flow_exists(state: &FlowState, auth_context: &AuthContext, id: &str) -> Result<(), StatusCode> {
+    state.flow_repo.get_in_workspace(&auth_context.workspace_id, id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(|_| ())
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Webhook secrets cannot be stored without a master key
+fn ensure_webhooks_enabled(state: &FlowState) -> Result<(), StatusCode> {
+    if state.webhook_repo.is_enabled() {
+        Ok(())
+    } else {
+        Err(StatusCode::SERVICE_UNAVAILABLE)
+    }
+}
+
+/// The given secret, or a generated one; short secrets are rejected
+fn webhook_secret(secret: Option<String>) -> Result<String, StatusCode> {
+    match secret {
+        Some(secret) if secret.len() < 16 => Err(StatusCode::BAD_REQUEST),
+        Some(secret) => Ok(secret),
+        None => Ok(generate_webhook_secret()),
+    }
+}
+
+/// Validates a submitted graph and keeps `bricks` in sync with its brick nodes.
+/// Without a graph, the linear brick list, each brick with its node id if
+/// given, is converted to a chain graph.
+fn resolve_flow_structure(
+    bricks: Vec<(Option<String>, BrickConfig)>,
+    graph: Option<FlowGraph>,
+) -> Result<(Vec<BrickConfig>, FlowGraph), StatusCode> {
+    let validate = match graph {
+        Some(_) => true,
+        None => bricks.iter().any(|(id, _)| id.is_some()),
+    };
+    let graph = graph.unwrap_or_else(|| FlowGraph::chain(bricks));
+    if validate {
+        if let Err(e) = GraphRunner::validate_graph(&graph) {
+            tracing::warn!(error = %e, "Invalid flow graph");
+            return Err(StatusCode::BAD_REQUEST);
+        }
+    }
+    let bricks = graph.brick_configs().into_iter().cloned().collect();
+    Ok((bricks, graph))
+}
+
+/// Pairs the bricks of a request with their node ids
+fn bricks_with_ids(bricks: Vec<BrickConfigDto>) -> Vec<(Option<String>, BrickConfig)> {
+    bricks.into_iter().map(|b| (b.id, BrickConfig {
+        brick_type: b.brick_type,
+        config: b.config,
+        policy: b.policy,
+    })).collect()
+}
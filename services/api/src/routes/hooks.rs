@@ -0,0 +1,118 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, RawQuery, State},
+    http::{HeaderMap, Method, StatusCode},
+    response::{IntoResponse, Response},
+    routing::any,
+    Router,
+};
+use crate::error::ApiError;
+use crate::routes::webhooks;
+use crate::routes::ExecutionState;
+
+/// Longest path a flow can be published under
+const MAX_PATH_LEN: usize = 256;
+
+pub fn routes() -> Router<ExecutionState> {
+    Router::new()
+        .route("/*path", any(call_flow_endpoint))
+}
+
+/// Runs the flow published under the request's method and path
+///
+/// Calls authenticate with the flow's webhook secret and support idempotency
+/// keys like webhook triggers. `GET` and `DELETE` calls pass their query
+/// parameters as the payload, other methods their JSON body; HMAC signatures
+/// cover the raw query string or the body respectively. Payloads that do
+/// not match the endpoint's request schema are rejected with 422 and the
+/// list of mismatches.
+async fn call_flow_endpoint(
+    State(state): State<ExecutionState>,
+    method: Method,
+    Path(path): Path<String>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, StatusCode> {
+    let method = normalize_method(method.as_str()).ok_or(StatusCode::METHOD_NOT_ALLOWED)?;
+    let path = normalize_path(&path).ok_or(StatusCode::NOT_FOUND)?;
+    let endpoint = state.endpoint_repo.find(&method, &path).await
+        .map_err(|e| {
+            tracing::error!(error = %e, method = %method, path = %path, "Failed to look up flow endpoint");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let flow = state.flow_repo.get(&endpoint.flow_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // What carries the payload is what the signature covers
+    let request = if takes_query(&method) {
+        query.unwrap_or_default().into_bytes()
+    } else {
+        body.to_vec()
+    };
+    let webhook = webhooks::authenticate(&state, &flow.id, &headers, &request).await?;
+
+    if !flow.active {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let input_payload = if takes_query(&method) {
+        query_payload(&request)
+    } else {
+        webhooks::parse_payload(&flow.id, &request)?
+    };
+    if let Some(schema) = &endpoint.request_schema {
+        if let Err(errors) = flowmason_core::schema::validate(schema, &input_payload) {
+            tracing::warn!(flow_id = %flow.id, errors = errors.len(), "Request does not match the endpoint schema");
+            return Ok(ApiError::Validation("Request does not match the endpoint schema".to_string(), errors).into_response());
+        }
+    }
+
+    webhooks::run_idempotent(state, flow, &webhook, &headers, &request, input_payload).await
+}
+
+/// Upper-cases a method flows can be published under
+pub(crate) fn normalize_method(method: &str) -> Option<String> {
+    let method = method.trim().to_ascii_uppercase();
+    matches!(method.as_str(), "GET" | "POST" | "PUT" | "PATCH" | "DELETE").then_some(method)
+}
+
+/// Normalizes an endpoint path to `/segment/segment`
+///
+/// Segments may contain letters, digits, `-`, `_`, `.` and `~`; empty, `.`
+/// and `..` segments are rejected.
+pub(crate) fn normalize_path(path: &str) -> Option<String> {
+    let path = path.trim().trim_matches('/');
+    if path.is_empty() || path.len() > MAX_PATH_LEN {
+        return None;
+    }
+    let valid = path.split('/').all(|segment| {
+        !segment.is_empty()
+            && segment != "."
+            && segment != ".."
+            && segment.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '~'))
+    });
+    valid.then(|| format!("/{}", path))
+}
+
+fn takes_query(method: &str) -> bool {
+    matches!(method, "GET" | "DELETE")
+}
+
+/// Query parameters as a payload object; repeated parameters become arrays
+fn query_payload(query: &[u8]) -> serde_json::Value {
+    let mut payload = serde_json::Map::new();
+    for (name, value) in url::form_urlencoded::parse(query) {
+        let value = serde_json::Value::String(value.into_owned());
+        match payload.get_mut(name.as_ref()) {
+            Some(serde_json::Value::Array(values)) => values.push(value),
+            Some(existing) => *existing = serde_json::Value::Array(vec![existing.take(), value]),
+            None => {
+                payload.insert(name.into_owned(), value);
+            }
+        }
+    }
+    serde_json::Value::Object(payload)
+}
@@ -1,29 +1,43 @@
 pub mod flows;
 pub mod bricks;
 pub mod executions;
+pub mod execution_events;
 pub mod usage;
 pub mod scheduler;
 pub mod auth;
+pub mod oidc;
 pub mod web;
 pub mod templates;
 pub mod webhooks;
+pub mod hooks;
+pub mod dead_letters;
+pub mod secrets;
+pub mod connections;
+pub mod workspaces;
 
 use axum::{Router, middleware, extract::Request, middleware::Next, response::Response, http::StatusCode, Json};
 use tower_http::services::ServeDir;
 use std::sync::Arc;
 use serde_json::json;
 use flowmason_core::quota::{QuotaManager, DatabaseQuotaManager};
-use flowmason_core::UsageLogger;
+use flowmason_core::{BrickRegistry, ConnectionResolver, ExecutionEventBus, SecretResolver, SubFlowExecutor, UsageLogger};
 use flowmason_meter::DatabaseUsageLogger;
 use flowmason_scheduler::CronExecutor;
-use flowmason_db::repositories::{FlowRepository, ExecutionRepository, UsageLogRepository, UserRepository, ApiKeyRepository, ScheduledFlowRepository, ExecutionDataRepository, TemplateRepository};
-use flowmason_auth::{auth_middleware, AuthStateForMiddleware, AuthContext, ApiKeyService};
+use flowmason_db::repositories::{FlowRepository, ExecutionRepository, UsageLogRepository, UserRepository, ApiKeyRepository, ScheduledFlowRepository, ExecutionDataRepository, TemplateRepository, JobRepository, DeadLetterRepository, ExecutionStepRepository, SecretRepository, ConnectionRepository, WorkspaceRepository, TokenRepository, WebhookRepository, IdempotencyRepository, EndpointRepository};
+use crate::dead_letter::DeadLetterReplayer;
+use flowmason_auth::{auth_middleware, optional_auth_middleware, AuthStateForMiddleware, AuthUser, ApiKeyService, Claims, JwtService, OidcClient, OidcConfig, ScopeResource, WorkspaceMembership};
 use sqlx::SqlitePool;
 
 #[derive(Clone)]
 pub struct FlowState {
     pub flow_repo: Arc<FlowRepository>,
     pub template_repo: Arc<TemplateRepository>,
+    pub execution_repo: Arc<ExecutionRepository>,
+    pub step_repo: Arc<ExecutionStepRepository>,
+    pub webhook_repo: Arc<WebhookRepository>,
+    pub endpoint_repo: Arc<EndpointRepository>,
+    /// Checks brick configs when flows are saved
+    pub brick_registry: Arc<BrickRegistry>,
 }
 
 #[derive(Clone)]
@@ -34,6 +48,17 @@ pub struct ExecutionState {
     pub execution_data_repo: Arc<ExecutionDataRepository>,
     pub quota_manager: Arc<dyn QuotaManager>,
     pub usage_logger: Arc<dyn UsageLogger>,
+    pub sub_flow_executor: Arc<dyn SubFlowExecutor>,
+    pub secret_resolver: Arc<dyn SecretResolver>,
+    pub connection_resolver: Arc<dyn ConnectionResolver>,
+    pub brick_registry: Arc<BrickRegistry>,
+    pub job_repo: Arc<JobRepository>,
+    pub dead_letter_repo: Arc<DeadLetterRepository>,
+    pub step_repo: Arc<ExecutionStepRepository>,
+    pub event_bus: Arc<ExecutionEventBus>,
+    pub webhook_repo: Arc<WebhookRepository>,
+    pub idempotency_repo: Arc<IdempotencyRepository>,
+    pub endpoint_repo: Arc<EndpointRepository>,
 }
 
 #[derive(Clone)]
@@ -42,14 +67,51 @@ pub struct SchedulerState {
     pub execution_repo: Arc<ExecutionRepository>,
     pub quota_manager: Arc<dyn QuotaManager>,
     pub usage_logger: Arc<dyn UsageLogger>,
+    pub sub_flow_executor: Arc<dyn SubFlowExecutor>,
+    pub secret_resolver: Arc<dyn SecretResolver>,
+    pub connection_resolver: Arc<dyn ConnectionResolver>,
+    pub brick_registry: Arc<BrickRegistry>,
     pub cron_executor: Arc<CronExecutor>,
     pub scheduled_flow_repo: Arc<ScheduledFlowRepository>,
 }
 
+#[derive(Clone)]
+pub struct DeadLetterState {
+    pub dead_letter_repo: Arc<DeadLetterRepository>,
+    pub replayer: Arc<DeadLetterReplayer>,
+}
+
+#[derive(Clone)]
+pub struct SecretState {
+    pub secret_repo: Arc<SecretRepository>,
+}
+
+#[derive(Clone)]
+pub struct ConnectionState {
+    pub connection_repo: Arc<ConnectionRepository>,
+    pub brick_registry: Arc<BrickRegistry>,
+}
+
+#[derive(Clone)]
+pub struct BrickState {
+    pub brick_registry: Arc<BrickRegistry>,
+    pub connection_repo: Arc<ConnectionRepository>,
+}
+
+#[derive(Clone)]
+pub struct WorkspaceState {
+    pub workspace_repo: Arc<WorkspaceRepository>,
+    pub user_repo: Arc<UserRepository>,
+}
+
 #[derive(Clone)]
 pub struct AuthState {
     pub user_repo: Arc<UserRepository>,
     pub api_key_repo: Arc<ApiKeyRepository>,
+    pub token_repo: Arc<TokenRepository>,
+    pub jwt: Arc<JwtService>,
+    /// Single sign-on provider; `None` unless `OIDC_ISSUER_URL` is set
+    pub oidc: Option<Arc<OidcClient>>,
 }
 
 #[derive(Clone)]
@@ -65,11 +127,40 @@ pub async fn create_router(pool: SqlitePool) -> Router {
     let usage_repo = Arc::new(UsageLogRepository::new(pool.clone()));
     let user_repo = Arc::new(UserRepository::new(pool.clone()));
     let api_key_repo = Arc::new(ApiKeyRepository::new(pool.clone()));
+    let token_repo = Arc::new(TokenRepository::new(pool.clone()));
+    let workspace_repo = Arc::new(WorkspaceRepository::new(pool.clone()));
     let scheduled_flow_repo = Arc::new(ScheduledFlowRepository::new(pool.clone()));
     let execution_data_repo = Arc::new(ExecutionDataRepository::new(pool.clone()));
+    let job_repo = Arc::new(JobRepository::new(pool.clone()));
+    let dead_letter_repo = Arc::new(DeadLetterRepository::new(pool.clone()));
+    let step_repo = Arc::new(ExecutionStepRepository::new(pool.clone()));
+    let secret_repo = Arc::new(SecretRepository::from_env(pool.clone()));
+    let secret_resolver: Arc<dyn SecretResolver> = secret_repo.clone();
+    let connection_repo = Arc::new(ConnectionRepository::new(pool.clone(), secret_repo.shared_cipher()));
+    let connection_resolver: Arc<dyn ConnectionResolver> = connection_repo.clone();
+    let webhook_repo = Arc::new(WebhookRepository::new(pool.clone(), secret_repo.shared_cipher()));
+    let endpoint_repo = Arc::new(EndpointRepository::new(pool.clone()));
     let quota_manager: Arc<dyn QuotaManager> = Arc::new(DatabaseQuotaManager::new(pool.clone()));
     let usage_logger: Arc<dyn UsageLogger> = Arc::new(DatabaseUsageLogger::new(usage_repo.clone()));
+    // Brick registry shared by every executor; register additional bricks here
+    let mut brick_registry = flowmason_bricks::default_registry();
+    flowmason_plugins::register_from_env(&mut brick_registry);
+    let brick_registry = Arc::new(brick_registry);
+    let sub_flow_executor: Arc<dyn SubFlowExecutor> = Arc::new(
+        flowmason_db::RepositorySubFlowExecutor::new(
+            flow_repo.clone(),
+            execution_repo.clone(),
+            brick_registry.clone(),
+        )
+    );
     
+    // Persist graphs for flows created before flow graphs existed
+    match flow_repo.migrate_linear_flows().await {
+        Ok(0) => {}
+        Ok(count) => tracing::info!(count, "Migrated linear flows to graphs"),
+        Err(e) => tracing::warn!(error = %e, "Failed to migrate linear flows to graphs"),
+    }
+
     // Create cron executor with repositories asynchronously
     let cron_executor = Arc::new(
         CronExecutor::with_repositories(
@@ -83,6 +174,10 @@ pub async fn create_router(pool: SqlitePool) -> Router {
     let execution_repo_clone = execution_repo.clone();
     let quota_manager_clone = quota_manager.clone();
     let usage_logger_clone = usage_logger.clone();
+    let sub_flow_executor_clone = sub_flow_executor.clone();
+    let secret_resolver_clone = secret_resolver.clone();
+    let connection_resolver_clone = connection_resolver.clone();
+    let brick_registry_clone = brick_registry.clone();
     
     tokio::spawn(async move {
         // Start the scheduler
@@ -96,58 +191,62 @@ pub async fn create_router(pool: SqlitePool) -> Router {
             let execution_repo = execution_repo_clone.clone();
             let quota_manager = quota_manager_clone.clone();
             let usage_logger = usage_logger_clone.clone();
+            let sub_flow_executor = sub_flow_executor_clone.clone();
+            let secret_resolver = secret_resolver_clone.clone();
+            let connection_resolver = connection_resolver_clone.clone();
+            let brick_registry = brick_registry_clone.clone();
             
             Arc::new(move |flow: flowmason_core::types::Flow, initial_payload: serde_json::Value| {
                 let execution_repo = execution_repo.clone();
                 let quota_manager = quota_manager.clone();
                 let usage_logger = usage_logger.clone();
+                let sub_flow_executor = sub_flow_executor.clone();
+                let secret_resolver = secret_resolver.clone();
+                let connection_resolver = connection_resolver.clone();
+                let brick_registry = brick_registry.clone();
                 
                 Box::pin(async move {
-                    use flowmason_bricks::*;
-                    use flowmason_bricks::RulesEngineBrick;
                     use flowmason_core::{FlowRunner, FlowRunnerContext};
                     
                     // Create brick instances
-                    let mut bricks: Vec<Box<dyn flowmason_core::Brick>> = Vec::new();
-                    
-                    for brick_config in &flow.bricks {
-                        let brick: Box<dyn flowmason_core::Brick> = match brick_config.brick_type {
-                            flowmason_core::types::BrickType::OpenAi => Box::new(OpenAiBrick),
-                            flowmason_core::types::BrickType::Nvidia => Box::new(NvidiaBrick),
-                            flowmason_core::types::BrickType::HubSpot => Box::new(HubSpotBrick),
-                            flowmason_core::types::BrickType::Notion => Box::new(NotionBrick),
-                            flowmason_core::types::BrickType::Odoo => Box::new(OdooBrick),
-                            flowmason_core::types::BrickType::N8n => Box::new(N8nBrick),
-                            flowmason_core::types::BrickType::FieldMapping => Box::new(FieldMappingBrick),
-                            flowmason_core::types::BrickType::CombineText => Box::new(CombineTextBrick),
-                            flowmason_core::types::BrickType::Conditional => Box::new(ConditionalBrick),
-                            flowmason_core::types::BrickType::RulesEngine => Box::new(RulesEngineBrick),
-                        };
-                        bricks.push(brick);
-                    }
+                    let bricks = brick_registry.create_all(&flow.bricks)?;
                     
                     // Create execution context
                     let context = FlowRunnerContext {
                         quota_manager: Some(quota_manager),
                         usage_logger: Some(usage_logger),
                         execution_data_storage: None, // Scheduler doesn't store execution data
+                        sub_flow_executor: Some(sub_flow_executor),
+                        brick_registry: Some(brick_registry),
+                        event_bus: None,
                         flow_id: flow.id.clone(),
                         execution_id: uuid::Uuid::new_v4().to_string(),
+                        workspace_id: flow.workspace_id.clone(),
+                        parent_execution_id: None,
+                        depth: 0,
+                        variables: Default::default(),
+                        secret_resolver: Some(secret_resolver),
+                        connection_resolver: Some(connection_resolver),
+                        redactor: Default::default(),
+                        responder: None,
                     };
                     
                     // Execute flow
-                    let execution = FlowRunner::execute_flow_with_tracking(
+                    let execution = FlowRunner::execute_flow_recorded(
                         &flow,
                         bricks,
                         initial_payload,
                         Some(context),
                     )
-                    .await
-                    .map_err(|e| anyhow::anyhow!("Flow execution error: {}", e))?;
+                    .await;
                     
-                    // Store execution in history
+                    // Store execution in history (failed runs are dead-lettered)
                     execution_repo.create(&execution).await?;
                     
+                    if let Some(error) = &execution.error {
+                        return Err(anyhow::anyhow!("Flow execution error: {}", error));
+                    }
+                    
                     Ok(execution)
                 })
             })
@@ -163,26 +262,99 @@ pub async fn create_router(pool: SqlitePool) -> Router {
         execution_data_repo: execution_data_repo.clone(),
         quota_manager: quota_manager.clone(),
         usage_logger: usage_logger.clone(),
+        sub_flow_executor: sub_flow_executor.clone(),
+        secret_resolver: secret_resolver.clone(),
+        connection_resolver: connection_resolver.clone(),
+        brick_registry: brick_registry.clone(),
+        job_repo: job_repo.clone(),
+        dead_letter_repo: dead_letter_repo.clone(),
+        step_repo: step_repo.clone(),
+        event_bus: Arc::new(ExecutionEventBus::new()),
+        webhook_repo: webhook_repo.clone(),
+        idempotency_repo: Arc::new(IdempotencyRepository::new(pool.clone())),
+        endpoint_repo: endpoint_repo.clone(),
     };
     
+    // Replay dead-lettered executions once their retry is due
+    let dead_letter_replayer = Arc::new(DeadLetterReplayer {
+        flow_repo: flow_repo.clone(),
+        execution_repo: execution_repo.clone(),
+        execution_data_repo: execution_data_repo.clone(),
+        dead_letter_repo: dead_letter_repo.clone(),
+        quota_manager: quota_manager.clone(),
+        usage_logger: usage_logger.clone(),
+        sub_flow_executor: sub_flow_executor.clone(),
+        secret_resolver: secret_resolver.clone(),
+        connection_resolver: connection_resolver.clone(),
+        brick_registry: brick_registry.clone(),
+    });
+    let dlq_retry_interval = std::env::var("DLQ_RETRY_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(30);
+    dead_letter_replayer.clone().spawn_retrier(std::time::Duration::from_secs(dlq_retry_interval));
+
     let scheduler_state = SchedulerState {
         flow_repo: flow_repo.clone(),
         execution_repo: execution_repo.clone(),
         quota_manager,
         usage_logger,
+        sub_flow_executor: sub_flow_executor.clone(),
+        secret_resolver: secret_resolver.clone(),
+        connection_resolver: connection_resolver.clone(),
+        brick_registry: brick_registry.clone(),
         cron_executor: cron_executor.clone(),
         scheduled_flow_repo: scheduled_flow_repo.clone(),
     };
 
+    // Token keys are loaded once; a broken configuration stops the server
+    let jwt = Arc::new(JwtService::from_env().unwrap_or_else(|e| {
+        tracing::error!(error = %e, "Failed to load JWT configuration");
+        panic!("Invalid JWT configuration: {}", e)
+    }));
+
+    let oidc = OidcConfig::from_env().map(|config| {
+        let config = config.unwrap_or_else(|e| {
+            tracing::error!(error = %e, "Failed to load OIDC configuration");
+            panic!("Invalid OIDC configuration: {}", e)
+        });
+        tracing::info!(issuer = %config.issuer_url, "OpenID Connect login enabled");
+        Arc::new(OidcClient::new(config))
+    });
+
     let auth_state = AuthState {
         user_repo: user_repo.clone(),
         api_key_repo: api_key_repo.clone(),
+        token_repo: token_repo.clone(),
+        jwt: jwt.clone(),
+        oidc,
     };
+
+    // Reject access tokens revoked by jti or by revoking all tokens of the user
+    let token_repo_for_middleware = token_repo.clone();
+    let validate_access_token: flowmason_auth::AccessTokenValidator = Arc::new(move |claims: Claims| {
+        let token_repo = token_repo_for_middleware.clone();
+        Box::pin(async move {
+            let revoked = token_repo.is_access_token_revoked(&claims.sub, &claims.jti, claims.iat).await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            if revoked {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            Ok(AuthUser {
+                user_id: claims.sub,
+                email: claims.email,
+                scopes: None,
+                groups: claims.groups,
+                key_expires_at: None,
+                key_allowed_ips: None,
+            })
+        })
+    });
     
     // Create API key validator closure that has access to repositories
     let user_repo_for_middleware = user_repo.clone();
     let api_key_repo_for_middleware = api_key_repo.clone();
-    let validate_api_key: flowmason_auth::ApiKeyValidator = Arc::new(move |api_key: String| {
+    let validate_api_key: flowmason_auth::ApiKeyValidator = Arc::new(move |api_key: String, client_ip: Option<std::net::IpAddr>| {
         let user_repo = user_repo_for_middleware.clone();
         let api_key_repo = api_key_repo_for_middleware.clone();
         Box::pin(async move {
@@ -191,6 +363,15 @@ pub async fn create_router(pool: SqlitePool) -> Router {
             let api_key_data = api_key_repo.get_by_hash(&key_hash).await
                 .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
                 .ok_or(StatusCode::UNAUTHORIZED)?;
+
+            // Revoked and expired keys no longer authenticate
+            if !api_key_data.is_active(chrono::Utc::now()) {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            if !api_key_data.allows_ip(client_ip) {
+                tracing::warn!(api_key_id = %api_key_data.id, client_ip = ?client_ip, "API key used from a client outside its IP allow-list");
+                return Err(StatusCode::FORBIDDEN);
+            }
             
             // Update last_used_at
             let _ = api_key_repo.update_last_used(&key_hash).await;
@@ -200,15 +381,46 @@ pub async fn create_router(pool: SqlitePool) -> Router {
                 .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
                 .ok_or(StatusCode::UNAUTHORIZED)?;
             
-            Ok(AuthContext {
+            Ok(AuthUser {
                 user_id: user.id,
                 email: user.email,
+                scopes: api_key_data.scopes,
+                groups: user.groups,
+                key_expires_at: api_key_data.expires_at,
+                key_allowed_ips: api_key_data.allowed_ips,
             })
         })
     });
+
+    // Resolve the workspace named in X-Workspace-Id, or the user's first one
+    let workspace_repo_for_middleware = workspace_repo.clone();
+    let resolve_workspace: flowmason_auth::WorkspaceResolver = Arc::new(move |user_id: String, requested: Option<String>| {
+        let workspace_repo = workspace_repo_for_middleware.clone();
+        Box::pin(async move {
+            match requested {
+                Some(workspace_id) => {
+                    let role = workspace_repo.get_role(&workspace_id, &user_id).await
+                        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+                        .ok_or(StatusCode::FORBIDDEN)?;
+                    Ok(WorkspaceMembership { workspace_id, role })
+                }
+                None => {
+                    let (workspace, role) = workspace_repo.default_for_user(&user_id).await
+                        .map_err(|e| {
+                            tracing::error!(error = %e, user_id = %user_id, "Failed to resolve default workspace");
+                            StatusCode::INTERNAL_SERVER_ERROR
+                        })?;
+                    Ok(WorkspaceMembership { workspace_id: workspace.id, role })
+                }
+            }
+        })
+    });
     
     let auth_state_for_middleware = AuthStateForMiddleware {
+        jwt: jwt.clone(),
+        validate_access_token: validate_access_token.clone(),
         validate_api_key: validate_api_key.clone(),
+        resolve_workspace: resolve_workspace.clone(),
     };
     
     // Create a middleware wrapper that injects AuthStateForMiddleware and calls auth_middleware
@@ -227,6 +439,12 @@ pub async fn create_router(pool: SqlitePool) -> Router {
     let auth_state_clone_3 = auth_state_for_middleware.clone();
     let auth_state_clone_4 = auth_state_for_middleware.clone();
     let auth_state_clone_5 = auth_state_for_middleware.clone();
+    let auth_state_clone_6 = auth_state_for_middleware.clone();
+    let auth_state_clone_7 = auth_state_for_middleware.clone();
+    let auth_state_clone_8 = auth_state_for_middleware.clone();
+    let auth_state_clone_9 = auth_state_for_middleware.clone();
+    let auth_state_for_bricks = auth_state_for_middleware.clone();
+    // Each nest below also names its ScopeResource, which scoped API keys must be granted
     
     // Also need to inject auth state for /auth/me route
     let auth_state_for_auth_routes = auth_state_for_middleware.clone();
@@ -236,6 +454,11 @@ pub async fn create_router(pool: SqlitePool) -> Router {
         .merge(web::routes().with_state(FlowState { 
             flow_repo: flow_repo.clone(),
             template_repo: template_repo.clone(),
+            execution_repo: execution_repo.clone(),
+            step_repo: step_repo.clone(),
+            webhook_repo: webhook_repo.clone(),
+            endpoint_repo: endpoint_repo.clone(),
+            brick_registry: brick_registry.clone(),
         }))
         .nest("/api/v1", Router::new()
             .nest("/auth", auth::routes()
@@ -247,21 +470,42 @@ pub async fn create_router(pool: SqlitePool) -> Router {
                     }
                 }))
                 .with_state(auth_state))
-            .nest("/bricks", bricks::routes())
+            .nest("/bricks", bricks::routes()
+                .layer(middleware::from_fn(move |mut request: Request, next: Next| {
+                    let state = auth_state_for_bricks.clone();
+                    async move {
+                        request.extensions_mut().insert(state);
+                        optional_auth_middleware(request, next).await
+                    }
+                }))
+                .with_state(BrickState {
+                    brick_registry: brick_registry.clone(),
+                    connection_repo: connection_repo.clone(),
+                }))
             .nest("/flows", flows::routes()
                 .layer(middleware::from_fn(move |mut request: Request, next: Next| {
                     let state = auth_state_clone_1.clone();
                     async move {
                         request.extensions_mut().insert(state);
+                        request.extensions_mut().insert(ScopeResource::Flows);
                         auth_middleware(request, next).await
                     }
                 }))
-                .with_state(FlowState { flow_repo: flow_repo.clone() }))
+                .with_state(FlowState {
+                    flow_repo: flow_repo.clone(),
+                    template_repo: template_repo.clone(),
+                    execution_repo: execution_repo.clone(),
+                    step_repo: step_repo.clone(),
+                    webhook_repo: webhook_repo.clone(),
+                    endpoint_repo: endpoint_repo.clone(),
+                    brick_registry: brick_registry.clone(),
+                }))
             .nest("/executions", executions::routes()
                 .layer(middleware::from_fn(move |mut request: Request, next: Next| {
                     let state = auth_state_clone_2.clone();
                     async move {
                         request.extensions_mut().insert(state);
+                        request.extensions_mut().insert(ScopeResource::Executions);
                         auth_middleware(request, next).await
                     }
                 }))
@@ -271,6 +515,7 @@ pub async fn create_router(pool: SqlitePool) -> Router {
                     let state = auth_state_clone_3.clone();
                     async move {
                         request.extensions_mut().insert(state);
+                        request.extensions_mut().insert(ScopeResource::Usage);
                         auth_middleware(request, next).await
                     }
                 }))
@@ -280,6 +525,7 @@ pub async fn create_router(pool: SqlitePool) -> Router {
                     let state = auth_state_clone_4.clone();
                     async move {
                         request.extensions_mut().insert(state);
+                        request.extensions_mut().insert(ScopeResource::Scheduler);
                         auth_middleware(request, next).await
                     }
                 }))
@@ -289,6 +535,7 @@ pub async fn create_router(pool: SqlitePool) -> Router {
                     let state = auth_state_clone_5.clone();
                     async move {
                         request.extensions_mut().insert(state);
+                        request.extensions_mut().insert(ScopeResource::Templates);
                         auth_middleware(request, next).await
                     }
                 }))
@@ -296,9 +543,63 @@ pub async fn create_router(pool: SqlitePool) -> Router {
                     template_repo: template_repo.clone(),
                     flow_repo: flow_repo.clone(),
                 }))
+            .nest("/dead-letters", dead_letters::routes()
+                .layer(middleware::from_fn(move |mut request: Request, next: Next| {
+                    let state = auth_state_clone_6.clone();
+                    async move {
+                        request.extensions_mut().insert(state);
+                        request.extensions_mut().insert(ScopeResource::DeadLetters);
+                        auth_middleware(request, next).await
+                    }
+                }))
+                .with_state(DeadLetterState {
+                    dead_letter_repo: dead_letter_repo.clone(),
+                    replayer: dead_letter_replayer.clone(),
+                }))
+            .nest("/secrets", secrets::routes()
+                .layer(middleware::from_fn(move |mut request: Request, next: Next| {
+                    let state = auth_state_clone_7.clone();
+                    async move {
+                        request.extensions_mut().insert(state);
+                        request.extensions_mut().insert(ScopeResource::Secrets);
+                        auth_middleware(request, next).await
+                    }
+                }))
+                .with_state(SecretState {
+                    secret_repo: secret_repo.clone(),
+                }))
+            .nest("/connections", connections::routes()
+                .layer(middleware::from_fn(move |mut request: Request, next: Next| {
+                    let state = auth_state_clone_8.clone();
+                    async move {
+                        request.extensions_mut().insert(state);
+                        request.extensions_mut().insert(ScopeResource::Connections);
+                        auth_middleware(request, next).await
+                    }
+                }))
+                .with_state(ConnectionState {
+                    connection_repo: connection_repo.clone(),
+                    brick_registry: brick_registry.clone(),
+                }))
+            .nest("/workspaces", workspaces::routes()
+                .layer(middleware::from_fn(move |mut request: Request, next: Next| {
+                    let state = auth_state_clone_9.clone();
+                    async move {
+                        request.extensions_mut().insert(state);
+                        request.extensions_mut().insert(ScopeResource::Workspaces);
+                        auth_middleware(request, next).await
+                    }
+                }))
+                .with_state(WorkspaceState {
+                    workspace_repo: workspace_repo.clone(),
+                    user_repo: user_repo.clone(),
+                }))
             .nest("/webhooks", webhooks::routes()
                 .with_state(execution_state.clone()))
-        );
+        )
+        // Custom flow endpoints authenticate like webhooks
+        .nest("/hooks", hooks::routes()
+            .with_state(execution_state.clone()));
 
     // Serve static files from React/Vite build directory if it exists
     let static_dir = std::path::Path::new("services/web-ui-vite/dist");
@@ -308,3 +609,4 @@ pub async fn create_router(pool: SqlitePool) -> Router {
 
     app
 }
+
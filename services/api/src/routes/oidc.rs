@@ -0,0 +1,143 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Redirect, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+
+use crate::routes::auth::issue_tokens;
+use crate::routes::AuthState;
+use flowmason_auth::oidc::LOGIN_TIMEOUT;
+use flowmason_auth::{OidcConfig, OidcError, OidcIdentity, User};
+
+/// Password hash of users provisioned by single sign-on; matches no password
+const SSO_PASSWORD_HASH: &str = "!sso";
+
+/// Cookie binding a started login to the browser that started it
+const STATE_COOKIE: &str = "flowmason_oidc_state";
+
+#[derive(Deserialize)]
+pub struct CallbackQuery {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Set by the provider when the user denied or the login failed
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+pub fn routes() -> Router<AuthState> {
+    Router::new()
+        .route("/login", get(login))
+        .route("/callback", get(callback))
+}
+
+/// Starts a single sign-on login by redirecting to the identity provider
+async fn login(State(state): State<AuthState>) -> Result<Response, StatusCode> {
+    let oidc = state.oidc.as_ref().ok_or(StatusCode::NOT_FOUND)?;
+    let (url, login_state) = oidc.authorization_url().await.map_err(oidc_status)?;
+    let cookie = state_cookie(oidc.config(), &login_state, LOGIN_TIMEOUT.as_secs());
+    Ok(([(header::SET_COOKIE, cookie)], Redirect::to(&url)).into_response())
+}
+
+/// Completes a single sign-on login and issues tokens
+///
+/// With `OIDC_POST_LOGIN_REDIRECT` set, redirects there with the tokens in
+/// the URL fragment; otherwise returns them as JSON like `/login`.
+async fn callback(
+    State(state): State<AuthState>,
+    headers: HeaderMap,
+    Query(query): Query<CallbackQuery>,
+) -> Result<Response, StatusCode> {
+    let oidc = state.oidc.clone().ok_or(StatusCode::NOT_FOUND)?;
+    if let Some(error) = query.error {
+        tracing::warn!(error = %error, description = ?query.error_description, "Identity provider rejected the login");
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let (code, login_state) = query.code.zip(query.state).ok_or(StatusCode::BAD_REQUEST)?;
+
+    let identity = oidc.complete(&code, &login_state, browser_state(&headers)).await.map_err(oidc_status)?;
+    let user = provision_user(&state, &identity).await?;
+    let tokens = issue_tokens(&state, user.id, user.email, identity.groups).await?;
+
+    let clear_cookie = [(header::SET_COOKIE, state_cookie(oidc.config(), "", 0))];
+    match &oidc.config().post_login_redirect {
+        Some(target) => {
+            let fragment = url::form_urlencoded::Serializer::new(String::new())
+                .append_pair("token", &tokens.token)
+                .append_pair("refresh_token", &tokens.refresh_token)
+                .append_pair("expires_in", &tokens.expires_in.to_string())
+                .finish();
+            Ok((clear_cookie, Redirect::to(&format!("{}#{}", target, fragment))).into_response())
+        }
+        None => Ok((clear_cookie, Json(tokens)).into_response()),
+    }
+}
+
+/// `Set-Cookie` value for the state cookie; Lax so that it is sent on the
+/// provider's top-level redirect back to the callback
+fn state_cookie(config: &OidcConfig, value: &str, max_age: u64) -> String {
+    let secure = if config.redirect_uri.starts_with("https://") { "; Secure" } else { "" };
+    format!(
+        "{}={}; Path=/api/v1/auth/oidc; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        STATE_COOKIE, value, max_age, secure
+    )
+}
+
+/// State of the login started by this browser, from its state cookie
+fn browser_state(headers: &HeaderMap) -> Option<&str> {
+    headers.get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .find_map(|cookie| cookie.trim().strip_prefix(STATE_COOKIE)?.strip_prefix('='))
+        .filter(|value| !value.is_empty())
+}
+
+/// Finds or creates the user of an identity and records its groups
+///
+/// Existing password users are linked by email only if the provider verified
+/// the address, so an unverified email cannot take over an account.
+async fn provision_user(state: &AuthState, identity: &OidcIdentity) -> Result<User, StatusCode> {
+    let linked = state.user_repo.get_by_oidc_subject(&identity.subject).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let user = match linked {
+        Some(user) => user,
+        None => match state.user_repo.get_by_email(&identity.email).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        {
+            Some(user) => {
+                let other_subject = state.user_repo.get_oidc_subject(&user.id).await
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                if !identity.email_verified || other_subject.is_some() {
+                    tracing::warn!(user_id = %user.id, subject = %identity.subject, "Refused to link single sign-on identity to existing user");
+                    return Err(StatusCode::CONFLICT);
+                }
+                user
+            }
+            None => {
+                let user = User::new(identity.email.clone(), SSO_PASSWORD_HASH.to_string());
+                state.user_repo.create(&user).await
+                    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+                tracing::info!(user_id = %user.id, subject = %identity.subject, "Provisioned user from single sign-on");
+                user
+            }
+        },
+    };
+
+    state.user_repo.set_oidc_identity(&user.id, &identity.subject, &identity.groups).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(User { groups: identity.groups.clone(), ..user })
+}
+
+fn oidc_status(error: OidcError) -> StatusCode {
+    tracing::warn!(error = %error, "Single sign-on login failed");
+    match error {
+        OidcError::UnknownState | OidcError::StateMismatch | OidcError::InvalidIdToken(_) => StatusCode::UNAUTHORIZED,
+        OidcError::TooManyLogins => StatusCode::SERVICE_UNAVAILABLE,
+        OidcError::Provider(_) => StatusCode::BAD_GATEWAY,
+        OidcError::Configuration(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
@@ -1,19 +1,19 @@
 use axum::{
-    extract::Path,
+    extract::{Extension, Path},
     http::StatusCode,
-    response::Json,
+    response::{IntoResponse, Json, Response},
     routing::{post, delete},
     Router,
 };
 use std::sync::Arc;
 
 use crate::dto::{ScheduleFlowRequest, ScheduleFlowResponse, ScheduledFlowsResponse};
+use crate::routes::executions;
 use crate::routes::SchedulerState;
 use crate::validation::validate_cron_expression;
+use flowmason_auth::{AuthContext, WorkspaceRole};
 use flowmason_scheduler::cron_executor::FlowExecutor;
 use flowmason_core::{FlowRunner, FlowRunnerContext};
-use flowmason_bricks::*;
-use flowmason_bricks::RulesEngineBrick;
 
 pub fn routes() -> Router<SchedulerState> {
     Router::new()
@@ -21,10 +21,16 @@ pub fn routes() -> Router<SchedulerState> {
         .route("/flows/:flow_id", delete(unschedule_flow))
 }
 
+/// Runs a flow on a cron schedule
+///
+/// Scheduled runs start with an empty payload, so flows whose input schema
+/// rejects `{}` cannot be scheduled (422).
 async fn schedule_flow(
     axum::extract::State(state): axum::extract::State<SchedulerState>,
+    Extension(auth_context): Extension<AuthContext>,
     Json(payload): Json<ScheduleFlowRequest>,
-) -> Result<Json<ScheduleFlowResponse>, StatusCode> {
+) -> Result<Response, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
     // Validate cron expression
     if let Err(e) = validate_cron_expression(&payload.cron_expression) {
         tracing::warn!(cron_expression = %payload.cron_expression, error = %e, "Invalid cron expression");
@@ -32,61 +38,70 @@ async fn schedule_flow(
     }
 
     // Get flow from repository
-    let flow = state.flow_repo.get(&payload.flow_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    let flow = state.flow_repo.get_in_workspace(&auth_context.workspace_id, &payload.flow_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    if let Some(response) = executions::reject_invalid_input(&flow, &serde_json::json!({})) {
+        return Ok(response);
+    }
 
     // Create executor function that will be called by the scheduler
     let execution_repo_clone = state.execution_repo.clone();
     let quota_manager_clone = state.quota_manager.clone();
     let usage_logger_clone = state.usage_logger.clone();
+    let sub_flow_executor_clone = state.sub_flow_executor.clone();
+    let secret_resolver_clone = state.secret_resolver.clone();
+    let connection_resolver_clone = state.connection_resolver.clone();
+    let brick_registry_clone = state.brick_registry.clone();
     
     let executor: FlowExecutor = Arc::new(move |flow: flowmason_core::types::Flow, initial_payload: serde_json::Value| {
         let execution_repo = execution_repo_clone.clone();
         let quota_manager = quota_manager_clone.clone();
         let usage_logger = usage_logger_clone.clone();
+        let sub_flow_executor = sub_flow_executor_clone.clone();
+        let secret_resolver = secret_resolver_clone.clone();
+        let connection_resolver = connection_resolver_clone.clone();
+        let brick_registry = brick_registry_clone.clone();
         
         Box::pin(async move {
             // Create brick instances
-            let mut bricks: Vec<Box<dyn flowmason_core::Brick>> = Vec::new();
-            
-            for brick_config in &flow.bricks {
-                let brick: Box<dyn flowmason_core::Brick> = match brick_config.brick_type {
-                    flowmason_core::types::BrickType::OpenAi => Box::new(OpenAiBrick),
-                    flowmason_core::types::BrickType::Nvidia => Box::new(NvidiaBrick),
-                    flowmason_core::types::BrickType::HubSpot => Box::new(HubSpotBrick),
-                    flowmason_core::types::BrickType::Notion => Box::new(NotionBrick),
-                    flowmason_core::types::BrickType::Odoo => Box::new(OdooBrick),
-                    flowmason_core::types::BrickType::N8n => Box::new(N8nBrick),
-                    flowmason_core::types::BrickType::FieldMapping => Box::new(FieldMappingBrick),
-                    flowmason_core::types::BrickType::CombineText => Box::new(CombineTextBrick),
-                            flowmason_core::types::BrickType::Conditional => Box::new(ConditionalBrick),
-                            flowmason_core::types::BrickType::RulesEngine => Box::new(RulesEngineBrick),
-                        };
-                        bricks.push(brick);
-                    }
+            let bricks = brick_registry.create_all(&flow.bricks)?;
             
             // Create execution context
             let context = FlowRunnerContext {
                 quota_manager: Some(quota_manager),
                 usage_logger: Some(usage_logger),
                 execution_data_storage: None, // Scheduler doesn't store execution data
+                sub_flow_executor: Some(sub_flow_executor),
+                brick_registry: Some(brick_registry),
+                event_bus: None,
                 flow_id: flow.id.clone(),
                 execution_id: uuid::Uuid::new_v4().to_string(),
+                workspace_id: flow.workspace_id.clone(),
+                parent_execution_id: None,
+                depth: 0,
+                variables: Default::default(),
+                secret_resolver: Some(secret_resolver),
+                connection_resolver: Some(connection_resolver),
+                redactor: Default::default(),
+                responder: None,
             };
             
             // Execute flow
-            let execution = FlowRunner::execute_flow_with_tracking(
+            let execution = FlowRunner::execute_flow_recorded(
                 &flow,
                 bricks,
                 initial_payload,
                 Some(context),
             )
-            .await
-            .map_err(|e| anyhow::anyhow!("Flow execution error: {}", e))?;
+            .await;
             
-            // Store execution in history
+            // Store execution in history (failed runs are dead-lettered)
             execution_repo.create(&execution).await?;
             
+            if let Some(error) = &execution.error {
+                return Err(anyhow::anyhow!("Flow execution error: {}", error));
+            }
+            
             Ok(execution)
         })
     });
@@ -105,21 +120,22 @@ async fn schedule_flow(
         flow_id: payload.flow_id,
         cron_expression: payload.cron_expression,
         scheduled_at: chrono::Utc::now().to_rfc3339(),
-    }))
+    }).into_response())
 }
 
 async fn list_scheduled_flows(
     axum::extract::State(state): axum::extract::State<SchedulerState>,
+    Extension(auth_context): Extension<AuthContext>,
 ) -> Result<Json<ScheduledFlowsResponse>, StatusCode> {
-    // Get scheduled flows with cron expressions from database
-    let scheduled_flows_with_cron = state.cron_executor.get_scheduled_flows_with_cron().await
+    // Get the workspace's scheduled flows with cron expressions from database
+    let scheduled_flows = state.scheduled_flow_repo.list_by_workspace(&auth_context.workspace_id).await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
-    let scheduled_flows: Vec<_> = scheduled_flows_with_cron
+    let scheduled_flows: Vec<_> = scheduled_flows
         .into_iter()
-        .map(|(flow_id, cron_expression)| crate::dto::ScheduledFlowResponse {
-            flow_id,
-            cron_expression,
+        .map(|scheduled| crate::dto::ScheduledFlowResponse {
+            flow_id: scheduled.flow_id,
+            cron_expression: scheduled.cron_expression,
         })
         .collect();
 
@@ -130,8 +146,13 @@ async fn list_scheduled_flows(
 
 async fn unschedule_flow(
     axum::extract::State(state): axum::extract::State<SchedulerState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(flow_id): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    state.flow_repo.get_in_workspace(&auth_context.workspace_id, &flow_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
     state.cron_executor
         .unschedule_flow(&flow_id)
         .await
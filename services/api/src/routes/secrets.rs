@@ -0,0 +1,112 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+
+use crate::dto::{CreateSecretRequest, SecretResponse, UpdateSecretRequest};
+use crate::routes::SecretState;
+use flowmason_auth::{AuthContext, WorkspaceRole};
+use flowmason_core::templating::is_secret_name_char;
+
+/// Maximum length of a secret name
+const MAX_SECRET_NAME_LEN: usize = 128;
+
+pub fn routes() -> Router<SecretState> {
+    Router::new()
+        .route("/", get(list_secrets).post(create_secret))
+        .route("/:name", get(get_secret).put(update_secret).delete(delete_secret))
+}
+
+async fn list_secrets(
+    State(state): State<SecretState>,
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<Vec<SecretResponse>>, StatusCode> {
+    let secrets = state.secret_repo.list(&auth_context.workspace_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(secrets.into_iter().map(SecretResponse::from).collect()))
+}
+
+async fn get_secret(
+    State(state): State<SecretState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(name): Path<String>,
+) -> Result<Json<SecretResponse>, StatusCode> {
+    let secret = state.secret_repo.get(&auth_context.workspace_id, &name).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(SecretResponse::from(secret)))
+}
+
+async fn create_secret(
+    State(state): State<SecretState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(payload): Json<CreateSecretRequest>,
+) -> Result<Json<SecretResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    ensure_enabled(&state)?;
+    if !is_valid_secret_name(&payload.name) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if state.secret_repo.get(&auth_context.workspace_id, &payload.name).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let secret = state.secret_repo
+        .create(&auth_context.workspace_id, &payload.name, &payload.value, payload.description.as_deref())
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, secret = %payload.name, "Failed to create secret");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    tracing::info!(secret = %secret.name, workspace_id = %auth_context.workspace_id, "Secret created");
+
+    Ok(Json(SecretResponse::from(secret)))
+}
+
+async fn update_secret(
+    State(state): State<SecretState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(name): Path<String>,
+    Json(payload): Json<UpdateSecretRequest>,
+) -> Result<Json<SecretResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    ensure_enabled(&state)?;
+    let secret = state.secret_repo
+        .update(&auth_context.workspace_id, &name, &payload.value, payload.description.as_deref())
+        .await
+        .map_err(|e| {
+            tracing::error!(error = %e, secret = %name, "Failed to update secret");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    tracing::info!(secret = %secret.name, "Secret updated");
+
+    Ok(Json(SecretResponse::from(secret)))
+}
+
+async fn delete_secret(
+    State(state): State<SecretState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
+    if !state.secret_repo.delete(&auth_context.workspace_id, &name).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    tracing::info!(secret = %name, "Secret deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Secret values cannot be written without a master key
+fn ensure_enabled(state: &SecretState) -> Result<(), StatusCode> {
+    if state.secret_repo.is_enabled() {
+        Ok(())
+    } else {
+        Err(StatusCode::SERVICE_UNAVAILABLE)
+    }
+}
+
+fn is_valid_secret_name(name: &str) -> bool {
+    !name.is_empty() && name.len() <= MAX_SECRET_NAME_LEN && name.chars().all(is_secret_name_char)
+}
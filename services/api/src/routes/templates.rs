@@ -8,10 +8,11 @@ use axum::{
 };
 use uuid::Uuid;
 use serde::Deserialize;
-use flowmason_auth::AuthContext;
+use flowmason_auth::{AuthContext, WorkspaceRole};
 
 use crate::dto::{CreateTemplateRequest, TemplateResponse, UpdateTemplateRequest, InstantiateTemplateRequest, PaginationParams, PaginatedResponse};
 use crate::routes::TemplateState;
+use flowmason_core::secrets::redact_flow_credentials;
 use flowmason_core::types::{Flow, BrickConfig};
 
 pub fn routes() -> Router<TemplateState> {
@@ -33,6 +34,7 @@ async fn create_template(
     Extension(auth_context): Extension<AuthContext>,
     Json(payload): Json<CreateTemplateRequest>,
 ) -> Result<Json<TemplateResponse>, StatusCode> {
+    auth_context.require(WorkspaceRole::Editor)?;
     let id = Uuid::new_v4().to_string();
     let now = chrono::Utc::now();
     
@@ -41,9 +43,10 @@ async fn create_template(
         name: payload.name,
         description: payload.description,
         category: payload.category,
-        flow_config: payload.flow_config,
+        flow_config: without_credentials(payload.flow_config),
         is_system: false,
         created_by: Some(auth_context.user_id),
+        workspace_id: Some(auth_context.workspace_id),
         created_at: now,
         updated_at: now,
     };
@@ -55,11 +58,13 @@ async fn create_template(
 
 async fn list_templates(
     State(state): State<TemplateState>,
+    Extension(auth_context): Extension<AuthContext>,
     Query(params): Query<PaginationParams>,
     Query(template_params): Query<TemplateQueryParams>,
 ) -> Result<Json<PaginatedResponse<TemplateResponse>>, StatusCode> {
     let include_system = template_params.include_system.unwrap_or(true);
     let templates = state.template_repo.list(
+        &auth_context.workspace_id,
         template_params.category.as_deref(),
         include_system,
         Some(params.limit),
@@ -74,9 +79,10 @@ async fn list_templates(
 
 async fn get_template(
     State(state): State<TemplateState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(id): Path<String>,
 ) -> Result<Json<TemplateResponse>, StatusCode> {
-    let template = state.template_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    let template = state.template_repo.get_in_workspace(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
     Ok(Json(TemplateResponse::from(template)))
 }
@@ -87,19 +93,15 @@ async fn update_template(
     Path(id): Path<String>,
     Json(payload): Json<UpdateTemplateRequest>,
 ) -> Result<Json<TemplateResponse>, StatusCode> {
-    let mut template = state.template_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    auth_context.require(WorkspaceRole::Editor)?;
+    let mut template = state.template_repo.get_in_workspace(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    // Only allow updating user templates
+    // Only allow updating workspace templates
     if template.is_system {
         return Err(StatusCode::FORBIDDEN);
     }
 
-    // Only allow updating own templates
-    if template.created_by.as_ref() != Some(&auth_context.user_id) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     if let Some(name) = payload.name {
         template.name = name;
     }
@@ -110,7 +112,7 @@ async fn update_template(
         template.category = category;
     }
     if let Some(flow_config) = payload.flow_config {
-        template.flow_config = flow_config;
+        template.flow_config = without_credentials(flow_config);
     }
     template.updated_at = chrono::Utc::now();
 
@@ -124,19 +126,15 @@ async fn delete_template(
     Extension(auth_context): Extension<AuthContext>,
     Path(id): Path<String>,
 ) -> Result<StatusCode, StatusCode> {
-    let template = state.template_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    auth_context.require(WorkspaceRole::Editor)?;
+    let template = state.template_repo.get_in_workspace(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
-    // Only allow deleting user templates
+    // Only allow deleting workspace templates
     if template.is_system {
         return Err(StatusCode::FORBIDDEN);
     }
 
-    // Only allow deleting own templates
-    if template.created_by.as_ref() != Some(&auth_context.user_id) {
-        return Err(StatusCode::FORBIDDEN);
-    }
-
     state.template_repo.delete(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
     Ok(StatusCode::NO_CONTENT)
@@ -144,10 +142,12 @@ async fn delete_template(
 
 async fn instantiate_template(
     State(state): State<TemplateState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(id): Path<String>,
     Json(payload): Json<InstantiateTemplateRequest>,
 ) -> Result<Json<crate::dto::FlowResponse>, StatusCode> {
-    let template = state.template_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    auth_context.require(WorkspaceRole::Editor)?;
+    let template = state.template_repo.get_in_workspace(&auth_context.workspace_id, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
     let flow_id = Uuid::new_v4().to_string();
@@ -157,6 +157,7 @@ async fn instantiate_template(
     flow.id = flow_id;
     flow.name = payload.name.unwrap_or_else(|| format!("{} (Copy)", template.name));
     flow.description = payload.description.or(template.description);
+    flow.workspace_id = Some(auth_context.workspace_id);
     flow.created_at = now;
     flow.updated_at = now;
     
@@ -167,7 +168,14 @@ async fn instantiate_template(
 
 async fn list_categories(
     State(state): State<TemplateState>,
+    Extension(auth_context): Extension<AuthContext>,
 ) -> Result<Json<Vec<String>>, StatusCode> {
-    let categories = state.template_repo.list_categories().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let categories = state.template_repo.list_categories(&auth_context.workspace_id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     Ok(Json(categories))
 }
+
+/// Templates are shared, so credentials are not copied into them
+fn without_credentials(mut flow: Flow) -> Flow {
+    redact_flow_credentials(&mut flow);
+    flow
+}
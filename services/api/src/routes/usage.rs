@@ -1,10 +1,11 @@
 use axum::{
-    extract::Path,
+    extract::{Extension, Path},
     http::StatusCode,
     response::Json,
     routing::get,
     Router,
 };
+use flowmason_auth::AuthContext;
 use flowmason_core::types::BrickType;
 use std::collections::HashSet;
 
@@ -13,18 +14,7 @@ use crate::routes::ExecutionState;
 
 /// Converts BrickType to its database name format (matches brick.name())
 fn brick_type_to_db_name(brick_type: &BrickType) -> String {
-    match brick_type {
-        BrickType::OpenAi => "openai",
-        BrickType::Nvidia => "nvidia",
-        BrickType::HubSpot => "hubspot",
-        BrickType::Notion => "notion",
-        BrickType::Odoo => "odoo",
-        BrickType::N8n => "n8n",
-        BrickType::FieldMapping => "field_mapping",
-        BrickType::CombineText => "combine_text",
-        BrickType::Conditional => "conditional",
-        BrickType::RulesEngine => "rules_engine",
-    }.to_string()
+    brick_type.id().to_string()
 }
 
 pub fn routes() -> Router<ExecutionState> {
@@ -36,8 +26,9 @@ pub fn routes() -> Router<ExecutionState> {
 
 async fn list_usage_logs(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
 ) -> Result<Json<Vec<UsageLogResponse>>, StatusCode> {
-    let logs = state.usage_logger.get_all_logs()
+    let logs = state.usage_repo.list_by_workspace(&auth_context.workspace_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
@@ -46,6 +37,7 @@ async fn list_usage_logs(
 
 async fn get_usage_stats(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
 ) -> Result<Json<Vec<UsageStatsResponse>>, StatusCode> {
     // Get stats for all predefined brick types from quota manager
     let brick_types = vec![
@@ -59,6 +51,7 @@ async fn get_usage_stats(
         BrickType::CombineText,
         BrickType::Conditional,
         BrickType::RulesEngine,
+        BrickType::Respond,
     ];
     
     // Map predefined brick types to their string representations (matching database format)
@@ -74,7 +67,8 @@ async fn get_usage_stats(
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         
-        let daily_usage = state.usage_logger.get_daily_usage_count(brick_type)
+        let daily_usage = state.usage_repo
+            .get_workspace_daily_usage_count(&auth_context.workspace_id, &brick_type_to_db_name(brick_type))
             .await
             .unwrap_or(0);
         
@@ -88,7 +82,7 @@ async fn get_usage_stats(
     }
     
     // Get custom brick names from database
-    let all_brick_names = state.usage_repo.get_unique_brick_names()
+    let all_brick_names = state.usage_repo.get_unique_brick_names(&auth_context.workspace_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
@@ -100,7 +94,7 @@ async fn get_usage_stats(
     
     // Get stats for custom bricks
     for brick_name in custom_brick_names {
-        let daily_usage = state.usage_repo.get_daily_usage_count_by_name(&brick_name)
+        let daily_usage = state.usage_repo.get_workspace_daily_usage_count(&auth_context.workspace_id, &brick_name)
             .await
             .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         
@@ -131,31 +125,18 @@ async fn get_usage_stats(
 
 async fn get_brick_stats(
     axum::extract::State(state): axum::extract::State<ExecutionState>,
+    Extension(auth_context): Extension<AuthContext>,
     Path(brick_type_str): Path<String>,
 ) -> Result<Json<UsageStatsResponse>, StatusCode> {
     // Try to match as predefined brick type first
-    match brick_type_str.as_str() {
-        "openai" | "nvidia" | "hubspot" | "notion" | "odoo" | "n8n" | 
-        "field_mapping" | "combine_text" | "conditional" => {
-            let brick_type = match brick_type_str.as_str() {
-                "openai" => BrickType::OpenAi,
-                "nvidia" => BrickType::Nvidia,
-                "hubspot" => BrickType::HubSpot,
-                "notion" => BrickType::Notion,
-                "odoo" => BrickType::Odoo,
-                "n8n" => BrickType::N8n,
-                "field_mapping" => BrickType::FieldMapping,
-                "combine_text" => BrickType::CombineText,
-                "conditional" => BrickType::Conditional,
-                "rules_engine" => BrickType::RulesEngine,
-                _ => unreachable!(),
-            };
-            
+    match BrickType::from_id(&brick_type_str) {
+        brick_type if !brick_type.is_custom() => {
             let quota = state.quota_manager.get_quota(&brick_type)
                 .await
                 .map_err(|_| StatusCode::NOT_FOUND)?;
             
-            let daily_usage = state.usage_logger.get_daily_usage_count(&brick_type)
+            let daily_usage = state.usage_repo
+                .get_workspace_daily_usage_count(&auth_context.workspace_id, &brick_type_to_db_name(&brick_type))
                 .await
                 .unwrap_or(0);
             
@@ -169,7 +150,7 @@ async fn get_brick_stats(
         }
         // Custom brick - check if it exists in usage logs
         _ => {
-            let daily_usage = state.usage_repo.get_daily_usage_count_by_name(&brick_type_str)
+            let daily_usage = state.usage_repo.get_workspace_daily_usage_count(&auth_context.workspace_id, &brick_type_str)
                 .await
                 .map_err(|_| StatusCode::NOT_FOUND)?;
             
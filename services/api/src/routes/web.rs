@@ -12,6 +12,7 @@ use crate::dto::{CreateFlowRequest, FlowResponse};
 use crate::routes::FlowState;
 use crate::templates::{BaseTemplate, components};
 use flowmason_core::types::Template;
+use flowmason_db::repositories::DEFAULT_WORKSPACE_ID;
 
 pub fn routes() -> Router<FlowState> {
     Router::new()
@@ -37,7 +38,7 @@ async fn dashboard(
     State(state): State<FlowState>,
 ) -> Result<Html<String>, StatusCode> {
     // Get flows, executions, and scheduled flows
-    let flows = state.flow_repo.list().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let flows = state.flow_repo.list(DEFAULT_WORKSPACE_ID, None, None).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
     // For now, we'll use placeholder data for executions and scheduled flows
     // In a full implementation, you'd inject ExecutionState and SchedulerState
@@ -107,7 +108,7 @@ async fn dashboard(
 async fn flows_list(
     State(state): State<FlowState>,
 ) -> Result<Html<String>, StatusCode> {
-    let flows = state.flow_repo.list().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let flows = state.flow_repo.list(DEFAULT_WORKSPACE_ID, None, None).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let flows_responses: Vec<FlowResponse> = flows.into_iter().map(FlowResponse::from).collect();
     
     let flows_table = if flows_responses.is_empty() {
@@ -247,6 +248,10 @@ async fn flows_create(
         name: form.name,
         description: form.description,
         bricks: vec![],
+        graph: None,
+        retry_policy: None,
+        variables: Default::default(),
+        schema: Default::default(),
     };
     
     // Create flow using the API logic
@@ -258,6 +263,11 @@ async fn flows_create(
         name: request.name,
         description: request.description,
         bricks: vec![],
+        graph: None,
+        retry_policy: None,
+        variables: Default::default(),
+        schema: Default::default(),
+        workspace_id: Some(DEFAULT_WORKSPACE_ID.to_string()),
         active: true,
         created_at: now,
         updated_at: now,
@@ -272,7 +282,7 @@ async fn flows_detail(
     State(state): State<FlowState>,
     Path(id): Path<String>,
 ) -> Result<Html<String>, StatusCode> {
-    let flow = state.flow_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    let flow = state.flow_repo.get_in_workspace(DEFAULT_WORKSPACE_ID, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
     
     let flow_response = FlowResponse::from(flow.clone());
@@ -359,7 +369,7 @@ async fn flows_edit(
     State(state): State<FlowState>,
     Path(id): Path<String>,
 ) -> Result<Html<String>, StatusCode> {
-    let flow = state.flow_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    let flow = state.flow_repo.get_in_workspace(DEFAULT_WORKSPACE_ID, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
     
     let flow_response = FlowResponse::from(flow);
@@ -417,7 +427,7 @@ async fn flows_update(
     Path(id): Path<String>,
     Form(form): Form<FlowUpdateForm>,
 ) -> Result<Redirect, StatusCode> {
-    let mut flow = state.flow_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    let mut flow = state.flow_repo.get_in_workspace(DEFAULT_WORKSPACE_ID, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
     
     if let Some(name) = form.name {
@@ -437,7 +447,7 @@ async fn flows_delete(
     State(state): State<FlowState>,
     Path(id): Path<String>,
 ) -> Result<Redirect, StatusCode> {
-    state.flow_repo.delete(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    state.flow_repo.delete(DEFAULT_WORKSPACE_ID, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     Ok(Redirect::to("/flows"))
 }
 
@@ -453,7 +463,7 @@ async fn flows_run(
 async fn templates(
     State(state): State<FlowState>,
 ) -> Result<Html<String>, StatusCode> {
-    let templates_list = state.template_repo.list(None, true, Some(100), Some(0))
+    let templates_list = state.template_repo.list(DEFAULT_WORKSPACE_ID, None, true, Some(100), Some(0))
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
@@ -556,8 +566,48 @@ async fn executions_list() -> Result<Html<String>, StatusCode> {
 }
 
 async fn executions_detail(
+    State(state): State<FlowState>,
     Path(id): Path<String>,
 ) -> Result<Html<String>, StatusCode> {
+    let execution = state.execution_repo.get_in_workspace(DEFAULT_WORKSPACE_ID, &id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    let steps = state.step_repo.list_by_execution(&id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let status = serde_json::to_value(&execution.status)
+        .ok()
+        .and_then(|v| v.as_str().map(str::to_lowercase))
+        .unwrap_or_default();
+    let error_html = execution.error.as_deref()
+        .map(|e| format!(r#"<p class="text-sm text-red-600 mt-4">{}</p>"#, components::escape_html(e)))
+        .unwrap_or_default();
+
+    let steps_html = if steps.is_empty() {
+        components::empty_state(
+            "No steps recorded",
+            "Step records appear here once the execution has run its bricks.",
+            None,
+            None,
+        )
+    } else {
+        let rows: Vec<Vec<String>> = steps.iter()
+            .map(|step| vec![
+                step.brick_index.to_string(),
+                components::escape_html(step.brick_type.id()),
+                components::status_badge(step.status.as_str()),
+                step.attempts.to_string(),
+                format!("{} ms", step.duration_ms),
+                step.started_at.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+                components::escape_html(step.error.as_deref().unwrap_or("")),
+            ])
+            .collect();
+        components::data_table(
+            &["#", "Brick", "Status", "Attempts", "Duration", "Started", "Error"],
+            &rows,
+        )
+    };
+
     let content = format!(
         r#"<div class="space-y-6">
             <div>
@@ -565,10 +615,28 @@ async fn executions_detail(
                 <p class="text-gray-600 mt-1">Execution ID: {}</p>
             </div>
             <div class="bg-white rounded-lg shadow-sm border border-gray-200 p-6">
-                <p class="text-gray-500">Execution details would appear here</p>
+                <div class="flex items-center justify-between">
+                    <a href="/flows/{}" class="text-primary-600 hover:text-primary-700">Flow {}</a>
+                    {}
+                </div>
+                <p class="text-sm text-gray-500 mt-2">Started {} &middot; Completed {}</p>
+                {}
+            </div>
+            <div class="bg-white rounded-lg shadow-sm border border-gray-200">
+                <h2 class="text-lg font-semibold text-gray-900 px-6 py-4">Steps</h2>
+                {}
             </div>
         </div>"#,
-        id
+        components::escape_html(&id),
+        components::escape_html(&execution.flow_id),
+        components::escape_html(&execution.flow_id),
+        components::status_badge(&status),
+        execution.started_at.format("%Y-%m-%d %H:%M:%S"),
+        execution.completed_at
+            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| "-".to_string()),
+        error_html,
+        steps_html,
     );
     
     let template = BaseTemplate {
@@ -758,7 +826,7 @@ async fn instantiate_template(
     State(state): State<FlowState>,
     Path(id): Path<String>,
 ) -> Result<Redirect, StatusCode> {
-    let template = state.template_repo.get(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    let template = state.template_repo.get_in_workspace(DEFAULT_WORKSPACE_ID, &id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
 
     let flow_id = uuid::Uuid::new_v4().to_string();
@@ -768,6 +836,7 @@ async fn instantiate_template(
     flow.id = flow_id.clone();
     flow.name = format!("{} (Copy)", template.name);
     flow.description = template.description.clone();
+    flow.workspace_id = Some(DEFAULT_WORKSPACE_ID.to_string());
     flow.created_at = now;
     flow.updated_at = now;
     
@@ -1,14 +1,18 @@
 use axum::{
     extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
     routing::post,
     Router,
     body::Bytes,
 };
 use serde_json::json;
+use crate::idempotency;
+use crate::routes::executions;
 use crate::routes::ExecutionState;
-use flowmason_core::types::BrickType;
+use flowmason_core::types::Flow;
+use flowmason_core::{FlowResponse, JsonPath, ResponseBody};
+use flowmason_db::repositories::ResolvedWebhook;
 
 pub fn routes() -> Router<ExecutionState> {
     Router::new()
@@ -20,82 +24,213 @@ struct WebhookTriggerRequest {
     payload: Option<serde_json::Value>,
 }
 
+/// Runs a flow for a webhook call
+///
+/// Calls authenticate with the flow's webhook secret (see
+/// `PUT /flows/:id/webhook`); flows without one cannot be triggered here.
+/// Retried calls with the same idempotency key, from the `Idempotency-Key`
+/// header or the configured payload path, run the flow only once.
 async fn trigger_flow_webhook(
     State(state): State<ExecutionState>,
     Path(flow_id): Path<String>,
+    headers: HeaderMap,
     body: Bytes,
-) -> Result<Json<serde_json::Value>, StatusCode> {
-    // Parse payload from body or use empty object
-    let input_payload = if body.is_empty() {
-        json!({})
-    } else {
-        serde_json::from_slice(&body)
-            .unwrap_or_else(|_| json!({}))
-    };
-
-    // Get flow
+) -> Result<Response, StatusCode> {
+    // Authenticate first, so unknown flows and flows without webhook
+    // authentication both answer 401 and flow ids cannot be probed
+    let webhook = authenticate(&state, &flow_id, &headers, &body).await?;
     let flow = state.flow_repo.get(&flow_id).await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::NOT_FOUND)?;
+        .ok_or(StatusCode::UNAUTHORIZED)?;
 
     if !flow.active {
         return Err(StatusCode::BAD_REQUEST);
     }
 
+    let input_payload = parse_payload(&flow_id, &body)?;
+    run_idempotent(state, flow, &webhook, &headers, &body, input_payload).await
+}
+
+/// Verifies a call against the flow's webhook authentication
+///
+/// Fails with 401 Unauthorized if the flow has none or the call does not match it.
+pub(crate) async fn authenticate(
+    state: &ExecutionState,
+    flow_id: &str,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<ResolvedWebhook, StatusCode> {
+    let webhook = state.webhook_repo.resolve(flow_id).await
+        .map_err(|e| {
+            tracing::error!(error = %e, flow_id = %flow_id, "Failed to load webhook secret");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?
+        .ok_or_else(|| {
+            tracing::warn!(flow_id = %flow_id, "Webhook call for a flow without webhook authentication");
+            StatusCode::UNAUTHORIZED
+        })?;
+    if let Err(e) = webhook.auth.verify(headers, body, &webhook.secrets, chrono::Utc::now().timestamp()) {
+        tracing::warn!(error = %e, flow_id = %flow_id, "Rejected webhook call");
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    Ok(webhook)
+}
+
+/// Parses a JSON request body; an empty body is an empty payload
+pub(crate) fn parse_payload(flow_id: &str, body: &[u8]) -> Result<serde_json::Value, StatusCode> {
+    if body.is_empty() {
+        return Ok(json!({}));
+    }
+    serde_json::from_slice(body).map_err(|e| {
+        tracing::warn!(error = %e, flow_id = %flow_id, "Malformed webhook payload");
+        StatusCode::BAD_REQUEST
+    })
+}
+
+/// Runs a flow at most once per idempotency key
+///
+/// `request` identifies the call for `Idempotency-Key` headers reused with
+/// another request. Payloads that do not match the flow's input schema are
+/// rejected with 422 before a key is claimed.
+pub(crate) async fn run_idempotent(
+    state: ExecutionState,
+    flow: Flow,
+    webhook: &ResolvedWebhook,
+    headers: &HeaderMap,
+    request: &[u8],
+    input_payload: serde_json::Value,
+) -> Result<Response, StatusCode> {
+    if let Some(response) = executions::reject_invalid_input(&flow, &input_payload) {
+        return Ok(response);
+    }
+    let scope = format!("webhook:{}", flow.id);
+    if let Some(key) = idempotency::key_from_headers(headers)? {
+        let fingerprint = idempotency::fingerprint(request);
+        let repo = state.idempotency_repo.clone();
+        return idempotency::run_once(repo, scope, key, Some(fingerprint), run_flow(state, flow, input_payload)).await;
+    }
+    // Payload keys identify an event; retries may differ in other fields
+    let payload_key = webhook.idempotency_key_path.as_deref()
+        .and_then(|path| payload_idempotency_key(&input_payload, path))
+        .transpose()?;
+    match payload_key {
+        Some(key) => {
+            let repo = state.idempotency_repo.clone();
+            idempotency::run_once(repo, scope, key, None, run_flow(state, flow, input_payload)).await
+        }
+        None => run_flow(state, flow, input_payload).await,
+    }
+}
+
+/// The idempotency key at `path` of a payload; strings and numbers are used as keys
+fn payload_idempotency_key(payload: &serde_json::Value, path: &str) -> Option<Result<String, StatusCode>> {
+    let value = JsonPath::cached(path).ok()?.get(payload)?;
+    match value {
+        serde_json::Value::String(key) => Some(idempotency::validate_key(&key)),
+        serde_json::Value::Number(key) => Some(Ok(key.to_string())),
+        _ => None,
+    }
+}
+
+/// Runs a flow and answers with the response of its first `respond` brick
+///
+/// The answer goes out as soon as that brick has run; the rest of the flow
+/// keeps running and is recorded as usual. Flows without a `respond` brick
+/// answer with the execution result once they complete.
+async fn run_flow(state: ExecutionState, flow: Flow, input_payload: serde_json::Value) -> Result<Response, StatusCode> {
+    let flow_id = flow.id.clone();
+
     // Create brick instances
-    use flowmason_bricks::*;
-    use flowmason_bricks::RulesEngineBrick;
-    use flowmason_core::{FlowRunner, FlowRunnerContext};
+    use flowmason_core::{FlowResponder, FlowRunner, FlowRunnerContext};
     use std::sync::Arc;
 
-    let mut bricks: Vec<Box<dyn flowmason_core::Brick>> = Vec::new();
-    
-    for brick_config in &flow.bricks {
-        let brick: Box<dyn flowmason_core::Brick> = match brick_config.brick_type {
-            BrickType::OpenAi => Box::new(OpenAiBrick),
-            BrickType::Nvidia => Box::new(NvidiaBrick),
-            BrickType::HubSpot => Box::new(HubSpotBrick),
-            BrickType::Notion => Box::new(NotionBrick),
-            BrickType::Odoo => Box::new(OdooBrick),
-            BrickType::N8n => Box::new(N8nBrick),
-            BrickType::FieldMapping => Box::new(FieldMappingBrick),
-            BrickType::CombineText => Box::new(CombineTextBrick),
-            BrickType::Conditional => Box::new(ConditionalBrick),
-            BrickType::RulesEngine => Box::new(RulesEngineBrick),
-        };
-        bricks.push(brick);
-    }
+    let bricks = state.brick_registry.create_all(&flow.bricks).map_err(|e| {
+        tracing::warn!(error = %e, flow_id = %flow_id, "Failed to resolve flow bricks");
+        StatusCode::BAD_REQUEST
+    })?;
 
+    let (responder, response) = FlowResponder::channel();
     let execution_data_storage: Arc<dyn flowmason_core::ExecutionDataStorage> = state.execution_data_repo.clone();
     let context = FlowRunnerContext {
         quota_manager: Some(state.quota_manager.clone()),
         usage_logger: Some(state.usage_logger.clone()),
         execution_data_storage: Some(execution_data_storage),
+        sub_flow_executor: Some(state.sub_flow_executor.clone()),
+        brick_registry: Some(state.brick_registry.clone()),
+        event_bus: None,
         flow_id: flow.id.clone(),
         execution_id: String::new(),
+        workspace_id: flow.workspace_id.clone(),
+        parent_execution_id: None,
+        depth: 0,
+        variables: Default::default(),
+        secret_resolver: Some(state.secret_resolver.clone()),
+        connection_resolver: Some(state.connection_resolver.clone()),
+        redactor: Default::default(),
+        responder: Some(responder),
     };
 
-    // Execute flow
-    let execution = FlowRunner::execute_flow_with_tracking(
-        &flow,
-        bricks,
-        input_payload,
-        Some(context),
-    )
-    .await
-    .map_err(|e| {
-        tracing::error!(error = %e, flow_id = %flow_id, "Webhook flow execution error");
-        StatusCode::INTERNAL_SERVER_ERROR
-    })?;
+    // Execute flow; it keeps running after an early response
+    let execution = tokio::spawn(async move {
+        let execution = FlowRunner::execute_flow_recorded(
+            &flow,
+            bricks,
+            input_payload,
+            Some(context),
+        )
+        .await;
+
+        // Store execution; failed executions land in the dead-letter queue
+        state.execution_repo.create(&execution).await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        Ok::<_, StatusCode>(execution)
+    });
 
-    // Store execution
-    state.execution_repo.create(&execution).await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // The responder is dropped with the context when the flow ends without responding
+    if let Ok(response) = response.await {
+        return Ok(flow_response(response));
+    }
+
+    let execution = execution.await.map_err(|e| {
+        tracing::error!(error = %e, flow_id = %flow_id, "Webhook flow execution panicked");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })??;
+    if let Some(error) = &execution.error {
+        tracing::error!(error = %error, flow_id = %flow_id, execution_id = %execution.execution_id, "Webhook flow execution error");
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
 
     Ok(Json(json!({
         "success": true,
         "execution_id": execution.execution_id,
         "status": format!("{:?}", execution.status),
         "output": execution.output_payload,
-    })))
+    })).into_response())
+}
+
+/// Builds the HTTP response set by a `respond` brick
+fn flow_response(response: FlowResponse) -> Response {
+    let status = StatusCode::from_u16(response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut http_response = match response.body {
+        ResponseBody::Json(body) => (status, Json(body)).into_response(),
+        ResponseBody::Text(body) => (status, body).into_response(),
+        ResponseBody::Redirect(location) => {
+            let mut redirect = status.into_response();
+            if let Ok(location) = HeaderValue::from_str(&location) {
+                redirect.headers_mut().insert(header::LOCATION, location);
+            }
+            redirect
+        }
+    };
+    // Configured headers may override the content type
+    for (name, value) in response.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(&value)) {
+            http_response.headers_mut().insert(name, value);
+        }
+    }
+    // Responses share the API's origin; bodies must not run as its pages
+    let headers = http_response.headers_mut();
+    headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(header::CONTENT_SECURITY_POLICY, HeaderValue::from_static("default-src 'none'; frame-ancestors 'none'; sandbox"));
+    http_response
 }
@@ -0,0 +1,226 @@
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::{get, put},
+    Router,
+};
+
+use crate::dto::{
+    AddMemberRequest, CreateWorkspaceRequest, MemberResponse, UpdateMemberRequest, UpdateWorkspaceRequest,
+    WorkspaceResponse,
+};
+use crate::routes::WorkspaceState;
+use flowmason_auth::{AuthContext, WorkspaceRole};
+
+/// Maximum length of a workspace name
+const MAX_WORKSPACE_NAME_LEN: usize = 128;
+
+pub fn routes() -> Router<WorkspaceState> {
+    Router::new()
+        .route("/", get(list_workspaces).post(create_workspace))
+        .route("/:id", get(get_workspace).put(update_workspace).delete(delete_workspace))
+        .route("/:id/members", get(list_members).post(add_member))
+        .route("/:id/members/:user_id", put(update_member).delete(remove_member))
+}
+
+async fn list_workspaces(
+    State(state): State<WorkspaceState>,
+    Extension(auth_context): Extension<AuthContext>,
+) -> Result<Json<Vec<WorkspaceResponse>>, StatusCode> {
+    let workspaces = state.workspace_repo.list_for_user(&auth_context.user_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(workspaces.into_iter().map(|(workspace, role)| WorkspaceResponse::new(workspace, role)).collect()))
+}
+
+async fn create_workspace(
+    State(state): State<WorkspaceState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Json(payload): Json<CreateWorkspaceRequest>,
+) -> Result<Json<WorkspaceResponse>, StatusCode> {
+    let name = valid_name(&payload.name)?;
+    let workspace = state.workspace_repo.create(name, &auth_context.user_id).await
+        .map_err(|e| {
+            tracing::error!(error = %e, "Failed to create workspace");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    tracing::info!(workspace_id = %workspace.id, user_id = %auth_context.user_id, "Workspace created");
+
+    Ok(Json(WorkspaceResponse::new(workspace, WorkspaceRole::Owner)))
+}
+
+async fn get_workspace(
+    State(state): State<WorkspaceState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<WorkspaceResponse>, StatusCode> {
+    let role = member_role(&state, &id, &auth_context).await?;
+    let workspace = state.workspace_repo.get(&id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(WorkspaceResponse::new(workspace, role)))
+}
+
+async fn update_workspace(
+    State(state): State<WorkspaceState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+    Json(payload): Json<UpdateWorkspaceRequest>,
+) -> Result<Json<WorkspaceResponse>, StatusCode> {
+    let role = require_role(&state, &id, &auth_context, WorkspaceRole::Admin).await?;
+    let name = valid_name(&payload.name)?;
+    let workspace = state.workspace_repo.rename(&id, name).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    Ok(Json(WorkspaceResponse::new(workspace, role)))
+}
+
+/// Deletes an empty workspace; flows must be deleted or moved first
+async fn delete_workspace(
+    State(state): State<WorkspaceState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    require_role(&state, &id, &auth_context, WorkspaceRole::Owner).await?;
+    if state.workspace_repo.count_flows(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? > 0 {
+        return Err(StatusCode::CONFLICT);
+    }
+    if !state.workspace_repo.delete(&id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    tracing::info!(workspace_id = %id, user_id = %auth_context.user_id, "Workspace deleted");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_members(
+    State(state): State<WorkspaceState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<MemberResponse>>, StatusCode> {
+    member_role(&state, &id, &auth_context).await?;
+    let members = state.workspace_repo.list_members(&id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(members.into_iter().map(MemberResponse::from).collect()))
+}
+
+async fn add_member(
+    State(state): State<WorkspaceState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path(id): Path<String>,
+    Json(payload): Json<AddMemberRequest>,
+) -> Result<Json<MemberResponse>, StatusCode> {
+    let role = require_role(&state, &id, &auth_context, WorkspaceRole::Admin).await?;
+    // Only owners can make other users owners
+    if payload.role == WorkspaceRole::Owner && role != WorkspaceRole::Owner {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let user = state.user_repo.get_by_email(&payload.email).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if state.workspace_repo.get_role(&id, &user.id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?.is_some() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    state.workspace_repo.add_member(&id, &user.id, payload.role).await
+        .map_err(|e| {
+            tracing::error!(error = %e, workspace_id = %id, "Failed to add workspace member");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    tracing::info!(workspace_id = %id, user_id = %user.id, role = %payload.role, "Workspace member added");
+
+    let member = state.workspace_repo.get_member(&id, &user.id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(MemberResponse::from(member)))
+}
+
+async fn update_member(
+    State(state): State<WorkspaceState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path((id, user_id)): Path<(String, String)>,
+    Json(payload): Json<UpdateMemberRequest>,
+) -> Result<Json<MemberResponse>, StatusCode> {
+    let role = require_role(&state, &id, &auth_context, WorkspaceRole::Admin).await?;
+    let member = state.workspace_repo.get_member(&id, &user_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    // Only owners can grant or take away ownership
+    let changes_owner = member.role == WorkspaceRole::Owner || payload.role == WorkspaceRole::Owner;
+    if changes_owner && role != WorkspaceRole::Owner {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if member.role == WorkspaceRole::Owner && payload.role != WorkspaceRole::Owner {
+        ensure_other_owner(&state, &id).await?;
+    }
+
+    state.workspace_repo.update_member(&id, &user_id, payload.role).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    tracing::info!(workspace_id = %id, user_id = %user_id, role = %payload.role, "Workspace member updated");
+
+    Ok(Json(MemberResponse { role: payload.role, ..MemberResponse::from(member) }))
+}
+
+/// Removes a member; admins remove others, and every member can leave
+async fn remove_member(
+    State(state): State<WorkspaceState>,
+    Extension(auth_context): Extension<AuthContext>,
+    Path((id, user_id)): Path<(String, String)>,
+) -> Result<StatusCode, StatusCode> {
+    let role = member_role(&state, &id, &auth_context).await?;
+    if user_id != auth_context.user_id && !role.allows(WorkspaceRole::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    let member_role = state.workspace_repo.get_role(&id, &user_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if member_role == WorkspaceRole::Owner {
+        if role != WorkspaceRole::Owner {
+            return Err(StatusCode::FORBIDDEN);
+        }
+        ensure_other_owner(&state, &id).await?;
+    }
+
+    state.workspace_repo.remove_member(&id, &user_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    tracing::info!(workspace_id = %id, user_id = %user_id, "Workspace member removed");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// The caller's role in a workspace; 404 for workspaces the caller is not a member of
+async fn member_role(state: &WorkspaceState, id: &str, auth_context: &AuthContext) -> Result<WorkspaceRole, StatusCode> {
+    state.workspace_repo.get_role(id, &auth_context.user_id).await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+async fn require_role(
+    state: &WorkspaceState,
+    id: &str,
+    auth_context: &AuthContext,
+    required: WorkspaceRole,
+) -> Result<WorkspaceRole, StatusCode> {
+    let role = member_role(state, id, auth_context).await?;
+    if !role.allows(required) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(role)
+}
+
+/// A workspace must keep at least one owner
+async fn ensure_other_owner(state: &WorkspaceState, id: &str) -> Result<(), StatusCode> {
+    let owners = state.workspace_repo.count_owners(id).await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if owners <= 1 {
+        return Err(StatusCode::CONFLICT);
+    }
+    Ok(())
+}
+
+fn valid_name(name: &str) -> Result<&str, StatusCode> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > MAX_WORKSPACE_NAME_LEN {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    Ok(name)
+}
@@ -3,10 +3,12 @@
 pub fn status_badge(status: &str) -> String {
     let (color_class, label) = match status {
         "completed" | "active" => ("bg-green-100 text-green-800", "Completed"),
+        "succeeded" => ("bg-green-100 text-green-800", "Succeeded"),
         "failed" => ("bg-red-100 text-red-800", "Failed"),
         "running" => ("bg-blue-100 text-blue-800", "Running"),
         "pending" => ("bg-yellow-100 text-yellow-800", "Pending"),
         "inactive" => ("bg-gray-100 text-gray-800", "Inactive"),
+        "skipped" => ("bg-gray-100 text-gray-800", "Skipped"),
         _ => ("bg-gray-100 text-gray-800", status),
     };
     
@@ -16,6 +18,22 @@ pub fn status_badge(status: &str) -> String {
     )
 }
 
+/// Escapes text for use in HTML content and attribute values
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
 pub fn stats_card(title: &str, value: &str, icon: Option<&str>) -> String {
     let icon_html = if let Some(icon_svg) = icon {
         format!(
@@ -30,6 +30,7 @@ fn create_customer_enrichment_template() -> Template {
                         { "source_path": "form.email", "target_path": "email" }
                     ]
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::CombineText,
@@ -38,6 +39,7 @@ fn create_customer_enrichment_template() -> Template {
                     "separator": " ",
                     "output_field": "full_name"
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::OpenAi,
@@ -48,6 +50,7 @@ fn create_customer_enrichment_template() -> Template {
                     "temperature": 0.7,
                     "max_tokens": 200
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::FieldMapping,
@@ -58,6 +61,7 @@ fn create_customer_enrichment_template() -> Template {
                         { "source_path": "content", "target_path": "properties.company_description" }
                     ]
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::HubSpot,
@@ -65,8 +69,14 @@ fn create_customer_enrichment_template() -> Template {
                     "api_key": "your-hubspot-api-key",
                     "operation": "create_deal"
                 }),
+                policy: None,
             },
         ],
+        graph: None,
+        retry_policy: None,
+        variables: Default::default(),
+        schema: Default::default(),
+        workspace_id: None,
         active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -80,6 +90,7 @@ fn create_customer_enrichment_template() -> Template {
         flow_config: flow,
         is_system: true,
         created_by: None,
+        workspace_id: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     }
@@ -100,6 +111,7 @@ fn create_hubspot_deal_creation_template() -> Template {
                         { "source_path": "form.stage", "target_path": "properties.dealstage" }
                     ]
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::HubSpot,
@@ -107,8 +119,14 @@ fn create_hubspot_deal_creation_template() -> Template {
                     "api_key": "your-hubspot-api-key",
                     "operation": "create_deal"
                 }),
+                policy: None,
             },
         ],
+        graph: None,
+        retry_policy: None,
+        variables: Default::default(),
+        schema: Default::default(),
+        workspace_id: None,
         active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -122,6 +140,7 @@ fn create_hubspot_deal_creation_template() -> Template {
         flow_config: flow,
         is_system: true,
         created_by: None,
+        workspace_id: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     }
@@ -142,6 +161,7 @@ fn create_ai_content_to_notion_template() -> Template {
                     "temperature": 0.8,
                     "max_tokens": 2000
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::FieldMapping,
@@ -151,6 +171,7 @@ fn create_ai_content_to_notion_template() -> Template {
                         { "source_path": "content", "target_path": "content" }
                     ]
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::Notion,
@@ -159,8 +180,14 @@ fn create_ai_content_to_notion_template() -> Template {
                     "database_id": "your-database-id",
                     "operation": "create_page"
                 }),
+                policy: None,
             },
         ],
+        graph: None,
+        retry_policy: None,
+        variables: Default::default(),
+        schema: Default::default(),
+        workspace_id: None,
         active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -174,6 +201,7 @@ fn create_ai_content_to_notion_template() -> Template {
         flow_config: flow,
         is_system: true,
         created_by: None,
+        workspace_id: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     }
@@ -194,6 +222,7 @@ fn create_webhook_to_odoo_template() -> Template {
                         { "source_path": "webhook.items", "target_path": "invoice_line_ids" }
                     ]
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::Conditional,
@@ -204,6 +233,7 @@ fn create_webhook_to_odoo_template() -> Template {
                     "false_value": "draft",
                     "output_field": "state"
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::Odoo,
@@ -214,8 +244,14 @@ fn create_webhook_to_odoo_template() -> Template {
                     "password": "your-password",
                     "operation": "create_invoice"
                 }),
+                policy: None,
             },
         ],
+        graph: None,
+        retry_policy: None,
+        variables: Default::default(),
+        schema: Default::default(),
+        workspace_id: None,
         active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -229,6 +265,7 @@ fn create_webhook_to_odoo_template() -> Template {
         flow_config: flow,
         is_system: true,
         created_by: None,
+        workspace_id: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     }
@@ -248,6 +285,7 @@ fn create_n8n_webhook_pipeline_template() -> Template {
                         { "source_path": "event.data", "target_path": "payload" }
                     ]
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::N8n,
@@ -255,6 +293,7 @@ fn create_n8n_webhook_pipeline_template() -> Template {
                     "webhook_url": "https://your-n8n-instance.com/webhook/process-data",
                     "method": "POST"
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::FieldMapping,
@@ -264,6 +303,7 @@ fn create_n8n_webhook_pipeline_template() -> Template {
                         { "source_path": "processed_data.amount", "target_path": "properties.amount" }
                     ]
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::HubSpot,
@@ -271,8 +311,14 @@ fn create_n8n_webhook_pipeline_template() -> Template {
                     "api_key": "your-hubspot-api-key",
                     "operation": "create_deal"
                 }),
+                policy: None,
             },
         ],
+        graph: None,
+        retry_policy: None,
+        variables: Default::default(),
+        schema: Default::default(),
+        workspace_id: None,
         active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -286,6 +332,7 @@ fn create_n8n_webhook_pipeline_template() -> Template {
         flow_config: flow,
         is_system: true,
         created_by: None,
+        workspace_id: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     }
@@ -303,6 +350,7 @@ fn create_hubspot_to_notion_template() -> Template {
                     "api_key": "your-hubspot-api-key",
                     "operation": "get_deals"
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::FieldMapping,
@@ -313,6 +361,7 @@ fn create_hubspot_to_notion_template() -> Template {
                         { "source_path": "results[].properties.dealstage", "target_path": "status" }
                     ]
                 }),
+                policy: None,
             },
             BrickConfig {
                 brick_type: BrickType::Notion,
@@ -321,8 +370,14 @@ fn create_hubspot_to_notion_template() -> Template {
                     "database_id": "your-database-id",
                     "operation": "create_page"
                 }),
+                policy: None,
             },
         ],
+        graph: None,
+        retry_policy: None,
+        variables: Default::default(),
+        schema: Default::default(),
+        workspace_id: None,
         active: true,
         created_at: Utc::now(),
         updated_at: Utc::now(),
@@ -336,6 +391,7 @@ fn create_hubspot_to_notion_template() -> Template {
         flow_config: flow,
         is_system: true,
         created_by: None,
+        workspace_id: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     }
@@ -1,20 +1,116 @@
+#![allow(dead_code)]
+
+use axum::body::Body;
+use axum::http::{header, Request, StatusCode};
 use axum::Router;
+use flowmason_auth::{JwtService, User};
 use flowmason_db::connection::create_pool;
+use flowmason_db::repositories::UserRepository;
+use serde_json::Value;
+use sqlx::SqlitePool;
+use tower::ServiceExt;
+
+/// Secret shared by the router under test and the tokens issued here
+const TEST_JWT_SECRET: &str = "flowmason-integration-test-secret";
+
+/// Master key for stored secrets, which webhook secrets need
+const TEST_MASTER_KEY: &str = "Zmxvd21hc29uLWludGVncmF0aW9uLXRlc3Qta2V5ISE=";
 
+/// Creates the router over a fresh database
 pub async fn create_test_app() -> Router {
-    let database_url = "sqlite::memory:";
-    let pool = create_pool(database_url).await.expect("Failed to create test database");
-    flowmason_api::routes::create_router(pool).await
+    create_test_app_with_pool().await.0
 }
 
-pub async fn create_test_app() -> Router {
-    let database_url = "sqlite::memory:";
-    let pool = create_pool(database_url).await.expect("Failed to create test database");
-    create_router(pool).await
+/// Creates the router over a fresh database, returning the pool for seeding data
+///
+/// Each test gets its own database file; an in-memory database would give
+/// every pooled connection a separate, empty database.
+pub async fn create_test_app_with_pool() -> (Router, SqlitePool) {
+    std::env::set_var("JWT_SECRET", TEST_JWT_SECRET);
+    std::env::set_var("FLOWMASON_MASTER_KEY", TEST_MASTER_KEY);
+    let path = std::env::temp_dir().join(format!("flowmason-test-{}.db", uuid::Uuid::new_v4()));
+    let database_url = format!("sqlite://{}", path.display());
+    let pool = create_pool(&database_url).await.expect("Failed to create test database");
+    let app = flowmason_api::routes::create_router(pool.clone()).await;
+    (app, pool)
+}
+
+/// Creates a user and returns `(user_id, access_token)`
+pub async fn create_test_user(pool: &SqlitePool, email: &str) -> (String, String) {
+    let user = User::new(email.to_string(), "unused".to_string());
+    UserRepository::new(pool.clone()).create(&user).await.expect("Failed to create test user");
+    let token = JwtService::new(TEST_JWT_SECRET.to_string())
+        .generate_token(&user.id, &user.email)
+        .expect("Failed to issue test token");
+    (user.id, token)
+}
+
+/// Sends `request` and returns the status and JSON body (`Null` if empty or not JSON)
+pub async fn send(app: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+/// Builds an authenticated JSON request
+pub fn json_request(method: &str, uri: &str, token: &str, body: Option<Value>) -> Request<Body> {
+    let builder = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header(header::AUTHORIZATION, format!("Bearer {}", token));
+    match body {
+        Some(body) => builder
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap(),
+        None => builder.body(Body::empty()).unwrap(),
+    }
+}
+
+/// Creates a flow through the API and returns its id
+pub async fn create_flow(app: &Router, token: &str, flow: Value) -> String {
+    let (status, body) = send(app, json_request("POST", "/api/v1/flows", token, Some(flow))).await;
+    assert_eq!(status, StatusCode::OK, "flow creation failed: {}", body);
+    body["id"].as_str().unwrap().to_string()
+}
+
+/// Runs a flow synchronously and returns the status and response body
+pub async fn run_flow(app: &Router, token: &str, flow_id: &str, input: Value) -> (StatusCode, Value) {
+    send(app, json_request("POST", "/api/v1/executions", token, Some(serde_json::json!({
+        "flow_id": flow_id,
+        "input_payload": input
+    })))).await
+}
+
+/// Sends a request in the workspace `workspace_id` instead of the caller's default one
+pub fn in_workspace(mut request: Request<Body>, workspace_id: &str) -> Request<Body> {
+    request.headers_mut().insert(flowmason_auth::WORKSPACE_HEADER, workspace_id.parse().unwrap());
+    request
+}
+
+/// Creates an API key of `user_id` and returns `(key_id, key)`
+pub async fn create_api_key(
+    pool: &SqlitePool,
+    user_id: &str,
+    scopes: Option<&[&str]>,
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+    allowed_ips: Option<&[&str]>,
+) -> (String, String) {
+    let key = flowmason_auth::ApiKeyService::generate();
+    let scopes = scopes.map(|scopes| flowmason_auth::ScopeSet::new(scopes.iter().map(|s| s.parse().unwrap())));
+    let allowed_ips: Option<Vec<flowmason_auth::IpRange>> = allowed_ips.map(|ips| ips.iter().map(|ip| ip.parse().unwrap()).collect());
+    let id = flowmason_db::repositories::ApiKeyRepository::new(pool.clone())
+        .create(user_id, &flowmason_auth::ApiKeyService::hash(&key), Some("test"), scopes.as_ref(), expires_at, allowed_ips.as_deref())
+        .await
+        .expect("Failed to create API key");
+    (id, key)
 }
 
-pub async fn create_test_user_and_token() -> (String, String) {
-    // This would create a test user and return (user_id, token)
-    // For now, return placeholder
-    ("test_user_id".to_string(), "test_token".to_string())
+/// Sets the webhook authentication of a flow and returns its secret
+pub async fn configure_webhook(app: &Router, token: &str, flow_id: &str, settings: Value) -> String {
+    let uri = format!("/api/v1/flows/{}/webhook", flow_id);
+    let (status, body) = send(app, json_request("PUT", &uri, token, Some(settings))).await;
+    assert_eq!(status, StatusCode::OK, "webhook configuration failed: {}", body);
+    body["secret"].as_str().unwrap().to_string()
 }
@@ -1,9 +1,7 @@
 use axum::http::{StatusCode, header};
-use flowmason_db::connection::create_pool;
-use flowmason_db::repositories::{FlowRepository, UserRepository};
-use flowmason_auth::JwtService;
+use flowmason_auth::WorkspaceRole;
+use flowmason_db::repositories::WorkspaceRepository;
 use serde_json::json;
-use std::sync::Arc;
 use tower::ServiceExt;
 
 mod common;
@@ -86,3 +84,720 @@ async fn test_get_bricks_no_auth() {
     // Bricks endpoint might not require auth, check if it's accessible
     assert!(response.status().is_success() || response.status() == StatusCode::UNAUTHORIZED);
 }
+
+fn combine_text_brick(field: &str) -> serde_json::Value {
+    json!({ "brick_type": "combine_text", "config": { "fields": [field], "output_field": field } })
+}
+
+#[tokio::test]
+async fn test_brick_list_update_keeps_non_linear_graph() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "editor@example.com").await;
+
+    let brick_node = |id: &str| {
+        let mut node = combine_text_brick(id);
+        node["id"] = json!(id);
+        node["kind"] = json!("brick");
+        node
+    };
+    let graph = json!({
+        "nodes": [brick_node("a"), brick_node("b"), brick_node("c"), { "id": "j", "kind": "join" }],
+        "edges": [
+            { "from": "a", "to": "b" },
+            { "from": "a", "to": "c" },
+            { "from": "b", "to": "j" },
+            { "from": "c", "to": "j" }
+        ]
+    });
+    let (status, flow) = send(&app, json_request("POST", "/api/v1/flows", &token, Some(json!({
+        "name": "Branching",
+        "graph": graph
+    })))).await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/api/v1/flows/{}", flow["id"].as_str().unwrap());
+
+    let (status, _) = send(&app, json_request("PUT", &uri, &token, Some(json!({
+        "bricks": [combine_text_brick("a")]
+    })))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, stored) = send(&app, json_request("GET", &uri, &token, None)).await;
+    assert_eq!(stored["graph"]["nodes"].as_array().unwrap().len(), 4);
+    assert_eq!(stored["graph"]["edges"].as_array().unwrap().len(), 4);
+
+    // The graph itself can still be replaced
+    let (status, _) = send(&app, json_request("PUT", &uri, &token, Some(json!({
+        "name": "Renamed",
+        "graph": graph
+    })))).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_brick_list_update_replaces_linear_flow() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "editor@example.com").await;
+
+    let (status, flow) = send(&app, json_request("POST", "/api/v1/flows", &token, Some(json!({
+        "name": "Chain",
+        "bricks": [combine_text_brick("a"), combine_text_brick("b")]
+    })))).await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/api/v1/flows/{}", flow["id"].as_str().unwrap());
+
+    let (status, updated) = send(&app, json_request("PUT", &uri, &token, Some(json!({
+        "bricks": [combine_text_brick("c")]
+    })))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(updated["bricks"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_web_execution_page_hides_other_workspaces() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+
+    // The user's flows live in their personal workspace, not the default one
+    let flow_id = create_flow(&app, &token, json!({
+        "name": "Private",
+        "bricks": [combine_text_brick("a")]
+    })).await;
+    let (status, execution) = run_flow(&app, &token, &flow_id, json!({ "a": "secret" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let execution_id = execution["execution_id"].as_str().unwrap();
+
+    let request = axum::http::Request::builder()
+        .uri(format!("/executions/{}", execution_id))
+        .body(axum::body::Body::empty())
+        .unwrap();
+    let (status, _) = send(&app, request).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_execution_events_are_limited_to_the_workspace() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, owner) = create_test_user(&pool, "owner@example.com").await;
+    let (_, outsider) = create_test_user(&pool, "outsider@example.com").await;
+
+    let flow_id = create_flow(&app, &owner, json!({
+        "name": "Background",
+        "bricks": [combine_text_brick("a")]
+    })).await;
+    let (status, started) = send(&app, json_request("POST", "/api/v1/executions", &owner, Some(json!({
+        "flow_id": flow_id,
+        "input_payload": { "a": "x" },
+        "mode": "background"
+    })))).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let events_url = started["events_url"].as_str().unwrap();
+
+    let (status, _) = send(&app, json_request("GET", events_url, &outsider, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let response = app.clone().oneshot(json_request("GET", events_url, &owner, None)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_execution_events_websocket_accepts_subprotocol_token() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+    let uri = "/api/v1/executions/unknown/events/ws";
+
+    let request = |protocols: Option<String>| {
+        let builder = axum::http::Request::builder().uri(uri);
+        let builder = match protocols {
+            Some(protocols) => builder.header(header::SEC_WEBSOCKET_PROTOCOL, protocols),
+            None => builder,
+        };
+        builder.body(axum::body::Body::empty()).unwrap()
+    };
+
+    let (status, _) = send(&app, request(None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, request(Some("flowmason.events, flowmason.bearer.not-a-token".to_string()))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Authenticated; this request is not a WebSocket upgrade, so it stops there
+    let (status, _) = send(&app, request(Some(format!("flowmason.events, flowmason.bearer.{}", token)))).await;
+    assert_ne!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_masked_credentials_follow_reordered_bricks() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "editor@example.com").await;
+
+    let openai = |key: &str, prompt: &str| json!({
+        "brick_type": "openai",
+        "config": { "api_key": key, "prompt_template": prompt }
+    });
+    let flow_id = create_flow(&app, &token, json!({
+        "name": "Two keys",
+        "bricks": [openai("sk-first", "first"), openai("sk-second", "second")]
+    })).await;
+    let uri = format!("/api/v1/flows/{}", flow_id);
+
+    let (_, flow) = send(&app, json_request("GET", &uri, &token, None)).await;
+    let mut bricks = flow["bricks"].as_array().unwrap().clone();
+    assert_eq!(bricks[0]["config"]["api_key"], "[REDACTED]");
+    assert!(bricks[0]["id"].is_string());
+    bricks.reverse();
+
+    let (status, _) = send(&app, json_request("PUT", &uri, &token, Some(json!({ "bricks": bricks })))).await;
+    assert_eq!(status, StatusCode::OK);
+    let stored = flowmason_db::repositories::FlowRepository::new(pool.clone()).get(&flow_id).await.unwrap().unwrap();
+    assert_eq!(stored.bricks[0].config["prompt_template"], "second");
+    assert_eq!(stored.bricks[0].config["api_key"], "sk-second");
+    assert_eq!(stored.bricks[1].config["api_key"], "sk-first");
+
+    // Without ids, masked keys cannot be matched to a stored brick
+    let (status, _) = send(&app, json_request("PUT", &uri, &token, Some(json!({
+        "bricks": [openai("[REDACTED]", "first"), openai("[REDACTED]", "second"), openai("[REDACTED]", "third")]
+    })))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Nor kept for a brick whose other settings change
+    let (_, flow) = send(&app, json_request("GET", &uri, &token, None)).await;
+    let mut bricks = flow["bricks"].as_array().unwrap().clone();
+    bricks[0]["config"]["prompt_template"] = json!("changed");
+    let (status, body) = send(&app, json_request("PUT", &uri, &token, Some(json!({ "bricks": bricks.clone() })))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("secret:name"), "{}", body);
+
+    bricks[0]["config"]["api_key"] = json!("sk-new");
+    let (status, _) = send(&app, json_request("PUT", &uri, &token, Some(json!({ "bricks": bricks })))).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+/// A workspace owned by a new user, with one flow and one execution of it
+struct SharedWorkspace {
+    workspace_id: String,
+    flow_id: String,
+    execution_id: String,
+}
+
+async fn shared_workspace(app: &axum::Router, pool: &sqlx::SqlitePool) -> SharedWorkspace {
+    let (owner_id, owner) = create_test_user(pool, "owner@example.com").await;
+    let workspace = WorkspaceRepository::new(pool.clone()).create("Team", &owner_id).await.unwrap();
+    let (status, flow) = send(app, in_workspace(json_request("POST", "/api/v1/flows", &owner, Some(json!({
+        "name": "Shared",
+        "bricks": [combine_text_brick("a")]
+    }))), &workspace.id)).await;
+    assert_eq!(status, StatusCode::OK);
+    let flow_id = flow["id"].as_str().unwrap().to_string();
+    let (status, execution) = send(app, in_workspace(json_request("POST", "/api/v1/executions", &owner, Some(json!({
+        "flow_id": flow_id,
+        "input_payload": { "a": "x" }
+    }))), &workspace.id)).await;
+    assert_eq!(status, StatusCode::OK);
+    SharedWorkspace {
+        workspace_id: workspace.id,
+        flow_id,
+        execution_id: execution["execution_id"].as_str().unwrap().to_string(),
+    }
+}
+
+async fn member(pool: &sqlx::SqlitePool, workspace_id: &str, email: &str, role: WorkspaceRole) -> String {
+    let (user_id, token) = create_test_user(pool, email).await;
+    WorkspaceRepository::new(pool.clone()).add_member(workspace_id, &user_id, role).await.unwrap();
+    token
+}
+
+#[tokio::test]
+async fn test_viewer_cannot_change_flows_or_run_them() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let shared = shared_workspace(&app, &pool).await;
+    let viewer = member(&pool, &shared.workspace_id, "viewer@example.com", WorkspaceRole::Viewer).await;
+    let flow_uri = format!("/api/v1/flows/{}", shared.flow_id);
+    let in_shared = |request| in_workspace(request, &shared.workspace_id);
+
+    let (status, _) = send(&app, in_shared(json_request("GET", &flow_uri, &viewer, None))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, in_shared(json_request("PUT", &flow_uri, &viewer, Some(json!({ "name": "Mine" }))))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, in_shared(json_request("DELETE", &flow_uri, &viewer, None))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, in_shared(json_request("POST", "/api/v1/executions", &viewer, Some(json!({
+        "flow_id": shared.flow_id,
+        "input_payload": {}
+    }))))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, json_request("PUT", &format!("/api/v1/workspaces/{}", shared.workspace_id), &viewer, Some(json!({ "name": "Mine" })))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // The flow is unchanged
+    let (_, flow) = send(&app, in_shared(json_request("GET", &flow_uri, &viewer, None))).await;
+    assert_eq!(flow["name"], "Shared");
+}
+
+#[tokio::test]
+async fn test_runner_can_run_but_not_edit_flows() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let shared = shared_workspace(&app, &pool).await;
+    let runner = member(&pool, &shared.workspace_id, "runner@example.com", WorkspaceRole::Runner).await;
+    let flow_uri = format!("/api/v1/flows/{}", shared.flow_id);
+    let in_shared = |request| in_workspace(request, &shared.workspace_id);
+
+    let (status, _) = send(&app, in_shared(json_request("POST", "/api/v1/executions", &runner, Some(json!({
+        "flow_id": shared.flow_id,
+        "input_payload": { "a": "y" }
+    }))))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, in_shared(json_request("PUT", &flow_uri, &runner, Some(json!({ "name": "Mine" }))))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, in_shared(json_request("POST", "/api/v1/flows", &runner, Some(json!({
+        "name": "New",
+        "bricks": [combine_text_brick("a")]
+    }))))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, in_shared(json_request("DELETE", &flow_uri, &runner, None))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_other_workspaces_ids_are_not_found() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let shared = shared_workspace(&app, &pool).await;
+    // The outsider acts in their own personal workspace
+    let (_, outsider) = create_test_user(&pool, "outsider@example.com").await;
+    let flow_uri = format!("/api/v1/flows/{}", shared.flow_id);
+    let execution_uri = format!("/api/v1/executions/{}", shared.execution_id);
+
+    let (status, _) = send(&app, json_request("GET", &flow_uri, &outsider, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, json_request("PUT", &flow_uri, &outsider, Some(json!({ "name": "Mine" })))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, json_request("DELETE", &flow_uri, &outsider, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = run_flow(&app, &outsider, &shared.flow_id, json!({})).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, json_request("GET", &execution_uri, &outsider, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, json_request("GET", &format!("{}/steps", execution_uri), &outsider, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&app, json_request("GET", &format!("/api/v1/workspaces/{}", shared.workspace_id), &outsider, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Naming the workspace does not help non-members
+    let (status, _) = send(&app, in_workspace(json_request("GET", &flow_uri, &outsider, None), &shared.workspace_id)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Nor is the flow listed in the outsider's workspace
+    let (_, flows) = send(&app, json_request("GET", "/api/v1/flows", &outsider, None)).await;
+    assert_eq!(flows["items"].as_array().map(Vec::len), Some(0));
+}
+
+#[tokio::test]
+async fn test_read_scope_cannot_write() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (user_id, token) = create_test_user(&pool, "owner@example.com").await;
+    create_flow(&app, &token, json!({ "name": "Existing", "bricks": [combine_text_brick("a")] })).await;
+    let (_, key) = create_api_key(&pool, &user_id, Some(&["flows:read"]), None, None).await;
+
+    let (status, flows) = send(&app, json_request("GET", "/api/v1/flows", &key, None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(flows["items"].as_array().map(Vec::len), Some(1));
+    let (status, _) = send(&app, json_request("POST", "/api/v1/flows", &key, Some(json!({
+        "name": "New",
+        "bricks": [combine_text_brick("a")]
+    })))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, json_request("GET", "/api/v1/executions", &key, None)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_trigger_scope_runs_only_its_flow() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (user_id, token) = create_test_user(&pool, "owner@example.com").await;
+    let allowed = create_flow(&app, &token, json!({ "name": "Allowed", "bricks": [combine_text_brick("a")] })).await;
+    let other = create_flow(&app, &token, json!({ "name": "Other", "bricks": [combine_text_brick("a")] })).await;
+    let scope = format!("flow:{}:trigger", allowed);
+    let (_, key) = create_api_key(&pool, &user_id, Some(&[scope.as_str()]), None, None).await;
+
+    let (status, _) = run_flow(&app, &key, &allowed, json!({ "a": "x" })).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = run_flow(&app, &key, &other, json!({ "a": "x" })).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, json_request("GET", "/api/v1/executions", &key, None)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = send(&app, json_request("GET", &format!("/api/v1/flows/{}", allowed), &key, None)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_expired_and_revoked_api_keys_are_rejected() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (user_id, _) = create_test_user(&pool, "owner@example.com").await;
+    let now = chrono::Utc::now();
+    let (_, active) = create_api_key(&pool, &user_id, None, Some(now + chrono::Duration::hours(1)), None).await;
+    let (_, expired) = create_api_key(&pool, &user_id, None, Some(now - chrono::Duration::seconds(1)), None).await;
+    let (revoked_id, revoked) = create_api_key(&pool, &user_id, None, None, None).await;
+    flowmason_db::repositories::ApiKeyRepository::new(pool.clone()).revoke(&revoked_id).await.unwrap();
+
+    let (status, _) = send(&app, json_request("GET", "/api/v1/flows", &active, None)).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, json_request("GET", "/api/v1/flows", &expired, None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, json_request("GET", "/api/v1/flows", &revoked, None)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_api_key_ip_allow_list() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (user_id, _) = create_test_user(&pool, "owner@example.com").await;
+    let (_, key) = create_api_key(&pool, &user_id, None, None, Some(&["10.0.0.0/8"])).await;
+
+    let from = |ip: Option<&str>| {
+        let mut request = json_request("GET", "/api/v1/flows", &key, None);
+        if let Some(ip) = ip {
+            let addr: std::net::SocketAddr = format!("{}:40000", ip).parse().unwrap();
+            request.extensions_mut().insert(axum::extract::ConnectInfo(addr));
+        }
+        request
+    };
+
+    let (status, _) = send(&app, from(Some("10.1.2.3"))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, from(Some("192.168.1.5"))).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    // Clients of unknown address are outside every allow-list
+    let (status, _) = send(&app, from(None)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_api_keys_cannot_create_less_restricted_keys() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (user_id, _) = create_test_user(&pool, "owner@example.com").await;
+    let expires_at = chrono::Utc::now() + chrono::Duration::hours(1);
+    let (_, key) = create_api_key(&pool, &user_id, Some(&["api-keys:write"]), Some(expires_at), Some(&["10.0.0.0/8"])).await;
+
+    let create = |body: serde_json::Value| {
+        let mut request = json_request("POST", "/api/v1/auth/api-keys", &key, Some(body));
+        let addr: std::net::SocketAddr = "10.1.2.3:40000".parse().unwrap();
+        request.extensions_mut().insert(axum::extract::ConnectInfo(addr));
+        request
+    };
+    let later = (expires_at + chrono::Duration::days(1)).to_rfc3339();
+    let sooner = (expires_at - chrono::Duration::minutes(30)).to_rfc3339();
+
+    for body in [
+        json!({ "scopes": ["api-keys:write"], "allowed_ips": ["10.1.0.0/16"] }),
+        json!({ "scopes": ["api-keys:write"], "expires_at": later, "allowed_ips": ["10.1.0.0/16"] }),
+        json!({ "scopes": ["api-keys:write"], "expires_at": sooner }),
+        json!({ "scopes": ["api-keys:write"], "expires_at": sooner, "allowed_ips": ["10.0.0.0/7"] }),
+    ] {
+        let (status, _) = send(&app, create(body)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    let (status, created) = send(&app, create(json!({
+        "scopes": ["api-keys:write"],
+        "expires_at": sooner,
+        "allowed_ips": ["10.1.0.0/16", "10.2.3.4"]
+    }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", created);
+}
+
+fn webhook_call(flow_id: &str, token: Option<&str>) -> axum::http::Request<axum::body::Body> {
+    let builder = axum::http::Request::builder()
+        .method("POST")
+        .uri(format!("/api/v1/webhooks/flows/{}/trigger", flow_id))
+        .header(header::CONTENT_TYPE, "application/json");
+    let builder = match token {
+        Some(token) => builder.header("X-Flowmason-Token", token),
+        None => builder,
+    };
+    builder.body(axum::body::Body::from(json!({ "a": "x" }).to_string())).unwrap()
+}
+
+#[tokio::test]
+async fn test_webhook_calls_do_not_reveal_flow_ids() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+    let without_webhook = create_flow(&app, &token, json!({ "name": "Plain", "bricks": [combine_text_brick("a")] })).await;
+    let with_webhook = create_flow(&app, &token, json!({ "name": "Hooked", "bricks": [combine_text_brick("a")] })).await;
+    let secret = configure_webhook(&app, &token, &with_webhook, json!({ "mode": "token" })).await;
+
+    // Unknown flows look like flows without webhook authentication
+    let (status, _) = send(&app, webhook_call("no-such-flow", Some(&secret))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, webhook_call(&without_webhook, Some(&secret))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&app, webhook_call(&with_webhook, Some("wrong-secret"))).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, body) = send(&app, webhook_call(&with_webhook, Some(&secret))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+}
+
+/// A synchronous execution request carrying `Idempotency-Key: key`
+fn idempotent_run(token: &str, flow_id: &str, key: &str, input: serde_json::Value) -> axum::http::Request<axum::body::Body> {
+    let mut request = json_request("POST", "/api/v1/executions", token, Some(json!({
+        "flow_id": flow_id,
+        "input_payload": input
+    })));
+    request.headers_mut().insert("Idempotency-Key", key.parse().unwrap());
+    request
+}
+
+#[tokio::test]
+async fn test_idempotency_key_replays_the_first_response() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+    let flow_id = create_flow(&app, &token, json!({ "name": "Once", "bricks": [combine_text_brick("a")] })).await;
+
+    let (status, first) = send(&app, idempotent_run(&token, &flow_id, "order-1", json!({ "a": "x" }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", first);
+
+    let response = app.clone().oneshot(idempotent_run(&token, &flow_id, "order-1", json!({ "a": "x" }))).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["idempotent-replayed"], "true");
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let replayed: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(replayed["execution_id"], first["execution_id"]);
+
+    // The same key for another request is refused
+    let (status, _) = send(&app, idempotent_run(&token, &flow_id, "order-1", json!({ "a": "y" }))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_idempotency_key_in_progress_conflicts_until_its_claim_lapses() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+    let flow_id = create_flow(&app, &token, json!({ "name": "Once", "bricks": [combine_text_brick("a")] })).await;
+    let (status, first) = send(&app, idempotent_run(&token, &flow_id, "order-1", json!({ "a": "x" }))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // As if the server stopped while handling the request
+    sqlx::query("UPDATE idempotency_keys SET status = 'in_progress', claimed_at = ?1 WHERE idempotency_key = 'order-1'")
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&pool)
+        .await
+        .unwrap();
+    let (status, _) = send(&app, idempotent_run(&token, &flow_id, "order-1", json!({ "a": "x" }))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // Once the claim is no longer renewed, a retry runs the flow
+    sqlx::query("UPDATE idempotency_keys SET claimed_at = ?1 WHERE idempotency_key = 'order-1'")
+        .bind((chrono::Utc::now() - chrono::Duration::hours(1)).to_rfc3339())
+        .execute(&pool)
+        .await
+        .unwrap();
+    let (status, retried) = send(&app, idempotent_run(&token, &flow_id, "order-1", json!({ "a": "x" }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", retried);
+    assert_ne!(retried["execution_id"], first["execution_id"]);
+}
+
+#[tokio::test]
+async fn test_signed_query_endpoints_reject_changed_queries() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+    let flow_id = create_flow(&app, &token, json!({ "name": "Lookup", "bricks": [combine_text_brick("id")] })).await;
+    let secret = configure_webhook(&app, &token, &flow_id, json!({ "mode": "hmac" })).await;
+    let uri = format!("/api/v1/flows/{}/endpoint", flow_id);
+    let (status, _) = send(&app, json_request("PUT", &uri, &token, Some(json!({ "method": "GET", "path": "/lookup" })))).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let timestamp = chrono::Utc::now().timestamp();
+    let call = |query: &str, signed: &str| {
+        axum::http::Request::builder()
+            .uri(format!("/hooks/lookup?{}", query))
+            .header("X-Flowmason-Timestamp", timestamp.to_string())
+            .header("X-Flowmason-Signature", flowmason_auth::sign_webhook(&secret, timestamp, signed.as_bytes()))
+            .body(axum::body::Body::empty())
+            .unwrap()
+    };
+
+    let (status, body) = send(&app, call("id=1", "id=1")).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    let (status, _) = send(&app, call("id=2", "id=1")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    // A signature over the empty body does not cover the query
+    let (status, _) = send(&app, call("id=1", "")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_endpoint_responses_cannot_set_cookies_or_run_scripts() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+    let respond = |headers: serde_json::Value| json!({
+        "name": "Page",
+        "bricks": [{
+            "brick_type": "respond",
+            "config": { "headers": headers, "body_type": "text", "body": "<script>alert(1)</script>" }
+        }]
+    });
+    let call = |path: &str, secret: &str| {
+        axum::http::Request::builder()
+            .uri(format!("/hooks/{}", path))
+            .header("X-Flowmason-Token", secret)
+            .body(axum::body::Body::empty())
+            .unwrap()
+    };
+    let mut endpoints = Vec::new();
+    for (path, headers) in [("page", json!({ "Content-Type": "text/html" })), ("cookie", json!({ "Set-Cookie": "session=attacker" }))] {
+        let flow_id = create_flow(&app, &token, respond(headers)).await;
+        let secret = configure_webhook(&app, &token, &flow_id, json!({ "mode": "token" })).await;
+        let uri = format!("/api/v1/flows/{}/endpoint", flow_id);
+        let (status, _) = send(&app, json_request("PUT", &uri, &token, Some(json!({ "method": "GET", "path": format!("/{}", path) })))).await;
+        assert_eq!(status, StatusCode::OK);
+        endpoints.push((path, secret));
+    }
+
+    let response = app.clone().oneshot(call(endpoints[0].0, &endpoints[0].1)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/html");
+    assert_eq!(response.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+    assert!(response.headers()[header::CONTENT_SECURITY_POLICY].to_str().unwrap().contains("default-src 'none'"));
+
+    // Reserved headers fail the respond brick
+    let response = app.clone().oneshot(call(endpoints[1].0, &endpoints[1].1)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(response.headers().get(header::SET_COOKIE).is_none());
+}
+
+/// A flow whose input must carry a string `email`
+async fn create_flow_with_input_schema(app: &axum::Router, token: &str) -> String {
+    create_flow(app, token, json!({
+        "name": "Signup",
+        "bricks": [combine_text_brick("email")],
+        "schema": {
+            "input": {
+                "type": "object",
+                "required": ["email"],
+                "properties": { "email": { "type": "string" } }
+            }
+        }
+    })).await
+}
+
+#[tokio::test]
+async fn test_flow_schema_endpoint() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+    let flow_id = create_flow_with_input_schema(&app, &token).await;
+
+    let (status, body) = send(&app, json_request("GET", &format!("/api/v1/flows/{}/schema", flow_id), &token, None)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["flow_id"], flow_id);
+    assert_eq!(body["input"]["required"], json!(["email"]));
+    assert!(body["output"].is_null());
+
+    let (status, _) = send(&app, json_request("GET", "/api/v1/flows/no-such-flow/schema", &token, None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Schemas that are not JSON Schema are refused
+    let (status, _) = send(&app, json_request("POST", "/api/v1/flows", &token, Some(json!({
+        "name": "Broken",
+        "bricks": [combine_text_brick("a")],
+        "schema": { "input": { "type": "no-such-type" } }
+    })))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_invalid_trigger_payloads_are_rejected_without_using_the_key() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+    let flow_id = create_flow_with_input_schema(&app, &token).await;
+
+    let (status, body) = send(&app, idempotent_run(&token, &flow_id, "signup-1", json!({ "email": 42 }))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["instance_path"], "/email");
+    assert_eq!(body["errors"][0]["schema_path"], "/properties/email/type");
+
+    let (status, body) = send(&app, idempotent_run(&token, &flow_id, "signup-1", json!({ "email": "jane@example.com" }))).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+
+    // Webhook triggers are validated the same way
+    let secret = configure_webhook(&app, &token, &flow_id, json!({ "mode": "token" })).await;
+    let (status, body) = send(&app, webhook_call(&flow_id, Some(&secret))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["schema_path"], "/required");
+}
+
+#[tokio::test]
+async fn test_filtered_secrets_are_refused() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "editor@example.com").await;
+
+    let (status, body) = send(&app, json_request("POST", "/api/v1/flows", &token, Some(json!({
+        "name": "Leak",
+        "bricks": [{
+            "brick_type": "combine_text",
+            "config": { "fields": ["a"], "output_field": "a", "separator": "{{ secret:openai_key | upper }}" }
+        }]
+    })))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["errors"][0]["instance_path"], "/bricks/0/config/separator");
+}
+
+#[tokio::test]
+async fn test_dead_letters_with_redacted_input_are_not_replayed() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+    let flow_id = create_flow(&app, &token, json!({ "name": "Sync", "bricks": [combine_text_brick("a")] })).await;
+
+    // A failed execution whose input held a secret
+    let execution = flowmason_core::types::FlowExecution {
+        flow_id: flow_id.clone(),
+        execution_id: uuid::Uuid::new_v4().to_string(),
+        status: flowmason_core::types::ExecutionStatus::Failed,
+        started_at: chrono::Utc::now(),
+        completed_at: Some(chrono::Utc::now()),
+        input_payload: json!({ "a": "[REDACTED]" }),
+        output_payload: None,
+        error: Some("boom".to_string()),
+        parent_execution_id: None,
+        replay_of_execution_id: None,
+        steps: Vec::new(),
+    };
+    flowmason_db::repositories::ExecutionRepository::new(pool.clone()).create(&execution).await.unwrap();
+    let (status, entries) = send(&app, json_request("GET", "/api/v1/dead-letters", &token, None)).await;
+    assert_eq!(status, StatusCode::OK, "{}", entries);
+    let entry_id = entries["items"][0]["id"].as_str().unwrap().to_string();
+
+    let uri = format!("/api/v1/dead-letters/{}/replay", entry_id);
+    let (status, replay) = send(&app, json_request("POST", &uri, &token, None)).await;
+    assert_eq!(status, StatusCode::OK, "{}", replay);
+    assert!(replay["execution"].is_null());
+    assert_eq!(replay["dead_letter"]["status"], "exhausted");
+    assert!(replay["dead_letter"]["error_message"].as_str().unwrap().contains("redacted"));
+}
+
+#[tokio::test]
+async fn test_connection_host_changes_need_credentials() {
+    let (app, pool) = create_test_app_with_pool().await;
+    let (_, token) = create_test_user(&pool, "owner@example.com").await;
+    let (status, connection) = send(&app, json_request("POST", "/api/v1/connections", &token, Some(json!({
+        "name": "ERP",
+        "connection_type": "odoo",
+        "base_url": "https://erp.example.com",
+        "credentials": { "database": "prod", "username": "flowmason", "password": "hunter2" }
+    })))).await;
+    assert_eq!(status, StatusCode::OK, "{}", connection);
+    let uri = format!("/api/v1/connections/{}", connection["id"].as_str().unwrap());
+
+    for base_url in ["https://attacker.example.com", ""] {
+        let (status, _) = send(&app, json_request("PUT", &uri, &token, Some(json!({ "base_url": base_url })))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+    let (_, unchanged) = send(&app, json_request("GET", &uri, &token, None)).await;
+    assert_eq!(unchanged["base_url"], "https://erp.example.com");
+
+    // Resending the same URL or entering the credentials again is fine
+    let (status, _) = send(&app, json_request("PUT", &uri, &token, Some(json!({ "base_url": "https://erp.example.com", "name": "ERP prod" })))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, moved) = send(&app, json_request("PUT", &uri, &token, Some(json!({
+        "base_url": "https://erp2.example.com",
+        "credentials": { "database": "prod", "username": "flowmason", "password": "hunter2" }
+    })))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(moved["base_url"], "https://erp2.example.com");
+}
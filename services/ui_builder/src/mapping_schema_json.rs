@@ -77,16 +77,9 @@ pub fn generate_mapping_schema() -> Value {
 }
 
 pub fn get_brick_schema(brick_type: &BrickType) -> Option<Value> {
-    match brick_type {
-        BrickType::OpenAi => Some(OpenAiBrick.config_schema()),
-        BrickType::Nvidia => Some(NvidiaBrick.config_schema()),
-        BrickType::HubSpot => Some(HubSpotBrick.config_schema()),
-        BrickType::Notion => Some(NotionBrick.config_schema()),
-        BrickType::Odoo => Some(OdooBrick.config_schema()),
-        BrickType::N8n => Some(N8nBrick.config_schema()),
-        BrickType::FieldMapping => Some(FieldMappingBrick.config_schema()),
-        BrickType::CombineText => Some(CombineTextBrick.config_schema()),
-        BrickType::Conditional => Some(ConditionalBrick.config_schema()),
-    }
+    default_registry()
+        .create(brick_type)
+        .ok()
+        .map(|brick| brick.config_schema())
 }
 
@@ -1,4 +1,5 @@
 pub mod sync_executor;
+pub mod worker;
 
 pub use sync_executor::SyncExecutor;
-
+pub use worker::{Worker, WorkerConfig};
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use flowmason_db::connection::create_pool;
+use flowmason_worker::{Worker, WorkerConfig};
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let env_filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(tracing_subscriber::fmt::layer().json())
+        .init();
+
+    let database_url = std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://flowmason.db".to_string());
+    let pool = create_pool(&database_url).await?;
+    tracing::info!(database_url = %database_url, "Database connection established");
+
+    // Resolve the same bricks as the API server, including plugins
+    let mut brick_registry = flowmason_bricks::default_registry();
+    flowmason_plugins::register_from_env(&mut brick_registry);
+
+    let worker = Worker::new(pool, Arc::new(brick_registry), WorkerConfig::from_env());
+    worker
+        .run(async {
+            let _ = tokio::signal::ctrl_c().await;
+            tracing::info!("Shutdown signal received");
+        })
+        .await;
+
+    Ok(())
+}
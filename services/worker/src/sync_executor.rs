@@ -1,39 +1,51 @@
 use anyhow::Result;
-use flowmason_core::{FlowRunner, types::Flow};
-use flowmason_bricks::*;
+use flowmason_core::{BrickRegistry, FlowRunner, GraphRunner, types::{Flow, FlowNodeKind}};
+use flowmason_bricks::default_registry;
 use serde_json::Value;
+use std::sync::Arc;
 
-pub struct SyncExecutor;
+pub struct SyncExecutor {
+    brick_registry: Arc<BrickRegistry>,
+}
+
+impl Default for SyncExecutor {
+    fn default() -> Self {
+        Self::new(Arc::new(default_registry()))
+    }
+}
 
 impl SyncExecutor {
+    pub fn new(brick_registry: Arc<BrickRegistry>) -> Self {
+        Self { brick_registry }
+    }
+
     /// Executes a flow synchronously
     pub async fn execute_flow(
+        &self,
         flow: &Flow,
         input: Value,
     ) -> Result<Value> {
         // Create brick instances
-        let mut bricks: Vec<Box<dyn flowmason_core::Brick>> = Vec::new();
-        
-        for brick_config in &flow.bricks {
-            let brick: Box<dyn flowmason_core::Brick> = match brick_config.brick_type {
-                flowmason_core::types::BrickType::OpenAi => Box::new(OpenAiBrick),
-                flowmason_core::types::BrickType::Nvidia => Box::new(NvidiaBrick),
-                flowmason_core::types::BrickType::HubSpot => Box::new(HubSpotBrick),
-                flowmason_core::types::BrickType::Notion => Box::new(NotionBrick),
-                flowmason_core::types::BrickType::Odoo => Box::new(OdooBrick),
-                flowmason_core::types::BrickType::N8n => Box::new(N8nBrick),
-                flowmason_core::types::BrickType::FieldMapping => Box::new(FieldMappingBrick),
-                flowmason_core::types::BrickType::CombineText => Box::new(CombineTextBrick),
-                flowmason_core::types::BrickType::Conditional => Box::new(ConditionalBrick),
-            };
-            bricks.push(brick);
-        }
+        let bricks = self.brick_registry.create_all(&flow.bricks)?;
         
         // Execute flow (without quota/usage tracking for worker)
-        let configs: Vec<Value> = flow.bricks.iter().map(|b| b.config.clone()).collect();
-        FlowRunner::execute_flow(bricks, configs, input, None)
-            .await
-            .map_err(|e| anyhow::anyhow!("Flow execution failed: {}", e))
+        let result = match flow.graph {
+            Some(ref graph) => {
+                // `flow.bricks` mirrors the graph's brick nodes in node order
+                let bricks_by_node = graph.nodes.iter()
+                    .filter(|n| matches!(n.kind, FlowNodeKind::Brick(_)))
+                    .map(|n| n.id.clone())
+                    .zip(bricks)
+                    .collect();
+                GraphRunner::execute_graph(graph, bricks_by_node, input, None).await
+            }
+            None => {
+                let configs: Vec<Value> = flow.bricks.iter().map(|b| b.config.clone()).collect();
+                FlowRunner::execute_flow(bricks, configs, input, None).await
+            }
+        };
+
+        result.map_err(|e| anyhow::anyhow!("Flow execution failed: {}", e))
     }
 }
 
@@ -0,0 +1,383 @@
+use anyhow::Result;
+use flowmason_core::quota::{DatabaseQuotaManager, QuotaManager};
+use flowmason_core::{
+    BrickRegistry, ConnectionResolver, ExecutionDataStorage, FlowRunner, FlowRunnerContext, SecretResolver, SubFlowExecutor,
+    UsageLogger,
+};
+use flowmason_db::repositories::{
+    ConnectionRepository, ExecutionDataRepository, ExecutionRepository, FlowRepository, Job, JobRepository,
+    SecretRepository, UsageLogRepository,
+};
+use flowmason_db::RepositorySubFlowExecutor;
+use flowmason_meter::DatabaseUsageLogger;
+use sqlx::SqlitePool;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Settings for a queue worker
+#[derive(Debug, Clone)]
+pub struct WorkerConfig {
+    /// Identifies this worker as the lease owner of claimed jobs
+    pub worker_id: String,
+    /// How long to wait before polling again when the queue is empty
+    pub poll_interval: Duration,
+    /// How long a claimed job stays invisible to other workers; renewed while running
+    pub lease_duration: Duration,
+    /// Delay before the first retry of a failed job, doubled on each further attempt
+    pub retry_backoff: Duration,
+}
+
+impl Default for WorkerConfig {
+    fn default() -> Self {
+        Self {
+            worker_id: format!("worker-{}", uuid::Uuid::new_v4()),
+            poll_interval: Duration::from_secs(1),
+            lease_duration: Duration::from_secs(60),
+            retry_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl WorkerConfig {
+    /// Reads `WORKER_ID`, `WORKER_POLL_INTERVAL_MS`, `WORKER_LEASE_SECS` and
+    /// `WORKER_RETRY_BACKOFF_MS`, falling back to defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let millis = |name: &str| std::env::var(name).ok().and_then(|v| v.parse().ok()).map(Duration::from_millis);
+
+        Self {
+            worker_id: std::env::var("WORKER_ID").unwrap_or(defaults.worker_id),
+            poll_interval: millis("WORKER_POLL_INTERVAL_MS").unwrap_or(defaults.poll_interval),
+            lease_duration: std::env::var("WORKER_LEASE_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(defaults.lease_duration),
+            retry_backoff: millis("WORKER_RETRY_BACKOFF_MS").unwrap_or(defaults.retry_backoff),
+        }
+    }
+}
+
+/// Polls the durable job queue, executes claimed flows and acknowledges them
+pub struct Worker {
+    config: WorkerConfig,
+    job_repo: Arc<JobRepository>,
+    flow_repo: Arc<FlowRepository>,
+    execution_repo: Arc<ExecutionRepository>,
+    execution_data_repo: Arc<ExecutionDataRepository>,
+    quota_manager: Arc<dyn QuotaManager>,
+    usage_logger: Arc<dyn UsageLogger>,
+    sub_flow_executor: Arc<dyn SubFlowExecutor>,
+    secret_resolver: Arc<dyn SecretResolver>,
+    connection_resolver: Arc<dyn ConnectionResolver>,
+    brick_registry: Arc<BrickRegistry>,
+}
+
+impl Worker {
+    pub fn new(pool: SqlitePool, brick_registry: Arc<BrickRegistry>, config: WorkerConfig) -> Self {
+        let flow_repo = Arc::new(FlowRepository::new(pool.clone()));
+        let execution_repo = Arc::new(ExecutionRepository::new(pool.clone()));
+        let sub_flow_executor: Arc<dyn SubFlowExecutor> = Arc::new(RepositorySubFlowExecutor::new(
+            flow_repo.clone(),
+            execution_repo.clone(),
+            brick_registry.clone(),
+        ));
+        let secret_repo = SecretRepository::from_env(pool.clone());
+        let connection_repo = ConnectionRepository::new(pool.clone(), secret_repo.shared_cipher());
+
+        Self {
+            config,
+            job_repo: Arc::new(JobRepository::new(pool.clone())),
+            flow_repo,
+            execution_repo,
+            execution_data_repo: Arc::new(ExecutionDataRepository::new(pool.clone())),
+            quota_manager: Arc::new(DatabaseQuotaManager::new(pool.clone())),
+            usage_logger: Arc::new(DatabaseUsageLogger::new(UsageLogRepository::new(pool.clone()))),
+            sub_flow_executor,
+            secret_resolver: Arc::new(secret_repo),
+            connection_resolver: Arc::new(connection_repo),
+            brick_registry,
+        }
+    }
+
+    pub fn config(&self) -> &WorkerConfig {
+        &self.config
+    }
+
+    /// Processes jobs until `shutdown` resolves. A job in progress is finished first.
+    pub async fn run(&self, shutdown: impl Future<Output = ()>) {
+        tokio::pin!(shutdown);
+        tracing::info!(worker_id = %self.config.worker_id, "Worker started");
+
+        loop {
+            let idle = match self.run_once().await {
+                Ok(processed) => !processed,
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to poll job queue");
+                    true
+                }
+            };
+
+            if idle {
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = tokio::time::sleep(self.config.poll_interval) => {}
+                }
+            } else if futures_ready(&mut shutdown).await {
+                break;
+            }
+        }
+
+        tracing::info!(worker_id = %self.config.worker_id, "Worker stopped");
+    }
+
+    /// Claims and processes a single job. Returns false if the queue was empty.
+    pub async fn run_once(&self) -> Result<bool> {
+        let job = match self.job_repo.claim(&self.config.worker_id, self.config.lease_duration).await? {
+            Some(job) => job,
+            None => return Ok(false),
+        };
+
+        tracing::info!(job_id = %job.id, flow_id = %job.flow_id, attempt = job.attempts, "Claimed job");
+
+        // Keep the lease alive while the flow runs
+        let heartbeat = {
+            let job_repo = self.job_repo.clone();
+            let job_id = job.id.clone();
+            let worker_id = self.config.worker_id.clone();
+            let lease = self.config.lease_duration;
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(lease / 3);
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    match job_repo.extend_lease(&job_id, &worker_id, lease).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            tracing::warn!(job_id = %job_id, "Lost job lease");
+                            break;
+                        }
+                        Err(e) => tracing::warn!(job_id = %job_id, error = %e, "Failed to extend job lease"),
+                    }
+                }
+            })
+        };
+
+        let result = self.execute(&job).await;
+        heartbeat.abort();
+
+        let acked = match result {
+            Ok(execution_id) => {
+                tracing::info!(job_id = %job.id, execution_id = %execution_id, "Job completed");
+                self.job_repo.complete(&job.id, &self.config.worker_id, &execution_id).await?
+            }
+            Err(JobFailure::Permanent(error)) => {
+                tracing::error!(job_id = %job.id, error = %error, "Job failed");
+                self.job_repo.fail(&job.id, &self.config.worker_id, None, &error).await?
+            }
+            Err(JobFailure::Execution { execution_id, error }) => {
+                tracing::error!(job_id = %job.id, execution_id = %execution_id, error = %error, "Job failed, execution dead-lettered");
+                self.job_repo.fail(&job.id, &self.config.worker_id, Some(&execution_id), &error).await?
+            }
+            Err(JobFailure::Retryable(error)) if job.attempts < job.max_attempts => {
+                let delay = self.retry_delay(job.attempts);
+                tracing::warn!(job_id = %job.id, error = %error, retry_in_ms = delay.as_millis() as u64, "Job failed, retrying");
+                self.job_repo.retry(&job.id, &self.config.worker_id, &error, delay).await?
+            }
+            Err(JobFailure::Retryable(error)) => {
+                tracing::error!(job_id = %job.id, error = %error, attempts = job.attempts, "Job failed on its last attempt");
+                self.job_repo.fail(&job.id, &self.config.worker_id, None, &error).await?
+            }
+        };
+
+        if !acked {
+            tracing::warn!(job_id = %job.id, "Job lease expired before it was acknowledged");
+        }
+
+        Ok(true)
+    }
+
+    async fn execute(&self, job: &Job) -> Result<String, JobFailure> {
+        // A job whose lease kept expiring (e.g. its worker crashed) is not retried forever
+        if job.attempts > job.max_attempts {
+            return Err(JobFailure::Permanent(format!(
+                "Job exceeded {} attempts",
+                job.max_attempts
+            )));
+        }
+
+        let flow = self.flow_repo.get(&job.flow_id).await
+            .map_err(|e| JobFailure::Retryable(format!("Failed to load flow: {}", e)))?
+            .ok_or_else(|| JobFailure::Permanent(format!("Flow not found: {}", job.flow_id)))?;
+
+        let bricks = self.brick_registry.create_all(&flow.bricks)
+            .map_err(|e| JobFailure::Permanent(e.to_string()))?;
+
+        let execution_data_storage: Arc<dyn ExecutionDataStorage> = self.execution_data_repo.clone();
+        let context = FlowRunnerContext {
+            quota_manager: Some(self.quota_manager.clone()),
+            usage_logger: Some(self.usage_logger.clone()),
+            execution_data_storage: Some(execution_data_storage),
+            sub_flow_executor: Some(self.sub_flow_executor.clone()),
+            brick_registry: Some(self.brick_registry.clone()),
+            event_bus: None,
+            flow_id: flow.id.clone(),
+            execution_id: String::new(), // Will be set in execute_flow_recorded
+            workspace_id: flow.workspace_id.clone(),
+            parent_execution_id: None,
+            depth: 0,
+            variables: Default::default(),
+            secret_resolver: Some(self.secret_resolver.clone()),
+            connection_resolver: Some(self.connection_resolver.clone()),
+            redactor: Default::default(),
+            responder: None,
+        };
+
+        let execution = FlowRunner::execute_flow_recorded(&flow, bricks, job.input_payload.clone(), Some(context)).await;
+
+        self.execution_repo.create(&execution).await
+            .map_err(|e| JobFailure::Retryable(format!("Failed to store execution: {}", e)))?;
+
+        match execution.error {
+            Some(error) => Err(JobFailure::Execution { execution_id: execution.execution_id, error }),
+            None => Ok(execution.execution_id),
+        }
+    }
+
+    fn retry_delay(&self, attempts: i64) -> Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+        self.config.retry_backoff.saturating_mul(2u32.pow(exponent))
+    }
+}
+
+enum JobFailure {
+    /// Retried with backoff until the job runs out of attempts
+    Retryable(String),
+    /// Retrying cannot succeed, e.g. the flow no longer exists
+    Permanent(String),
+    /// The flow ran and failed; retries are left to the dead-letter queue
+    Execution { execution_id: String, error: String },
+}
+
+/// Returns true if the future has already completed, without waiting for it
+async fn futures_ready<F: Future<Output = ()> + Unpin>(future: &mut F) -> bool {
+    tokio::select! {
+        biased;
+        _ = future => true,
+        _ = std::future::ready(()) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flowmason_core::types::{BrickConfig, BrickType, Flow};
+    use flowmason_db::repositories::{DeadLetterRepository, DeadLetterStatus, JobStatus};
+    use serde_json::json;
+
+    async fn test_worker() -> (Worker, SqlitePool) {
+        let pool = flowmason_db::connection::create_pool("sqlite::memory:").await.unwrap();
+        let config = WorkerConfig {
+            worker_id: "test-worker".to_string(),
+            retry_backoff: Duration::ZERO,
+            ..WorkerConfig::default()
+        };
+        let worker = Worker::new(pool.clone(), Arc::new(flowmason_bricks::default_registry()), config);
+        (worker, pool)
+    }
+
+    #[tokio::test]
+    async fn test_worker_executes_and_acks_job() {
+        let (worker, pool) = test_worker().await;
+        let now = chrono::Utc::now();
+        let flow = Flow {
+            id: "queued-flow".to_string(),
+            name: "Queued Flow".to_string(),
+            description: None,
+            bricks: vec![BrickConfig {
+                brick_type: BrickType::CombineText,
+                config: json!({"fields": ["first", "last"], "output_field": "full"}),
+                policy: None,
+            }],
+            graph: None,
+            retry_policy: None,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: Some("ws-1".to_string()),
+            active: true,
+            created_at: now,
+            updated_at: now,
+        };
+        FlowRepository::new(pool.clone()).create(&flow).await.unwrap();
+
+        let job_repo = JobRepository::new(pool.clone());
+        let job = job_repo.enqueue(&flow.id, &json!({"first": "Ada", "last": "Lovelace"}), 3).await.unwrap();
+
+        assert!(worker.run_once().await.unwrap());
+        assert!(!worker.run_once().await.unwrap());
+
+        let job = job_repo.get(&job.id).await.unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Completed);
+        let execution = ExecutionRepository::new(pool)
+            .get(job.execution_id.as_deref().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(execution.output_payload.unwrap()["full"], "Ada Lovelace");
+    }
+
+    #[tokio::test]
+    async fn test_failed_flow_is_dead_lettered_not_retried() {
+        let (worker, pool) = test_worker().await;
+        let now = chrono::Utc::now();
+        let flow = Flow {
+            id: "broken-flow".to_string(),
+            name: "Broken Flow".to_string(),
+            description: None,
+            bricks: vec![BrickConfig {
+                brick_type: BrickType::CombineText,
+                config: json!({"output_field": "full"}),
+                policy: None,
+            }],
+            graph: None,
+            retry_policy: None,
+            variables: Default::default(),
+            schema: Default::default(),
+            workspace_id: Some("ws-1".to_string()),
+            active: true,
+            created_at: now,
+            updated_at: now,
+        };
+        FlowRepository::new(pool.clone()).create(&flow).await.unwrap();
+
+        let job_repo = JobRepository::new(pool.clone());
+        let job = job_repo.enqueue(&flow.id, &json!({}), 3).await.unwrap();
+
+        assert!(worker.run_once().await.unwrap());
+
+        let job = job_repo.get(&job.id).await.unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.attempts, 1);
+        let execution_id = job.execution_id.unwrap();
+
+        let entries = DeadLetterRepository::new(pool).list("ws-1", None, None, None).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].execution_id, execution_id);
+        assert_eq!(entries[0].status, DeadLetterStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_missing_flow_fails_without_retry() {
+        let (worker, pool) = test_worker().await;
+        let job_repo = JobRepository::new(pool);
+        let job = job_repo.enqueue("missing-flow", &json!({}), 3).await.unwrap();
+
+        assert!(worker.run_once().await.unwrap());
+
+        let job = job_repo.get(&job.id).await.unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert_eq!(job.attempts, 1);
+        assert!(job.error.unwrap().contains("Flow not found"));
+    }
+}